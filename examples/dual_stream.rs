@@ -0,0 +1,88 @@
+use std::{net::SocketAddr, time::Duration};
+
+use clap::Parser;
+
+use futures::StreamExt;
+use gpsd_json::{
+    client::{GpsdClient, Merged, StreamOptions, merge},
+    protocol::v3::ResponseMessage,
+};
+
+/// Compares two receivers side by side: JSON from one GPSD, NMEA from another
+#[derive(Debug, Parser)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// GPSD providing JSON reports
+    #[arg(short, long, default_value = "127.0.0.1:2947")]
+    json: SocketAddr,
+    /// GPSD providing NMEA sentences
+    #[arg(short, long, default_value = "127.0.0.1:2948")]
+    nmea: SocketAddr,
+    /// Statistics interval in seconds
+    #[arg(short, long, default_value = "5")]
+    interval: u64,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    json: usize,
+    nmea: usize,
+    errors: usize,
+    last_fix: Option<(f64, f64)>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    let json = GpsdClient::connect(args.json)
+        .await
+        .unwrap()
+        .stream(StreamOptions::json())
+        .await
+        .unwrap();
+    let nmea = GpsdClient::connect(args.nmea)
+        .await
+        .unwrap()
+        .stream(StreamOptions::nmea())
+        .await
+        .unwrap();
+
+    let mut merged = merge(json, nmea);
+    let mut stats = Stats::default();
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval));
+
+    loop {
+        tokio::select! {
+            item = merged.next() => match item {
+                Some(Merged::First(Ok(ResponseMessage::Tpv(tpv)))) => {
+                    stats.json += 1;
+                    if let (Some(lat), Some(lon)) = (tpv.lat, tpv.lon) {
+                        stats.last_fix = Some((lat, lon));
+                    }
+                }
+                Some(Merged::First(Ok(_))) => stats.json += 1,
+                Some(Merged::Second(Ok(sentence))) => {
+                    stats.nmea += 1;
+                    if sentence.as_str().and_then(|s| s.get(3..6)) == Some("GGA") {
+                        println!("[nmea] {sentence}");
+                    }
+                }
+                Some(Merged::First(Err(e)) | Merged::Second(Err(e))) => {
+                    stats.errors += 1;
+                    eprintln!("Error receiving message: {e}");
+                }
+                None => {
+                    eprintln!("Both streams ended");
+                    return;
+                }
+            },
+            _ = ticker.tick() => {
+                println!(
+                    "[stats] json: {} msgs, nmea: {} sentences, errors: {}, last fix: {:?}",
+                    stats.json, stats.nmea, stats.errors, stats.last_fix
+                );
+            }
+        }
+    }
+}
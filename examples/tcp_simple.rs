@@ -2,7 +2,6 @@ use std::net::IpAddr;
 
 use clap::Parser;
 
-use futures::StreamExt;
 use gpsd_json::{
     client::{GpsdClient, StreamOptions},
     protocol::v3::ResponseMessage,
@@ -0,0 +1,195 @@
+//! Conversions between horizontal accuracy conventions
+//!
+//! Receivers and specifications state horizontal accuracy as radii of
+//! different confidence, and comparing them without conversion is a common
+//! source of errors. The conversions here assume a circular normal error
+//! distribution with the same standard deviation `σ` along each axis, under
+//! which the radial error follows a Rayleigh distribution:
+//!
+//! | Convention         | Radius           | Probability |
+//! |--------------------|------------------|-------------|
+//! | [`Drms`] (1-sigma) | `√2 · σ`         | ≈ 63 %      |
+//! | [`Cep50`]          | `√(2 ln 2) · σ`  | 50 %        |
+//! | [`R95`]            | `√(2 ln 20) · σ` | 95 %        |
+//! | [`TwoDrms`]        | `2√2 · σ`        | ≈ 98 %      |
+//!
+//! GPSD's `eph` is taken as the 1-sigma radius ([`Drms`]). For elliptical
+//! errors, as reported in GST, the factors are approximations.
+//!
+//! [`Drms`]: crate::accuracy::Confidence::Drms
+//! [`Cep50`]: crate::accuracy::Confidence::Cep50
+//! [`R95`]: crate::accuracy::Confidence::R95
+//! [`TwoDrms`]: crate::accuracy::Confidence::TwoDrms
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::accuracy::{Accuracy, Confidence, convert};
+//!
+//! // A datasheet states 2.0 m CEP50; compare with an eph of 2.5 m
+//! let spec = Accuracy::new(2.0, Confidence::Cep50);
+//! let fix = Accuracy::from_eph(2.5);
+//! assert!(fix.cep50() > spec.cep50());
+//!
+//! assert!((convert(1.0, Confidence::Drms, Confidence::TwoDrms) - 2.0).abs() < 1e-12);
+//! ```
+
+use crate::protocol::v3::response::Tpv;
+
+/// Confidence convention of a horizontal accuracy radius
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Confidence {
+    /// Distance root mean square, the 1-sigma radius `√(σx² + σy²)`
+    Drms,
+    /// Circular error probable, the radius containing 50 % of the fixes
+    Cep50,
+    /// Radius containing 95 % of the fixes
+    R95,
+    /// Twice the distance root mean square
+    TwoDrms,
+}
+
+impl Confidence {
+    /// Returns the radius of this convention for a 1-sigma radius of one
+    pub fn drms_factor(self) -> f64 {
+        match self {
+            Confidence::Drms => 1.0,
+            // √(2 ln 2) / √2
+            Confidence::Cep50 => std::f64::consts::LN_2.sqrt(),
+            // √(2 ln 20) / √2
+            Confidence::R95 => 20f64.ln().sqrt(),
+            Confidence::TwoDrms => 2.0,
+        }
+    }
+}
+
+/// Converts a radius between confidence conventions
+pub fn convert(radius: f64, from: Confidence, to: Confidence) -> f64 {
+    radius / from.drms_factor() * to.drms_factor()
+}
+
+/// Converts a 1-sigma radius, such as `eph`, to CEP50
+pub fn drms_to_cep50(drms: f64) -> f64 {
+    convert(drms, Confidence::Drms, Confidence::Cep50)
+}
+
+/// Converts a 1-sigma radius, such as `eph`, to R95
+pub fn drms_to_r95(drms: f64) -> f64 {
+    convert(drms, Confidence::Drms, Confidence::R95)
+}
+
+/// Converts a 1-sigma radius, such as `eph`, to 2DRMS
+pub fn drms_to_2drms(drms: f64) -> f64 {
+    convert(drms, Confidence::Drms, Confidence::TwoDrms)
+}
+
+/// Converts a CEP50 radius to the 1-sigma radius
+pub fn cep50_to_drms(cep50: f64) -> f64 {
+    convert(cep50, Confidence::Cep50, Confidence::Drms)
+}
+
+/// Converts an R95 radius to the 1-sigma radius
+pub fn r95_to_drms(r95: f64) -> f64 {
+    convert(r95, Confidence::R95, Confidence::Drms)
+}
+
+/// Converts a 2DRMS radius to the 1-sigma radius
+pub fn two_drms_to_drms(two_drms: f64) -> f64 {
+    convert(two_drms, Confidence::TwoDrms, Confidence::Drms)
+}
+
+/// Horizontal accuracy radius in meters with its confidence convention
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accuracy {
+    radius: f64,
+    confidence: Confidence,
+}
+
+impl Accuracy {
+    /// Creates an accuracy of a radius in meters
+    pub fn new(radius: f64, confidence: Confidence) -> Self {
+        Accuracy { radius, confidence }
+    }
+
+    /// Creates an accuracy of GPSD's `eph`, taken as the 1-sigma radius
+    pub fn from_eph(eph: f64) -> Self {
+        Accuracy::new(eph, Confidence::Drms)
+    }
+
+    /// Returns the accuracy of the `eph` of a TPV report, if present
+    pub fn from_tpv(tpv: &Tpv) -> Option<Self> {
+        tpv.eph.map(Accuracy::from_eph)
+    }
+
+    /// Returns the radius in meters
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns the confidence convention of the radius
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    /// Returns the accuracy in another convention
+    pub fn to(&self, confidence: Confidence) -> Accuracy {
+        Accuracy::new(
+            convert(self.radius, self.confidence, confidence),
+            confidence,
+        )
+    }
+
+    /// Returns the 1-sigma radius in meters
+    pub fn drms(&self) -> f64 {
+        self.to(Confidence::Drms).radius
+    }
+
+    /// Returns the CEP50 radius in meters
+    pub fn cep50(&self) -> f64 {
+        self.to(Confidence::Cep50).radius
+    }
+
+    /// Returns the R95 radius in meters
+    pub fn r95(&self) -> f64 {
+        self.to(Confidence::R95).radius
+    }
+
+    /// Returns the 2DRMS radius in meters
+    pub fn two_drms(&self) -> f64 {
+        self.to(Confidence::TwoDrms).radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accuracy_conversions() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-4;
+
+        // Per-axis sigma of one
+        let sigma = Accuracy::new(std::f64::consts::SQRT_2, Confidence::Drms);
+        assert!(close(sigma.cep50(), 1.1774));
+        assert!(close(sigma.r95(), 2.4477));
+        assert!(close(sigma.two_drms(), 2.8284));
+
+        let r95 = Accuracy::new(5.0, Confidence::R95);
+        assert!(close(
+            r95.to(Confidence::Cep50).to(Confidence::R95).radius(),
+            5.0
+        ));
+        assert!(close(r95_to_drms(5.0), r95.drms()));
+        assert!(close(drms_to_r95(r95_to_drms(5.0)), 5.0));
+        assert!(close(cep50_to_drms(drms_to_cep50(3.0)), 3.0));
+        assert!(close(two_drms_to_drms(drms_to_2drms(3.0)), 3.0));
+
+        let msg = serde_json::from_str(crate::fixtures::TPV).unwrap();
+        let crate::protocol::v3::response::Message::Tpv(tpv) = msg else {
+            unreachable!()
+        };
+        let accuracy = Accuracy::from_tpv(&tpv).unwrap();
+        assert_eq!(accuracy.confidence(), Confidence::Drms);
+        assert_eq!(accuracy.radius(), 5.1);
+    }
+}
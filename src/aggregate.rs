@@ -0,0 +1,241 @@
+//! Merging of TPV, SKY and GST reports into one fix per epoch
+//!
+//! GPSD reports the solution of each measurement epoch in several
+//! messages: a TPV with position and velocity, then typically a SKY with
+//! the satellites and DOPs and a GST with error statistics. A
+//! [`FixAggregator`] correlates these reports per device and per epoch and
+//! combines them into a [`GpsFix`], similar to the `gps_data_t` structure
+//! filled by libgps.
+//!
+//! Each TPV starts a new epoch of its device. SKY and GST reports join the
+//! current epoch if they carry its time or no time at all; reports of
+//! other epochs are dropped. An epoch is complete when the next TPV of the
+//! device arrives, so fixes are yielded one epoch late. Use
+//! [`FixAggregator::fix`] to inspect the epoch in progress.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::aggregate::FixAggregator;
+//! use gpsd_json::client::{StreamOptions, blocking::GpsdClient};
+//!
+//! let client = GpsdClient::connect("127.0.0.1:2947").unwrap();
+//! let mut aggregator = FixAggregator::new();
+//! for msg in client.stream(StreamOptions::json()).unwrap() {
+//!     if let Some(fix) = aggregator.update(&msg.unwrap()) {
+//!         println!("{:?} {:?} {:?}", fix.mode, fix.position, fix.satellites_used);
+//!     }
+//! }
+//! ```
+//!
+//! [`FixAggregator`]: crate::aggregate::FixAggregator
+//! [`FixAggregator::fix`]: crate::aggregate::FixAggregator::fix
+//! [`GpsFix`]: crate::aggregate::GpsFix
+
+use std::collections::BTreeMap;
+
+use crate::{
+    Timestamp,
+    position::Position,
+    protocol::v3::{
+        response::{Gst, Message, Sky, Tpv},
+        types::{Dop, FixMode},
+    },
+};
+
+/// Velocity of a fix
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Velocity {
+    /// Speed over ground in meters per second
+    pub speed: Option<f64>,
+    /// Course over ground in degrees from true north
+    pub track: Option<f64>,
+    /// Climb rate in meters per second, positive upwards
+    pub climb: Option<f64>,
+}
+
+/// Reports of one epoch of a device, combined
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpsFix {
+    /// Device that reported the epoch
+    pub device: Option<String>,
+    /// Time of the epoch
+    pub time: Option<Timestamp>,
+    /// Mode of the fix
+    pub mode: FixMode,
+    /// Position with its accuracy, if the TPV has coordinates
+    pub position: Option<Position>,
+    /// Velocity of the TPV
+    pub velocity: Velocity,
+    /// Dilutions of precision of the SKY report
+    pub dop: Option<Dop>,
+    /// Error statistics of the GST report
+    pub error_stats: Option<Gst>,
+    /// Number of satellites used in the solution, from the SKY report
+    pub satellites_used: Option<usize>,
+}
+
+impl GpsFix {
+    fn new(epoch: &Epoch) -> Self {
+        let tpv = &epoch.tpv;
+        GpsFix {
+            device: tpv.device.clone(),
+            time: tpv.time,
+            mode: tpv.mode,
+            position: Position::from_tpv(tpv),
+            velocity: Velocity {
+                speed: tpv.speed,
+                track: tpv.track,
+                climb: tpv.climb,
+            },
+            dop: epoch.sky.as_ref().map(|sky| sky.dop.clone()),
+            error_stats: epoch.gst.clone(),
+            satellites_used: epoch.sky.as_ref().map(|sky| {
+                sky.u_sat
+                    .and_then(|used| usize::try_from(used).ok())
+                    .unwrap_or_else(|| sky.satellites.iter().filter(|sat| sat.used).count())
+            }),
+        }
+    }
+}
+
+/// Reports of the epoch in progress of a device
+#[derive(Debug, Clone)]
+struct Epoch {
+    tpv: Tpv,
+    sky: Option<Sky>,
+    gst: Option<Gst>,
+}
+
+impl Epoch {
+    /// Returns true if a report of the given time belongs to this epoch
+    #[cfg(any(feature = "msg-sky", feature = "msg-gst"))]
+    fn contains(&self, time: Option<Timestamp>) -> bool {
+        match (self.tpv.time, time) {
+            (Some(epoch), Some(time)) => epoch == time,
+            _ => true,
+        }
+    }
+}
+
+/// Correlation of reports into one fix per device and epoch
+#[derive(Debug, Clone, Default)]
+pub struct FixAggregator {
+    epochs: BTreeMap<String, Epoch>,
+}
+
+impl FixAggregator {
+    /// Creates an aggregator without epochs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes a message
+    ///
+    /// Returns the fix of the previous epoch of the device once its next
+    /// TPV arrives. Messages of other classes are ignored.
+    pub fn update(&mut self, message: &Message) -> Option<GpsFix> {
+        let device = message.device().unwrap_or_default();
+        match message {
+            Message::Tpv(tpv) => {
+                let epoch = Epoch {
+                    tpv: tpv.clone(),
+                    sky: None,
+                    gst: None,
+                };
+                return self
+                    .epochs
+                    .insert(device.to_string(), epoch)
+                    .map(|done| GpsFix::new(&done));
+            }
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => {
+                if let Some(epoch) = self.current(device, sky.time) {
+                    epoch.sky = Some(sky.clone());
+                }
+            }
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(gst) => {
+                if let Some(epoch) = self.current(device, gst.time) {
+                    epoch.gst = Some(gst.clone());
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Returns the fix of the epoch in progress of a device
+    ///
+    /// Reports without a device are kept under the empty path.
+    pub fn fix(&self, device: &str) -> Option<GpsFix> {
+        self.epochs.get(device).map(GpsFix::new)
+    }
+
+    /// Returns the fixes of the epochs in progress and forgets them, e.g.
+    /// at the end of a log
+    pub fn take_fixes(&mut self) -> Vec<GpsFix> {
+        std::mem::take(&mut self.epochs)
+            .values()
+            .map(GpsFix::new)
+            .collect()
+    }
+
+    /// Returns the epoch in progress of a device if a report of the given
+    /// time belongs to it
+    #[cfg(any(feature = "msg-sky", feature = "msg-gst"))]
+    fn current(&mut self, device: &str, time: Option<Timestamp>) -> Option<&mut Epoch> {
+        self.epochs
+            .get_mut(device)
+            .filter(|epoch| epoch.contains(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_fix_aggregator() {
+        let parse = |json| serde_json::from_str::<Message>(json).unwrap();
+        let mut aggregator = FixAggregator::new();
+
+        // SKY without a preceding TPV has no epoch to join
+        assert!(aggregator.update(&parse(fixtures::SKY)).is_none());
+        assert!(aggregator.fix("/dev/ttyACM0").is_none());
+
+        assert!(aggregator.update(&parse(fixtures::TPV)).is_none());
+        assert!(aggregator.update(&parse(fixtures::SKY)).is_none());
+        assert!(aggregator.update(&parse(fixtures::GST)).is_none());
+        let current = aggregator.fix("/dev/ttyACM0").unwrap();
+        assert_eq!(
+            current.satellites_used,
+            cfg!(feature = "msg-sky").then_some(2)
+        );
+
+        // The next epoch completes the first one
+        let fix = aggregator.update(&parse(fixtures::TPV_NO_FIX)).unwrap();
+        assert_eq!(fix, current);
+        assert_eq!(fix.mode, FixMode::Fix3D);
+        assert_eq!(fix.position.unwrap().lat, 35.681236);
+        assert_eq!(fix.velocity.speed, Some(0.012));
+        assert_eq!(
+            fix.dop.and_then(|dop| dop.h),
+            cfg!(feature = "msg-sky").then_some(0.99)
+        );
+        assert_eq!(
+            fix.error_stats.and_then(|gst| gst.rms),
+            cfg!(feature = "msg-gst").then_some(1.8)
+        );
+
+        // SKY of another epoch is dropped
+        assert!(aggregator.update(&parse(fixtures::SKY)).is_none());
+        let fixes = aggregator.take_fixes();
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].mode, FixMode::NoFix);
+        assert!(fixes[0].position.is_none());
+        assert!(fixes[0].dop.is_none());
+        assert!(aggregator.take_fixes().is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+//! Compact seekable archive of received lines
+//!
+//! Requires the `archive` feature. Month-long captures stored as JSON lines
+//! grow large and can only be searched by reading them from the start. An
+//! archive stores every received line with its receive time as a
+//! length-prefixed CBOR frame. Frames are grouped into blocks compressed
+//! independently with zstd, and a footer indexes the blocks by time, so a
+//! reader can [seek](crate::archive::ArchiveReader::seek) to any point of a capture by
+//! decompressing a single block.
+//!
+//! # Layout
+//!
+//! All integers are little-endian.
+//!
+//! - Header: the magic `GPSDARC` followed by the format version byte
+//! - Blocks: `u32` compressed length, then a zstd frame holding the
+//!   block's frames, each a `u32` length followed by the CBOR frame
+//! - Index: CBOR array of the block entries of [`BlockInfo`]
+//! - Trailer: `u64` offset of the index, then the magic `GPSDIDX` followed
+//!   by the format version byte
+//!
+//! Archives can be replayed by a mock server with
+//! [`Scenario::then_archive`](crate::testing::mock::Scenario::then_archive)
+//! when the `testing` feature is enabled as well.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::archive::{ArchiveReader, ArchiveWriter};
+//!
+//! # fn example() -> gpsd_json::Result<()> {
+//! let mut writer = ArchiveWriter::create("capture.gpsdarc")?;
+//! writer.append(br#"{"class":"TPV","mode":3,"lat":35.0,"lon":139.0}"#)?;
+//! writer.finish()?;
+//!
+//! let mut reader = ArchiveReader::open("capture.gpsdarc")?;
+//! reader.seek("2025-01-01T12:00:00Z".parse().unwrap())?;
+//! for frame in reader {
+//!     let frame = frame?;
+//!     println!("{} {}", frame.received, String::from_utf8_lossy(&frame.line));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`BlockInfo`]: crate::archive::BlockInfo
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, Timestamp, error::GpsdJsonError};
+
+/// Version of the archive format
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Magic at the start of an archive, followed by the version
+const HEADER_MAGIC: &[u8; 7] = b"GPSDARC";
+
+/// Magic at the end of an archive, followed by the version
+const TRAILER_MAGIC: &[u8; 7] = b"GPSDIDX";
+
+/// Length of the trailer: index offset, magic and version
+const TRAILER_LEN: u64 = 16;
+
+/// Line received at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Receive time
+    #[serde(rename = "t")]
+    pub received: Timestamp,
+    /// Line without its line ending
+    #[serde(rename = "l", with = "serde_bytes")]
+    pub line: Vec<u8>,
+}
+
+/// Index entry of a block of frames
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    /// Offset of the block from the start of the archive
+    pub offset: u64,
+    /// Number of frames in the block
+    pub frames: u32,
+    /// Receive time of the first frame
+    pub first: Timestamp,
+    /// Receive time of the last frame
+    pub last: Timestamp,
+}
+
+/// Writes lines to an archive
+///
+/// The index is written by [`finish`](Self::finish); archives of writers
+/// dropped without finishing cannot be read.
+#[derive(Debug)]
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    offset: u64,
+    block: Vec<u8>,
+    current: Option<BlockInfo>,
+    blocks: Vec<BlockInfo>,
+    block_frames: u32,
+    level: i32,
+}
+
+impl ArchiveWriter<BufWriter<File>> {
+    /// Creates an archive file, replacing an existing one
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::create(path).map_err(GpsdJsonError::IoError)?;
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Starts an archive with blocks of 4096 frames at zstd level 3
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer
+            .write_all(HEADER_MAGIC)
+            .and_then(|_| writer.write_all(&[ARCHIVE_VERSION]))
+            .map_err(GpsdJsonError::IoError)?;
+        Ok(ArchiveWriter {
+            writer,
+            offset: HEADER_MAGIC.len() as u64 + 1,
+            block: Vec::new(),
+            current: None,
+            blocks: Vec::new(),
+            block_frames: 4096,
+            level: 3,
+        })
+    }
+
+    /// Sets the number of frames per block
+    ///
+    /// Smaller blocks make seeking cheaper and compress worse.
+    pub fn block_frames(mut self, frames: u32) -> Self {
+        self.block_frames = frames.max(1);
+        self
+    }
+
+    /// Sets the zstd compression level
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Appends a line received now
+    ///
+    /// The line should not contain its line terminator.
+    pub fn append(&mut self, line: &[u8]) -> Result<()> {
+        self.append_at(line, Utc::now())
+    }
+
+    /// Appends a line with an explicit receive time
+    ///
+    /// Lines should be appended in the order they were received, as the
+    /// index assumes increasing receive times.
+    pub fn append_at(&mut self, line: &[u8], received: Timestamp) -> Result<()> {
+        let frame = Frame {
+            received,
+            line: line.to_vec(),
+        };
+        let mut encoded = Vec::new();
+        ciborium::into_writer(&frame, &mut encoded).map_err(|e| invalid_data(e.to_string()))?;
+        self.block
+            .extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+        self.block.extend_from_slice(&encoded);
+
+        let offset = self.offset;
+        let current = self.current.get_or_insert(BlockInfo {
+            offset,
+            frames: 0,
+            first: received,
+            last: received,
+        });
+        current.frames += 1;
+        current.last = received;
+        if current.frames >= self.block_frames {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the pending frames as a block
+    fn write_block(&mut self) -> Result<()> {
+        let Some(info) = self.current.take() else {
+            return Ok(());
+        };
+        let compressed =
+            zstd::bulk::compress(&self.block, self.level).map_err(GpsdJsonError::IoError)?;
+        self.writer
+            .write_all(&(compressed.len() as u32).to_le_bytes())
+            .and_then(|_| self.writer.write_all(&compressed))
+            .map_err(GpsdJsonError::IoError)?;
+        self.offset += 4 + compressed.len() as u64;
+        self.block.clear();
+        self.blocks.push(info);
+        Ok(())
+    }
+
+    /// Writes the pending frames and the index, returning the writer
+    pub fn finish(mut self) -> Result<W> {
+        self.write_block()?;
+
+        let mut index = Vec::new();
+        ciborium::into_writer(&self.blocks, &mut index).map_err(|e| invalid_data(e.to_string()))?;
+        self.writer
+            .write_all(&index)
+            .and_then(|_| self.writer.write_all(&self.offset.to_le_bytes()))
+            .and_then(|_| self.writer.write_all(TRAILER_MAGIC))
+            .and_then(|_| self.writer.write_all(&[ARCHIVE_VERSION]))
+            .and_then(|_| self.writer.flush())
+            .map_err(GpsdJsonError::IoError)?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the frames of an archive in order
+///
+/// Iterating yields the frames from the current position, initially the
+/// start of the archive.
+#[derive(Debug)]
+pub struct ArchiveReader<R> {
+    reader: R,
+    blocks: Vec<BlockInfo>,
+    next_block: usize,
+    pending: VecDeque<Frame>,
+}
+
+impl ArchiveReader<BufReader<File>> {
+    /// Opens an archive file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path).map_err(GpsdJsonError::IoError)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// Reads the header and the index of an archive
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 8];
+        reader
+            .read_exact(&mut header)
+            .map_err(GpsdJsonError::IoError)?;
+        check_magic(&header, HEADER_MAGIC)?;
+
+        let mut trailer = [0u8; TRAILER_LEN as usize];
+        let end = reader
+            .seek(SeekFrom::End(-(TRAILER_LEN as i64)))
+            .and_then(|end| reader.read_exact(&mut trailer).map(|_| end))
+            .map_err(GpsdJsonError::IoError)?;
+        check_magic(&trailer[8..], TRAILER_MAGIC)?;
+
+        let index_offset = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+        let index_len = end
+            .checked_sub(index_offset)
+            .ok_or(GpsdJsonError::ProtocolError("Invalid archive index offset"))?;
+        let mut index = vec![0u8; index_len as usize];
+        reader
+            .seek(SeekFrom::Start(index_offset))
+            .and_then(|_| reader.read_exact(&mut index))
+            .map_err(GpsdJsonError::IoError)?;
+        let blocks =
+            ciborium::from_reader(index.as_slice()).map_err(|e| invalid_data(e.to_string()))?;
+
+        Ok(ArchiveReader {
+            reader,
+            blocks,
+            next_block: 0,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Returns the index of the blocks
+    pub fn blocks(&self) -> &[BlockInfo] {
+        &self.blocks
+    }
+
+    /// Returns the total number of frames
+    pub fn frames(&self) -> u64 {
+        self.blocks
+            .iter()
+            .map(|block| u64::from(block.frames))
+            .sum()
+    }
+
+    /// Moves to the first frame received at or after a time
+    pub fn seek(&mut self, time: Timestamp) -> Result<()> {
+        let block = self
+            .blocks
+            .iter()
+            .position(|block| block.last >= time)
+            .unwrap_or(self.blocks.len());
+        self.next_block = block;
+        self.pending.clear();
+        if block < self.blocks.len() {
+            self.read_block()?;
+            while self
+                .pending
+                .front()
+                .is_some_and(|frame| frame.received < time)
+            {
+                self.pending.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the next block into the pending frames
+    fn read_block(&mut self) -> Result<()> {
+        let info = &self.blocks[self.next_block];
+        self.next_block += 1;
+
+        let mut len = [0u8; 4];
+        self.reader
+            .seek(SeekFrom::Start(info.offset))
+            .and_then(|_| self.reader.read_exact(&mut len))
+            .map_err(GpsdJsonError::IoError)?;
+        let mut compressed = vec![0u8; u32::from_le_bytes(len) as usize];
+        self.reader
+            .read_exact(&mut compressed)
+            .map_err(GpsdJsonError::IoError)?;
+        let block =
+            zstd::stream::decode_all(compressed.as_slice()).map_err(GpsdJsonError::IoError)?;
+
+        let mut rest = block.as_slice();
+        while rest.len() >= 4 {
+            let (len, tail) = rest.split_at(4);
+            let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+            if tail.len() < len {
+                return Err(GpsdJsonError::ProtocolError("Truncated archive frame"));
+            }
+            let (frame, tail) = tail.split_at(len);
+            self.pending
+                .push_back(ciborium::from_reader(frame).map_err(|e| invalid_data(e.to_string()))?);
+            rest = tail;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Iterator for ArchiveReader<R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.next_block >= self.blocks.len() {
+                return None;
+            }
+            if let Err(e) = self.read_block() {
+                // Skip the rest of the archive after a corrupt block
+                self.next_block = self.blocks.len();
+                return Some(Err(e));
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Checks the magic and version of the header or trailer
+fn check_magic(bytes: &[u8], magic: &[u8; 7]) -> Result<()> {
+    if &bytes[..7] != magic {
+        return Err(GpsdJsonError::ProtocolError("Not a GPSD archive"));
+    }
+    if bytes[7] != ARCHIVE_VERSION {
+        return Err(GpsdJsonError::ProtocolError("Unsupported archive version"));
+    }
+    Ok(())
+}
+
+fn invalid_data(message: String) -> GpsdJsonError {
+    GpsdJsonError::IoError(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_archive_round_trip_and_seek() {
+        let start: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap().block_frames(2);
+        for i in 0..5 {
+            let line = format!(r#"{{"class":"TPV","mode":{i}}}"#);
+            writer
+                .append_at(line.as_bytes(), start + chrono::Duration::seconds(i))
+                .unwrap();
+        }
+        let archive = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(Cursor::new(archive)).unwrap();
+        assert_eq!(reader.blocks().len(), 3);
+        assert_eq!(reader.frames(), 5);
+
+        reader.seek(start + chrono::Duration::seconds(3)).unwrap();
+        let rest: Vec<Frame> = reader.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].line, br#"{"class":"TPV","mode":3}"#);
+        assert_eq!(rest[1].received, start + chrono::Duration::seconds(4));
+
+        reader.seek(start).unwrap();
+        assert_eq!(reader.count(), 5);
+    }
+}
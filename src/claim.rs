@@ -0,0 +1,152 @@
+//! Heuristic checks before claiming a device for exclusive raw access
+//!
+//! Tooling such as firmware updaters needs the receiver to itself: a
+//! driver of GPSD probing the device or another client watching it in raw
+//! or NMEA mode can corrupt the transfer. GPSD does not report the watches
+//! of other clients, so [`check_claim`] infers conflicts from the state of
+//! the device in a DEVICES reply and returns them as warnings.
+//!
+//! Check before starting your own watch on the device, since your own
+//! watch activates it as well.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{claim::check_claim, protocol::v3::response::DeviceList};
+//!
+//! let devices: DeviceList = serde_json::from_str(
+//!     r#"{"class":"DEVICES","devices":[{"class":"DEVICE","path":"/dev/ttyACM0","driver":"u-blox","activated":"2025-01-01T00:00:00.000Z","flags":1,"native":1}]}"#,
+//! )?;
+//!
+//! let check = check_claim(&devices, "/dev/ttyACM0");
+//! for warning in &check.warnings {
+//!     eprintln!("{}: {warning}", check.path);
+//! }
+//! assert!(!check.is_clear());
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! [`check_claim`]: crate::claim::check_claim
+
+use crate::{
+    Timestamp,
+    protocol::v3::{
+        response::DeviceList,
+        types::{Device, PropertyFlags},
+    },
+};
+
+/// Sign that a device may be in use by GPSD or another client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimWarning {
+    /// The device is not known to GPSD, so it may be opened by a process
+    /// other than GPSD
+    NotListed,
+    /// The device is open, which GPSD does while any client watches it
+    Activated(Option<Timestamp>),
+    /// A driver of GPSD has identified the device and may send it
+    /// configuration commands
+    DriverActive(String),
+    /// The device was switched to binary mode, e.g. by a driver or by a
+    /// client using raw access
+    BinaryMode,
+    /// GPSD is decoding data from the device
+    DataSeen(PropertyFlags),
+}
+
+impl core::fmt::Display for ClaimWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClaimWarning::NotListed => write!(f, "device is not managed by GPSD"),
+            ClaimWarning::Activated(Some(time)) => write!(f, "device is open since {time}"),
+            ClaimWarning::Activated(None) => write!(f, "device is open"),
+            ClaimWarning::DriverActive(driver) => write!(f, "driver {driver} is active"),
+            ClaimWarning::BinaryMode => write!(f, "device is in binary mode"),
+            ClaimWarning::DataSeen(flags) => write!(f, "data is decoded ({flags:?})"),
+        }
+    }
+}
+
+/// Result of checking a device before claiming it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimCheck {
+    /// Path of the checked device
+    pub path: String,
+    /// State of the device as reported by GPSD, if listed
+    pub device: Option<Device>,
+    /// Signs of conflicting use, empty if none were found
+    pub warnings: Vec<ClaimWarning>,
+}
+
+impl ClaimCheck {
+    /// Returns `true` if no signs of conflicting use were found
+    pub fn is_clear(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Checks a device of a DEVICES reply for signs of conflicting use
+pub fn check_claim(devices: &DeviceList, path: &str) -> ClaimCheck {
+    let device = devices
+        .devices
+        .iter()
+        .find(|device| device.path.as_deref() == Some(path))
+        .cloned();
+
+    let mut warnings = Vec::new();
+    match &device {
+        None => warnings.push(ClaimWarning::NotListed),
+        Some(device) => {
+            if device.activated.is_some() {
+                warnings.push(ClaimWarning::Activated(device.activated));
+            }
+            if let Some(driver) = &device.driver {
+                warnings.push(ClaimWarning::DriverActive(driver.clone()));
+            }
+            if device.native == Some(1) {
+                warnings.push(ClaimWarning::BinaryMode);
+            }
+            if let Some(flags) = device.flags.clone().filter(|flags| !flags.is_empty()) {
+                warnings.push(ClaimWarning::DataSeen(flags));
+            }
+        }
+    }
+
+    ClaimCheck {
+        path: path.to_string(),
+        device,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_check_claim() {
+        let devices: DeviceList = serde_json::from_str(fixtures::DEVICES).unwrap();
+
+        let check = check_claim(&devices, "/dev/ttyACM0");
+        assert_eq!(
+            check.warnings,
+            [
+                ClaimWarning::Activated(Some("2025-01-01T00:00:00Z".parse().unwrap())),
+                ClaimWarning::DriverActive("u-blox".to_string()),
+                ClaimWarning::BinaryMode,
+                ClaimWarning::DataSeen(PropertyFlags::SEEN_GPS),
+            ]
+        );
+
+        let idle: DeviceList = serde_json::from_str(
+            r#"{"class":"DEVICES","devices":[{"class":"DEVICE","path":"/dev/ttyUSB0","native":0}]}"#,
+        )
+        .unwrap();
+        assert!(check_claim(&idle, "/dev/ttyUSB0").is_clear());
+        assert_eq!(
+            check_claim(&idle, "/dev/ttyACM0").warnings,
+            [ClaimWarning::NotListed]
+        );
+    }
+}
@@ -10,7 +10,6 @@
 //!
 //! ```no_run
 //! use gpsd_json::client::{GpsdClient, StreamOptions};
-//! use futures::StreamExt;
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 //! // Connect to GPSD asynchronously
@@ -32,11 +31,54 @@
 use crate::{
     Result,
     error::GpsdJsonError,
-    protocol::{GpsdJsonDecodeAsync, GpsdJsonEncodeAsync, GpsdJsonRequest, GpsdJsonResponse, v3},
+    protocol::{
+        GpsdJsonDecodeAsync, GpsdJsonEncodeAsync, GpsdJsonRequest, GpsdJsonResponse, ParseMode,
+        decode_line,
+        machine::{check_version, parse_version},
+        strip_line_ending, v3,
+    },
 };
 
+/// Connection helpers for the async-std runtime
+#[cfg(all(feature = "async-std", feature = "net", feature = "proto-v3"))]
+pub mod async_std;
 /// Blocking (synchronous) client implementation
 pub mod blocking;
+/// Latest fix of each device, kept up to date in the background
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub mod cache;
+/// Per-class callbacks as an alternative to matching stream messages
+#[cfg(feature = "proto-v3")]
+pub mod callbacks;
+/// Driver control strings sent through GPSD
+pub mod control;
+/// Filtered subscriptions sharing one connection
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub mod fanout;
+/// Device configurations applied to matching receivers on hotplug
+#[cfg(feature = "proto-v3")]
+pub mod profile;
+/// Rate-limited reconnection for flapping networks
+pub mod reconnect;
+/// Background connection publishing messages to subscribers
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub mod service;
+/// Connection helpers for the smol runtime
+#[cfg(all(feature = "smol", feature = "net", feature = "proto-v3"))]
+pub mod smol;
+/// Supervised connections reporting connection state changes
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub mod supervisor;
+/// Connector trait and builder for opening clients on any transport
+pub mod transport;
+/// Copies of the raw traffic of a connection for debugging
+pub mod wiretap;
+
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub use self::reconnect::async_client::ReconnectingClient;
+
+/// Default TCP port GPSD listens on
+pub const DEFAULT_PORT: u16 = 2947;
 
 /// Trait defining a GPSD protocol version implementation
 ///
@@ -51,26 +93,43 @@ pub trait GpsdJsonProtocol: Send + Sync {
     /// Request message type for this protocol version
     type Request: GpsdJsonRequest + Send + Sync;
     /// Response message type for this protocol version
-    type Response: GpsdJsonResponse + Send + Sync;
+    type Response: GpsdJsonResponse + Send + Sync + Unpin;
+
+    /// Returns true if this implementation can talk to a server announcing
+    /// the given protocol version in its VERSION greeting
+    ///
+    /// By default the major version must match exactly and the minor
+    /// version must be greater than or equal to `API_VERSION_MINOR`.
+    fn supports(major: i32, minor: i32) -> bool {
+        major == Self::API_VERSION_MAJOR && minor >= Self::API_VERSION_MINOR
+    }
 }
 
 /// Marker trait for data stream output formats
 ///
 /// This trait is used to distinguish between different output formats
 /// (JSON, NMEA, Raw) at the type level.
-pub trait StreamFormat {}
+pub trait StreamFormat {
+    /// Whether the stream yields messages decoded from JSON rather than
+    /// the lines as received
+    const DECODED: bool = false;
+}
 
 /// JSON format for structured GPS data
 ///
 /// Provides parsed GPS data as JSON objects including TPV (time/position/velocity),
 /// SKY (satellite information), and other message types.
+#[derive(Debug, Clone, Copy)]
 pub struct Json;
-impl StreamFormat for Json {}
+impl StreamFormat for Json {
+    const DECODED: bool = true;
+}
 
 /// NMEA format for raw GPS sentences
 ///
 /// Provides raw NMEA 0183 sentences from the GPS receiver,
 /// such as $GPGGA, $GPRMC, etc.
+#[derive(Debug, Clone, Copy)]
 pub struct Nmea;
 impl StreamFormat for Nmea {}
 
@@ -78,9 +137,224 @@ impl StreamFormat for Nmea {}
 ///
 /// Provides raw binary data from the GPS receiver,
 /// optionally with hex dump formatting.
+#[derive(Debug, Clone, Copy)]
 pub struct Raw;
 impl StreamFormat for Raw {}
 
+/// Capture format for recording all output of a device
+///
+/// Enables JSON and NMEA output at the same time, with timing and PPS
+/// information, and yields every line unparsed. Intended for drive tests
+/// and archival, see the [`recorder`](crate::recorder) module.
+#[derive(Debug, Clone, Copy)]
+pub struct Capture;
+impl StreamFormat for Capture {}
+
+/// Handling of NMEA sentences that are not valid UTF-8
+///
+/// Vendor proprietary sentences may contain bytes outside of ASCII.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Utf8Policy {
+    /// Replace invalid sequences with U+FFFD and yield text
+    #[default]
+    Lossy,
+    /// Yield an error for sentences that are not valid UTF-8
+    Strict,
+    /// Yield every sentence as bytes, unmodified
+    Bytes,
+}
+
+impl Utf8Policy {
+    /// Decodes a received sentence with its line ending removed
+    pub(crate) fn decode(self, line: Vec<u8>) -> Result<NmeaLine> {
+        match self {
+            Utf8Policy::Lossy => Ok(NmeaLine::Text(match String::from_utf8(line) {
+                Ok(text) => text,
+                Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            })),
+            Utf8Policy::Strict => String::from_utf8(line)
+                .map(NmeaLine::Text)
+                .map_err(|e| GpsdJsonError::Utf8Error(e.utf8_error())),
+            Utf8Policy::Bytes => Ok(NmeaLine::Bytes(line)),
+        }
+    }
+}
+
+/// Handling of JSON lines that cannot be parsed
+///
+/// A line is unparsable when it is not valid JSON or UTF-8, or when it is
+/// rejected by the [`ParseMode`] of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseErrorPolicy {
+    /// Yield an error for the line and continue with the next one
+    #[default]
+    Yield,
+    /// Drop the line; the stream counts dropped lines and keeps the error
+    /// of the last one for logging
+    Skip,
+    /// Yield an error for the line and end the stream
+    Abort,
+}
+
+/// Unparsable lines seen by a JSON stream
+#[derive(Debug, Default)]
+pub(crate) struct ParseErrors {
+    policy: ParseErrorPolicy,
+    skipped: u64,
+    last: Option<GpsdJsonError>,
+    aborted: bool,
+}
+
+impl ParseErrors {
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    pub(crate) fn new(policy: ParseErrorPolicy) -> Self {
+        ParseErrors {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true once the stream ended on an unparsable line
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Applies the policy to an item, returning `None` if it is dropped
+    pub(crate) fn filter<T>(&mut self, item: Result<T>) -> Option<Result<T>> {
+        let Err(
+            e @ (GpsdJsonError::SerdeError(_)
+            | GpsdJsonError::Utf8Error(_)
+            | GpsdJsonError::ValidationError(_)
+            | GpsdJsonError::UnknownFields(_)),
+        ) = item
+        else {
+            return Some(item);
+        };
+        match self.policy {
+            ParseErrorPolicy::Yield => Some(Err(e)),
+            ParseErrorPolicy::Skip => {
+                self.skipped += 1;
+                self.last = Some(e);
+                None
+            }
+            ParseErrorPolicy::Abort => {
+                self.aborted = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Line received without its line ending, with the message decoded from it
+pub(crate) type DecodedLine<R> = (Vec<u8>, Result<R>);
+
+/// Data received while waiting for a reply, kept for the stream
+///
+/// JSON streams keep the decoded messages, streams of other formats the
+/// lines as received, e.g. NMEA sentences that do not decode at all.
+#[derive(Debug)]
+pub(crate) struct Pending<R> {
+    decoded: bool,
+    messages: std::collections::VecDeque<Result<R>>,
+    lines: std::collections::VecDeque<Vec<u8>>,
+}
+
+impl<R> Pending<R> {
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    pub(crate) fn new<Format: StreamFormat>() -> Self {
+        Pending {
+            decoded: Format::DECODED,
+            messages: std::collections::VecDeque::new(),
+            lines: std::collections::VecDeque::new(),
+        }
+    }
+
+    /// Keeps a received line, or in JSON streams the message decoded from
+    /// it unless `wanted` rejects the message
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    pub(crate) fn keep(&mut self, line: Vec<u8>, msg: Result<R>, wanted: impl FnOnce(&R) -> bool) {
+        if !self.decoded {
+            self.lines.push_back(line);
+        } else if msg.as_ref().map_or(true, wanted) {
+            self.messages.push_back(msg);
+        }
+    }
+
+    /// Drops the kept messages rejected by `wanted`
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    pub(crate) fn retain(&mut self, mut wanted: impl FnMut(&R) -> bool) {
+        self.messages
+            .retain(|item| item.as_ref().map_or(true, &mut wanted));
+    }
+
+    pub(crate) fn pop_message(&mut self) -> Option<Result<R>> {
+        self.messages.pop_front()
+    }
+
+    pub(crate) fn pop_line(&mut self) -> Option<Vec<u8>> {
+        self.lines.pop_front()
+    }
+
+    /// Returns the number of kept messages and lines
+    pub(crate) fn len(&self) -> usize {
+        self.messages.len() + self.lines.len()
+    }
+
+    /// Returns the bytes allocated by the queues, not counting the lines
+    pub(crate) fn capacity(&self) -> usize {
+        self.messages.capacity() * std::mem::size_of::<Result<R>>()
+            + self.lines.capacity() * std::mem::size_of::<Vec<u8>>()
+    }
+}
+
+/// NMEA sentence received from GPSD, without line ending
+///
+/// Sentences are yielded as text unless the stream is configured with
+/// [`Utf8Policy::Bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NmeaLine {
+    /// Sentence decoded as UTF-8
+    Text(String),
+    /// Sentence as received
+    Bytes(Vec<u8>),
+}
+
+impl NmeaLine {
+    /// Returns the sentence as text, if it is available as text
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            NmeaLine::Text(text) => Some(text),
+            NmeaLine::Bytes(_) => None,
+        }
+    }
+
+    /// Returns the sentence as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            NmeaLine::Text(text) => text.as_bytes(),
+            NmeaLine::Bytes(bytes) => bytes,
+        }
+    }
+
+    /// Converts the sentence into bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            NmeaLine::Text(text) => text.into_bytes(),
+            NmeaLine::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+impl std::fmt::Display for NmeaLine {
+    /// Formats the sentence, replacing invalid UTF-8 sequences with U+FFFD
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NmeaLine::Text(text) => f.write_str(text),
+            NmeaLine::Bytes(bytes) => f.write_str(&String::from_utf8_lossy(bytes)),
+        }
+    }
+}
+
 /// Configuration options for GPS data streams
 ///
 /// This struct allows configuring various aspects of the data stream,
@@ -91,10 +365,63 @@ impl StreamFormat for Raw {}
 #[derive(Debug, Clone)]
 pub struct StreamOptions<F: StreamFormat> {
     inner: v3::types::Watch,
+    devices: Vec<String>,
+    utf8: Utf8Policy,
+    on_parse_error: ParseErrorPolicy,
+    classes: Vec<v3::response::Class>,
     _format: std::marker::PhantomData<F>,
 }
 
 impl<F: StreamFormat> StreamOptions<F> {
+    /// Subscribes to a set of GPS devices
+    ///
+    /// GPSD accepts one device per WATCH command, so the stream sends one
+    /// WATCH with the same settings per device and waits for each
+    /// confirmation. Without devices, the stream watches all devices.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::StreamOptions;
+    /// let opts = StreamOptions::json().devices(["/dev/ttyACM0", "/dev/ttyUSB0"]);
+    /// assert_eq!(opts.watches().len(), 2);
+    /// ```
+    pub fn devices<I, S>(mut self, devices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for device in devices {
+            let device = device.as_ref();
+            if !self.devices.iter().any(|path| path == device) {
+                self.devices.push(device.to_string());
+            }
+        }
+        self
+    }
+
+    /// Returns the devices the stream is scoped to, empty for all devices
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    fn scope(&self) -> Vec<String> {
+        self.watches()
+            .into_iter()
+            .filter_map(|watch| watch.device)
+            .collect()
+    }
+
+    /// Returns the WATCH settings sent to GPSD, one per subscribed device
+    pub fn watches(&self) -> Vec<v3::types::Watch> {
+        if self.devices.is_empty() {
+            return vec![self.inner.clone()];
+        }
+        self.devices
+            .iter()
+            .map(|device| v3::types::Watch {
+                device: Some(device.clone()),
+                ..self.inner.clone()
+            })
+            .collect()
+    }
+
     /// Enables or disables scaled output
     ///
     /// When enabled, GPSD applies scaling to output values.
@@ -114,6 +441,109 @@ impl<F: StreamFormat> StreamOptions<F> {
     }
 }
 
+/// Configuration of the no-data watchdog of a data stream
+///
+/// Some GPSD versions stop streaming after internal device resets while
+/// keeping the socket open. When no data arrives within the timeout, the
+/// watchdog first re-sends the WATCH command of the stream, and fails the
+/// stream once the re-sends are exhausted so that the caller can reconnect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogConfig {
+    /// Time without data after which the watchdog fires
+    pub timeout: std::time::Duration,
+    /// Number of times WATCH is re-sent before the stream fails
+    pub rewatch_attempts: u32,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        WatchdogConfig {
+            timeout: std::time::Duration::from_secs(10),
+            rewatch_attempts: 1,
+        }
+    }
+}
+
+/// Configuration of the keepalive of a data stream
+///
+/// Serial receivers occasionally go silent, and so do half-open TCP
+/// connections. While no data arrives, the stream sends `?VERSION;` every
+/// interval; when neither data nor a reply arrives within the window, the
+/// stream returns [`GpsdJsonError::ConnectionStale`] so that the caller can
+/// reconnect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Time without data after which a ping is sent, and between pings
+    pub interval: std::time::Duration,
+    /// Time without data after which the connection is stale
+    pub window: std::time::Duration,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        KeepaliveConfig {
+            interval: std::time::Duration::from_secs(5),
+            window: std::time::Duration::from_secs(15),
+        }
+    }
+}
+
+/// State of the keepalive of a stream
+#[derive(Debug)]
+pub(crate) struct Keepalive {
+    config: KeepaliveConfig,
+    last_data: std::time::Instant,
+    last_ping: std::time::Instant,
+}
+
+/// Action of the keepalive on a quiet connection
+pub(crate) enum KeepaliveAction {
+    /// Wait for data at most this long before polling again
+    #[cfg_attr(not(all(feature = "proto-v3", feature = "tokio")), allow(dead_code))]
+    Wait(std::time::Duration),
+    /// Send a ping now
+    Ping,
+    /// Give up after this long without data
+    Stale(std::time::Duration),
+}
+
+impl Keepalive {
+    pub(crate) fn new(config: KeepaliveConfig) -> Self {
+        let now = std::time::Instant::now();
+        Keepalive {
+            config,
+            last_data: now,
+            last_ping: now,
+        }
+    }
+
+    /// Records that data arrived
+    pub(crate) fn received(&mut self) {
+        let now = std::time::Instant::now();
+        self.last_data = now;
+        self.last_ping = now;
+    }
+
+    /// Returns what to do while no data arrives
+    pub(crate) fn poll(&mut self) -> KeepaliveAction {
+        let now = std::time::Instant::now();
+        let silence = now.duration_since(self.last_data);
+        if silence >= self.config.window {
+            return KeepaliveAction::Stale(silence);
+        }
+        let next_ping = self.last_ping + self.config.interval;
+        match next_ping.checked_duration_since(now) {
+            Some(wait) if !wait.is_zero() => {
+                KeepaliveAction::Wait(wait.min(self.config.window - silence))
+            }
+            _ => {
+                self.last_ping = now;
+                KeepaliveAction::Ping
+            }
+        }
+    }
+}
+
 impl StreamOptions<Json> {
     /// Creates stream options for JSON format output
     ///
@@ -128,6 +558,10 @@ impl StreamOptions<Json> {
 
         StreamOptions::<Json> {
             inner: opts,
+            devices: Vec::new(),
+            utf8: Utf8Policy::default(),
+            on_parse_error: ParseErrorPolicy::default(),
+            classes: Vec::new(),
             _format: std::marker::PhantomData,
         }
     }
@@ -149,6 +583,66 @@ impl StreamOptions<Json> {
         self.inner.timing = Some(enable);
         self
     }
+
+    /// Yields only messages of the given classes
+    ///
+    /// GPSD has no per-class subscription, so the other messages are still
+    /// received and dropped by the stream. Without classes, the stream
+    /// yields all messages.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::StreamOptions;
+    /// use gpsd_json::protocol::v3::response::Class;
+    ///
+    /// let opts = StreamOptions::json().classes(&[Class::Tpv, Class::Device]);
+    /// ```
+    pub fn classes(mut self, classes: &[v3::response::Class]) -> Self {
+        for class in classes {
+            if !self.classes.contains(class) {
+                self.classes.push(*class);
+            }
+        }
+        self
+    }
+
+    /// Sets the handling of lines that cannot be parsed
+    ///
+    /// Defaults to [`ParseErrorPolicy::Yield`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, ParseErrorPolicy, StreamOptions};
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let opts = StreamOptions::json().on_parse_error(ParseErrorPolicy::Skip);
+    /// let mut stream = client.stream(opts).await?;
+    /// while let Some(msg) = stream.try_next().await? {
+    ///     println!("{msg:?}");
+    /// }
+    /// if let Some(e) = stream.last_parse_error() {
+    ///     eprintln!("skipped {} lines, last: {e}", stream.skipped_lines());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_parse_error(mut self, policy: ParseErrorPolicy) -> Self {
+        self.on_parse_error = policy;
+        self
+    }
+
+    /// Specifies a particular GPS device to stream from
+    ///
+    /// Besides asking GPSD for the device only, the stream drops messages
+    /// of other devices, which GPSD may still send, e.g. to other watches
+    /// of the same connection.
+    ///
+    /// # Arguments
+    /// * `device` - Path to the GPS device (e.g., "/dev/ttyUSB0")
+    pub fn device<S: AsRef<str>>(mut self, device: S) -> Self {
+        self.inner.device = Some(device.as_ref().into());
+        self
+    }
 }
 
 impl StreamOptions<Nmea> {
@@ -165,6 +659,10 @@ impl StreamOptions<Nmea> {
 
         StreamOptions::<Nmea> {
             inner: opts,
+            devices: Vec::new(),
+            utf8: Utf8Policy::default(),
+            on_parse_error: ParseErrorPolicy::default(),
+            classes: Vec::new(),
             _format: std::marker::PhantomData,
         }
     }
@@ -177,6 +675,14 @@ impl StreamOptions<Nmea> {
         self.inner.device = Some(device.as_ref().into());
         self
     }
+
+    /// Sets how sentences that are not valid UTF-8 are handled
+    ///
+    /// Defaults to [`Utf8Policy::Lossy`].
+    pub fn utf8(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
 }
 
 impl StreamOptions<Raw> {
@@ -193,6 +699,10 @@ impl StreamOptions<Raw> {
 
         StreamOptions::<Raw> {
             inner: opts,
+            devices: Vec::new(),
+            utf8: Utf8Policy::default(),
+            on_parse_error: ParseErrorPolicy::default(),
+            classes: Vec::new(),
             _format: std::marker::PhantomData,
         }
     }
@@ -220,432 +730,2477 @@ impl StreamOptions<Raw> {
     }
 }
 
-/// Core implementation of an asynchronous GPSD client
-///
-/// This struct provides the fundamental functionality for asynchronous
-/// communication with a GPSD server. It handles protocol negotiation,
-/// message serialization/deserialization, and maintains the connection state.
-///
-/// # Type Parameters
-/// * `Stream` - The underlying async I/O stream type (e.g., TcpStream)
-/// * `Proto` - The GPSD protocol version implementation
-#[derive(Debug)]
-pub struct GpsdClientCore<Stream, Proto> {
-    reader: futures_util::io::BufReader<Stream>,
-    buf: Vec<u8>,
-    _proto: std::marker::PhantomData<Proto>,
-}
-
-impl<Stream, Proto> GpsdClientCore<Stream, Proto>
-where
-    Proto: GpsdJsonProtocol,
-{
-    /// Opens a new GPSD client connection using the provided async stream
-    ///
-    /// This method initializes the client with the given async I/O stream and
-    /// performs protocol version negotiation with the GPSD server.
-    ///
-    /// # Arguments
-    /// * `stream` - The async I/O stream for communication with GPSD
+impl StreamOptions<Capture> {
+    /// Creates stream options for capturing JSON, NMEA and timing output
     ///
-    /// # Returns
-    /// * `Ok(client)` - Successfully connected and negotiated protocol
-    /// * `Err(_)` - Connection or protocol negotiation failed
-    pub async fn open(stream: Stream) -> Result<Self>
-    where
-        Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
-    {
-        let reader = futures_util::io::BufReader::new(stream);
-        let mut client = GpsdClientCore {
-            reader,
-            buf: Vec::new(),
-            _proto: std::marker::PhantomData,
+    /// Returns a configuration with JSON, NMEA, PPS and timing output all
+    /// enabled, so that a single connection sees everything GPSD reports.
+    pub fn capture() -> StreamOptions<Capture> {
+        let opts = v3::types::Watch {
+            enable: Some(true),
+            json: Some(true),
+            nmea: Some(true),
+            pps: Some(true),
+            timing: Some(true),
+            ..Default::default()
         };
 
-        client.ensure_version().await?;
-        Ok(client)
+        StreamOptions::<Capture> {
+            inner: opts,
+            devices: Vec::new(),
+            utf8: Utf8Policy::default(),
+            on_parse_error: ParseErrorPolicy::default(),
+            classes: Vec::new(),
+            _format: std::marker::PhantomData,
+        }
     }
 
-    /// Sends a request message to the GPSD server asynchronously
-    async fn send(&mut self, msg: &Proto::Request) -> Result<()>
-    where
-        Stream: futures_io::AsyncWrite + Unpin,
-    {
-        self.reader.write_request(msg).await
+    /// Enables or disables PPS (Pulse Per Second) messages
+    pub fn pps(mut self, enable: bool) -> Self {
+        self.inner.pps = Some(enable);
+        self
     }
 
-    /// Receives a response message from the GPSD server asynchronously
-    ///
-    /// Returns `None` if the connection is closed.
-    fn recv(&mut self) -> impl std::future::Future<Output = Result<Option<Proto::Response>>>
-    where
-        Stream: futures_io::AsyncRead + Unpin,
-    {
-        futures_util::future::poll_fn(|cx| {
-            std::pin::Pin::new(&mut self.reader).poll_response::<Proto::Response>(cx, &mut self.buf)
-        })
+    /// Enables or disables timing information
+    pub fn timing(mut self, enable: bool) -> Self {
+        self.inner.timing = Some(enable);
+        self
     }
 
-    /// Ensures the connected GPSD server supports this protocol version
+    /// Specifies a particular GPS device to capture from
     ///
-    /// Reads the version message from GPSD and verifies compatibility.
-    /// The client requires the major version to match exactly and the
-    /// minor version to be greater than or equal to the expected version.
-    async fn ensure_version(&mut self) -> Result<()>
-    where
-        Stream: futures_io::AsyncRead + Unpin,
-    {
-        use futures_util::AsyncBufReadExt;
-        self.buf.clear();
-        let bytes_read = self
-            .reader
-            .read_until(b'\n', &mut self.buf)
-            .await
-            .map_err(GpsdJsonError::IoError)?;
+    /// # Arguments
+    /// * `device` - Path to the GPS device (e.g., "/dev/ttyUSB0")
+    pub fn device<S: AsRef<str>>(mut self, device: S) -> Self {
+        self.inner.device = Some(device.as_ref().into());
+        self
+    }
 
-        if bytes_read == 0 {
-            return Err(GpsdJsonError::ProtocolError(
-                "Connection closed by GPSD before version message",
-            ));
-        }
+    /// Returns the WATCH settings that will be sent to GPSD
+    pub fn watch(&self) -> &v3::types::Watch {
+        &self.inner
+    }
+}
 
-        let ret = if let Ok(Some(v3::ResponseMessage::Version(version))) =
-            serde_json::from_slice(&self.buf)
-        {
-            if Proto::API_VERSION_MAJOR != version.proto_major
-                || Proto::API_VERSION_MINOR < version.proto_minor
-            {
-                Err(GpsdJsonError::UnsupportedProtocolVersion((
-                    version.proto_major,
-                    version.proto_minor,
+/// Number of times a speed change is sent before giving up
+pub const SPEED_SWITCH_ATTEMPTS: u32 = 3;
+
+/// Time GPSD is given to confirm that the temporary watch of
+/// `wait_for_fix` is disabled
+#[cfg(feature = "proto-v3")]
+pub(crate) const UNWATCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Settings of a DEVICE configuration command
+///
+/// Settings that are not set keep their current value. GPSD echoes the
+/// resulting state of the device, which
+/// [`configure_device`](GpsdClient::configure_device) checks against the
+/// requested settings.
+///
+/// # Example
+/// ```
+/// # use gpsd_json::{client::DeviceConfig, protocol::v3::types::Parity};
+/// let config = DeviceConfig::new("/dev/ttyUSB0")
+///     .bps(9600)
+///     .parity(Parity::No)
+///     .stopbits(1);
+/// assert_eq!(config.device().bps, Some(9600));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceConfig {
+    inner: v3::types::Device,
+}
+
+impl DeviceConfig {
+    /// Creates a configuration for the device at `path` that changes nothing
+    pub fn new<S: AsRef<str>>(path: S) -> Self {
+        DeviceConfig {
+            inner: v3::types::Device {
+                path: Some(path.as_ref().into()),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Sets the path of the device to configure
+    pub fn with_path<S: AsRef<str>>(mut self, path: S) -> Self {
+        self.inner.path = Some(path.as_ref().into());
+        self
+    }
+
+    /// Sets the serial port speed in bits per second
+    pub fn bps(mut self, bps: i32) -> Self {
+        self.inner.bps = Some(bps);
+        self
+    }
+
+    /// Sets the serial port parity
+    pub fn parity(mut self, parity: v3::types::Parity) -> Self {
+        self.inner.parity = Some(parity);
+        self
+    }
+
+    /// Sets the number of stop bits, 1 or 2
+    pub fn stopbits(mut self, stopbits: u32) -> Self {
+        self.inner.stopbits = Some(stopbits);
+        self
+    }
+
+    /// Sets the cycle time of the receiver in seconds
+    pub fn cycle(mut self, cycle: f64) -> Self {
+        self.inner.cycle = Some(cycle);
+        self
+    }
+
+    /// Switches the receiver to its binary (`true`) or NMEA (`false`) mode
+    pub fn native(mut self, native: bool) -> Self {
+        self.inner.native = Some(i32::from(native));
+        self
+    }
+
+    /// Returns the DEVICE settings that will be sent to GPSD
+    pub fn device(&self) -> &v3::types::Device {
+        &self.inner
+    }
+
+    /// Returns `true` if `device` reports the requested settings
+    ///
+    /// The cycle time is compared with a tolerance of a millisecond, since
+    /// receivers round it to their own resolution.
+    pub fn is_applied(&self, device: &v3::types::Device) -> bool {
+        fn matches<T: PartialEq>(requested: &Option<T>, reported: &Option<T>) -> bool {
+            requested.is_none() || requested == reported
+        }
+
+        let cycle = match (self.inner.cycle, device.cycle) {
+            (None, _) => true,
+            (Some(requested), Some(reported)) => (requested - reported).abs() < 1e-3,
+            (Some(_), None) => false,
+        };
+        device.path == self.inner.path
+            && matches(&self.inner.bps, &device.bps)
+            && matches(&self.inner.parity, &device.parity)
+            && matches(&self.inner.stopbits, &device.stopbits)
+            && matches(&self.inner.native, &device.native)
+            && cycle
+    }
+}
+
+/// Returns `true` if a message belongs to a stream scoped to `devices`
+///
+/// Messages without a device, e.g. replies to pings, always belong.
+fn in_scope<R: GpsdJsonResponse>(devices: &[String], msg: &R) -> bool {
+    devices.is_empty()
+        || msg
+            .device()
+            .is_none_or(|device| devices.iter().any(|d| d == device))
+}
+
+/// Returns true if a stream filtered by `classes` yields the message
+fn in_classes<R: GpsdJsonResponse>(classes: &[v3::response::Class], msg: &R) -> bool {
+    classes.is_empty()
+        || msg
+            .class_name()
+            .is_none_or(|name| classes.iter().any(|class| class.name() == name))
+}
+
+static NEXT_CONNECTION_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Metadata identifying the connection an item was received on
+///
+/// Attach it to stream items with [`GpsdDataStream::tagged`] when the
+/// origin of messages matters, e.g. for logging in multi-host setups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionInfo {
+    /// Process-wide unique identifier of the connection
+    pub id: u64,
+    /// Address of the GPSD server, if known
+    pub peer: Option<std::net::SocketAddr>,
+    /// Number of times the connection has been re-established
+    pub generation: u32,
+}
+
+impl ConnectionInfo {
+    /// Creates metadata for a new connection with a fresh identifier
+    pub(crate) fn new() -> Self {
+        ConnectionInfo {
+            id: NEXT_CONNECTION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            peer: None,
+            generation: 0,
+        }
+    }
+}
+
+/// Stream item tagged with the connection it was received on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sourced<T> {
+    /// Connection the item was received on
+    pub source: ConnectionInfo,
+    /// The received item
+    pub item: T,
+}
+
+/// Stream adapter tagging every item with connection metadata
+///
+/// Created by [`GpsdDataStream::tagged`]. Implements `futures::Stream` for
+/// async streams and `Iterator` for blocking streams.
+#[derive(Debug)]
+pub struct Tagged<S> {
+    inner: S,
+    source: ConnectionInfo,
+}
+
+impl<S> Tagged<S> {
+    pub(crate) fn new(inner: S, source: ConnectionInfo) -> Self {
+        Tagged { inner, source }
+    }
+
+    /// Returns the connection metadata attached to items
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.source
+    }
+
+    /// Returns the underlying stream
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> futures_util::Stream for Tagged<S>
+where
+    S: futures_util::Stream + Unpin,
+{
+    type Item = Sourced<S::Item>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let source = this.source;
+        std::pin::Pin::new(&mut this.inner)
+            .poll_next(cx)
+            .map(|item| item.map(|item| Sourced { source, item }))
+    }
+}
+
+impl<S: Iterator> Iterator for Tagged<S> {
+    type Item = Sourced<S::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.source;
+        self.inner.next().map(|item| Sourced { source, item })
+    }
+}
+
+/// Stream adapter yielding only TPV reports
+///
+/// Created by [`GpsdClient::tpv_stream`]. Messages of other classes are
+/// discarded and errors are passed through. Implements `futures::Stream`
+/// for async streams and `Iterator` for blocking streams.
+#[derive(Debug)]
+pub struct TpvStream<S> {
+    inner: S,
+}
+
+impl<S> TpvStream<S> {
+    /// Wraps a stream of JSON messages
+    pub fn new(inner: S) -> Self {
+        TpvStream { inner }
+    }
+
+    /// Returns the underlying stream, e.g. to close it
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> futures_util::Stream for TpvStream<S>
+where
+    S: futures_util::Stream<Item = Result<v3::response::Message>> + Unpin,
+{
+    type Item = Result<v3::response::Tpv>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            return match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
+                std::task::Poll::Ready(Some(Ok(v3::response::Message::Tpv(tpv)))) => {
+                    std::task::Poll::Ready(Some(Ok(tpv)))
+                }
+                std::task::Poll::Ready(Some(Ok(_))) => continue,
+                std::task::Poll::Ready(Some(Err(e))) => std::task::Poll::Ready(Some(Err(e))),
+                std::task::Poll::Ready(None) => std::task::Poll::Ready(None),
+                std::task::Poll::Pending => std::task::Poll::Pending,
+            };
+        }
+    }
+}
+
+impl<S> Iterator for TpvStream<S>
+where
+    S: Iterator<Item = Result<v3::response::Message>>,
+{
+    type Item = Result<v3::response::Tpv>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            return match self.inner.next()? {
+                Ok(v3::response::Message::Tpv(tpv)) => Some(Ok(tpv)),
+                Ok(_) => continue,
+                Err(e) => Some(Err(e)),
+            };
+        }
+    }
+}
+
+/// Stream adapter limiting the rate of items
+///
+/// Created by [`GpsdDataStream::throttle`]. Yields at most one item per
+/// period: the first item is yielded at once, later items are held until
+/// the period since the last yielded item has passed and only the most
+/// recent of them is kept. When the underlying stream ends, the held item
+/// is yielded before the end. Errors are passed through at once.
+///
+/// Requires a tokio runtime with timers enabled. Blocking streams, which
+/// cannot wake up when a period passes, have
+/// [`rate_limit`](blocking::GpsdDataStream::rate_limit) instead.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct Throttle<S, T> {
+    inner: S,
+    period: std::time::Duration,
+    latest: Option<T>,
+    done: bool,
+    sleep: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+}
+
+#[cfg(feature = "tokio")]
+impl<S, T> Throttle<S, T> {
+    /// Wraps a stream, yielding at most one item per `period`
+    pub fn new(inner: S, period: std::time::Duration) -> Self {
+        Throttle {
+            inner,
+            period,
+            latest: None,
+            done: false,
+            sleep: None,
+        }
+    }
+
+    /// Returns the underlying stream, e.g. to close it
+    ///
+    /// An item held back by the throttle is dropped.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, T> Throttle<S, T> {
+    /// Returns true if the period since the last yielded item has passed,
+    /// registering a wakeup for its end otherwise
+    fn poll_window(&mut self, cx: &mut std::task::Context<'_>) -> bool {
+        match &mut self.sleep {
+            Some(sleep) => std::future::Future::poll(sleep.as_mut(), cx).is_ready(),
+            None => true,
+        }
+    }
+
+    /// Starts the period of a yielded item
+    fn start_window(&mut self) {
+        let deadline = tokio::time::Instant::now() + self.period;
+        match &mut self.sleep {
+            Some(sleep) => sleep.as_mut().reset(deadline),
+            None => self.sleep = Some(Box::pin(tokio::time::sleep_until(deadline))),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S, T> futures_util::Stream for Throttle<S, T>
+where
+    S: futures_util::Stream<Item = Result<T>> + Unpin,
+    T: Unpin,
+{
+    type Item = Result<T>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return std::task::Poll::Ready(None);
+        }
+        loop {
+            let pending = match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
+                std::task::Poll::Ready(Some(Ok(item))) => {
+                    this.latest = Some(item);
+                    false
+                }
+                std::task::Poll::Ready(Some(Err(e))) => {
+                    return std::task::Poll::Ready(Some(Err(e)));
+                }
+                std::task::Poll::Ready(None) => {
+                    this.done = true;
+                    return std::task::Poll::Ready(this.latest.take().map(Ok));
+                }
+                std::task::Poll::Pending => true,
+            };
+            if this.latest.is_some() && this.poll_window(cx) {
+                this.start_window();
+                return std::task::Poll::Ready(this.latest.take().map(Ok));
+            }
+            if pending {
+                return std::task::Poll::Pending;
+            }
+        }
+    }
+}
+
+/// Iterator adapter limiting the rate of items
+///
+/// Created by [`blocking::GpsdDataStream::rate_limit`]. Yields the first
+/// item at once and drops later items until the period since the last
+/// yielded item has passed; the next item received is yielded then. Unlike
+/// the `Throttle` of async streams, an item received within a period is
+/// never yielded later. Errors are passed through at once.
+#[derive(Debug)]
+pub struct RateLimit<S> {
+    inner: S,
+    period: std::time::Duration,
+    next_emit: Option<std::time::Instant>,
+}
+
+impl<S> RateLimit<S> {
+    /// Wraps an iterator, yielding at most one item per `period`
+    pub fn new(inner: S, period: std::time::Duration) -> Self {
+        RateLimit {
+            inner,
+            period,
+            next_emit: None,
+        }
+    }
+
+    /// Returns the underlying iterator, e.g. to close it
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, T> Iterator for RateLimit<S>
+where
+    S: Iterator<Item = Result<T>>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(item) => {
+                    let now = std::time::Instant::now();
+                    if self.next_emit.is_none_or(|at| now >= at) {
+                        self.next_emit = Some(now + self.period);
+                        return Some(Ok(item));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Stream adapter yielding every n-th item
+///
+/// Created by [`GpsdDataStream::decimate`]. Of every `n` items the last
+/// one is yielded and the others are dropped, e.g. to reduce a 10 Hz
+/// receiver to 1 Hz. Errors are passed through and not counted.
+/// Implements `futures::Stream` for async streams and `Iterator` for
+/// blocking streams.
+#[derive(Debug)]
+pub struct Decimate<S> {
+    inner: S,
+    n: usize,
+    count: usize,
+}
+
+impl<S> Decimate<S> {
+    /// Wraps a stream, yielding every `n`-th item; zero is treated as one
+    pub fn new(inner: S, n: usize) -> Self {
+        Decimate {
+            inner,
+            n: n.max(1),
+            count: 0,
+        }
+    }
+
+    /// Returns the underlying stream, e.g. to close it
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Counts an item, returning true if it is to be yielded
+    fn keep(&mut self) -> bool {
+        self.count += 1;
+        if self.count < self.n {
+            return false;
+        }
+        self.count = 0;
+        true
+    }
+}
+
+impl<S, T> futures_util::Stream for Decimate<S>
+where
+    S: futures_util::Stream<Item = Result<T>> + Unpin,
+{
+    type Item = Result<T>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            return match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
+                std::task::Poll::Ready(Some(Ok(item))) => {
+                    if !this.keep() {
+                        continue;
+                    }
+                    std::task::Poll::Ready(Some(Ok(item)))
+                }
+                other => other,
+            };
+        }
+    }
+}
+
+impl<S, T> Iterator for Decimate<S>
+where
+    S: Iterator<Item = Result<T>>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            return match self.inner.next()? {
+                Ok(_) if !self.keep() => continue,
+                item => Some(item),
+            };
+        }
+    }
+}
+
+/// Stream adapter dropping repeated TPV reports of an epoch
+///
+/// Created by [`GpsdDataStream::dedup_by_time`]. A TPV report is dropped
+/// if the last TPV report of the same device carried the same time, as
+/// GPSD may send several during bursts. Reports without a time, messages
+/// of other classes and errors are passed through. Implements
+/// `futures::Stream` for async streams and `Iterator` for blocking streams.
+#[derive(Debug)]
+pub struct DedupByTime<S> {
+    inner: S,
+    last: std::collections::BTreeMap<String, crate::Timestamp>,
+    dropped: u64,
+}
+
+impl<S> DedupByTime<S> {
+    /// Wraps a stream of JSON messages
+    pub fn new(inner: S) -> Self {
+        DedupByTime {
+            inner,
+            last: std::collections::BTreeMap::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Returns the number of repeated reports dropped so far
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Returns the underlying stream, e.g. to close it
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Returns true if the message repeats the epoch of the last TPV
+    /// report of its device, recording its time otherwise
+    fn is_repeat(&mut self, msg: &v3::response::Message) -> bool {
+        let v3::response::Message::Tpv(tpv) = msg else {
+            return false;
+        };
+        let Some(time) = tpv.time else {
+            return false;
+        };
+        let device = tpv.device.as_deref().unwrap_or_default();
+        if self.last.get(device) == Some(&time) {
+            self.dropped += 1;
+            return true;
+        }
+        self.last.insert(device.to_string(), time);
+        false
+    }
+}
+
+impl<S> futures_util::Stream for DedupByTime<S>
+where
+    S: futures_util::Stream<Item = Result<v3::response::Message>> + Unpin,
+{
+    type Item = Result<v3::response::Message>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            return match std::pin::Pin::new(&mut this.inner).poll_next(cx) {
+                std::task::Poll::Ready(Some(Ok(msg))) if this.is_repeat(&msg) => continue,
+                other => other,
+            };
+        }
+    }
+}
+
+impl<S> Iterator for DedupByTime<S>
+where
+    S: Iterator<Item = Result<v3::response::Message>>,
+{
+    type Item = Result<v3::response::Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            return match self.inner.next()? {
+                Ok(msg) if self.is_repeat(&msg) => continue,
+                item => Some(item),
+            };
+        }
+    }
+}
+
+/// Core implementation of an asynchronous GPSD client
+///
+/// This struct provides the fundamental functionality for asynchronous
+/// communication with a GPSD server. It handles protocol negotiation,
+/// message serialization/deserialization, and maintains the connection state.
+///
+/// # Type Parameters
+/// * `Stream` - The underlying async I/O stream type (e.g., TcpStream)
+/// * `Proto` - The GPSD protocol version implementation
+#[derive(Debug)]
+pub struct GpsdClientCore<Stream, Proto> {
+    reader: futures_util::io::BufReader<Stream>,
+    buf: Vec<u8>,
+    info: ConnectionInfo,
+    parse_mode: ParseMode,
+    _proto: std::marker::PhantomData<Proto>,
+}
+
+impl<Stream, Proto> GpsdClientCore<Stream, Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Opens a new GPSD client connection using the provided async stream
+    ///
+    /// This method initializes the client with the given async I/O stream and
+    /// performs protocol version negotiation with the GPSD server.
+    ///
+    /// # Arguments
+    /// * `stream` - The async I/O stream for communication with GPSD
+    ///
+    /// # Returns
+    /// * `Ok(client)` - Successfully connected and negotiated protocol
+    /// * `Err(_)` - Connection or protocol negotiation failed
+    ///
+    /// Any `futures_io` stream can be used, so sockets created outside of
+    /// Rust (e.g. received from Java on Android) work without the `net`
+    /// feature. See [`blocking::GpsdClientCore::open`] for an example.
+    pub async fn open(stream: Stream) -> Result<Self>
+    where
+        Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    {
+        let reader = futures_util::io::BufReader::new(stream);
+        let mut client = GpsdClientCore {
+            reader,
+            buf: Vec::new(),
+            info: ConnectionInfo::new(),
+            parse_mode: ParseMode::Lenient,
+            _proto: std::marker::PhantomData,
+        };
+
+        client.ensure_version().await?;
+        Ok(client)
+    }
+
+    /// Returns metadata about this connection
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.info
+    }
+
+    /// Sets the peer address reported in the connection metadata
+    ///
+    /// Clients created with `connect` record the peer address
+    /// automatically; use this for clients created with `open`.
+    pub fn with_peer(mut self, peer: std::net::SocketAddr) -> Self {
+        self.info.peer = Some(peer);
+        self
+    }
+
+    /// Sets the reconnect generation reported in the connection metadata
+    pub fn with_generation(mut self, generation: u32) -> Self {
+        self.info.generation = generation;
+        self
+    }
+
+    /// Sets how fields unknown to the message types are handled
+    ///
+    /// Defaults to [`ParseMode::Lenient`]. Use [`ParseMode::Strict`] in
+    /// integration tests to catch schema drift of new GPSD releases.
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// Sends a request message to the GPSD server asynchronously
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    async fn send(&mut self, msg: &Proto::Request) -> Result<()>
+    where
+        Stream: futures_io::AsyncWrite + Unpin,
+    {
+        self.reader.write_request(msg).await
+    }
+
+    /// Receives a response message from the GPSD server asynchronously
+    ///
+    /// Returns `None` if the connection is closed.
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    fn recv(&mut self) -> impl std::future::Future<Output = Result<Option<Proto::Response>>>
+    where
+        Stream: futures_io::AsyncRead + Unpin,
+    {
+        futures_util::future::poll_fn(|cx| {
+            std::pin::Pin::new(&mut self.reader).poll_response_with::<Proto::Response>(
+                cx,
+                &mut self.buf,
+                self.parse_mode,
+            )
+        })
+    }
+
+    /// Receives the next line, along with the message decoded from it
+    ///
+    /// Unlike `recv`, lines that do not decode, e.g. NMEA sentences, are
+    /// returned as well.
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    async fn recv_line(&mut self) -> Result<Option<DecodedLine<Proto::Response>>>
+    where
+        Stream: futures_io::AsyncRead + Unpin,
+    {
+        let line = futures_util::future::poll_fn(|cx| {
+            std::pin::Pin::new(&mut self.reader).poll_raw(cx, &mut self.buf)
+        })
+        .await?;
+        Ok(line.map(|mut line| {
+            strip_line_ending(&mut line);
+            let msg = decode_line(&line, self.parse_mode);
+            (line, msg)
+        }))
+    }
+
+    /// Ensures the connected GPSD server supports this protocol version
+    ///
+    /// Reads the version message from GPSD and verifies compatibility.
+    /// The client requires the major version to match exactly and the
+    /// minor version to be greater than or equal to the expected version.
+    async fn ensure_version(&mut self) -> Result<()>
+    where
+        Stream: futures_io::AsyncRead + Unpin,
+    {
+        read_greeting(&mut self.reader, &mut self.buf).await?;
+        let ret = check_version::<Proto>(&self.buf).map(|_| ());
+        self.buf.clear();
+        ret
+    }
+}
+
+/// Reads the version greeting line GPSD sends on connect into `buf`
+async fn read_greeting<Stream: futures_io::AsyncRead + Unpin>(
+    reader: &mut futures_util::io::BufReader<Stream>,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    use futures_util::AsyncBufReadExt;
+    buf.clear();
+    let bytes_read = reader
+        .read_until(b'\n', buf)
+        .await
+        .map_err(GpsdJsonError::IoError)?;
+
+    if bytes_read == 0 {
+        return Err(GpsdJsonError::ProtocolError(
+            "Connection closed by GPSD before version message",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "tokio", feature = "net"))]
+impl<Proto> GpsdClientCore<tokio_util::compat::Compat<tokio::net::TcpStream>, Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Connects to a GPSD server over TCP asynchronously
+    ///
+    /// Creates an async TCP connection to the specified address and initializes
+    /// a GPSD client with protocol negotiation.
+    ///
+    /// # Arguments
+    /// * `addr` - Socket address of the GPSD server (e.g., "127.0.0.1:2947")
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::GpsdClient;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect<A: tokio::net::ToSocketAddrs + Sync>(addr: A) -> Result<Self> {
+        transport::GpsdClientBuilder::with_protocol()
+            .connect_with(&transport::TcpConnector::new(addr))
+            .await
+    }
+}
+
+#[cfg(all(feature = "tokio", feature = "rustls"))]
+impl<Proto>
+    GpsdClientCore<
+        tokio_util::compat::Compat<tokio_rustls::client::TlsStream<tokio::net::TcpStream>>,
+        Proto,
+    >
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Connects to a GPSD server over TLS asynchronously
+    ///
+    /// Wraps the TCP connection in TLS before protocol negotiation, e.g. for
+    /// GPSD exposed through stunnel or a remote gateway. The server
+    /// certificate is verified against `domain` with the given
+    /// configuration.
+    ///
+    /// # Arguments
+    /// * `addr` - Socket address of the TLS endpoint
+    /// * `domain` - DNS name or IP address the certificate is issued for
+    /// * `config` - TLS client configuration, e.g. with the trusted roots
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use gpsd_json::{client::GpsdClient, rustls};
+    /// # async fn example(config: Arc<rustls::ClientConfig>) -> gpsd_json::Result<()> {
+    /// let client =
+    ///     GpsdClient::connect_tls("gpsd.example.com:2948", "gpsd.example.com", config).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_tls<A: tokio::net::ToSocketAddrs + Sync>(
+        addr: A,
+        domain: &str,
+        config: std::sync::Arc<rustls::ClientConfig>,
+    ) -> Result<Self> {
+        let connector =
+            transport::TlsConnector::new(addr, domain, config).map_err(GpsdJsonError::IoError)?;
+        transport::GpsdClientBuilder::with_protocol()
+            .connect_with(&connector)
+            .await
+    }
+}
+
+#[cfg(all(feature = "tokio", feature = "net", unix))]
+impl<Proto> GpsdClientCore<tokio_util::compat::Compat<tokio::net::UnixStream>, Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Connects to a GPSD server over a Unix domain socket asynchronously
+    ///
+    /// Local clients can use this to avoid TCP, e.g. when TCP is firewalled.
+    /// The connection metadata has no peer address.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the socket GPSD listens on
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::GpsdClient;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = GpsdClient::connect_unix("/run/gpsd.sock").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_unix<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        transport::GpsdClientBuilder::with_protocol()
+            .connect_with(&transport::UnixConnector::new(path.as_ref()))
+            .await
+    }
+}
+
+/// Protocol implementation registered with a [`Negotiator`]
+struct Registered<Stream, Client> {
+    supports: fn(i32, i32) -> bool,
+    open: Box<dyn Fn(futures_util::io::BufReader<Stream>, ConnectionInfo) -> Client + Send + Sync>,
+}
+
+/// Selects the protocol implementation at runtime from the VERSION greeting
+///
+/// This is the async equivalent of [`blocking::Negotiator`]. The first
+/// registered implementation that [supports](GpsdJsonProtocol::supports)
+/// the version announced by GPSD is used.
+///
+/// # Example
+/// ```no_run
+/// use gpsd_json::{
+///     client::{GpsdClient, Negotiator},
+///     protocol::v3::V3,
+/// };
+/// use tokio_util::compat::Compat;
+///
+/// enum Client {
+///     V3(GpsdClient<Compat<tokio::net::TcpStream>>),
+///     // Further variants for other protocol implementations
+/// }
+///
+/// # async fn example() -> gpsd_json::Result<()> {
+/// let negotiator = Negotiator::new().register::<V3>(Client::V3);
+/// match negotiator.connect("127.0.0.1:2947").await? {
+///     Client::V3(mut client) => println!("{:?}", client.version().await?),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Negotiator<Stream, Client> {
+    protocols: Vec<Registered<Stream, Client>>,
+}
+
+impl<Stream, Client> std::fmt::Debug for Negotiator<Stream, Client> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Negotiator")
+            .field("protocols", &self.protocols.len())
+            .finish()
+    }
+}
+
+impl<Stream, Client> Default for Negotiator<Stream, Client> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Stream, Client> Negotiator<Stream, Client> {
+    /// Creates a negotiator without registered protocols
+    pub fn new() -> Self {
+        Negotiator {
+            protocols: Vec::new(),
+        }
+    }
+
+    /// Registers a protocol implementation
+    ///
+    /// `wrap` converts the opened client into the common client type.
+    /// Implementations are tried in the order they were registered.
+    pub fn register<Proto>(mut self, wrap: fn(GpsdClientCore<Stream, Proto>) -> Client) -> Self
+    where
+        Proto: GpsdJsonProtocol + 'static,
+        Stream: 'static,
+        Client: 'static,
+    {
+        self.protocols.push(Registered {
+            supports: Proto::supports,
+            open: Box::new(move |reader, info| {
+                wrap(GpsdClientCore {
+                    reader,
+                    buf: Vec::new(),
+                    info,
+                    parse_mode: ParseMode::Lenient,
+                    _proto: std::marker::PhantomData,
+                })
+            }),
+        });
+        self
+    }
+
+    /// Opens a client using the provided async stream
+    ///
+    /// Reads the version greeting and opens a client for the first
+    /// registered implementation supporting it. Returns
+    /// [`GpsdJsonError::UnsupportedProtocolVersion`] if there is none.
+    pub async fn open(&self, stream: Stream) -> Result<Client>
+    where
+        Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    {
+        self.open_with(stream, ConnectionInfo::new()).await
+    }
+
+    async fn open_with(&self, stream: Stream, info: ConnectionInfo) -> Result<Client>
+    where
+        Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    {
+        let mut reader = futures_util::io::BufReader::new(stream);
+        let mut buf = Vec::new();
+        read_greeting(&mut reader, &mut buf).await?;
+        let version = parse_version(&buf)?;
+
+        let (major, minor) = (version.proto_major, version.proto_minor);
+        match self.protocols.iter().find(|p| (p.supports)(major, minor)) {
+            Some(protocol) => Ok((protocol.open)(reader, info)),
+            None => Err(GpsdJsonError::UnsupportedProtocolVersion((major, minor))),
+        }
+    }
+}
+
+#[cfg(all(feature = "tokio", feature = "net"))]
+impl<Client> Negotiator<tokio_util::compat::Compat<tokio::net::TcpStream>, Client> {
+    /// Connects to a GPSD server over TCP and opens a client for the
+    /// announced protocol version
+    pub async fn connect<A: tokio::net::ToSocketAddrs>(&self, addr: A) -> Result<Client> {
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        let stream = tokio::net::TcpStream::connect(addr)
+            .await
+            .map_err(GpsdJsonError::IoError)?;
+        let mut info = ConnectionInfo::new();
+        info.peer = stream.peer_addr().ok();
+        self.open_with(stream.compat(), info).await
+    }
+}
+
+/// Type alias for an async GPSD client using protocol version 3
+///
+/// This is the most common async client type and should be used for
+/// connecting to modern GPSD servers (version 3.x) with async/await.
+#[cfg(feature = "proto-v3")]
+pub type GpsdClient<Stream> = GpsdClientCore<Stream, v3::V3>;
+
+#[cfg(feature = "proto-v3")]
+impl<Stream> GpsdClient<Stream>
+where
+    Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+{
+    /// Requests version information from the GPSD server
+    ///
+    /// Returns details about the GPSD server version, protocol version,
+    /// and capabilities.
+    pub async fn version(&mut self) -> Result<v3::response::Version> {
+        self.send(&v3::RequestMessage::Version).await?;
+        if let Some(v3::ResponseMessage::Version(version)) = self.recv().await? {
+            Ok(version)
+        } else {
+            Err(GpsdJsonError::ProtocolError(
+                "Expected version response from GPSD",
+            ))
+        }
+    }
+
+    /// Lists all GPS devices known to the GPSD server
+    ///
+    /// Returns information about each connected GPS receiver including
+    /// device paths, driver information, and current status.
+    pub async fn devices(&mut self) -> Result<v3::response::DeviceList> {
+        self.send(&v3::RequestMessage::Devices).await?;
+        if let Some(v3::ResponseMessage::Devices(devices)) = self.recv().await? {
+            Ok(devices)
+        } else {
+            Err(GpsdJsonError::ProtocolError(
+                "Expected devices response from GPSD",
+            ))
+        }
+    }
+
+    /// Gets information about the currently active GPS device
+    ///
+    /// Returns detailed information about the device currently being
+    /// used for GPS data.
+    pub async fn device(&mut self) -> Result<v3::types::Device> {
+        self.send(&v3::RequestMessage::Device(None)).await?;
+        if let Some(v3::ResponseMessage::Device(device)) = self.recv().await? {
+            Ok(device)
+        } else {
+            Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            ))
+        }
+    }
+
+    /// Gets information about the GPS device at `path`
+    ///
+    /// Unlike [`device`](Self::device), this selects the receiver explicitly,
+    /// which matters on hosts with several receivers attached.
+    pub async fn device_by_path(&mut self, path: &str) -> Result<v3::types::Device> {
+        let request = v3::types::Device {
+            path: Some(path.to_string()),
+            ..Default::default()
+        };
+        self.send(&v3::RequestMessage::Device(Some(request)))
+            .await?;
+        match self.recv().await? {
+            Some(v3::ResponseMessage::Device(device)) if device.path.as_deref() == Some(path) => {
+                Ok(device)
+            }
+            Some(v3::ResponseMessage::Device(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device response from GPSD for another path",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
+    /// Applies a configuration to a GPS device
+    ///
+    /// Sends the settings of `config` in a DEVICE command and returns the
+    /// state of the device echoed by GPSD. Fails if GPSD rejects the command
+    /// or the echoed state does not reflect the requested settings.
+    pub async fn configure_device(&mut self, config: &DeviceConfig) -> Result<v3::types::Device> {
+        self.send(&v3::RequestMessage::Device(Some(config.device().clone())))
+            .await?;
+        match self.recv().await? {
+            Some(v3::ResponseMessage::Device(device)) if config.is_applied(&device) => Ok(device),
+            Some(v3::ResponseMessage::Device(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device configuration not applied by GPSD",
+            )),
+            Some(v3::ResponseMessage::Error(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device configuration rejected by GPSD",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
+    /// Sends a driver control string to the GPS device at `path`
+    ///
+    /// GPSD writes the bytes to the receiver as they are and echoes the
+    /// state of the device. The returned report lists conditions under
+    /// which the receiver may ignore the control string; GPSD does not
+    /// report whether the receiver accepted it.
+    pub async fn send_control(
+        &mut self,
+        path: &str,
+        data: &control::HexData,
+    ) -> Result<control::ControlReport> {
+        self.send(&v3::RequestMessage::Device(Some(data.request(path))))
+            .await?;
+        match self.recv().await? {
+            Some(v3::ResponseMessage::Device(device)) if device.path.as_deref() == Some(path) => {
+                Ok(control::ControlReport::new(data, device))
+            }
+            Some(v3::ResponseMessage::Error(_)) => Err(GpsdJsonError::ProtocolError(
+                "Control string rejected by GPSD",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
+    /// Enables data streaming from GPSD with default settings
+    ///
+    /// Returns the current watch configuration and list of available devices.
+    /// After calling this method, GPS data will be streamed from the server.
+    pub async fn watch(&mut self) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
+        self.send(&v3::RequestMessage::Watch(None)).await?;
+        let Some(v3::ResponseMessage::Devices(devices)) = self.recv().await? else {
+            return Err(GpsdJsonError::ProtocolError(
+                "Expected devices response from GPSD",
+            ));
+        };
+        let Some(v3::ResponseMessage::Watch(watch)) = self.recv().await? else {
+            return Err(GpsdJsonError::ProtocolError(
+                "Expected watch response from GPSD",
+            ));
+        };
+
+        Ok((watch, devices))
+    }
+
+    /// Polls for the current GPS fix data
+    ///
+    /// Returns the most recent GPS fix information available from
+    /// all active devices.
+    pub async fn poll(&mut self) -> Result<v3::response::Poll> {
+        self.send(&v3::RequestMessage::Poll).await?;
+        if let Some(v3::ResponseMessage::Poll(poll)) = self.recv().await? {
+            Ok(poll)
+        } else {
+            Err(GpsdJsonError::ProtocolError(
+                "Expected poll response from GPSD",
+            ))
+        }
+    }
+
+    /// Enables or disables data streaming mode
+    ///
+    /// # Arguments
+    /// * `enable` - true to start streaming, false to stop
+    pub async fn watch_mode(&mut self, enable: bool) -> Result<()> {
+        let (watch, _devices) = self
+            .set_watch(v3::types::Watch {
+                enable: Some(enable),
+                ..Default::default()
+            })
+            .await?;
+
+        if watch.enable != Some(enable) {
+            return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+        }
+        Ok(())
+    }
+
+    /// Switches the serial port speed of a GPS device
+    ///
+    /// GPSD acknowledges a speed change before the receiver has resynced, so
+    /// the first DEVICE reply often still reports the old speed, and lines
+    /// garbled during the switch fail to decode. This sends the
+    /// change, skips such replies until the device reports the new speed,
+    /// and re-sends the change up to [`SPEED_SWITCH_ATTEMPTS`] times when no
+    /// confirmation arrives within `timeout`. Before giving up, it queries
+    /// the device once more, since the confirmation may have been lost.
+    ///
+    /// Fails with a [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if
+    /// the device never reports the new speed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::GpsdClient;
+    /// # use std::time::Duration;
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let mut client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let device = client
+    ///     .set_speed("/dev/ttyUSB0", 115200, Duration::from_secs(5))
+    ///     .await?;
+    /// assert_eq!(device.bps, Some(115200));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn set_speed(
+        &mut self,
+        path: &str,
+        bps: i32,
+        timeout: std::time::Duration,
+    ) -> Result<v3::types::Device> {
+        let config = DeviceConfig::new(path).bps(bps);
+        for _ in 0..SPEED_SWITCH_ATTEMPTS {
+            self.send(&v3::RequestMessage::Device(Some(config.device().clone())))
+                .await?;
+            let wait = async {
+                loop {
+                    match self.recv().await {
+                        Ok(Some(v3::ResponseMessage::Device(device)))
+                            if config.is_applied(&device) =>
+                        {
+                            return Ok(device);
+                        }
+                        Ok(Some(v3::ResponseMessage::Error(_))) => {
+                            return Err(GpsdJsonError::ProtocolError(
+                                "Device configuration rejected by GPSD",
+                            ));
+                        }
+                        Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                        Err(e) => return Err(e),
+                        Ok(None) => {
+                            return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                        }
+                    }
+                }
+            };
+            if let Ok(result) = tokio::time::timeout(timeout, wait).await {
+                return result;
+            }
+        }
+
+        match self.device_by_path(path).await {
+            Ok(device) if device.bps == Some(bps) => Ok(device),
+            Ok(_) | Err(GpsdJsonError::ProtocolError(_)) => {
+                Err(GpsdJsonError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "speed change not confirmed by GPSD",
+                )))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Waits for the first fix of at least the given mode
+    ///
+    /// Starts a temporary JSON watch, waits for the first TPV report with
+    /// `mode >= min_mode`, disables the watch again and returns the report.
+    /// Reports that cannot be decoded are skipped. Fails with a
+    /// [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if no such fix
+    /// arrives within `timeout`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::{client::GpsdClient, protocol::v3::types::FixMode};
+    /// # use std::time::Duration;
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let mut client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let tpv = client.wait_for_fix(FixMode::Fix3D, Duration::from_secs(60)).await?;
+    /// println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn wait_for_fix(
+        &mut self,
+        min_mode: v3::types::FixMode,
+        timeout: std::time::Duration,
+    ) -> Result<v3::response::Tpv> {
+        self.set_watch(v3::types::Watch {
+            enable: Some(true),
+            json: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+        let wait = async {
+            loop {
+                match self.recv().await {
+                    Ok(Some(v3::ResponseMessage::Tpv(tpv))) if tpv.mode >= min_mode => {
+                        return Ok(tpv);
+                    }
+                    Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                    Err(e) => return Err(e),
+                    Ok(None) => {
+                        return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                    }
+                }
+            }
+        };
+        let fix = match tokio::time::timeout(timeout, wait).await {
+            Ok(fix) => fix,
+            Err(_) => Err(GpsdJsonError::IoError(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "no fix received from GPSD",
+            ))),
+        };
+
+        // Errors of the wait take precedence, the connection is likely gone.
+        // A fix is kept if GPSD is merely slow to confirm the disabled watch.
+        let disabled = self.unwatch().await;
+        let tpv = fix?;
+        match disabled {
+            Err(GpsdJsonError::IoError(err)) if err.kind() == std::io::ErrorKind::TimedOut => {
+                Ok(tpv)
+            }
+            disabled => disabled.map(|()| tpv),
+        }
+    }
+
+    /// Disables WATCH mode, skipping messages until GPSD confirms
+    ///
+    /// Fails with a [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if
+    /// the confirmation does not arrive within [`UNWATCH_TIMEOUT`].
+    #[cfg(feature = "tokio")]
+    async fn unwatch(&mut self) -> Result<()> {
+        self.send(&v3::RequestMessage::Watch(
+            Some(v3::types::Watch::default()),
+        ))
+        .await?;
+        let confirm = async {
+            loop {
+                match self.recv().await {
+                    Ok(Some(v3::ResponseMessage::Watch(_))) => return Ok(()),
+                    Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                    Err(e) => return Err(e),
+                    Ok(None) => {
+                        return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(UNWATCH_TIMEOUT, confirm)
+            .await
+            .unwrap_or_else(|_| {
+                Err(GpsdJsonError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "disabled watch not confirmed by GPSD",
                 )))
-            } else {
-                Ok(())
+            })
+    }
+
+    /// Starts a data stream with the specified format and options
+    ///
+    /// This method consumes the client and returns a stream iterator
+    /// that yields GPS data in the requested format.
+    ///
+    /// # Arguments
+    /// * `opts` - Stream configuration options
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # async fn example(client: GpsdClient<impl futures_io::AsyncRead + futures_io::AsyncWrite + Unpin>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut stream = client.stream(StreamOptions::json()).await?;
+    /// while let Some(msg) = stream.next().await {
+    ///     println!("GPS data: {:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn stream<Format: StreamFormat>(
+        mut self,
+        opts: StreamOptions<Format>,
+    ) -> Result<GpsdDataStream<Stream, v3::V3, Format>> {
+        let devices = opts.scope();
+        let mut command = String::new();
+        let mut confirmations = Vec::new();
+        let mut pending = Pending::new::<Format>();
+        for watch in opts.watches() {
+            command.push_str(&v3::RequestMessage::Watch(Some(watch.clone())).to_command()?);
+            let (watch, _devices) = self.set_watch_keeping(watch, &mut pending).await?;
+            if watch.enable != Some(true) {
+                return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+            }
+            confirmations.push(watch);
+        }
+        pending.retain(|msg| in_scope(&devices, msg) && in_classes(&opts.classes, msg));
+
+        Ok(GpsdDataStream {
+            inner: self,
+            utf8: opts.utf8,
+            parse_errors: ParseErrors::new(opts.on_parse_error),
+            watch: command,
+            confirmations,
+            devices,
+            classes: opts.classes,
+            pending,
+            keepalive: None,
+            #[cfg(feature = "tokio")]
+            watchdog: None,
+            terminated: false,
+            outgoing: Vec::new(),
+            _format: std::marker::PhantomData,
+        })
+    }
+
+    /// Starts a JSON stream yielding only TPV reports
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # use futures::TryStreamExt;
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut tpvs = client.tpv_stream(StreamOptions::json()).await?;
+    /// while let Some(tpv) = tpvs.try_next().await? {
+    ///     println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn tpv_stream(
+        self,
+        opts: StreamOptions<Json>,
+    ) -> Result<TpvStream<GpsdDataStream<Stream, v3::V3, Json>>> {
+        let opts = opts.classes(&[v3::response::Class::Tpv]);
+        Ok(TpvStream::new(self.stream(opts).await?))
+    }
+
+    /// Configures watch mode settings
+    ///
+    /// Internal method to set watch parameters and receive confirmation.
+    async fn set_watch(
+        &mut self,
+        watch: v3::types::Watch,
+    ) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
+        self.set_watch_keeping(watch, &mut Pending::new::<Json>())
+            .await
+    }
+
+    /// Configures watch mode settings, keeping the messages that arrive
+    /// ahead of the confirmation in `pending`
+    ///
+    /// Devices watched before keep streaming while GPSD confirms the next
+    /// WATCH, so their data may arrive between the DEVICES and WATCH replies.
+    async fn set_watch_keeping(
+        &mut self,
+        watch: v3::types::Watch,
+        pending: &mut Pending<v3::ResponseMessage>,
+    ) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
+        self.send(&v3::RequestMessage::Watch(Some(watch))).await?;
+        let mut devices = None;
+        loop {
+            let Some((line, msg)) = self.recv_line().await? else {
+                return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+            };
+            match msg {
+                Ok(v3::ResponseMessage::Devices(list)) if devices.is_none() => {
+                    devices = Some(list);
+                }
+                Ok(v3::ResponseMessage::Watch(watch)) => {
+                    let Some(devices) = devices else {
+                        return Err(GpsdJsonError::ProtocolError(
+                            "Expected devices response from GPSD",
+                        ));
+                    };
+                    return Ok((watch, devices));
+                }
+                Ok(v3::ResponseMessage::Error(_)) => {
+                    return Err(GpsdJsonError::ProtocolError("Request rejected by GPSD"));
+                }
+                msg => pending.keep(line, msg, |_| true),
+            }
+        }
+    }
+}
+
+/// Async stream for receiving GPS data from GPSD
+///
+/// This struct provides an async stream interface (implements `futures::Stream`)
+/// for receiving continuous GPS data from a GPSD server. The format of the data
+/// depends on the stream format type parameter.
+///
+/// The stream continues until explicitly closed or an error occurs.
+///
+/// # Example
+/// ```no_run
+/// # use gpsd_json::client::{GpsdClient, StreamOptions};
+/// # async fn example(client: GpsdClient<impl futures_io::AsyncRead + futures_io::AsyncWrite + Unpin>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut stream = client.stream(StreamOptions::json()).await?;
+/// while let Some(result) = stream.next().await {
+///     match result {
+///         Ok(data) => println!("Received: {:?}", data),
+///         Err(e) => eprintln!("Error: {}", e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct GpsdDataStream<Stream, Proto, Format>
+where
+    Proto: GpsdJsonProtocol,
+    Format: StreamFormat,
+{
+    inner: GpsdClientCore<Stream, Proto>,
+    utf8: Utf8Policy,
+    parse_errors: ParseErrors,
+    watch: String,
+    confirmations: Vec<v3::types::Watch>,
+    devices: Vec<String>,
+    classes: Vec<v3::response::Class>,
+    pending: Pending<Proto::Response>,
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    keepalive: Option<Keepalive>,
+    #[cfg(feature = "tokio")]
+    watchdog: Option<AsyncWatchdog<Self>>,
+    terminated: bool,
+    outgoing: Vec<u8>,
+    _format: std::marker::PhantomData<Format>,
+}
+
+/// State of the no-data watchdog of an async stream
+#[cfg(feature = "tokio")]
+struct AsyncWatchdog<S> {
+    config: WatchdogConfig,
+    attempts: u32,
+    deadline: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+    /// Writes the queued requests of the stream, which needs a writable
+    /// stream the polling code does not require
+    flush: fn(&mut S, &mut std::task::Context<'_>) -> std::task::Poll<Result<()>>,
+}
+
+impl<Stream, Proto, Format> GpsdDataStream<Stream, Proto, Format>
+where
+    Proto: GpsdJsonProtocol,
+    Format: StreamFormat,
+{
+    /// Returns metadata about the underlying connection
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.inner.info
+    }
+
+    /// Returns the WATCH confirmations of GPSD, one per subscribed device
+    pub fn confirmations(&self) -> &[v3::types::Watch] {
+        &self.confirmations
+    }
+
+    /// Returns the number of unparsable lines dropped under
+    /// [`ParseErrorPolicy::Skip`]
+    pub fn skipped_lines(&self) -> u64 {
+        self.parse_errors.skipped
+    }
+
+    /// Returns the error of the last unparsable line dropped under
+    /// [`ParseErrorPolicy::Skip`]
+    pub fn last_parse_error(&self) -> Option<&GpsdJsonError> {
+        self.parse_errors.last.as_ref()
+    }
+
+    /// Receives the next item, or `None` once the connection is closed
+    ///
+    /// Same as `StreamExt::next`, without depending on `futures` for it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client.stream(StreamOptions::json()).await?;
+    /// while let Some(msg) = stream.next().await {
+    ///     println!("{:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    // Deliberately mirrors `StreamExt::next`, which the stream implements too
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> futures_util::stream::Next<'_, Self>
+    where
+        Self: futures_util::Stream + Unpin,
+    {
+        futures_util::StreamExt::next(self)
+    }
+
+    /// Receives the next item, returning `Ok(None)` once the connection is
+    /// closed
+    ///
+    /// Same as `TryStreamExt::try_next`, convenient with `?`:
+    /// `while let Some(msg) = stream.try_next().await? {}`.
+    pub fn try_next<Item>(&mut self) -> futures_util::stream::TryNext<'_, Self>
+    where
+        Self: futures_util::Stream<Item = Result<Item>> + Unpin,
+    {
+        futures_util::TryStreamExt::try_next(self)
+    }
+
+    /// Tags every item of this stream with connection metadata
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # use futures::StreamExt;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client.stream(StreamOptions::json()).await?.tagged();
+    /// while let Some(sourced) = stream.next().await {
+    ///     println!("{:?} from {:?}", sourced.item?, sourced.source.peer);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tagged(self) -> Tagged<Self> {
+        let source = self.inner.info;
+        Tagged::new(self, source)
+    }
+
+    /// Yields only every `n`-th item, e.g. to reduce a 10 Hz receiver to
+    /// 1 Hz with `decimate(10)`
+    pub fn decimate(self, n: usize) -> Decimate<Self> {
+        Decimate::new(self, n)
+    }
+
+    /// Re-sends the WATCH commands that started this stream
+    ///
+    /// Use this when no data arrived for a while, e.g. after a timeout
+    /// around `next()`, before falling back to a full reconnect. The
+    /// replies of GPSD arrive as regular stream items.
+    pub async fn rewatch(&mut self) -> Result<()>
+    where
+        Stream: futures_io::AsyncWrite + Unpin,
+    {
+        self.outgoing.extend_from_slice(self.watch.as_bytes());
+        futures_util::future::poll_fn(|cx| self.poll_write_outgoing(cx)).await
+    }
+
+    /// Writes a request behind the requests queued through the `Sink`
+    /// implementation, so that commands are never interleaved
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    async fn send_queued(&mut self, request: &Proto::Request) -> Result<()>
+    where
+        Stream: futures_io::AsyncWrite + Unpin,
+    {
+        let command = request.to_command()?;
+        self.outgoing.extend_from_slice(command.as_bytes());
+        futures_util::future::poll_fn(|cx| self.poll_write_outgoing(cx)).await
+    }
+
+    /// Enables the no-data watchdog
+    ///
+    /// When no item arrives within the timeout while the stream is polled,
+    /// the WATCH command is re-sent; when the re-sends are exhausted, the
+    /// stream yields [`GpsdJsonError::ProtocolError`] once per timeout.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions, WatchdogConfig};
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client
+    ///     .stream(StreamOptions::json())
+    ///     .await?
+    ///     .watchdog(WatchdogConfig::default());
+    /// while let Some(msg) = stream.next().await {
+    ///     println!("{:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn watchdog(mut self, config: WatchdogConfig) -> Self
+    where
+        Stream: futures_io::AsyncWrite + Unpin,
+    {
+        self.watchdog = Some(AsyncWatchdog {
+            config,
+            attempts: 0,
+            deadline: None,
+            flush: Self::poll_write_outgoing,
+        });
+        self
+    }
+
+    /// Writes the queued requests to the connection
+    fn poll_write_outgoing(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>>
+    where
+        Stream: futures_io::AsyncWrite + Unpin,
+    {
+        let writer = self.inner.reader.get_mut();
+        while !self.outgoing.is_empty() {
+            match std::pin::Pin::new(&mut *writer).poll_write(cx, &self.outgoing) {
+                std::task::Poll::Ready(Ok(0)) => {
+                    return std::task::Poll::Ready(Err(GpsdJsonError::IoError(
+                        std::io::ErrorKind::WriteZero.into(),
+                    )));
+                }
+                std::task::Poll::Ready(Ok(n)) => {
+                    self.outgoing.drain(..n);
+                }
+                std::task::Poll::Ready(Err(e)) => {
+                    return std::task::Poll::Ready(Err(GpsdJsonError::IoError(e)));
+                }
+                std::task::Poll::Pending => return std::task::Poll::Pending,
+            }
+        }
+        std::pin::Pin::new(writer)
+            .poll_flush(cx)
+            .map_err(GpsdJsonError::IoError)
+    }
+
+    /// Passes a polled item through the watchdog, re-sending WATCH when
+    /// nothing arrived within the timeout
+    #[cfg(feature = "tokio")]
+    fn watch_poll<T>(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        poll: std::task::Poll<Option<Result<T>>>,
+    ) -> std::task::Poll<Option<Result<T>>> {
+        use std::future::Future;
+
+        let Some(watchdog) = &mut self.watchdog else {
+            return poll;
+        };
+        let flush = watchdog.flush;
+        let timeout = watchdog.config.timeout;
+        let deadline = watchdog
+            .deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        if poll.is_ready() {
+            watchdog.attempts = 0;
+            deadline
+                .as_mut()
+                .reset(tokio::time::Instant::now() + timeout);
+        } else if deadline.as_mut().poll(cx).is_ready() {
+            deadline
+                .as_mut()
+                .reset(tokio::time::Instant::now() + timeout);
+            // Registers the waker for the next timeout
+            let _ = deadline.as_mut().poll(cx);
+            if watchdog.attempts >= watchdog.config.rewatch_attempts {
+                return std::task::Poll::Ready(Some(Err(GpsdJsonError::ProtocolError(
+                    "No data received from GPSD after re-sending WATCH",
+                ))));
             }
+            watchdog.attempts += 1;
+            self.outgoing.extend_from_slice(self.watch.as_bytes());
+        }
+
+        let flushed = if self.outgoing.is_empty() {
+            std::task::Poll::Ready(Ok(()))
         } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Failed to read version message from GPSD",
-            ))
+            flush(self, cx)
         };
+        match flushed {
+            std::task::Poll::Ready(Err(e)) => std::task::Poll::Ready(Some(Err(e))),
+            _ => poll,
+        }
+    }
 
-        self.buf.clear();
-        ret
+    fn fuse<T>(&mut self, poll: std::task::Poll<Option<T>>) -> std::task::Poll<Option<T>> {
+        if let std::task::Poll::Ready(None) = poll {
+            self.terminated = true;
+        }
+        poll
+    }
+
+    /// Returns the bounds on the remaining items
+    ///
+    /// Lines kept while waiting for a reply are all yielded, kept messages
+    /// of JSON streams unless they may be dropped as parse errors.
+    fn hint(&self) -> (usize, Option<usize>) {
+        if self.terminated {
+            (0, Some(0))
+        } else if !Format::DECODED || self.parse_errors.policy == ParseErrorPolicy::Yield {
+            (self.pending.len(), None)
+        } else {
+            (0, None)
+        }
     }
 }
 
-#[cfg(feature = "tokio")]
-impl<Proto> GpsdClientCore<tokio_util::compat::Compat<tokio::net::TcpStream>, Proto>
+#[cfg(feature = "proto-v3")]
+impl<Stream, Format> GpsdDataStream<Stream, v3::V3, Format>
 where
-    Proto: GpsdJsonProtocol,
+    Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    Format: StreamFormat,
 {
-    /// Connects to a GPSD server over TCP asynchronously
+    /// Sends `?VERSION;` to check that GPSD is still responsive
     ///
-    /// Creates an async TCP connection to the specified address and initializes
-    /// a GPSD client with protocol negotiation.
+    /// The reply of GPSD arrives as a regular stream item.
+    pub async fn ping(&mut self) -> Result<()> {
+        self.send_queued(&v3::RequestMessage::Version).await
+    }
+
+    /// Sends DEVICE settings without leaving the stream
     ///
-    /// # Arguments
-    /// * `addr` - Socket address of the GPSD server (e.g., "127.0.0.1:2947")
+    /// The state of the device echoed by GPSD arrives as a regular stream
+    /// item; use [`DeviceConfig::is_applied`] on it to check the result.
+    pub async fn send_config(&mut self, config: &DeviceConfig) -> Result<()> {
+        self.send_queued(&v3::RequestMessage::Device(Some(config.device().clone())))
+            .await
+    }
+
+    /// Enables the keepalive of [`next_alive`](Self::next_alive)
+    pub fn keepalive(mut self, config: KeepaliveConfig) -> Self {
+        self.keepalive = Some(Keepalive::new(config));
+        self
+    }
+
+    /// Waits for the next item, pinging GPSD while no data arrives
+    ///
+    /// Returns [`GpsdJsonError::ConnectionStale`] when neither data nor a
+    /// reply to a ping arrives within the window of the keepalive. Without
+    /// a keepalive, this is the same as `next()`.
     ///
     /// # Example
     /// ```no_run
-    /// # use gpsd_json::client::GpsdClient;
-    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use gpsd_json::client::{GpsdClient, KeepaliveConfig, StreamOptions};
+    /// # async fn example() -> gpsd_json::Result<()> {
     /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client
+    ///     .stream(StreamOptions::json())
+    ///     .await?
+    ///     .keepalive(KeepaliveConfig::default());
+    /// while let Some(msg) = stream.next_alive().await {
+    ///     println!("{:?}", msg?);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn connect<A: tokio::net::ToSocketAddrs>(addr: A) -> Result<Self> {
-        use tokio_util::compat::TokioAsyncReadCompatExt;
+    #[cfg(feature = "tokio")]
+    pub async fn next_alive<Item>(&mut self) -> Option<Result<Item>>
+    where
+        Self: futures_util::Stream<Item = Result<Item>> + Unpin,
+    {
+        loop {
+            let Some(keepalive) = &mut self.keepalive else {
+                return self.next().await;
+            };
+            match keepalive.poll() {
+                KeepaliveAction::Stale(silence) => {
+                    return Some(Err(GpsdJsonError::ConnectionStale(silence)));
+                }
+                KeepaliveAction::Ping => {
+                    if let Err(e) = self.ping().await {
+                        return Some(Err(e));
+                    }
+                }
+                KeepaliveAction::Wait(wait) => {
+                    if let Ok(item) = tokio::time::timeout(wait, self.next()).await {
+                        if let Some(keepalive) = &mut self.keepalive {
+                            keepalive.received();
+                        }
+                        return item;
+                    }
+                }
+            }
+        }
+    }
 
-        let stream = tokio::net::TcpStream::connect(addr)
-            .await
-            .map_err(GpsdJsonError::IoError)?;
-        let client = GpsdClientCore::open(stream.compat()).await?;
-        Ok(client)
+    /// Closes the data stream and returns the client
+    ///
+    /// This method stops the GPS data stream and returns the underlying
+    /// client for further operations.
+    pub async fn close(mut self) -> Result<GpsdClient<Stream>> {
+        let watch = v3::types::Watch::default();
+        self.send_queued(&v3::RequestMessage::Watch(Some(watch)))
+            .await?;
+
+        loop {
+            match self.inner.recv().await {
+                Ok(Some(v3::ResponseMessage::Watch(watch))) => {
+                    if watch.enable != Some(false) {
+                        return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+                    }
+                    break;
+                }
+                Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                Err(e) => return Err(e),
+                Ok(None) => {
+                    return Err(GpsdJsonError::ProtocolError(
+                        "Stream ended unexpectedly while closing",
+                    ));
+                }
+            }
+        }
+
+        self.inner.buf.clear();
+        Ok(self.inner)
+    }
+
+    /// Passes every item to a callback until a shutdown signal completes
+    ///
+    /// When `shutdown` completes, WATCH mode is disabled and the client is
+    /// returned, as with [`close`](Self::close). This packages the usual
+    /// service loop around signal handling such as SIGTERM.
+    ///
+    /// Items are passed to the callback as received, including decode
+    /// errors. The run ends early with an error if the connection fails
+    /// or GPSD closes it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # async fn example(shutdown: impl std::future::Future) -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let stream = client.stream(StreamOptions::json()).await?;
+    /// // `shutdown` is e.g. `tokio::signal::ctrl_c()`
+    /// let client = stream.run_until(shutdown, |msg| println!("{msg:?}")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_until<Item, Shutdown, Callback>(
+        mut self,
+        shutdown: Shutdown,
+        mut callback: Callback,
+    ) -> Result<GpsdClient<Stream>>
+    where
+        Self: futures_util::Stream<Item = Result<Item>> + Unpin,
+        Shutdown: std::future::Future,
+        Callback: FnMut(Result<Item>),
+    {
+        use futures_util::future::Either;
+
+        let mut shutdown = std::pin::pin!(shutdown);
+        loop {
+            match futures_util::future::select(shutdown.as_mut(), self.next()).await {
+                Either::Left(_) => break,
+                Either::Right((Some(Err(GpsdJsonError::IoError(err))), _)) => {
+                    return Err(GpsdJsonError::IoError(err));
+                }
+                Either::Right((Some(item), _)) => callback(item),
+                Either::Right((None, _)) => {
+                    return Err(GpsdJsonError::ProtocolError(
+                        "Stream ended before shutdown was requested",
+                    ));
+                }
+            }
+        }
+
+        self.close().await
     }
 }
 
-/// Type alias for an async GPSD client using protocol version 3
-///
-/// This is the most common async client type and should be used for
-/// connecting to modern GPSD servers (version 3.x) with async/await.
 #[cfg(feature = "proto-v3")]
-pub type GpsdClient<Stream> = GpsdClientCore<Stream, v3::V3>;
-
-impl<Stream> GpsdClient<Stream>
+impl<Stream> GpsdDataStream<Stream, v3::V3, Json>
 where
     Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
 {
-    /// Requests version information from the GPSD server
+    /// Yields at most one message per `period`, keeping the most recent
     ///
-    /// Returns details about the GPSD server version, protocol version,
-    /// and capabilities.
-    pub async fn version(&mut self) -> Result<v3::response::Version> {
-        self.send(&v3::RequestMessage::Version).await?;
-        if let Some(v3::ResponseMessage::Version(version)) = self.recv().await? {
-            Ok(version)
-        } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Expected version response from GPSD",
-            ))
-        }
+    /// Messages of all classes count against the period, so restrict the
+    /// stream with [`StreamOptions::classes`] to throttle e.g. only TPV
+    /// reports. Requires a tokio runtime with timers enabled.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # use gpsd_json::protocol::v3::response::Class;
+    /// # use futures::StreamExt;
+    /// # use std::time::Duration;
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client
+    ///     .stream(StreamOptions::json().classes(&[Class::Tpv]))
+    ///     .await?
+    ///     .throttle(Duration::from_secs(1));
+    /// while let Some(msg) = stream.next().await {
+    ///     println!("{:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn throttle(self, period: std::time::Duration) -> Throttle<Self, v3::response::Message> {
+        Throttle::new(self, period)
     }
 
-    /// Lists all GPS devices known to the GPSD server
+    /// Drops TPV reports repeating the time of the last TPV report of
+    /// their device
+    pub fn dedup_by_time(self) -> DedupByTime<Self> {
+        DedupByTime::new(self)
+    }
+
+    /// Receives the next TPV report, skipping messages of other classes
     ///
-    /// Returns information about each connected GPS receiver including
-    /// device paths, driver information, and current status.
-    pub async fn devices(&mut self) -> Result<v3::response::DeviceList> {
-        self.send(&v3::RequestMessage::Devices).await?;
-        if let Some(v3::ResponseMessage::Devices(devices)) = self.recv().await? {
-            Ok(devices)
-        } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Expected devices response from GPSD",
-            ))
-        }
+    /// Returns `Ok(None)` once the connection is closed. Errors are
+    /// returned as received, as with [`try_next`](Self::try_next).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client.stream(StreamOptions::json()).await?;
+    /// while let Some(tpv) = stream.next_tpv().await? {
+    ///     println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn next_tpv(&mut self) -> Result<Option<v3::response::Tpv>> {
+        self.next_of(|msg| match msg {
+            v3::ResponseMessage::Tpv(tpv) => Some(tpv),
+            _ => None,
+        })
+        .await
     }
 
-    /// Gets information about the currently active GPS device
+    /// Receives the next SKY report, skipping messages of other classes
     ///
-    /// Returns detailed information about the device currently being
-    /// used for GPS data.
-    pub async fn device(&mut self) -> Result<v3::types::Device> {
-        self.send(&v3::RequestMessage::Device(None)).await?;
-        if let Some(v3::ResponseMessage::Device(device)) = self.recv().await? {
-            Ok(device)
-        } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Expected device response from GPSD",
-            ))
-        }
+    /// Returns `Ok(None)` once the connection is closed. Errors are
+    /// returned as received, as with [`try_next`](Self::try_next).
+    #[cfg(feature = "msg-sky")]
+    pub async fn next_sky(&mut self) -> Result<Option<v3::response::Sky>> {
+        self.next_of(|msg| match msg {
+            v3::ResponseMessage::Sky(sky) => Some(sky),
+            _ => None,
+        })
+        .await
     }
 
-    /// Enables data streaming from GPSD with default settings
+    /// Polls GPSD for the current fixes without leaving the stream
     ///
-    /// Returns the current watch configuration and list of available devices.
-    /// After calling this method, GPS data will be streamed from the server.
-    pub async fn watch(&mut self) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
-        self.send(&v3::RequestMessage::Watch(None)).await?;
-        let Some(v3::ResponseMessage::Devices(devices)) = self.recv().await? else {
+    /// Messages received before the reply are kept and returned by the
+    /// stream afterwards, so control commands can be interleaved with
+    /// streaming.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
+    /// let mut stream = client.stream(StreamOptions::json()).await?;
+    /// println!("{:?}", stream.poll().await?);
+    /// while let Some(msg) = stream.next().await {
+    ///     println!("{:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn poll(&mut self) -> Result<v3::response::Poll> {
+        let v3::ResponseMessage::Poll(poll) = self
+            .query(v3::RequestMessage::Poll, |msg| {
+                matches!(msg, v3::ResponseMessage::Poll(_))
+            })
+            .await?
+        else {
             return Err(GpsdJsonError::ProtocolError(
-                "Expected devices response from GPSD",
+                "Expected poll response from GPSD",
             ));
         };
-        let Some(v3::ResponseMessage::Watch(watch)) = self.recv().await? else {
+        Ok(poll)
+    }
+
+    /// Lists the devices known to GPSD without leaving the stream
+    ///
+    /// Messages received before the reply are kept, as with
+    /// [`poll`](Self::poll).
+    pub async fn devices(&mut self) -> Result<v3::response::DeviceList> {
+        let v3::ResponseMessage::Devices(devices) = self
+            .query(v3::RequestMessage::Devices, |msg| {
+                matches!(msg, v3::ResponseMessage::Devices(_))
+            })
+            .await?
+        else {
             return Err(GpsdJsonError::ProtocolError(
-                "Expected watch response from GPSD",
+                "Expected devices response from GPSD",
             ));
         };
-
-        Ok((watch, devices))
+        Ok(devices)
     }
 
-    /// Polls for the current GPS fix data
-    ///
-    /// Returns the most recent GPS fix information available from
-    /// all active devices.
-    pub async fn poll(&mut self) -> Result<v3::response::Poll> {
-        self.send(&v3::RequestMessage::Poll).await?;
-        if let Some(v3::ResponseMessage::Poll(poll)) = self.recv().await? {
-            Ok(poll)
-        } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Expected poll response from GPSD",
-            ))
+    /// Sends a request and waits for the reply recognized by `is_reply`,
+    /// keeping other messages for the stream
+    async fn query(
+        &mut self,
+        request: v3::RequestMessage,
+        is_reply: fn(&v3::ResponseMessage) -> bool,
+    ) -> Result<v3::ResponseMessage> {
+        self.send_queued(&request).await?;
+        loop {
+            let Some((line, msg)) = self.inner.recv_line().await? else {
+                return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+            };
+            match msg {
+                Ok(msg) if is_reply(&msg) => return Ok(msg),
+                Ok(v3::ResponseMessage::Error(_)) => {
+                    return Err(GpsdJsonError::ProtocolError("Request rejected by GPSD"));
+                }
+                msg => self.pending.keep(line, msg, |msg| {
+                    in_scope(&self.devices, msg) && in_classes(&self.classes, msg)
+                }),
+            }
         }
     }
 
-    /// Enables or disables data streaming mode
-    ///
-    /// # Arguments
-    /// * `enable` - true to start streaming, false to stop
-    pub async fn watch_mode(&mut self, enable: bool) -> Result<()> {
-        let (watch, _devices) = self
-            .set_watch(v3::types::Watch {
-                enable: Some(enable),
-                ..Default::default()
-            })
-            .await?;
-
-        assert_eq!(watch.enable, Some(enable));
-        Ok(())
+    async fn next_of<T>(
+        &mut self,
+        select: impl Fn(v3::ResponseMessage) -> Option<T>,
+    ) -> Result<Option<T>> {
+        while let Some(msg) = self.try_next().await? {
+            if let Some(report) = select(msg) {
+                return Ok(Some(report));
+            }
+        }
+        Ok(None)
     }
 
-    /// Starts a data stream with the specified format and options
-    ///
-    /// This method consumes the client and returns a stream iterator
-    /// that yields GPS data in the requested format.
+    /// Drives the stream until a TPV report with at least `min_mode`
+    /// arrives, or fails after `timeout`
     ///
-    /// # Arguments
-    /// * `opts` - Stream configuration options
+    /// Unlike [`GpsdClient::wait_for_fix`], the stream stays open, so it
+    /// can continue after the first fix. Other messages and unparsable
+    /// lines received meanwhile are dropped.
     ///
     /// # Example
     /// ```no_run
-    /// # use gpsd_json::client::{GpsdClient, StreamOptions};
-    /// # use futures::StreamExt;
-    /// # async fn example(client: GpsdClient<impl futures_io::AsyncRead + futures_io::AsyncWrite + Unpin>) -> Result<(), Box<dyn std::error::Error>> {
+    /// # use gpsd_json::{client::{GpsdClient, StreamOptions}, protocol::v3::types::FixMode};
+    /// # use std::time::Duration;
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947").await?;
     /// let mut stream = client.stream(StreamOptions::json()).await?;
+    /// let first = stream.wait_for_fix(FixMode::Fix2D, Duration::from_secs(60)).await?;
+    /// println!("first fix at {:?}", first.time);
     /// while let Some(msg) = stream.next().await {
-    ///     println!("GPS data: {:?}", msg?);
+    ///     println!("{:?}", msg?);
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn stream<Format: StreamFormat>(
-        mut self,
-        opts: StreamOptions<Format>,
-    ) -> Result<GpsdDataStream<Stream, v3::V3, Format>> {
-        let (watch, _devices) = self.set_watch(opts.inner).await?;
-        assert_eq!(watch.enable, Some(true));
-
-        Ok(GpsdDataStream {
-            inner: self,
-            _format: std::marker::PhantomData,
-        })
+    #[cfg(feature = "tokio")]
+    pub async fn wait_for_fix(
+        &mut self,
+        min_mode: v3::types::FixMode,
+        timeout: std::time::Duration,
+    ) -> Result<v3::response::Tpv> {
+        let wait = async {
+            loop {
+                match self.next().await {
+                    Some(Ok(v3::ResponseMessage::Tpv(tpv))) if tpv.mode >= min_mode => {
+                        return Ok(tpv);
+                    }
+                    Some(Ok(_)) | Some(Err(GpsdJsonError::SerdeError(_))) => continue,
+                    Some(Err(e)) => return Err(e),
+                    None => {
+                        return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or_else(|_| {
+                Err(GpsdJsonError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "no fix received from GPSD",
+                )))
+            })
     }
+}
 
-    /// Configures watch mode settings
-    ///
-    /// Internal method to set watch parameters and receive confirmation.
-    async fn set_watch(
+impl<Stream, Proto> GpsdDataStream<Stream, Proto, Json>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    /// Polls for the next message, without tracking termination
+    fn poll_message(
         &mut self,
-        watch: v3::types::Watch,
-    ) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
-        self.send(&v3::RequestMessage::Watch(Some(watch))).await?;
-        let Some(v3::ResponseMessage::Devices(devices)) = self.recv().await? else {
-            return Err(GpsdJsonError::ProtocolError(
-                "Expected devices response from GPSD",
-            ));
-        };
-        let Some(v3::ResponseMessage::Watch(watch)) = self.recv().await? else {
-            return Err(GpsdJsonError::ProtocolError(
-                "Expected watch response from GPSD",
-            ));
-        };
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Proto::Response>>> {
+        while let Some(item) = self.pending.pop_message() {
+            if self.parse_errors.aborted() {
+                return std::task::Poll::Ready(None);
+            }
+            if let Some(item) = self.parse_errors.filter(item) {
+                return std::task::Poll::Ready(Some(item));
+            }
+        }
 
-        Ok((watch, devices))
+        loop {
+            if self.parse_errors.aborted() {
+                return std::task::Poll::Ready(None);
+            }
+            let reader = std::pin::Pin::new(&mut self.inner.reader);
+            return match reader.poll_response_with::<Proto::Response>(
+                cx,
+                &mut self.inner.buf,
+                self.inner.parse_mode,
+            ) {
+                std::task::Poll::Ready(Ok(Some(msg)))
+                    if !in_scope(&self.devices, &msg) || !in_classes(&self.classes, &msg) =>
+                {
+                    continue;
+                }
+                std::task::Poll::Ready(Ok(Some(msg))) => std::task::Poll::Ready(Some(Ok(msg))),
+                std::task::Poll::Ready(Ok(None)) => std::task::Poll::Ready(None),
+                std::task::Poll::Ready(Err(e)) => match self.parse_errors.filter(Err(e)) {
+                    Some(item) => std::task::Poll::Ready(Some(item)),
+                    None => continue,
+                },
+                std::task::Poll::Pending => std::task::Poll::Pending,
+            };
+        }
     }
 }
 
-/// Async stream for receiving GPS data from GPSD
-///
-/// This struct provides an async stream interface (implements `futures::Stream`)
-/// for receiving continuous GPS data from a GPSD server. The format of the data
-/// depends on the stream format type parameter.
+impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Json>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    type Item = Result<Proto::Response>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.terminated {
+            return std::task::Poll::Ready(None);
+        }
+        let poll = this.poll_message(cx);
+        #[cfg(feature = "tokio")]
+        let poll = this.watch_poll(cx, poll);
+        this.fuse(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.hint()
+    }
+}
+
+impl<Stream, Proto> futures_util::stream::FusedStream for GpsdDataStream<Stream, Proto, Json>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// Sends requests while streaming, e.g. `?POLL;` or `?DEVICE;`
 ///
-/// The stream continues until explicitly closed or an error occurs.
+/// Replies of GPSD arrive as regular stream items. Requests are buffered
+/// by `start_send` and written by `poll_flush`; use
+/// [`SinkExt::send`](futures_util::SinkExt::send) to do both.
 ///
 /// # Example
 /// ```no_run
 /// # use gpsd_json::client::{GpsdClient, StreamOptions};
-/// # use futures::StreamExt;
-/// # async fn example(client: GpsdClient<impl futures_io::AsyncRead + futures_io::AsyncWrite + Unpin>) -> Result<(), Box<dyn std::error::Error>> {
+/// # use gpsd_json::protocol::v3::RequestMessage;
+/// # use futures::{SinkExt, StreamExt};
+/// # async fn example() -> gpsd_json::Result<()> {
+/// let client = GpsdClient::connect("127.0.0.1:2947").await?;
 /// let mut stream = client.stream(StreamOptions::json()).await?;
-/// while let Some(result) = stream.next().await {
-///     match result {
-///         Ok(data) => println!("Received: {:?}", data),
-///         Err(e) => eprintln!("Error: {}", e),
-///     }
+/// stream.send(RequestMessage::Poll).await?;
+/// while let Some(msg) = stream.next().await {
+///     println!("{:?}", msg?);
 /// }
 /// # Ok(())
 /// # }
 /// ```
-pub struct GpsdDataStream<Stream, Proto, Format>
+#[cfg(feature = "proto-v3")]
+impl<Stream> futures_util::Sink<v3::RequestMessage> for GpsdDataStream<Stream, v3::V3, Json>
 where
-    Proto: GpsdJsonProtocol,
-    Format: StreamFormat,
+    Stream: futures_io::AsyncWrite + Unpin,
 {
-    inner: GpsdClientCore<Stream, Proto>,
-    _format: std::marker::PhantomData<Format>,
+    type Error = GpsdJsonError;
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>> {
+        if self.outgoing.is_empty() {
+            return std::task::Poll::Ready(Ok(()));
+        }
+        self.poll_flush(cx)
+    }
+
+    fn start_send(self: std::pin::Pin<&mut Self>, request: v3::RequestMessage) -> Result<()> {
+        let command = request.to_command()?;
+        self.get_mut()
+            .outgoing
+            .extend_from_slice(command.as_bytes());
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>> {
+        self.get_mut().poll_write_outgoing(cx)
+    }
+
+    /// Flushes pending requests; the connection stays open until the
+    /// stream is [closed](GpsdDataStream::close) or dropped
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
 }
 
-impl<Stream, Format> GpsdDataStream<Stream, v3::V3, Format>
+impl<Stream, Proto, Format> GpsdDataStream<Stream, Proto, Format>
 where
-    Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol,
     Format: StreamFormat,
 {
-    /// Closes the data stream and returns the client
-    ///
-    /// This method stops the GPS data stream and returns the underlying
-    /// client for further operations.
-    pub async fn close(mut self) -> Result<GpsdClient<Stream>> {
-        let watch = v3::types::Watch::default();
-        self.inner
-            .send(&v3::RequestMessage::Watch(Some(watch)))
-            .await?;
-
-        loop {
-            match self.inner.recv().await {
-                Ok(Some(v3::ResponseMessage::Watch(watch))) => {
-                    assert_eq!(watch.enable, Some(false));
-                    break;
-                }
-                Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
-                Err(e) => return Err(e),
-                Ok(None) => {
-                    return Err(GpsdJsonError::ProtocolError(
-                        "Stream ended unexpectedly while closing",
-                    ));
-                }
-            }
+    /// Polls for the next line without its line ending, starting with the
+    /// lines kept while waiting for replies
+    fn poll_line(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<Option<Vec<u8>>>> {
+        if let Some(line) = self.pending.pop_line() {
+            return std::task::Poll::Ready(Ok(Some(line)));
         }
-
-        self.inner.buf.clear();
-        Ok(self.inner)
+        let reader = std::pin::Pin::new(&mut self.inner.reader);
+        reader.poll_raw(cx, &mut self.inner.buf).map_ok(|line| {
+            line.map(|mut line| {
+                strip_line_ending(&mut line);
+                line
+            })
+        })
     }
 }
 
-impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Json>
+impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Nmea>
 where
     Stream: futures_io::AsyncRead + Unpin,
     Proto: GpsdJsonProtocol + Unpin,
 {
-    type Item = Result<Proto::Response>;
+    type Item = Result<NmeaLine>;
 
     fn poll_next(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        let reader = std::pin::Pin::new(&mut this.inner.reader);
-
-        match reader.poll_response::<Proto::Response>(cx, &mut this.inner.buf) {
-            std::task::Poll::Ready(Ok(Some(msg))) => std::task::Poll::Ready(Some(Ok(msg))),
+        if this.terminated {
+            return std::task::Poll::Ready(None);
+        }
+        let poll = match this.poll_line(cx) {
+            std::task::Poll::Ready(Ok(Some(line))) => {
+                std::task::Poll::Ready(Some(this.utf8.decode(line)))
+            }
             std::task::Poll::Ready(Ok(None)) => std::task::Poll::Ready(None),
             std::task::Poll::Ready(Err(e)) => std::task::Poll::Ready(Some(Err(e))),
             std::task::Poll::Pending => std::task::Poll::Pending,
-        }
+        };
+        #[cfg(feature = "tokio")]
+        let poll = this.watch_poll(cx, poll);
+        this.fuse(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.hint()
     }
 }
 
-impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Nmea>
+impl<Stream, Proto> futures_util::stream::FusedStream for GpsdDataStream<Stream, Proto, Nmea>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Raw>
 where
     Stream: futures_io::AsyncRead + Unpin,
     Proto: GpsdJsonProtocol + Unpin,
 {
-    type Item = Result<String>;
+    type Item = Result<Vec<u8>>;
 
     fn poll_next(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        let reader = std::pin::Pin::new(&mut this.inner.reader);
-
-        match reader.poll_raw(cx, &mut this.inner.buf) {
-            std::task::Poll::Ready(Ok(Some(line))) => {
-                let line_str = String::from_utf8_lossy(&line).trim_end().to_string();
-                std::task::Poll::Ready(Some(Ok(line_str)))
-            }
+        if this.terminated {
+            return std::task::Poll::Ready(None);
+        }
+        let poll = match this.poll_line(cx) {
+            std::task::Poll::Ready(Ok(Some(line))) => std::task::Poll::Ready(Some(Ok(line))),
             std::task::Poll::Ready(Ok(None)) => std::task::Poll::Ready(None),
             std::task::Poll::Ready(Err(e)) => std::task::Poll::Ready(Some(Err(e))),
             std::task::Poll::Pending => std::task::Poll::Pending,
-        }
+        };
+        #[cfg(feature = "tokio")]
+        let poll = this.watch_poll(cx, poll);
+        this.fuse(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.hint()
     }
 }
 
-impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Raw>
+impl<Stream, Proto> futures_util::stream::FusedStream for GpsdDataStream<Stream, Proto, Raw>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<Stream, Proto> futures_util::Stream for GpsdDataStream<Stream, Proto, Capture>
 where
     Stream: futures_io::AsyncRead + Unpin,
     Proto: GpsdJsonProtocol + Unpin,
@@ -657,13 +3212,396 @@ where
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
         let this = self.get_mut();
-        let reader = std::pin::Pin::new(&mut this.inner.reader);
-
-        match reader.poll_raw(cx, &mut this.inner.buf) {
+        if this.terminated {
+            return std::task::Poll::Ready(None);
+        }
+        let poll = match this.poll_line(cx) {
             std::task::Poll::Ready(Ok(Some(line))) => std::task::Poll::Ready(Some(Ok(line))),
             std::task::Poll::Ready(Ok(None)) => std::task::Poll::Ready(None),
             std::task::Poll::Ready(Err(e)) => std::task::Poll::Ready(Some(Err(e))),
             std::task::Poll::Pending => std::task::Poll::Pending,
+        };
+        #[cfg(feature = "tokio")]
+        let poll = this.watch_poll(cx, poll);
+        this.fuse(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.hint()
+    }
+}
+
+impl<Stream, Proto> futures_util::stream::FusedStream for GpsdDataStream<Stream, Proto, Capture>
+where
+    Stream: futures_io::AsyncRead + Unpin,
+    Proto: GpsdJsonProtocol + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+/// Item of a [`merge`]d stream, tagged with the stream it came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Merged<A, B> {
+    /// Item from the first stream
+    First(A),
+    /// Item from the second stream
+    Second(B),
+}
+
+/// Merges two streams into one, tagging each item with its source
+///
+/// Both streams are polled in turn so that a busy stream cannot starve
+/// the other. The merged stream ends when both streams have ended. This
+/// allows two clients, e.g. JSON from one GPSD and NMEA from another, to
+/// be consumed in a single task for comparing receivers side by side.
+///
+/// # Example
+/// ```no_run
+/// # use gpsd_json::client::{GpsdClient, Merged, StreamOptions, merge};
+/// # use futures::StreamExt;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let json = GpsdClient::connect("10.0.0.1:2947").await?.stream(StreamOptions::json()).await?;
+/// let nmea = GpsdClient::connect("10.0.0.2:2947").await?.stream(StreamOptions::nmea()).await?;
+///
+/// let mut merged = merge(json, nmea);
+/// while let Some(item) = merged.next().await {
+///     match item {
+///         Merged::First(msg) => println!("receiver A: {:?}", msg?),
+///         Merged::Second(sentence) => println!("receiver B: {}", sentence?),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge<A, B>(a: A, b: B) -> impl futures_util::Stream<Item = Merged<A::Item, B::Item>>
+where
+    A: futures_util::Stream,
+    B: futures_util::Stream,
+{
+    use futures_util::StreamExt;
+
+    futures_util::stream::select(a.map(Merged::First), b.map(Merged::Second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Client of a server sending the greeting and `lines`, the server side
+    /// stays open for writing
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    async fn streaming_client(
+        lines: &[&str],
+    ) -> (
+        GpsdClient<tokio_util::compat::Compat<tokio::net::UnixStream>>,
+        std::os::unix::net::UnixStream,
+    ) {
+        use std::io::Write;
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        let (stream, mut server) = std::os::unix::net::UnixStream::pair().unwrap();
+        writeln!(server, "{}", crate::fixtures::VERSION).unwrap();
+        for line in lines {
+            writeln!(server, "{line}").unwrap();
+        }
+        stream.set_nonblocking(true).unwrap();
+        let stream = tokio::net::UnixStream::from_std(stream).unwrap().compat();
+        (GpsdClient::open(stream).await.unwrap(), server)
+    }
+
+    #[test]
+    fn test_utf8_policy_decode() {
+        let sentence = b"$PVND,\xb0C*00".to_vec();
+
+        let lossy = Utf8Policy::Lossy.decode(sentence.clone()).unwrap();
+        assert_eq!(lossy.as_str(), Some("$PVND,\u{fffd}C*00"));
+        assert!(matches!(
+            Utf8Policy::Strict.decode(sentence.clone()),
+            Err(GpsdJsonError::Utf8Error(_))
+        ));
+        let bytes = Utf8Policy::Bytes.decode(sentence.clone()).unwrap();
+        assert_eq!(bytes.as_bytes(), sentence);
+        assert_eq!(bytes.as_str(), None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_throttle() {
+        use futures_util::StreamExt;
+
+        // An item every 10 ms, throttled to one per 35 ms
+        let items = futures_util::stream::unfold(0, |n| async move {
+            if n == 10 {
+                return None;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            Some((Ok(n), n + 1))
+        })
+        .boxed();
+        let yielded: Vec<u32> = Throttle::new(items, std::time::Duration::from_millis(35))
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        assert_eq!(yielded.first(), Some(&0));
+        assert_eq!(yielded.last(), Some(&9));
+        assert!(yielded.len() > 2 && yielded.len() < 10, "{yielded:?}");
+        assert!(yielded.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", feature = "net", unix))]
+    #[tokio::test]
+    async fn test_connect_unix() {
+        use futures_util::AsyncWriteExt;
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        let path = std::env::temp_dir().join(format!("gpsd-json-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            stream
+                .compat()
+                .write_all(format!("{}\n", crate::fixtures::VERSION).as_bytes())
+                .await
+                .unwrap();
+        });
+
+        let client = GpsdClient::connect_unix(&path).await.unwrap();
+        server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(client.connection().peer, None);
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_try_next() {
+        use crate::fixtures;
+
+        let (client, server) =
+            streaming_client(&[fixtures::DEVICES, fixtures::WATCH, fixtures::TPV]).await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut stream = client.stream(StreamOptions::json()).await.unwrap();
+        let msg = stream.try_next().await.unwrap();
+        assert!(matches!(msg, Some(v3::ResponseMessage::Tpv(_))));
+        assert!(stream.try_next().await.unwrap().is_none());
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_sink() {
+        use futures_util::SinkExt;
+        use std::io::{BufRead, Write};
+
+        use crate::fixtures;
+
+        let (client, mut server) = streaming_client(&[fixtures::DEVICES, fixtures::WATCH]).await;
+        let mut stream = client.stream(StreamOptions::json()).await.unwrap();
+        stream.send(v3::RequestMessage::Device(None)).await.unwrap();
+
+        let mut commands = std::io::BufReader::new(server.try_clone().unwrap());
+        let mut command = Vec::new();
+        commands.read_until(b';', &mut command).unwrap();
+        assert!(command.starts_with(b"?WATCH="));
+        command.clear();
+        commands.read_until(b';', &mut command).unwrap();
+        assert_eq!(command, b"?DEVICE;");
+
+        // Requests queued through the sink are written before a ping
+        stream.feed(v3::RequestMessage::Poll).await.unwrap();
+        stream.ping().await.unwrap();
+        for expected in [&b"?POLL;"[..], b"?VERSION;"] {
+            command.clear();
+            commands.read_until(b';', &mut command).unwrap();
+            assert_eq!(command, expected);
         }
+
+        // The reply is surfaced in the stream
+        writeln!(server, "{}", fixtures::DEVICE).unwrap();
+        let msg = stream.try_next().await.unwrap();
+        assert!(matches!(msg, Some(v3::ResponseMessage::Device(_))));
+
+        // ... and before the WATCH closing the stream
+        stream.feed(v3::RequestMessage::Poll).await.unwrap();
+        writeln!(server, r#"{{"class":"WATCH","enable":false}}"#).unwrap();
+        stream.close().await.unwrap();
+        command.clear();
+        commands.read_until(b';', &mut command).unwrap();
+        assert_eq!(command, b"?POLL;");
+        command.clear();
+        commands.read_until(b';', &mut command).unwrap();
+        assert!(command.starts_with(b"?WATCH="));
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_multiple_devices() {
+        use crate::fixtures;
+
+        // The first device streams while the second one is confirmed
+        let (client, server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::DEVICES,
+            fixtures::TPV,
+            fixtures::WATCH,
+        ])
+        .await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let opts = StreamOptions::json().devices(["/dev/ttyACM0", "/dev/ttyUSB0"]);
+        let mut stream = client.stream(opts).await.unwrap();
+        assert_eq!(stream.confirmations().len(), 2);
+        let msg = stream.try_next().await.unwrap();
+        assert!(matches!(msg, Some(v3::ResponseMessage::Tpv(_))));
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_multiple_devices_nmea() {
+        use futures_util::{Stream, StreamExt};
+
+        use crate::fixtures;
+
+        let watch = |device| {
+            format!(r#"{{"class":"WATCH","enable":true,"nmea":true,"device":"{device}"}}"#)
+        };
+        let (acm, usb) = (watch("/dev/ttyACM0"), watch("/dev/ttyUSB0"));
+        // The sentence of the first device arrives while the second one is
+        // confirmed and must not be lost
+        let (client, server) = streaming_client(&[
+            fixtures::DEVICES,
+            &acm,
+            fixtures::DEVICES,
+            "$GPGGA,1*5B",
+            &usb,
+            "$GPGGA,2*58",
+        ])
+        .await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let opts = StreamOptions::nmea().devices(["/dev/ttyACM0", "/dev/ttyUSB0"]);
+        let stream = client.stream(opts).await.unwrap();
+        assert_eq!(stream.size_hint(), (1, None));
+        let lines: Vec<_> = stream.map(|line| line.unwrap()).collect().await;
+        assert_eq!(
+            lines,
+            [
+                NmeaLine::Text("$GPGGA,1*5B".to_string()),
+                NmeaLine::Text("$GPGGA,2*58".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_watchdog() {
+        use std::io::BufRead;
+
+        use crate::fixtures;
+
+        let (client, server) = streaming_client(&[fixtures::DEVICES, fixtures::WATCH]).await;
+        let mut stream = client
+            .stream(StreamOptions::json())
+            .await
+            .unwrap()
+            .watchdog(WatchdogConfig {
+                timeout: std::time::Duration::from_millis(30),
+                rewatch_attempts: 1,
+            });
+        let msg = stream.next().await;
+        assert!(matches!(msg, Some(Err(GpsdJsonError::ProtocolError(_)))));
+
+        let mut commands = std::io::BufReader::new(server);
+        for _ in 0..2 {
+            let mut command = Vec::new();
+            commands.read_until(b';', &mut command).unwrap();
+            assert!(command.starts_with(b"?WATCH="));
+        }
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_fused() {
+        use futures_util::{Stream, stream::FusedStream};
+
+        use crate::fixtures;
+
+        let (client, server) =
+            streaming_client(&[fixtures::DEVICES, fixtures::WATCH, fixtures::TPV]).await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut stream = client.stream(StreamOptions::json()).await.unwrap();
+        assert!(!stream.is_terminated());
+        assert_eq!(stream.size_hint(), (0, None));
+        assert!(stream.next().await.is_some());
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_terminated());
+        assert_eq!(stream.size_hint(), (0, Some(0)));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_wait_for_fix() {
+        use crate::fixtures;
+
+        let (client, server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV_NO_FIX,
+            fixtures::TPV,
+            fixtures::SKY,
+        ])
+        .await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut stream = client.stream(StreamOptions::json()).await.unwrap();
+        let timeout = std::time::Duration::from_secs(5);
+        let tpv = stream
+            .wait_for_fix(v3::types::FixMode::Fix2D, timeout)
+            .await
+            .unwrap();
+        assert_eq!(tpv.mode, v3::types::FixMode::Fix3D);
+        let msg = stream.try_next().await.unwrap().unwrap();
+        assert_eq!(msg.class_name(), "SKY");
+        assert!(
+            stream
+                .wait_for_fix(v3::types::FixMode::Fix2D, timeout)
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_wait_for_fix_unconfirmed_unwatch() {
+        use crate::fixtures;
+
+        // GPSD never confirms the disabled watch, the fix is still returned
+        let (mut client, _server) =
+            streaming_client(&[fixtures::DEVICES, fixtures::WATCH, fixtures::TPV]).await;
+        let tpv = client
+            .wait_for_fix(v3::types::FixMode::Fix2D, std::time::Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(tpv.mode, v3::types::FixMode::Fix3D);
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_stream_next_of_class() {
+        use crate::fixtures;
+
+        let (client, server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::SKY,
+            fixtures::PPS,
+            fixtures::TPV,
+        ])
+        .await;
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut stream = client.stream(StreamOptions::json()).await.unwrap();
+        let tpv = stream.next_tpv().await.unwrap().unwrap();
+        assert_eq!(tpv.lat, Some(35.681236));
+        assert!(stream.next_tpv().await.unwrap().is_none());
     }
 }
@@ -4,12 +4,24 @@
 //! applications that don't require asynchronous I/O. It offers the same
 //! functionality as the async client but with blocking operations.
 
-use std::io::BufRead;
+use std::io::{BufRead, ErrorKind};
+#[cfg(feature = "net")]
 use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Instant;
 
-use crate::client::{Json, Nmea, Raw, StreamFormat, StreamOptions};
+use crate::client::{
+    Capture, ConnectionInfo, Decimate, DecodedLine, Json, Keepalive, KeepaliveAction,
+    KeepaliveConfig, Nmea, NmeaLine, ParseErrors, Pending, RateLimit, Raw, StreamFormat, Tagged,
+    Utf8Policy, WatchdogConfig, in_classes, in_scope,
+};
 use crate::error::GpsdJsonError;
-use crate::protocol::{GpsdJsonDecode, GpsdJsonEncode, v3};
+use crate::protocol::{
+    GpsdJsonDecode, GpsdJsonEncode, ParseMode, decode_line,
+    machine::{check_version, parse_version},
+    strip_line_ending,
+};
+#[cfg(feature = "proto-v3")]
+use crate::protocol::{GpsdJsonRequest, v3};
 use crate::{Result, client::GpsdJsonProtocol};
 
 /// Core implementation of a blocking GPSD client
@@ -25,6 +37,8 @@ use crate::{Result, client::GpsdJsonProtocol};
 pub struct GpsdClientCore<Stream, Proto> {
     reader: std::io::BufReader<Stream>,
     buf: Vec<u8>,
+    info: ConnectionInfo,
+    parse_mode: ParseMode,
     _proto: std::marker::PhantomData<Proto>,
 }
 
@@ -43,6 +57,27 @@ where
     /// # Returns
     /// * `Ok(client)` - Successfully connected and negotiated protocol
     /// * `Err(_)` - Connection or protocol negotiation failed
+    ///
+    /// Use this on targets where the socket is created outside of Rust,
+    /// e.g. Android apps receiving a connected socket from Java as a file
+    /// descriptor. It does not require the `net` feature.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::blocking::GpsdClient;
+    /// use std::os::fd::{FromRawFd, RawFd};
+    ///
+    /// # fn example(fd: RawFd) -> gpsd_json::Result<()> {
+    /// // `fd` was detached from a `ParcelFileDescriptor` on the Java side
+    /// let stream = unsafe { std::net::TcpStream::from_raw_fd(fd) };
+    /// let peer = stream.peer_addr().ok();
+    /// let mut client = GpsdClient::open(stream)?;
+    /// if let Some(peer) = peer {
+    ///     client = client.with_peer(peer);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn open(stream: Stream) -> Result<Self>
     where
         Stream: std::io::Read + std::io::Write,
@@ -51,6 +86,8 @@ where
         let mut client = GpsdClientCore {
             reader,
             buf: Vec::new(),
+            info: ConnectionInfo::new(),
+            parse_mode: ParseMode::Lenient,
             _proto: std::marker::PhantomData,
         };
 
@@ -58,7 +95,37 @@ where
         Ok(client)
     }
 
+    /// Returns metadata about this connection
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.info
+    }
+
+    /// Sets the peer address reported in the connection metadata
+    ///
+    /// Clients created with `connect` record the peer address
+    /// automatically; use this for clients created with `open`.
+    pub fn with_peer(mut self, peer: std::net::SocketAddr) -> Self {
+        self.info.peer = Some(peer);
+        self
+    }
+
+    /// Sets the reconnect generation reported in the connection metadata
+    pub fn with_generation(mut self, generation: u32) -> Self {
+        self.info.generation = generation;
+        self
+    }
+
+    /// Sets how fields unknown to the message types are handled
+    ///
+    /// Defaults to [`ParseMode::Lenient`]. Use [`ParseMode::Strict`] in
+    /// integration tests to catch schema drift of new GPSD releases.
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
     /// Sends a request message to the GPSD server
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
     fn send(&mut self, msg: &Proto::Request) -> Result<()>
     where
         Stream: std::io::Write,
@@ -74,12 +141,34 @@ where
         Stream: std::io::Read,
     {
         self.buf.clear();
-        match self.reader.read_response(&mut self.buf)? {
+        match self
+            .reader
+            .read_response_with(&mut self.buf, self.parse_mode)?
+        {
             Some(resp) => Ok(Some(resp)),
             None => Ok(None), // EOF reached
         }
     }
 
+    /// Receives the next line, along with the message decoded from it
+    ///
+    /// Unlike [`recv`](Self::recv), lines that do not decode, e.g. NMEA
+    /// sentences, are returned as well.
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    fn recv_line(&mut self) -> Result<Option<DecodedLine<Proto::Response>>>
+    where
+        Stream: std::io::Read,
+    {
+        self.buf.clear();
+        if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+            return Ok(None); // EOF reached
+        }
+        let mut line = std::mem::take(&mut self.buf);
+        strip_line_ending(&mut line);
+        let msg = decode_line(&line, self.parse_mode);
+        Ok(Some((line, msg)))
+    }
+
     /// Ensures the connected GPSD server supports this protocol version
     ///
     /// Reads the version message from GPSD and verifies compatibility.
@@ -89,28 +178,160 @@ where
     where
         Stream: std::io::Read,
     {
+        read_greeting(&mut self.reader, &mut self.buf)?;
+        let ret = check_version::<Proto>(&self.buf).map(|_| ());
         self.buf.clear();
-        if let Ok(Some(v3::ResponseMessage::Version(version))) =
-            self.reader.read_response(&mut self.buf)
-        {
-            if Proto::API_VERSION_MAJOR != version.proto_major
-                || Proto::API_VERSION_MINOR < version.proto_minor
-            {
-                Err(GpsdJsonError::UnsupportedProtocolVersion((
-                    version.proto_major,
-                    version.proto_minor,
-                )))
-            } else {
-                Ok(())
-            }
-        } else {
-            Err(GpsdJsonError::ProtocolError(
-                "Failed to read version message from GPSD",
-            ))
+        ret
+    }
+}
+
+/// Reads the version greeting line GPSD sends on connect into `buf`
+fn read_greeting<Stream: std::io::Read>(
+    reader: &mut std::io::BufReader<Stream>,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    buf.clear();
+    let bytes_read = reader
+        .read_until(b'\n', buf)
+        .map_err(GpsdJsonError::IoError)?;
+
+    if bytes_read == 0 {
+        return Err(GpsdJsonError::ProtocolError(
+            "Connection closed by GPSD before version message",
+        ));
+    }
+    Ok(())
+}
+
+/// Protocol implementation registered with a [`Negotiator`]
+struct Registered<Stream, Client> {
+    supports: fn(i32, i32) -> bool,
+    open: Box<dyn Fn(std::io::BufReader<Stream>, ConnectionInfo) -> Client + Send + Sync>,
+}
+
+/// Selects the protocol implementation at runtime from the VERSION greeting
+///
+/// Protocol implementations are registered together with a function that
+/// wraps the opened client into a common type, typically an enum with one
+/// variant per protocol. On connect, the first registered implementation
+/// that [supports](GpsdJsonProtocol::supports) the version announced by
+/// GPSD is used, so one binary can talk to daemons of different protocol
+/// generations.
+///
+/// # Example
+/// ```no_run
+/// use std::net::TcpStream;
+/// use gpsd_json::{
+///     client::blocking::{GpsdClient, Negotiator},
+///     protocol::v3::V3,
+/// };
+///
+/// enum Client {
+///     V3(GpsdClient<TcpStream>),
+///     // Further variants for other protocol implementations
+/// }
+///
+/// # fn example() -> gpsd_json::Result<()> {
+/// let negotiator = Negotiator::new().register::<V3>(Client::V3);
+/// match negotiator.connect("127.0.0.1:2947")? {
+///     Client::V3(mut client) => println!("{:?}", client.version()?),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Negotiator<Stream, Client> {
+    protocols: Vec<Registered<Stream, Client>>,
+}
+
+impl<Stream, Client> std::fmt::Debug for Negotiator<Stream, Client> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Negotiator")
+            .field("protocols", &self.protocols.len())
+            .finish()
+    }
+}
+
+impl<Stream, Client> Default for Negotiator<Stream, Client> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Stream, Client> Negotiator<Stream, Client> {
+    /// Creates a negotiator without registered protocols
+    pub fn new() -> Self {
+        Negotiator {
+            protocols: Vec::new(),
+        }
+    }
+
+    /// Registers a protocol implementation
+    ///
+    /// `wrap` converts the opened client into the common client type.
+    /// Implementations are tried in the order they were registered.
+    pub fn register<Proto>(mut self, wrap: fn(GpsdClientCore<Stream, Proto>) -> Client) -> Self
+    where
+        Proto: GpsdJsonProtocol + 'static,
+        Stream: 'static,
+        Client: 'static,
+    {
+        self.protocols.push(Registered {
+            supports: Proto::supports,
+            open: Box::new(move |reader, info| {
+                wrap(GpsdClientCore {
+                    reader,
+                    buf: Vec::new(),
+                    info,
+                    parse_mode: ParseMode::Lenient,
+                    _proto: std::marker::PhantomData,
+                })
+            }),
+        });
+        self
+    }
+
+    /// Opens a client using the provided stream
+    ///
+    /// Reads the version greeting and opens a client for the first
+    /// registered implementation supporting it. Returns
+    /// [`GpsdJsonError::UnsupportedProtocolVersion`] if there is none.
+    pub fn open(&self, stream: Stream) -> Result<Client>
+    where
+        Stream: std::io::Read + std::io::Write,
+    {
+        self.open_with(stream, ConnectionInfo::new())
+    }
+
+    fn open_with(&self, stream: Stream, info: ConnectionInfo) -> Result<Client>
+    where
+        Stream: std::io::Read + std::io::Write,
+    {
+        let mut reader = std::io::BufReader::new(stream);
+        let mut buf = Vec::new();
+        read_greeting(&mut reader, &mut buf)?;
+        let version = parse_version(&buf)?;
+
+        let (major, minor) = (version.proto_major, version.proto_minor);
+        match self.protocols.iter().find(|p| (p.supports)(major, minor)) {
+            Some(protocol) => Ok((protocol.open)(reader, info)),
+            None => Err(GpsdJsonError::UnsupportedProtocolVersion((major, minor))),
         }
     }
 }
 
+#[cfg(feature = "net")]
+impl<Client> Negotiator<TcpStream, Client> {
+    /// Connects to a GPSD server over TCP and opens a client for the
+    /// announced protocol version
+    pub fn connect<A: ToSocketAddrs>(&self, addr: A) -> Result<Client> {
+        let stream = TcpStream::connect(addr).map_err(GpsdJsonError::IoError)?;
+        let mut info = ConnectionInfo::new();
+        info.peer = stream.peer_addr().ok();
+        self.open_with(stream, info)
+    }
+}
+
+#[cfg(feature = "net")]
 impl<Proto> GpsdClientCore<TcpStream, Proto>
 where
     Proto: GpsdJsonProtocol,
@@ -134,10 +355,11 @@ where
     /// ```
     pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
         let stream = TcpStream::connect(addr).map_err(GpsdJsonError::IoError)?;
-        Self::open(stream)
+        Self::try_from(stream)
     }
 }
 
+#[cfg(feature = "net")]
 impl<Proto> TryFrom<TcpStream> for GpsdClientCore<TcpStream, Proto>
 where
     Proto: GpsdJsonProtocol,
@@ -145,6 +367,80 @@ where
     type Error = GpsdJsonError;
 
     fn try_from(stream: TcpStream) -> Result<Self> {
+        let peer = stream.peer_addr().ok();
+        let mut client = Self::open(stream)?;
+        client.info.peer = peer;
+        Ok(client)
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl<Proto> GpsdClientCore<rustls::StreamOwned<rustls::ClientConnection, TcpStream>, Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Connects to a GPSD server over TLS
+    ///
+    /// Wraps the TCP connection in TLS before protocol negotiation, e.g. for
+    /// GPSD exposed through stunnel or a remote gateway. The server
+    /// certificate is verified against `domain` with the given
+    /// configuration.
+    ///
+    /// # Arguments
+    /// * `addr` - Socket address of the TLS endpoint
+    /// * `domain` - DNS name or IP address the certificate is issued for
+    /// * `config` - TLS client configuration, e.g. with the trusted roots
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use gpsd_json::{client::blocking::GpsdClient, rustls};
+    /// # fn example(config: Arc<rustls::ClientConfig>) -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect_tls("gpsd.example.com:2948", "gpsd.example.com", config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        domain: &str,
+        config: std::sync::Arc<rustls::ClientConfig>,
+    ) -> Result<Self> {
+        let server_name =
+            rustls::pki_types::ServerName::try_from(domain.to_string()).map_err(|e| {
+                GpsdJsonError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+            })?;
+        let connection = rustls::ClientConnection::new(config, server_name)
+            .map_err(|e| GpsdJsonError::IoError(std::io::Error::other(e)))?;
+
+        let stream = TcpStream::connect(addr).map_err(GpsdJsonError::IoError)?;
+        let peer = stream.peer_addr().ok();
+        let mut client = Self::open(rustls::StreamOwned::new(connection, stream))?;
+        client.info.peer = peer;
+        Ok(client)
+    }
+}
+
+#[cfg(unix)]
+impl<Proto> GpsdClientCore<std::os::unix::net::UnixStream, Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Connects to a GPSD server over a Unix domain socket
+    ///
+    /// Local clients can use this to avoid TCP, e.g. when TCP is firewalled.
+    /// The connection metadata has no peer address.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the socket GPSD listens on
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::blocking::GpsdClient;
+    /// let client = GpsdClient::connect_unix("/run/gpsd.sock").unwrap();
+    /// ```
+    pub fn connect_unix<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let stream =
+            std::os::unix::net::UnixStream::connect(path).map_err(GpsdJsonError::IoError)?;
         Self::open(stream)
     }
 }
@@ -156,6 +452,7 @@ where
 #[cfg(feature = "proto-v3")]
 pub type GpsdClient<Stream> = GpsdClientCore<Stream, v3::V3>;
 
+#[cfg(feature = "proto-v3")]
 impl<Stream> GpsdClient<Stream>
 where
     Stream: std::io::Read + std::io::Write,
@@ -205,6 +502,75 @@ where
         }
     }
 
+    /// Gets information about the GPS device at `path`
+    ///
+    /// Unlike [`device`](Self::device), this selects the receiver explicitly,
+    /// which matters on hosts with several receivers attached.
+    pub fn device_by_path(&mut self, path: &str) -> Result<v3::types::Device> {
+        let request = v3::types::Device {
+            path: Some(path.to_string()),
+            ..Default::default()
+        };
+        self.send(&v3::RequestMessage::Device(Some(request)))?;
+        match self.recv()? {
+            Some(v3::ResponseMessage::Device(device)) if device.path.as_deref() == Some(path) => {
+                Ok(device)
+            }
+            Some(v3::ResponseMessage::Device(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device response from GPSD for another path",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
+    /// Applies a configuration to a GPS device
+    ///
+    /// Sends the settings of `config` in a DEVICE command and returns the
+    /// state of the device echoed by GPSD. Fails if GPSD rejects the command
+    /// or the echoed state does not reflect the requested settings.
+    pub fn configure_device(&mut self, config: &super::DeviceConfig) -> Result<v3::types::Device> {
+        self.send(&v3::RequestMessage::Device(Some(config.device().clone())))?;
+        match self.recv()? {
+            Some(v3::ResponseMessage::Device(device)) if config.is_applied(&device) => Ok(device),
+            Some(v3::ResponseMessage::Device(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device configuration not applied by GPSD",
+            )),
+            Some(v3::ResponseMessage::Error(_)) => Err(GpsdJsonError::ProtocolError(
+                "Device configuration rejected by GPSD",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
+    /// Sends a driver control string to the GPS device at `path`
+    ///
+    /// GPSD writes the bytes to the receiver as they are and echoes the
+    /// state of the device. The returned report lists conditions under
+    /// which the receiver may ignore the control string; GPSD does not
+    /// report whether the receiver accepted it.
+    pub fn send_control(
+        &mut self,
+        path: &str,
+        data: &super::control::HexData,
+    ) -> Result<super::control::ControlReport> {
+        self.send(&v3::RequestMessage::Device(Some(data.request(path))))?;
+        match self.recv()? {
+            Some(v3::ResponseMessage::Device(device)) if device.path.as_deref() == Some(path) => {
+                Ok(super::control::ControlReport::new(data, device))
+            }
+            Some(v3::ResponseMessage::Error(_)) => Err(GpsdJsonError::ProtocolError(
+                "Control string rejected by GPSD",
+            )),
+            _ => Err(GpsdJsonError::ProtocolError(
+                "Expected device response from GPSD",
+            )),
+        }
+    }
+
     /// Enables data streaming from GPSD with default settings
     ///
     /// Returns the current watch configuration and list of available devices.
@@ -250,10 +616,187 @@ where
             ..Default::default()
         })?;
 
-        assert_eq!(watch.enable, Some(enable));
+        if watch.enable != Some(enable) {
+            return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+        }
         Ok(())
     }
 
+    /// Switches the serial port speed of a GPS device
+    ///
+    /// GPSD acknowledges a speed change before the receiver has resynced, so
+    /// the first DEVICE reply often still reports the old speed, and lines
+    /// garbled during the switch fail to decode. This sends the
+    /// change, skips such replies until the device reports the new speed,
+    /// and re-sends the change up to [`SPEED_SWITCH_ATTEMPTS`](super::SPEED_SWITCH_ATTEMPTS) times when no
+    /// confirmation arrives within `timeout`. Before giving up, it queries
+    /// the device once more, since the confirmation may have been lost.
+    ///
+    /// Fails with a [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if
+    /// the device never reports the new speed.
+    ///
+    /// The timeout is checked after every received line. To bound the wait
+    /// when GPSD is quiet, set a read timeout on the underlying socket;
+    /// read timeouts are not treated as errors.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::blocking::GpsdClient;
+    /// # use std::time::Duration;
+    /// # fn example() -> gpsd_json::Result<()> {
+    /// let mut client = GpsdClient::connect("127.0.0.1:2947")?;
+    /// let device = client.set_speed("/dev/ttyUSB0", 115200, Duration::from_secs(5))?;
+    /// assert_eq!(device.bps, Some(115200));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_speed(
+        &mut self,
+        path: &str,
+        bps: i32,
+        timeout: std::time::Duration,
+    ) -> Result<v3::types::Device> {
+        let config = super::DeviceConfig::new(path).bps(bps);
+        for _ in 0..super::SPEED_SWITCH_ATTEMPTS {
+            let deadline = Instant::now() + timeout;
+            self.send(&v3::RequestMessage::Device(Some(config.device().clone())))?;
+            while Instant::now() < deadline {
+                match self.recv() {
+                    Ok(Some(v3::ResponseMessage::Device(device))) if config.is_applied(&device) => {
+                        return Ok(device);
+                    }
+                    Ok(Some(v3::ResponseMessage::Error(_))) => {
+                        return Err(GpsdJsonError::ProtocolError(
+                            "Device configuration rejected by GPSD",
+                        ));
+                    }
+                    Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                    Err(GpsdJsonError::IoError(err))
+                        if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                    {
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                    Ok(None) => {
+                        return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                    }
+                }
+            }
+        }
+
+        match self.device_by_path(path) {
+            Ok(device) if device.bps == Some(bps) => Ok(device),
+            Ok(_) | Err(GpsdJsonError::ProtocolError(_)) => {
+                Err(GpsdJsonError::IoError(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "speed change not confirmed by GPSD",
+                )))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Waits for the first fix of at least the given mode
+    ///
+    /// Starts a temporary JSON watch, waits for the first TPV report with
+    /// `mode >= min_mode`, disables the watch again and returns the report.
+    /// Reports that cannot be decoded are skipped. Fails with a
+    /// [`TimedOut`](ErrorKind::TimedOut) I/O error if no such fix arrives
+    /// within `timeout`.
+    ///
+    /// The timeout is checked after every received line. To bound the wait
+    /// when GPSD is quiet, set a read timeout on the underlying socket;
+    /// read timeouts are not treated as errors.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::{client::blocking::GpsdClient, protocol::v3::types::FixMode};
+    /// # use std::time::Duration;
+    /// # fn example() -> gpsd_json::Result<()> {
+    /// let mut client = GpsdClient::connect("127.0.0.1:2947")?;
+    /// let tpv = client.wait_for_fix(FixMode::Fix3D, Duration::from_secs(60))?;
+    /// println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_for_fix(
+        &mut self,
+        min_mode: v3::types::FixMode,
+        timeout: std::time::Duration,
+    ) -> Result<v3::response::Tpv> {
+        let deadline = Instant::now() + timeout;
+        self.set_watch(v3::types::Watch {
+            enable: Some(true),
+            json: Some(true),
+            ..Default::default()
+        })?;
+
+        let fix = loop {
+            if Instant::now() >= deadline {
+                break Err(GpsdJsonError::IoError(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    "no fix received from GPSD",
+                )));
+            }
+            match self.recv() {
+                Ok(Some(v3::ResponseMessage::Tpv(tpv))) if tpv.mode >= min_mode => break Ok(tpv),
+                Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                Err(GpsdJsonError::IoError(err))
+                    if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    continue;
+                }
+                Err(e) => break Err(e),
+                Ok(None) => {
+                    break Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                }
+            }
+        };
+
+        // Errors of the wait take precedence, the connection is likely gone.
+        // A fix is kept if GPSD is merely slow to confirm the disabled watch.
+        let disabled = self.unwatch();
+        let tpv = fix?;
+        match disabled {
+            Err(GpsdJsonError::IoError(err)) if err.kind() == ErrorKind::TimedOut => Ok(tpv),
+            disabled => disabled.map(|()| tpv),
+        }
+    }
+
+    /// Disables WATCH mode, skipping messages until GPSD confirms
+    ///
+    /// Read timeouts are retried as in [`wait_for_fix`](Self::wait_for_fix).
+    /// Fails with a [`TimedOut`](ErrorKind::TimedOut) I/O error if the
+    /// confirmation does not arrive within
+    /// [`UNWATCH_TIMEOUT`](crate::client::UNWATCH_TIMEOUT).
+    fn unwatch(&mut self) -> Result<()> {
+        let deadline = Instant::now() + crate::client::UNWATCH_TIMEOUT;
+        self.send(&v3::RequestMessage::Watch(
+            Some(v3::types::Watch::default()),
+        ))?;
+        loop {
+            if Instant::now() >= deadline {
+                return Err(GpsdJsonError::IoError(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    "disabled watch not confirmed by GPSD",
+                )));
+            }
+            match self.recv() {
+                Ok(Some(v3::ResponseMessage::Watch(_))) => return Ok(()),
+                Ok(Some(_)) | Err(GpsdJsonError::SerdeError(_)) => continue,
+                Err(GpsdJsonError::IoError(err))
+                    if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    continue;
+                }
+                Err(e) => return Err(e),
+                Ok(None) => {
+                    return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+                }
+            }
+        }
+    }
+
     /// Starts a data stream with the specified format and options
     ///
     /// This method consumes the client and returns a stream iterator
@@ -271,17 +814,58 @@ where
     /// ```
     pub fn stream<Format: StreamFormat>(
         mut self,
-        opts: StreamOptions<Format>,
+        opts: crate::client::StreamOptions<Format>,
     ) -> Result<GpsdDataStream<Stream, v3::V3, Format>> {
-        let (watch, _devices) = self.set_watch(opts.inner)?;
-        assert_eq!(watch.enable, Some(true));
+        let devices = opts.scope();
+        let mut command = String::new();
+        let mut confirmations = Vec::new();
+        let mut pending = Pending::new::<Format>();
+        for watch in opts.watches() {
+            command.push_str(&v3::RequestMessage::Watch(Some(watch.clone())).to_command()?);
+            let (watch, _devices) = self.set_watch_keeping(watch, &mut pending)?;
+            if watch.enable != Some(true) {
+                return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+            }
+            confirmations.push(watch);
+        }
+        pending.retain(|msg| in_scope(&devices, msg) && in_classes(&opts.classes, msg));
 
         Ok(GpsdDataStream {
             inner: self,
+            utf8: opts.utf8,
+            parse_errors: ParseErrors::new(opts.on_parse_error),
+            watch: command,
+            confirmations,
+            devices,
+            classes: opts.classes,
+            pending,
+            peeked: None,
+            watchdog: None,
+            keepalive: None,
             _format: std::marker::PhantomData,
         })
     }
 
+    /// Starts a JSON stream yielding only TPV reports
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::blocking::GpsdClient;
+    /// # use gpsd_json::client::StreamOptions;
+    /// # let client = GpsdClient::connect("127.0.0.1:2947").unwrap();
+    /// for tpv in client.tpv_stream(StreamOptions::json()).unwrap() {
+    ///     let tpv = tpv.unwrap();
+    ///     println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// }
+    /// ```
+    pub fn tpv_stream(
+        self,
+        opts: crate::client::StreamOptions<Json>,
+    ) -> Result<crate::client::TpvStream<GpsdDataStream<Stream, v3::V3, Json>>> {
+        let opts = opts.classes(&[v3::response::Class::Tpv]);
+        Ok(crate::client::TpvStream::new(self.stream(opts)?))
+    }
+
     /// Configures watch mode settings
     ///
     /// Internal method to set watch parameters and receive confirmation.
@@ -289,19 +873,43 @@ where
         &mut self,
         watch: v3::types::Watch,
     ) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
-        self.send(&v3::RequestMessage::Watch(Some(watch)))?;
-        let Some(v3::ResponseMessage::Devices(devices)) = self.recv()? else {
-            return Err(GpsdJsonError::ProtocolError(
-                "Expected devices response from GPSD",
-            ));
-        };
-        let Some(v3::ResponseMessage::Watch(watch)) = self.recv()? else {
-            return Err(GpsdJsonError::ProtocolError(
-                "Expected watch response from GPSD",
-            ));
-        };
+        self.set_watch_keeping(watch, &mut Pending::new::<Json>())
+    }
 
-        Ok((watch, devices))
+    /// Configures watch mode settings, keeping the messages that arrive
+    /// ahead of the confirmation in `pending`
+    ///
+    /// Devices watched before keep streaming while GPSD confirms the next
+    /// WATCH, so their data may arrive between the DEVICES and WATCH replies.
+    fn set_watch_keeping(
+        &mut self,
+        watch: v3::types::Watch,
+        pending: &mut Pending<v3::ResponseMessage>,
+    ) -> Result<(v3::types::Watch, v3::response::DeviceList)> {
+        self.send(&v3::RequestMessage::Watch(Some(watch)))?;
+        let mut devices = None;
+        loop {
+            let Some((line, msg)) = self.recv_line()? else {
+                return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+            };
+            match msg {
+                Ok(v3::ResponseMessage::Devices(list)) if devices.is_none() => {
+                    devices = Some(list);
+                }
+                Ok(v3::ResponseMessage::Watch(watch)) => {
+                    let Some(devices) = devices else {
+                        return Err(GpsdJsonError::ProtocolError(
+                            "Expected devices response from GPSD",
+                        ));
+                    };
+                    return Ok((watch, devices));
+                }
+                Ok(v3::ResponseMessage::Error(_)) => {
+                    return Err(GpsdJsonError::ProtocolError("Request rejected by GPSD"));
+                }
+                msg => pending.keep(line, msg, |_| true),
+            }
+        }
     }
 }
 
@@ -318,9 +926,210 @@ where
     Format: StreamFormat,
 {
     inner: GpsdClientCore<Stream, Proto>,
+    utf8: Utf8Policy,
+    parse_errors: ParseErrors,
+    watch: String,
+    confirmations: Vec<crate::protocol::v3::types::Watch>,
+    devices: Vec<String>,
+    classes: Vec<crate::protocol::v3::response::Class>,
+    pending: Pending<Proto::Response>,
+    peeked: Option<Option<Result<Proto::Response>>>,
+    watchdog: Option<Watchdog>,
+    keepalive: Option<Keepalive>,
     _format: std::marker::PhantomData<Format>,
 }
 
+/// State of the no-data watchdog of a stream
+#[derive(Debug)]
+struct Watchdog {
+    config: WatchdogConfig,
+    last_data: Instant,
+    attempts: u32,
+}
+
+impl<Stream, Proto, Format> GpsdDataStream<Stream, Proto, Format>
+where
+    Proto: GpsdJsonProtocol,
+    Format: StreamFormat,
+{
+    /// Returns metadata about the underlying connection
+    pub fn connection(&self) -> &ConnectionInfo {
+        &self.inner.info
+    }
+
+    /// Returns the WATCH confirmations of GPSD, one per subscribed device
+    pub fn confirmations(&self) -> &[crate::protocol::v3::types::Watch] {
+        &self.confirmations
+    }
+
+    /// Returns the number of unparsable lines dropped under
+    /// [`ParseErrorPolicy::Skip`](crate::client::ParseErrorPolicy::Skip)
+    pub fn skipped_lines(&self) -> u64 {
+        self.parse_errors.skipped
+    }
+
+    /// Returns the error of the last unparsable line dropped under
+    /// [`ParseErrorPolicy::Skip`](crate::client::ParseErrorPolicy::Skip)
+    pub fn last_parse_error(&self) -> Option<&GpsdJsonError> {
+        self.parse_errors.last.as_ref()
+    }
+
+    /// Returns the bytes allocated by the read buffers and the queue of
+    /// pending messages of this stream
+    ///
+    /// This stays constant while streaming lines of bounded length, so
+    /// growth over a long run indicates a leak.
+    pub fn buffer_capacity(&self) -> usize {
+        self.inner.reader.capacity() + self.inner.buf.capacity() + self.pending.capacity()
+    }
+
+    /// Tags every item of this stream with connection metadata
+    pub fn tagged(self) -> Tagged<Self> {
+        let source = self.inner.info;
+        Tagged::new(self, source)
+    }
+
+    /// Yields only every `n`-th item, e.g. to reduce a 10 Hz receiver to
+    /// 1 Hz with `decimate(10)`
+    pub fn decimate(self, n: usize) -> Decimate<Self> {
+        Decimate::new(self, n)
+    }
+
+    /// Enables the no-data watchdog
+    ///
+    /// The watchdog is checked when a read times out, so set a read
+    /// timeout shorter than the watchdog timeout on the socket. Read
+    /// timeouts are still returned as items. When the watchdog fires, the
+    /// WATCH command is re-sent; when the re-sends are exhausted, the
+    /// stream returns [`GpsdJsonError::ProtocolError`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{StreamOptions, WatchdogConfig, blocking::GpsdClient};
+    /// # use std::time::Duration;
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let socket = std::net::TcpStream::connect("127.0.0.1:2947")?;
+    /// socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    /// let stream = GpsdClient::try_from(socket)?
+    ///     .stream(StreamOptions::json())?
+    ///     .watchdog(WatchdogConfig::default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watchdog(mut self, config: WatchdogConfig) -> Self {
+        self.watchdog = Some(Watchdog {
+            config,
+            last_data: Instant::now(),
+            attempts: 0,
+        });
+        self
+    }
+
+    /// Re-sends the WATCH commands that started this stream
+    ///
+    /// The replies of GPSD arrive as regular stream items.
+    pub fn rewatch(&mut self) -> Result<()>
+    where
+        Stream: std::io::Write,
+    {
+        self.inner
+            .reader
+            .get_mut()
+            .write_all(self.watch.as_bytes())
+            .map_err(GpsdJsonError::IoError)
+    }
+
+    /// Sends `?VERSION;` to check that GPSD is still responsive
+    ///
+    /// The reply of GPSD arrives as a regular stream item.
+    pub fn ping(&mut self) -> Result<()>
+    where
+        Stream: std::io::Write,
+    {
+        self.inner
+            .reader
+            .get_mut()
+            .write_all(b"?VERSION;")
+            .map_err(GpsdJsonError::IoError)
+    }
+
+    /// Enables the keepalive
+    ///
+    /// Like the watchdog, the keepalive is checked when a read times out,
+    /// so set a read timeout shorter than the interval on the socket. Read
+    /// timeouts are still returned as items. See [`KeepaliveConfig`].
+    pub fn keepalive(mut self, config: KeepaliveConfig) -> Self {
+        self.keepalive = Some(Keepalive::new(config));
+        self
+    }
+
+    /// Passes a received item through the keepalive
+    fn keepalive_item<T>(&mut self, item: Option<Result<T>>) -> Option<Result<T>>
+    where
+        Stream: std::io::Write,
+    {
+        let Some(keepalive) = &mut self.keepalive else {
+            return item;
+        };
+
+        let timed_out = matches!(
+            &item,
+            Some(Err(GpsdJsonError::IoError(err)))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+        );
+        if !timed_out {
+            keepalive.received();
+            return item;
+        }
+        match keepalive.poll() {
+            KeepaliveAction::Wait(_) => item,
+            KeepaliveAction::Stale(silence) => Some(Err(GpsdJsonError::ConnectionStale(silence))),
+            KeepaliveAction::Ping => match self.ping() {
+                Ok(()) => item,
+                Err(err) => Some(Err(err)),
+            },
+        }
+    }
+
+    /// Passes a received item through the watchdog
+    fn watch_item<T>(&mut self, item: Option<Result<T>>) -> Option<Result<T>>
+    where
+        Stream: std::io::Write,
+    {
+        let Some(watchdog) = &mut self.watchdog else {
+            return item;
+        };
+        let now = Instant::now();
+
+        let timed_out = matches!(
+            &item,
+            Some(Err(GpsdJsonError::IoError(err)))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+        );
+        if !timed_out {
+            watchdog.last_data = now;
+            watchdog.attempts = 0;
+            return item;
+        }
+        if now.duration_since(watchdog.last_data) < watchdog.config.timeout {
+            return item;
+        }
+        if watchdog.attempts >= watchdog.config.rewatch_attempts {
+            return Some(Err(GpsdJsonError::ProtocolError(
+                "No data received from GPSD after re-sending WATCH",
+            )));
+        }
+
+        watchdog.attempts += 1;
+        watchdog.last_data = now;
+        match self.rewatch() {
+            Ok(()) => item,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(feature = "proto-v3")]
 impl<Stream, Format> GpsdDataStream<Stream, v3::V3, Format>
 where
     Stream: std::io::Read + std::io::Write,
@@ -334,60 +1143,888 @@ where
         let watch = v3::types::Watch::default();
 
         let (watch, _devices) = self.inner.set_watch(watch)?;
-        assert_eq!(watch.enable, Some(false));
+        if watch.enable != Some(false) {
+            return Err(GpsdJsonError::UnexpectedWatch(Box::new(watch)));
+        }
 
         Ok(self.inner)
     }
+
+    /// Passes every item to a callback until a shutdown flag is set
+    ///
+    /// When `shutdown` is set, WATCH mode is disabled and the client is
+    /// returned, as with [`close`](Self::close). This packages the usual
+    /// service loop around signal handling such as SIGTERM.
+    ///
+    /// The flag is checked after every received line. To bound the
+    /// shutdown delay when GPSD is quiet, set a read timeout on the
+    /// underlying socket; read timeouts are not treated as errors.
+    ///
+    /// Items are passed to the callback as received, including decode
+    /// errors. The run ends early with an error if the connection fails
+    /// or GPSD closes it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{blocking::GpsdClient, StreamOptions};
+    /// # use std::sync::atomic::AtomicBool;
+    /// static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947")?;
+    /// let stream = client.stream(StreamOptions::json())?;
+    /// let client = stream.run_until_flag(&SHUTDOWN, |msg| println!("{msg:?}"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_until_flag<Item, Callback>(
+        mut self,
+        shutdown: &std::sync::atomic::AtomicBool,
+        mut callback: Callback,
+    ) -> Result<GpsdClient<Stream>>
+    where
+        Self: Iterator<Item = Result<Item>>,
+        Callback: FnMut(Result<Item>),
+    {
+        use std::{io::ErrorKind, sync::atomic::Ordering};
+
+        while !shutdown.load(Ordering::Relaxed) {
+            match self.next() {
+                Some(Err(GpsdJsonError::IoError(err)))
+                    if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    continue;
+                }
+                Some(Err(GpsdJsonError::IoError(err))) => return Err(GpsdJsonError::IoError(err)),
+                Some(item) => callback(item),
+                None => {
+                    return Err(GpsdJsonError::ProtocolError(
+                        "Stream ended before shutdown was requested",
+                    ));
+                }
+            }
+        }
+
+        self.close()
+    }
+}
+
+#[cfg(feature = "proto-v3")]
+impl<Stream> GpsdDataStream<Stream, v3::V3, Json>
+where
+    Stream: std::io::Read + std::io::Write,
+{
+    /// Sends a request while streaming, e.g. `?POLL;` or `?DEVICE;`
+    ///
+    /// Unlike [`poll`](Self::poll), this does not wait for the reply,
+    /// which arrives as a regular stream item.
+    pub fn send_request(&mut self, request: &v3::RequestMessage) -> Result<()> {
+        self.inner.send(request)
+    }
+
+    /// Drops TPV reports repeating the time of the last TPV report of
+    /// their device
+    pub fn dedup_by_time(self) -> crate::client::DedupByTime<Self> {
+        crate::client::DedupByTime::new(self)
+    }
+
+    /// Polls GPSD for the current fixes without leaving the stream
+    ///
+    /// Messages received before the reply are kept and returned by the
+    /// stream afterwards, so control commands can be interleaved with
+    /// streaming.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{blocking::GpsdClient, StreamOptions};
+    /// # fn example() -> gpsd_json::Result<()> {
+    /// let client = GpsdClient::connect("127.0.0.1:2947")?;
+    /// let mut stream = client.stream(StreamOptions::json())?;
+    /// println!("{:?}", stream.poll()?);
+    /// for msg in stream {
+    ///     println!("{:?}", msg?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn poll(&mut self) -> Result<v3::response::Poll> {
+        let v3::ResponseMessage::Poll(poll) = self.query(v3::RequestMessage::Poll, |msg| {
+            matches!(msg, v3::ResponseMessage::Poll(_))
+        })?
+        else {
+            return Err(GpsdJsonError::ProtocolError(
+                "Expected poll response from GPSD",
+            ));
+        };
+        Ok(poll)
+    }
+
+    /// Lists the devices known to GPSD without leaving the stream
+    ///
+    /// Messages received before the reply are kept, as with
+    /// [`poll`](Self::poll).
+    pub fn devices(&mut self) -> Result<v3::response::DeviceList> {
+        let v3::ResponseMessage::Devices(devices) = self
+            .query(v3::RequestMessage::Devices, |msg| {
+                matches!(msg, v3::ResponseMessage::Devices(_))
+            })?
+        else {
+            return Err(GpsdJsonError::ProtocolError(
+                "Expected devices response from GPSD",
+            ));
+        };
+        Ok(devices)
+    }
+
+    /// Sends a request and waits for the reply recognized by `is_reply`,
+    /// keeping other messages for the stream
+    fn query(
+        &mut self,
+        request: v3::RequestMessage,
+        is_reply: fn(&v3::ResponseMessage) -> bool,
+    ) -> Result<v3::ResponseMessage> {
+        self.inner.send(&request)?;
+        loop {
+            let Some((line, msg)) = self.inner.recv_line()? else {
+                return Err(GpsdJsonError::ProtocolError("Connection closed by GPSD"));
+            };
+            match msg {
+                Ok(msg) if is_reply(&msg) => return Ok(msg),
+                Ok(v3::ResponseMessage::Error(_)) => {
+                    return Err(GpsdJsonError::ProtocolError("Request rejected by GPSD"));
+                }
+                msg => self.pending.keep(line, msg, |msg| {
+                    in_scope(&self.devices, msg) && in_classes(&self.classes, msg)
+                }),
+            }
+        }
+    }
+}
+
+impl<Stream, Proto> GpsdDataStream<Stream, Proto, Json>
+where
+    Stream: std::io::Read + std::io::Write,
+    Proto: GpsdJsonProtocol,
+{
+    /// Returns the next item without consuming it
+    ///
+    /// The item is read ahead and returned by the following call of
+    /// `next()`, also through [`by_ref`](Iterator::by_ref).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use gpsd_json::client::{StreamOptions, blocking::GpsdClient};
+    /// # use gpsd_json::protocol::v3::response::Message;
+    /// # let client = GpsdClient::connect("127.0.0.1:2947").unwrap();
+    /// let mut stream = client.stream(StreamOptions::json()).unwrap();
+    /// // Skip to the first TPV of the next epoch
+    /// while let Some(Ok(msg)) = stream.peek() {
+    ///     if matches!(msg, Message::Tpv(_)) {
+    ///         break;
+    ///     }
+    ///     stream.next();
+    /// }
+    /// ```
+    pub fn peek(&mut self) -> Option<&Result<Proto::Response>> {
+        if self.peeked.is_none() {
+            let item = self.next();
+            self.peeked = Some(item);
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    /// Consumes the next item if it is a message satisfying `func`
+    pub fn next_if(
+        &mut self,
+        func: impl FnOnce(&Proto::Response) -> bool,
+    ) -> Option<Proto::Response> {
+        match self.peek() {
+            Some(Ok(msg)) if func(msg) => self.next().and_then(Result::ok),
+            _ => None,
+        }
+    }
+
+    /// Yields at most one message per `period`, dropping the others
+    ///
+    /// The first message received after the period of the last yielded
+    /// message is yielded, messages received within the period are dropped.
+    /// Messages of all classes count against the period, so restrict the
+    /// stream with
+    /// [`StreamOptions::classes`](crate::client::StreamOptions::classes) to
+    /// rate limit e.g. only TPV reports.
+    pub fn rate_limit(self, period: std::time::Duration) -> RateLimit<Self> {
+        RateLimit::new(self, period)
+    }
 }
 
 impl<Stream, Proto> Iterator for GpsdDataStream<Stream, Proto, Json>
 where
-    Stream: std::io::Read,
+    Stream: std::io::Read + std::io::Write,
     Proto: GpsdJsonProtocol,
 {
     type Item = Result<Proto::Response>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.recv().transpose()
+        if let Some(item) = self.peeked.take() {
+            return item;
+        }
+        while let Some(item) = self.pending.pop_message() {
+            if self.parse_errors.aborted() {
+                return None;
+            }
+            if let Some(item) = self.parse_errors.filter(item) {
+                return Some(item);
+            }
+        }
+        loop {
+            if self.parse_errors.aborted() {
+                return None;
+            }
+            let item = self.inner.recv().transpose();
+            let item = self.watch_item(item);
+            match self.keepalive_item(item) {
+                Some(Ok(msg))
+                    if !in_scope(&self.devices, &msg) || !in_classes(&self.classes, &msg) =>
+                {
+                    continue;
+                }
+                Some(item) => match self.parse_errors.filter(item) {
+                    Some(item) => return Some(item),
+                    None => continue,
+                },
+                None => return None,
+            }
+        }
     }
 }
 
 impl<Stream, Proto> Iterator for GpsdDataStream<Stream, Proto, Nmea>
 where
-    Stream: std::io::Read,
+    Stream: std::io::Read + std::io::Write,
     Proto: GpsdJsonProtocol,
 {
-    type Item = Result<String>;
+    type Item = Result<NmeaLine>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line) = self.pending.pop_line() {
+            return Some(self.utf8.decode(line));
+        }
         self.inner.buf.clear();
 
-        match self.inner.reader.read_until(b'\n', &mut self.inner.buf) {
+        let item = match self.inner.reader.read_until(b'\n', &mut self.inner.buf) {
             Ok(0) => None, // EOF reached
-            Ok(_) => Some(Ok(String::from_utf8_lossy(&self.inner.buf)
-                .trim_end()
-                .to_string())),
+            Ok(_) => {
+                let mut line = std::mem::take(&mut self.inner.buf);
+                strip_line_ending(&mut line);
+                Some(self.utf8.decode(line))
+            }
             Err(e) => Some(Err(GpsdJsonError::IoError(e))),
-        }
+        };
+        let item = self.watch_item(item);
+        self.keepalive_item(item)
     }
 }
 
 impl<Stream, Proto> Iterator for GpsdDataStream<Stream, Proto, Raw>
 where
-    Stream: std::io::Read,
+    Stream: std::io::Read + std::io::Write,
     Proto: GpsdJsonProtocol,
 {
     type Item = Result<String>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line) = self.pending.pop_line() {
+            return Some(Ok(String::from_utf8_lossy(&line).into_owned()));
+        }
         self.inner.buf.clear();
 
-        match self.inner.reader.read_until(b'\n', &mut self.inner.buf) {
+        let item = match self.inner.reader.read_until(b'\n', &mut self.inner.buf) {
             Ok(0) => None, // EOF reached
-            Ok(_) => Some(Ok(String::from_utf8_lossy(&self.inner.buf)
-                .trim_end()
-                .to_string())),
+            Ok(_) => {
+                strip_line_ending(&mut self.inner.buf);
+                Some(Ok(String::from_utf8_lossy(&self.inner.buf).into_owned()))
+            }
             Err(e) => Some(Err(GpsdJsonError::IoError(e))),
+        };
+        let item = self.watch_item(item);
+        self.keepalive_item(item)
+    }
+}
+
+impl<Stream, Proto> Iterator for GpsdDataStream<Stream, Proto, Capture>
+where
+    Stream: std::io::Read + std::io::Write,
+    Proto: GpsdJsonProtocol,
+{
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line) = self.pending.pop_line() {
+            return Some(Ok(line));
+        }
+        self.inner.buf.clear();
+
+        let item = match self.inner.reader.read_until(b'\n', &mut self.inner.buf) {
+            Ok(0) => None, // EOF reached
+            Ok(_) => {
+                let mut line = std::mem::take(&mut self.inner.buf);
+                strip_line_ending(&mut line);
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(GpsdJsonError::IoError(e))),
+        };
+        let item = self.watch_item(item);
+        self.keepalive_item(item)
+    }
+}
+
+#[cfg(all(test, unix, feature = "proto-v3"))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+
+    use crate::fixtures;
+
+    /// Client of a server sending the greeting and `lines`, then closing
+    fn streaming_client(lines: &[&str]) -> (GpsdClient<UnixStream>, UnixStream) {
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        writeln!(server, "{}", fixtures::VERSION).unwrap();
+        for line in lines {
+            writeln!(server, "{line}").unwrap();
+        }
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        (GpsdClient::open(stream).unwrap(), server)
+    }
+
+    #[test]
+    fn test_open_external_socket() {
+        // Reply to the VERSION request
+        let (mut client, _server) = streaming_client(&[fixtures::VERSION]);
+        assert_eq!(client.connection().peer, None);
+        assert_eq!(client.version().unwrap().release, "3.25");
+    }
+
+    #[test]
+    fn test_connect_unix() {
+        let path =
+            std::env::temp_dir().join(format!("gpsd-json-blocking-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            writeln!(stream, "{}", fixtures::VERSION).unwrap();
+        });
+
+        let client = GpsdClient::connect_unix(&path).unwrap();
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(client.connection().peer, None);
+    }
+
+    #[test]
+    fn test_negotiator_selects_protocol() {
+        enum Client {
+            V3(GpsdClient<UnixStream>),
+        }
+        let negotiator = Negotiator::new().register::<v3::V3>(Client::V3);
+
+        // Newer minor versions are supported
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        writeln!(
+            server,
+            r#"{{"class":"VERSION","release":"3.26","rev":"3.26","proto_major":3,"proto_minor":16}}"#
+        )
+        .unwrap();
+        let Ok(Client::V3(client)) = negotiator.open(stream) else {
+            panic!("expected a protocol version 3 client");
+        };
+        assert_eq!(client.connection().generation, 0);
+
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        writeln!(
+            server,
+            r#"{{"class":"VERSION","release":"4.0","rev":"4.0","proto_major":4,"proto_minor":0}}"#
+        )
+        .unwrap();
+        assert!(matches!(
+            negotiator.open(stream),
+            Err(GpsdJsonError::UnsupportedProtocolVersion((4, 0)))
+        ));
+    }
+
+    #[test]
+    fn test_watchdog_rewatch() {
+        use std::io::Read;
+        use std::time::Duration;
+
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        let version =
+            r#"{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}"#;
+        let devices = r#"{"class":"DEVICES","devices":[]}"#;
+        let watch = r#"{"class":"WATCH","enable":true,"json":true}"#;
+        writeln!(server, "{version}\n{devices}\n{watch}").unwrap();
+
+        let mut stream = GpsdClient::open(stream)
+            .unwrap()
+            .stream(crate::client::StreamOptions::json())
+            .unwrap()
+            .watchdog(WatchdogConfig {
+                timeout: Duration::from_millis(30),
+                rewatch_attempts: 1,
+            });
+        let failure = stream.find(|item| matches!(item, Err(GpsdJsonError::ProtocolError(_))));
+        assert!(failure.is_some());
+
+        drop(stream);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert_eq!(requests.matches("?WATCH=").count(), 2);
+    }
+
+    #[test]
+    fn test_stream_multiple_devices() {
+        use std::io::Read;
+
+        let watch = |device| {
+            format!(r#"{{"class":"WATCH","enable":true,"json":true,"device":"{device}"}}"#)
+        };
+        let (acm, usb) = (watch("/dev/ttyACM0"), watch("/dev/ttyUSB0"));
+        // The first device streams while the second one is confirmed
+        let (client, mut server) = streaming_client(&[
+            fixtures::DEVICES,
+            &acm,
+            fixtures::DEVICES,
+            fixtures::TPV,
+            &usb,
+        ]);
+
+        let opts = crate::client::StreamOptions::json().devices([
+            "/dev/ttyACM0",
+            "/dev/ttyUSB0",
+            "/dev/ttyACM0",
+        ]);
+        let mut stream = client.stream(opts).unwrap();
+        let confirmed: Vec<_> = stream
+            .confirmations()
+            .iter()
+            .map(|watch| watch.device.as_deref())
+            .collect();
+        assert_eq!(confirmed, [Some("/dev/ttyACM0"), Some("/dev/ttyUSB0")]);
+        assert!(matches!(
+            stream.next(),
+            Some(Ok(v3::ResponseMessage::Tpv(_)))
+        ));
+
+        drop(stream);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        let watches: Vec<_> = requests
+            .split_terminator(';')
+            .filter(|command| command.trim_start().starts_with("?WATCH="))
+            .collect();
+        assert_eq!(watches.len(), 2);
+        assert!(watches[0].contains("/dev/ttyACM0") && watches[1].contains("/dev/ttyUSB0"));
+    }
+
+    #[test]
+    fn test_stream_multiple_devices_nmea() {
+        let watch = |device| {
+            format!(r#"{{"class":"WATCH","enable":true,"nmea":true,"device":"{device}"}}"#)
+        };
+        let (acm, usb) = (watch("/dev/ttyACM0"), watch("/dev/ttyUSB0"));
+        // The sentence of the first device arrives while the second one is
+        // confirmed and must not be lost
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            &acm,
+            fixtures::DEVICES,
+            "$GPGGA,1*5B",
+            &usb,
+            "$GPGGA,2*58",
+        ]);
+
+        let opts = crate::client::StreamOptions::nmea().devices(["/dev/ttyACM0", "/dev/ttyUSB0"]);
+        let lines: Vec<_> = client
+            .stream(opts)
+            .unwrap()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                NmeaLine::Text("$GPGGA,1*5B".to_string()),
+                NmeaLine::Text("$GPGGA,2*58".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_device_scope() {
+        let other = fixtures::TPV.replace("/dev/ttyACM0", "/dev/ttyUSB0");
+        let (client, _server) =
+            streaming_client(&[fixtures::DEVICES, fixtures::WATCH, &other, fixtures::TPV]);
+
+        let opts = crate::client::StreamOptions::json().device("/dev/ttyACM0");
+        let stream = client.stream(opts).unwrap();
+        let devices: Vec<_> = stream
+            .map(|msg| msg.unwrap().device().map(str::to_string))
+            .collect();
+        assert_eq!(devices, [Some("/dev/ttyACM0".to_string())]);
+    }
+
+    #[test]
+    fn test_device_by_path() {
+        use std::io::Read;
+
+        let device = r#"{"class":"DEVICE","path":"/dev/ttyUSB0","driver":"NMEA0183","bps":4800}"#;
+        let (mut client, mut server) = streaming_client(&[device]);
+        let device = client.device_by_path("/dev/ttyUSB0").unwrap();
+        assert_eq!(device.driver.as_deref(), Some("NMEA0183"));
+        assert_eq!(device.bps, Some(4800));
+
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert_eq!(requests, r#"?DEVICE={"path":"/dev/ttyUSB0"};"#);
+    }
+
+    #[test]
+    fn test_configure_device() {
+        use std::io::Read;
+
+        use crate::client::DeviceConfig;
+
+        let applied =
+            r#"{"class":"DEVICE","path":"/dev/ttyUSB0","bps":9600,"parity":"N","stopbits":1}"#;
+        let unchanged =
+            r#"{"class":"DEVICE","path":"/dev/ttyUSB0","bps":4800,"parity":"N","stopbits":1}"#;
+        let (mut client, mut server) = streaming_client(&[applied, unchanged]);
+        let config = DeviceConfig::new("/dev/ttyUSB0").bps(9600).stopbits(1);
+        assert_eq!(client.configure_device(&config).unwrap().bps, Some(9600));
+        assert!(matches!(
+            client.configure_device(&config),
+            Err(GpsdJsonError::ProtocolError(_))
+        ));
+
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert_eq!(
+            requests.split_terminator(';').next(),
+            Some(r#"?DEVICE={"path":"/dev/ttyUSB0","bps":9600,"stopbits":1}"#)
+        );
+    }
+
+    #[test]
+    fn test_set_speed() {
+        use std::io::Read;
+        use std::time::Duration;
+
+        let stale = r#"{"class":"DEVICE","path":"/dev/ttyUSB0","bps":9600}"#;
+        let switched = r#"{"class":"DEVICE","path":"/dev/ttyUSB0","bps":115200}"#;
+        // The old speed is acknowledged first, then a line garbled by the switch
+        let (mut client, mut server) = streaming_client(&[stale, "$GP#garbled", switched]);
+        let device = client
+            .set_speed("/dev/ttyUSB0", 115200, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(device.bps, Some(115200));
+
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert_eq!(requests, r#"?DEVICE={"path":"/dev/ttyUSB0","bps":115200};"#);
+    }
+
+    #[test]
+    fn test_stream_unexpected_watch() {
+        let watch = r#"{"class":"WATCH","enable":false,"json":false}"#;
+        let (client, _server) = streaming_client(&[fixtures::DEVICES, watch]);
+        let result = client.stream(crate::client::StreamOptions::json());
+        assert!(matches!(
+            result,
+            Err(GpsdJsonError::UnexpectedWatch(watch)) if watch.enable == Some(false)
+        ));
+    }
+
+    #[test]
+    fn test_stream_poll() {
+        let poll = r#"{"class":"POLL","time":"2025-01-01T00:00:01.000Z","active":1,"tpv":[],"gst":[],"sky":[]}"#;
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+            poll,
+            fixtures::SKY,
+        ]);
+        let mut stream = client.stream(crate::client::StreamOptions::json()).unwrap();
+        stream.poll().unwrap();
+        let classes: Vec<_> = stream
+            .map(|msg| msg.unwrap().class_name().to_string())
+            .collect();
+        assert_eq!(classes, ["TPV", "SKY"]);
+    }
+
+    #[test]
+    fn test_stream_parse_error_policy() {
+        use crate::client::{ParseErrorPolicy, StreamOptions};
+
+        let open = |policy| {
+            let (client, server) = streaming_client(&[
+                fixtures::DEVICES,
+                fixtures::WATCH,
+                fixtures::TPV,
+                r#"{"class":"TPV",,}"#,
+                fixtures::SKY,
+            ]);
+            let stream = client
+                .stream(StreamOptions::json().on_parse_error(policy))
+                .unwrap();
+            (stream, server)
+        };
+
+        let (mut stream, _server) = open(ParseErrorPolicy::Skip);
+        let classes: Vec<_> = stream
+            .by_ref()
+            .map(|msg| msg.unwrap().class_name().to_string())
+            .collect();
+        assert_eq!(classes, ["TPV", "SKY"]);
+        assert_eq!(stream.skipped_lines(), 1);
+        assert!(matches!(
+            stream.last_parse_error(),
+            Some(GpsdJsonError::SerdeError(_))
+        ));
+
+        let (stream, _server) = open(ParseErrorPolicy::Abort);
+        let results: Vec<_> = stream.map(|msg| msg.is_ok()).collect();
+        assert_eq!(results, [true, false]);
+        let (stream, _server) = open(ParseErrorPolicy::Yield);
+        let results: Vec<_> = stream.map(|msg| msg.is_ok()).collect();
+        assert_eq!(results, [true, false, true]);
+    }
+
+    #[test]
+    fn test_stream_peek() {
+        use crate::protocol::v3::response::Message;
+
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+            fixtures::SKY,
+        ]);
+        let mut stream = client.stream(crate::client::StreamOptions::json()).unwrap();
+        assert!(matches!(stream.peek(), Some(Ok(Message::Tpv(_)))));
+        assert!(matches!(stream.peek(), Some(Ok(Message::Tpv(_)))));
+        assert!(stream.next_if(|msg| msg.class_name() == "SKY").is_none());
+        assert!(matches!(stream.by_ref().next(), Some(Ok(Message::Tpv(_)))));
+        assert!(stream.next_if(|msg| msg.class_name() == "SKY").is_some());
+        assert!(stream.peek().is_none());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_stream_classes() {
+        use crate::protocol::v3::response::Class;
+
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+            fixtures::PPS,
+            fixtures::DEVICE,
+        ]);
+        let opts = crate::client::StreamOptions::json().classes(&[Class::Device, Class::Tpv]);
+        let classes: Vec<_> = client
+            .stream(opts)
+            .unwrap()
+            .map(|msg| msg.unwrap().class().unwrap())
+            .collect();
+        assert_eq!(classes, [Class::Tpv, Class::Device]);
+        assert_eq!(Class::from_name(Class::Watch.name()), Some(Class::Watch));
+    }
+
+    #[test]
+    fn test_tpv_stream() {
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+            fixtures::SKY,
+            fixtures::TPV_NO_FIX,
+        ]);
+        let lats: Vec<_> = client
+            .tpv_stream(crate::client::StreamOptions::json())
+            .unwrap()
+            .map(|tpv| tpv.unwrap().lat)
+            .collect();
+        assert_eq!(lats, [Some(35.681236), None]);
+    }
+
+    #[test]
+    fn test_rate_limit_decimate() {
+        let open = || {
+            let mut lines = vec![fixtures::DEVICES, fixtures::WATCH];
+            for _ in 0..5 {
+                lines.extend([fixtures::TPV, fixtures::TPV_NO_FIX]);
+            }
+            let (client, server) = streaming_client(&lines);
+            let stream = client.stream(crate::client::StreamOptions::json()).unwrap();
+            (stream, server)
+        };
+        let mode = |msg: Result<v3::response::Message>| match msg.unwrap() {
+            v3::response::Message::Tpv(tpv) => tpv.mode,
+            other => panic!("unexpected message {other:?}"),
+        };
+
+        // The second of every pair
+        let (stream, _server) = open();
+        let modes: Vec<_> = stream.decimate(2).map(mode).collect();
+        assert_eq!(modes, [v3::types::FixMode::NoFix; 5]);
+
+        // The first only, the others are within the period
+        let (stream, _server) = open();
+        let modes: Vec<_> = stream
+            .rate_limit(std::time::Duration::from_secs(60))
+            .map(mode)
+            .collect();
+        assert_eq!(modes, [v3::types::FixMode::Fix3D]);
+    }
+
+    #[test]
+    fn test_dedup_by_time() {
+        let (client, _server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+            fixtures::TPV,
+            fixtures::SKY,
+            fixtures::TPV_NO_FIX,
+            fixtures::TPV_NO_FIX,
+        ]);
+        let mut stream = client
+            .stream(crate::client::StreamOptions::json())
+            .unwrap()
+            .dedup_by_time();
+        let classes: Vec<_> = stream
+            .by_ref()
+            .map(|msg| msg.unwrap().class_name().to_string())
+            .collect();
+        assert_eq!(classes, ["TPV", "SKY", "TPV"]);
+        assert_eq!(stream.dropped(), 2);
+    }
+
+    #[test]
+    fn test_send_control() {
+        use std::io::Read;
+
+        use crate::client::control::{ControlWarning, HexData};
+
+        let (mut client, mut server) = streaming_client(&[fixtures::DEVICE]);
+        let poll = HexData::from_hex("b5620a0400000e34").unwrap();
+        let report = client.send_control("/dev/ttyACM0", &poll).unwrap();
+        assert_eq!(report.device.driver.as_deref(), Some("u-blox"));
+        assert_eq!(report.warnings, Vec::<ControlWarning>::new());
+
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert_eq!(
+            requests,
+            r#"?DEVICE={"path":"/dev/ttyACM0","hexdata":"b5620a0400000e34"};"#
+        );
+    }
+
+    #[test]
+    fn test_wait_for_fix() {
+        use std::io::Read;
+        use std::time::Duration;
+
+        let unwatched = r#"{"class":"WATCH","enable":false}"#;
+        let (mut client, mut server) = streaming_client(&[
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV_NO_FIX,
+            fixtures::TPV,
+            unwatched,
+        ]);
+        let tpv = client
+            .wait_for_fix(v3::types::FixMode::Fix2D, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(tpv.lat, Some(35.681236));
+
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert!(requests.contains(r#""enable":true"#) && requests.contains(r#""enable":false"#));
+
+        // WATCH is disabled when waiting fails, too
+        let (mut client, mut server) = streaming_client(&[fixtures::DEVICES, fixtures::WATCH]);
+        assert!(matches!(
+            client.wait_for_fix(v3::types::FixMode::Fix2D, Duration::from_secs(5)),
+            Err(GpsdJsonError::ProtocolError(_))
+        ));
+        drop(client);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert!(requests.contains(r#""enable":false"#));
+
+        // The fix is kept when GPSD does not confirm the disabled watch
+        // before the read timeouts add up
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        for line in [
+            fixtures::VERSION,
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::TPV,
+        ] {
+            writeln!(server, "{line}").unwrap();
         }
+        let mut client = GpsdClient::open(stream).unwrap();
+        let tpv = client
+            .wait_for_fix(v3::types::FixMode::Fix2D, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(tpv.mode, v3::types::FixMode::Fix3D);
+    }
+
+    #[test]
+    fn test_keepalive_stale() {
+        use std::io::Read;
+        use std::time::Duration;
+
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        let version =
+            r#"{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}"#;
+        let devices = r#"{"class":"DEVICES","devices":[]}"#;
+        let watch = r#"{"class":"WATCH","enable":true,"json":true}"#;
+        writeln!(server, "{version}\n{devices}\n{watch}").unwrap();
+
+        let mut stream = GpsdClient::open(stream)
+            .unwrap()
+            .stream(crate::client::StreamOptions::json())
+            .unwrap()
+            .keepalive(KeepaliveConfig {
+                interval: Duration::from_millis(20),
+                window: Duration::from_millis(100),
+            });
+        let stale = stream.find_map(|item| match item {
+            Err(GpsdJsonError::ConnectionStale(silence)) => Some(silence),
+            _ => None,
+        });
+        assert!(stale.unwrap() >= Duration::from_millis(100));
+
+        drop(stream);
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert!(requests.matches("?VERSION;").count() >= 2);
     }
 }
@@ -0,0 +1,199 @@
+//! Cache of the latest fix of each device
+//!
+//! Many consumers only need the current position, not every message. A
+//! [`FixCache`] consumes a JSON stream on its own tokio task and keeps the
+//! latest TPV, SKY and GST reports of each device in a
+//! [`watch`](tokio::sync::watch) channel, so the current state can be read
+//! at any time with [`FixCache::latest_fix`] or awaited with
+//! [`FixCache::subscribe`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::{GpsdClient, StreamOptions, cache::FixCache};
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let client = GpsdClient::connect("127.0.0.1:2947").await?;
+//! let cache = FixCache::spawn(client.stream(StreamOptions::json()).await?);
+//!
+//! // Later, wherever the position is needed
+//! if let Some(tpv) = cache.latest_fix("/dev/ttyACM0").and_then(|fix| fix.tpv) {
+//!     println!("{:?}, {:?}", tpv.lat, tpv.lon);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FixCache`]: crate::client::cache::FixCache
+//! [`FixCache::latest_fix`]: crate::client::cache::FixCache::latest_fix
+//! [`FixCache::subscribe`]: crate::client::cache::FixCache::subscribe
+
+use std::collections::BTreeMap;
+
+use futures_util::StreamExt;
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    Result,
+    client::service::GpsdService,
+    error::GpsdJsonError,
+    protocol::v3::response::{Gst, Message, Sky, Tpv},
+};
+
+/// Latest reports of one device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceFix {
+    /// Latest TPV report
+    pub tpv: Option<Tpv>,
+    /// Latest SKY report
+    pub sky: Option<Sky>,
+    /// Latest GST report
+    pub gst: Option<Gst>,
+}
+
+/// Latest reports by device path
+///
+/// Reports without a device are kept under the empty path.
+pub type Fixes = BTreeMap<String, DeviceFix>;
+
+/// Latest fix of each device, kept up to date on a background task
+///
+/// The task ends when the stream ends or fails with an I/O error; other
+/// errors, such as unparsable lines, are skipped. Dropping the cache stops
+/// the task.
+#[derive(Debug)]
+pub struct FixCache {
+    fixes: watch::Receiver<Fixes>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl FixCache {
+    /// Consumes a JSON stream, e.g. a
+    /// [`GpsdDataStream`](crate::client::GpsdDataStream)
+    pub fn spawn<S>(stream: S) -> Self
+    where
+        S: futures_util::Stream<Item = Result<Message>> + Send + 'static,
+    {
+        let (sender, fixes) = watch::channel(Fixes::new());
+        let task = tokio::spawn(async move {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(msg) = stream.next().await {
+                match msg {
+                    Ok(msg) => {
+                        sender.send_if_modified(|fixes| update(fixes, &msg));
+                    }
+                    Err(GpsdJsonError::IoError(_) | GpsdJsonError::ConnectionStale(_)) => break,
+                    Err(_) => {}
+                }
+            }
+        });
+        FixCache { fixes, task }
+    }
+
+    /// Follows the messages published by a service
+    ///
+    /// Messages missed by lagging behind the service are skipped.
+    pub fn from_service(service: &GpsdService) -> Self {
+        let mut messages = service.subscribe();
+        let (sender, fixes) = watch::channel(Fixes::new());
+        let task = tokio::spawn(async move {
+            loop {
+                match messages.recv().await {
+                    Ok(msg) => {
+                        sender.send_if_modified(|fixes| update(fixes, &msg));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        FixCache { fixes, task }
+    }
+
+    /// Returns the latest reports of a device
+    pub fn latest_fix(&self, device: &str) -> Option<DeviceFix> {
+        self.fixes.borrow().get(device).cloned()
+    }
+
+    /// Returns the paths of the devices with reports
+    pub fn devices(&self) -> Vec<String> {
+        self.fixes.borrow().keys().cloned().collect()
+    }
+
+    /// Returns a receiver notified of every new report
+    pub fn subscribe(&self) -> watch::Receiver<Fixes> {
+        self.fixes.clone()
+    }
+
+    /// Returns true while the cache follows its stream
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+}
+
+impl Drop for FixCache {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Keeps a TPV, SKY or GST report and returns true if it was one
+fn update(fixes: &mut Fixes, msg: &Message) -> bool {
+    let device = || msg.device().unwrap_or_default().to_string();
+    match msg {
+        Message::Tpv(tpv) => fixes.entry(device()).or_default().tpv = Some(tpv.clone()),
+        #[cfg(feature = "msg-sky")]
+        Message::Sky(sky) => fixes.entry(device()).or_default().sky = Some(sky.clone()),
+        #[cfg(feature = "msg-gst")]
+        Message::Gst(gst) => fixes.entry(device()).or_default().gst = Some(gst.clone()),
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write;
+
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    use super::*;
+    use crate::{
+        client::{GpsdClient, StreamOptions},
+        fixtures,
+    };
+
+    #[tokio::test]
+    async fn test_fix_cache() {
+        let (stream, mut server) = std::os::unix::net::UnixStream::pair().unwrap();
+        writeln!(
+            server,
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            fixtures::VERSION,
+            fixtures::DEVICES,
+            fixtures::WATCH,
+            fixtures::SKY,
+            fixtures::PPS,
+            fixtures::TPV
+        )
+        .unwrap();
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let stream = tokio::net::UnixStream::from_std(stream).unwrap().compat();
+
+        let client = GpsdClient::open(stream).await.unwrap();
+        let cache = FixCache::spawn(client.stream(StreamOptions::json()).await.unwrap());
+        cache
+            .subscribe()
+            .wait_for(|fixes| fixes.values().any(|fix| fix.tpv.is_some()))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.devices(), ["/dev/ttyACM0"]);
+        let fix = cache.latest_fix("/dev/ttyACM0").unwrap();
+        assert_eq!(fix.tpv.unwrap().lat, Some(35.681236));
+        assert_eq!(fix.sky.is_some(), cfg!(feature = "msg-sky"));
+        assert!(fix.gst.is_none());
+        assert!(cache.latest_fix("/dev/ttyUSB0").is_none());
+    }
+}
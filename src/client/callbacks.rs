@@ -0,0 +1,234 @@
+//! Callback-based consumption of GPSD reports
+//!
+//! Instead of matching on the messages of a data stream, callbacks are
+//! registered per report class and called while the stream runs, similar
+//! to the callback API of libgps. The callbacks are built on the JSON data
+//! stream of the [async](crate::client::GpsdClient) and
+//! [blocking](crate::client::blocking::GpsdClient) clients.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::GpsdClient;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! GpsdClient::connect("127.0.0.1:2947")
+//!     .await?
+//!     .on_tpv(|tpv| println!("position: {:?}, {:?}", tpv.lat, tpv.lon))
+//!     .on_device(|device| println!("device: {:?}", device.path))
+//!     .run()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Result,
+    client::{Json, StreamOptions, blocking},
+    protocol::v3::{
+        response::{Message, Tpv},
+        types::Device,
+    },
+};
+
+/// Callback called with every message of the stream
+type Handler = Box<dyn FnMut(&Message) + Send>;
+
+/// Client with callbacks registered per report class
+///
+/// Callbacks are called in the order they were registered. Messages of
+/// classes without callbacks are dropped.
+pub struct Callbacks<Client> {
+    client: Client,
+    options: StreamOptions<Json>,
+    handlers: Vec<Handler>,
+}
+
+impl<Client: std::fmt::Debug> std::fmt::Debug for Callbacks<Client> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Callbacks")
+            .field("client", &self.client)
+            .field("options", &self.options)
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+impl<Client> Callbacks<Client> {
+    /// Creates callbacks for a client, streaming with
+    /// [`StreamOptions::json`] by default
+    pub fn new(client: Client) -> Self {
+        Callbacks {
+            client,
+            options: StreamOptions::json(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Sets the options of the stream started by `run`
+    pub fn options(mut self, options: StreamOptions<Json>) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Calls `f` for every message, whatever its class
+    pub fn on_message(mut self, f: impl FnMut(&Message) + Send + 'static) -> Self {
+        self.handlers.push(Box::new(f));
+        self
+    }
+}
+
+/// Calls every handler with a message
+fn dispatch(handlers: &mut [Handler], msg: &Message) {
+    for handler in handlers {
+        handler(msg);
+    }
+}
+
+macro_rules! class_callbacks {
+    ($($(#[$cfg:meta])* $name:ident => $variant:ident($ty:ty), $doc:literal;)*) => {
+        impl<Client> Callbacks<Client> {
+            $(
+                $(#[$cfg])*
+                #[doc = concat!("Calls `f` for every ", $doc)]
+                pub fn $name(self, mut f: impl FnMut(&$ty) + Send + 'static) -> Self {
+                    self.on_message(move |msg| {
+                        if let Message::$variant(report) = msg {
+                            f(report)
+                        }
+                    })
+                }
+            )*
+        }
+
+        impl<Stream> crate::client::GpsdClient<Stream> {
+            /// Registers a callback for every message, see [`Callbacks`]
+            pub fn on_message(self, f: impl FnMut(&Message) + Send + 'static) -> Callbacks<Self> {
+                Callbacks::new(self).on_message(f)
+            }
+
+            $(
+                $(#[$cfg])*
+                #[doc = concat!("Registers a callback for every ", $doc, ", see [`Callbacks`]")]
+                pub fn $name(self, f: impl FnMut(&$ty) + Send + 'static) -> Callbacks<Self> {
+                    Callbacks::new(self).$name(f)
+                }
+            )*
+        }
+
+        impl<Stream> blocking::GpsdClient<Stream> {
+            /// Registers a callback for every message, see [`Callbacks`]
+            pub fn on_message(self, f: impl FnMut(&Message) + Send + 'static) -> Callbacks<Self> {
+                Callbacks::new(self).on_message(f)
+            }
+
+            $(
+                $(#[$cfg])*
+                #[doc = concat!("Registers a callback for every ", $doc, ", see [`Callbacks`]")]
+                pub fn $name(self, f: impl FnMut(&$ty) + Send + 'static) -> Callbacks<Self> {
+                    Callbacks::new(self).$name(f)
+                }
+            )*
+        }
+    };
+}
+
+class_callbacks! {
+    on_tpv => Tpv(Tpv), "TPV report";
+    #[cfg(feature = "msg-sky")]
+    on_sky => Sky(crate::protocol::v3::response::Sky), "SKY report";
+    #[cfg(feature = "msg-gst")]
+    on_gst => Gst(crate::protocol::v3::response::Gst), "GST report";
+    #[cfg(feature = "msg-att")]
+    on_att => Att(crate::protocol::v3::response::Attitude), "ATT report";
+    #[cfg(feature = "msg-imu")]
+    on_imu => Imu(crate::protocol::v3::response::Imu), "IMU report";
+    on_device => Device(Device), "DEVICE notification";
+    #[cfg(feature = "msg-toff")]
+    on_toff => Toff(crate::protocol::v3::response::TimeOffset), "TOFF report";
+    #[cfg(feature = "msg-pps")]
+    on_pps => Pps(crate::protocol::v3::response::Pps), "PPS report";
+    #[cfg(feature = "msg-osc")]
+    on_osc => Osc(crate::protocol::v3::response::Oscillator), "OSC report";
+    #[cfg(feature = "ais")]
+    on_ais => Ais(crate::protocol::v3::ais::Ais), "AIS report";
+}
+
+impl<Stream> Callbacks<crate::client::GpsdClient<Stream>>
+where
+    Stream: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+{
+    /// Starts the stream and calls the callbacks until it ends
+    ///
+    /// Returns the first error of the stream.
+    pub async fn run(mut self) -> Result<()> {
+        let mut stream = self.client.stream(self.options).await?;
+        while let Some(msg) = stream.next().await {
+            dispatch(&mut self.handlers, &msg?);
+        }
+        Ok(())
+    }
+}
+
+impl<Stream> Callbacks<blocking::GpsdClient<Stream>>
+where
+    Stream: std::io::Read + std::io::Write,
+{
+    /// Starts the stream and calls the callbacks until it ends
+    ///
+    /// Returns the first error of the stream.
+    pub fn run(mut self) -> Result<()> {
+        let stream = self.client.stream(self.options)?;
+        for msg in stream {
+            dispatch(&mut self.handlers, &msg?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_callbacks_per_class() {
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        writeln!(
+            server,
+            r#"{{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}}
+{{"class":"DEVICES","devices":[]}}
+{{"class":"WATCH","enable":true,"json":true}}
+{{"class":"TPV","mode":3,"lat":35.0,"lon":139.0}}
+{{"class":"DEVICE","path":"/dev/ttyACM0"}}
+{{"class":"TPV","mode":2,"lat":35.1,"lon":139.1}}"#
+        )
+        .unwrap();
+        // End of stream after the reports
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (tpv, device, all) = (calls.clone(), calls.clone(), calls.clone());
+        blocking::GpsdClient::open(stream)
+            .unwrap()
+            .on_tpv(move |t| tpv.lock().unwrap().push(format!("TPV {:?}", t.lat)))
+            .on_device(move |_| device.lock().unwrap().push("DEVICE".to_string()))
+            .on_message(move |m| all.lock().unwrap().push(m.class_name().to_string()))
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "TPV Some(35.0)",
+                "TPV",
+                "DEVICE",
+                "DEVICE",
+                "TPV Some(35.1)",
+                "TPV"
+            ]
+        );
+    }
+}
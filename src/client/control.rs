@@ -0,0 +1,214 @@
+//! Driver control strings sent through GPSD
+//!
+//! GPSD forwards the `hexdata` of a DEVICE command verbatim to the
+//! receiver, which lets clients push vendor commands such as u-blox CFG
+//! messages without opening the serial port themselves. [`HexData`]
+//! validates a control string before it is sent, and
+//! [`send_control`](crate::client::GpsdClient::send_control) returns the
+//! state of the device with warnings about conditions under which the
+//! receiver may ignore or misinterpret it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::{GpsdClient, control::HexData};
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let mut client = GpsdClient::connect("127.0.0.1:2947").await?;
+//! // UBX-MON-VER poll
+//! let poll = HexData::from_hex("b5620a0400000e34")?;
+//! let report = client.send_control("/dev/ttyACM0", &poll).await?;
+//! for warning in &report.warnings {
+//!     eprintln!("{warning}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HexData`]: crate::client::control::HexData
+
+use crate::{Result, error::GpsdJsonError, protocol::v3::types::Device};
+
+/// Maximum length of a control string in bytes
+///
+/// GPSD keeps at most 512 hex digits of a control string.
+pub const HEXDATA_MAX_BYTES: usize = 256;
+
+/// Validated control string for a receiver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexData {
+    bytes: Vec<u8>,
+}
+
+impl HexData {
+    /// Creates a control string of raw bytes
+    ///
+    /// Fails with an [`InvalidInput`](std::io::ErrorKind::InvalidInput) I/O
+    /// error if the string is empty or longer than [`HEXDATA_MAX_BYTES`].
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Result<Self> {
+        let bytes = bytes.into();
+        if bytes.is_empty() {
+            return Err(invalid("empty control string"));
+        }
+        if bytes.len() > HEXDATA_MAX_BYTES {
+            return Err(invalid("control string too long for GPSD"));
+        }
+        Ok(HexData { bytes })
+    }
+
+    /// Parses a control string of hex digits, e.g. `b5620a0400000e34`
+    ///
+    /// Whitespace between bytes is ignored. Fails with an
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) I/O error on
+    /// other characters or an odd number of digits.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits: Vec<u8> = hex
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid("control string contains non-hex characters"))?;
+        let pairs = digits.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(invalid("control string has an odd number of hex digits"));
+        }
+        Self::new(
+            pairs
+                .map(|pair| (pair[0] << 4) | pair[1])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns the bytes sent to the receiver
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the control string as lowercase hex digits, as sent to GPSD
+    pub fn to_hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Returns the DEVICE settings sending the control string to `path`
+    pub fn request(&self, path: &str) -> Device {
+        Device {
+            path: Some(path.to_string()),
+            hexdata: Some(self.to_hex()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns `false` for a UBX frame whose checksum does not match, and
+    /// `true` for valid frames and other control strings
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    fn ubx_checksum_ok(&self) -> bool {
+        let [0xb5, 0x62, frame @ ..] = self.bytes.as_slice() else {
+            return true;
+        };
+        let Some((body, checksum)) = frame.split_last_chunk::<2>() else {
+            return false;
+        };
+        let (mut a, mut b) = (0u8, 0u8);
+        for byte in body {
+            a = a.wrapping_add(*byte);
+            b = b.wrapping_add(a);
+        }
+        *checksum == [a, b]
+    }
+}
+
+/// Condition under which a receiver may ignore or misinterpret a control
+/// string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlWarning {
+    /// No driver of GPSD has identified the device, so its command set is
+    /// unknown
+    NoDriver,
+    /// The device is in NMEA mode and may ignore binary commands
+    NmeaMode,
+    /// The control string is a UBX frame with a wrong checksum, which u-blox
+    /// receivers discard
+    UbxChecksum,
+}
+
+impl core::fmt::Display for ControlWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlWarning::NoDriver => write!(f, "device has no active driver"),
+            ControlWarning::NmeaMode => write!(f, "device is in NMEA mode"),
+            ControlWarning::UbxChecksum => write!(f, "UBX frame has a wrong checksum"),
+        }
+    }
+}
+
+/// Result of sending a control string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlReport {
+    /// State of the device echoed by GPSD
+    pub device: Device,
+    /// Conditions that may keep the control string from taking effect
+    pub warnings: Vec<ControlWarning>,
+}
+
+impl ControlReport {
+    /// Builds the report of a control string from the echoed device
+    #[cfg_attr(not(feature = "proto-v3"), allow(dead_code))]
+    pub(crate) fn new(data: &HexData, device: Device) -> Self {
+        let mut warnings = Vec::new();
+        if device.driver.is_none() {
+            warnings.push(ControlWarning::NoDriver);
+        }
+        if device.native == Some(0) {
+            warnings.push(ControlWarning::NmeaMode);
+        }
+        if !data.ubx_checksum_ok() {
+            warnings.push(ControlWarning::UbxChecksum);
+        }
+        ControlReport { device, warnings }
+    }
+}
+
+fn invalid(msg: &'static str) -> GpsdJsonError {
+    GpsdJsonError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexdata() {
+        let poll = HexData::from_hex("B5 62 0a 04 00 00 0e 34").unwrap();
+        assert_eq!(poll.to_hex(), "b5620a0400000e34");
+        assert!(poll.ubx_checksum_ok());
+        assert!(
+            !HexData::from_hex("b5620a0400000e35")
+                .unwrap()
+                .ubx_checksum_ok()
+        );
+
+        assert!(HexData::from_hex("b56").is_err());
+        assert!(HexData::from_hex("zz").is_err());
+        assert!(HexData::from_hex("").is_err());
+        assert!(HexData::new(vec![0; HEXDATA_MAX_BYTES + 1]).is_err());
+
+        let report = ControlReport::new(
+            &HexData::from_hex("b5620a0400000e00").unwrap(),
+            Device {
+                native: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            report.warnings,
+            [
+                ControlWarning::NoDriver,
+                ControlWarning::NmeaMode,
+                ControlWarning::UbxChecksum
+            ]
+        );
+    }
+}
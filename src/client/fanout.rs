@@ -0,0 +1,216 @@
+//! Several filtered subscriptions sharing one connection
+//!
+//! A [`FanOut`] drives a JSON data stream on its own tokio task and hands
+//! out any number of [`Subscription`]s, each with its own class and device
+//! filters. Components interested in some messages only, e.g. an NTP module
+//! in PPS and TOFF reports and a map in the TPV reports of one receiver,
+//! share the connection instead of opening connections of their own.
+//!
+//! Messages are shared as [`Arc`]s, so subscribers do not copy them. A
+//! subscriber that falls behind by more than the capacity of the channel
+//! misses messages and receives
+//! [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::{GpsdClient, StreamOptions, fanout::FanOut};
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let client = GpsdClient::connect("127.0.0.1:2947").await?;
+//! let fanout = FanOut::spawn(client.stream(StreamOptions::json()).await?, 256);
+//!
+//! let mut timing = fanout.subscription().classes(["PPS", "TOFF"]);
+//! let mut map = fanout.subscription().classes(["TPV"]).devices(["/dev/ttyACM0"]);
+//! tokio::spawn(async move {
+//!     while let Ok(msg) = timing.recv().await {
+//!         println!("timing: {msg:?}");
+//!     }
+//! });
+//! while let Ok(msg) = map.recv().await {
+//!     println!("map: {msg:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Arc`]: std::sync::Arc
+//! [`FanOut`]: crate::client::fanout::FanOut
+//! [`Subscription`]: crate::client::fanout::Subscription
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+
+use crate::{Result, client::in_scope, error::GpsdJsonError, protocol::v3};
+
+/// JSON data stream shared by subscriptions, running on its own task
+///
+/// The task ends when the stream ends or fails with an I/O error, or when
+/// the fan-out is dropped; subscriptions then receive
+/// [`RecvError::Closed`](broadcast::error::RecvError::Closed). Other
+/// errors, such as unparsable lines, are skipped.
+#[derive(Debug)]
+pub struct FanOut {
+    messages: broadcast::Receiver<Arc<v3::ResponseMessage>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl FanOut {
+    /// Moves the stream to a new task, buffering up to `capacity` messages
+    /// for each subscription
+    pub fn spawn<S>(stream: S, capacity: usize) -> Self
+    where
+        S: futures_util::Stream<Item = Result<v3::ResponseMessage>> + Send + 'static,
+    {
+        let (sender, messages) = broadcast::channel(capacity.max(1));
+        let task = tokio::spawn(async move {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(msg) = stream.next().await {
+                match msg {
+                    Ok(msg) => {
+                        // Fails only without subscriptions, which is fine
+                        let _ = sender.send(Arc::new(msg));
+                    }
+                    Err(GpsdJsonError::IoError(_) | GpsdJsonError::ConnectionStale(_)) => break,
+                    Err(_) => {}
+                }
+            }
+        });
+        FanOut { messages, task }
+    }
+
+    /// Returns a subscription to all messages received from now on
+    pub fn subscription(&self) -> Subscription {
+        Subscription::new(self.messages.resubscribe())
+    }
+
+    /// Stops the task
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for FanOut {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Receiver of the messages of a shared connection passing its own filters
+///
+/// Without filters, all messages are received. Messages without a device
+/// pass the device filter, as with [`StreamOptions::devices`]. Messages
+/// dropped by the filters still count against the capacity of the channel.
+///
+/// [`StreamOptions::devices`]: crate::client::StreamOptions::devices
+#[derive(Debug)]
+pub struct Subscription {
+    messages: broadcast::Receiver<Arc<v3::ResponseMessage>>,
+    classes: Vec<String>,
+    devices: Vec<String>,
+}
+
+impl Subscription {
+    pub(crate) fn new(messages: broadcast::Receiver<Arc<v3::ResponseMessage>>) -> Self {
+        Subscription {
+            messages,
+            classes: Vec::new(),
+            devices: Vec::new(),
+        }
+    }
+
+    /// Restricts the subscription to messages of the given classes, named
+    /// as in the `class` field, e.g. `"TPV"`
+    pub fn classes<I, S>(mut self, classes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for class in classes {
+            let class = class.as_ref();
+            if !self.classes.iter().any(|name| name == class) {
+                self.classes.push(class.to_string());
+            }
+        }
+        self
+    }
+
+    /// Restricts the subscription to messages of the given devices
+    pub fn devices<I, S>(mut self, devices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for device in devices {
+            let device = device.as_ref();
+            if !self.devices.iter().any(|path| path == device) {
+                self.devices.push(device.to_string());
+            }
+        }
+        self
+    }
+
+    /// Receives the next message passing the filters
+    ///
+    /// Fails as [`broadcast::Receiver::recv`] when messages were missed or
+    /// the connection ended.
+    pub async fn recv(
+        &mut self,
+    ) -> std::result::Result<Arc<v3::ResponseMessage>, broadcast::error::RecvError> {
+        loop {
+            let msg = self.messages.recv().await?;
+            let class = msg.class_name();
+            if (self.classes.is_empty() || self.classes.iter().any(|name| name == class))
+                && in_scope(&self.devices, msg.as_ref())
+            {
+                return Ok(msg);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    use super::*;
+    use crate::{
+        client::{GpsdClient, StreamOptions},
+        fixtures,
+    };
+
+    #[tokio::test]
+    async fn test_fanout_subscriptions() {
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        writeln!(
+            server,
+            "{}\n{}\n{}",
+            fixtures::VERSION,
+            fixtures::DEVICES,
+            fixtures::WATCH
+        )
+        .unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let stream = tokio::net::UnixStream::from_std(stream).unwrap().compat();
+        let client = GpsdClient::open(stream).await.unwrap();
+        let fanout = FanOut::spawn(client.stream(StreamOptions::json()).await.unwrap(), 8);
+
+        let mut all = fanout.subscription();
+        let mut device = fanout.subscription().classes(["DEVICE"]);
+        let mut other_device = fanout.subscription().devices(["/dev/ttyUSB0"]);
+        // A malformed line does not end the subscriptions
+        writeln!(server, "not json\n{}\n{}", fixtures::TPV, fixtures::DEVICE).unwrap();
+        let msg = all.recv().await.unwrap();
+        assert!(matches!(msg.as_ref(), v3::ResponseMessage::Tpv(_)));
+        let msg = device.recv().await.unwrap();
+        assert!(matches!(msg.as_ref(), v3::ResponseMessage::Device(_)));
+        assert!(Arc::ptr_eq(&msg, &all.recv().await.unwrap()));
+
+        drop(server);
+        assert!(other_device.recv().await.is_err());
+    }
+}
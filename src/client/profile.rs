@@ -0,0 +1,271 @@
+//! Device configurations applied to matching receivers on hotplug
+//!
+//! Fleets of identical receivers usually need the same serial speed and
+//! cycle time whenever one is plugged in. A [`ProfileManager`] holds rules
+//! that map receivers, matched by driver, subtype and path, to a
+//! [`DeviceConfig`]. Fed with the messages of a JSON stream, it applies the
+//! configuration of the first matching rule when GPSD reports a device as
+//! activated, including the devices listed when the stream starts.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::{
+//!     DeviceConfig, GpsdClient, StreamOptions,
+//!     profile::{DeviceMatch, ProfileManager},
+//! };
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let mut profiles = ProfileManager::new().rule(
+//!     DeviceMatch::any().driver("u-blox").path("/dev/ttyACM*"),
+//!     DeviceConfig::default().bps(115200).cycle(0.2),
+//! );
+//!
+//! let client = GpsdClient::connect("127.0.0.1:2947").await?;
+//! let mut stream = client.stream(StreamOptions::json()).await?;
+//! while let Some(msg) = stream.try_next().await? {
+//!     for path in profiles.apply(&mut stream, &msg).await? {
+//!         println!("configured {path}");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ProfileManager`]: crate::client::profile::ProfileManager
+
+use std::collections::HashMap;
+
+use crate::{
+    Result, Timestamp,
+    client::{DeviceConfig, GpsdDataStream, Json},
+    protocol::v3::{self, response::Message, types::Device},
+};
+
+/// Criteria selecting the receivers a profile applies to
+///
+/// Criteria that are not set match any device. Subtypes and paths are
+/// matched as glob patterns, where `*` matches any sequence of characters
+/// and `?` a single character.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceMatch {
+    driver: Option<String>,
+    subtype: Option<String>,
+    path: Option<String>,
+}
+
+impl DeviceMatch {
+    /// Creates criteria matching any device
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Matches devices of a GPSD driver, e.g. `u-blox`
+    pub fn driver<S: Into<String>>(mut self, driver: S) -> Self {
+        self.driver = Some(driver.into());
+        self
+    }
+
+    /// Matches devices whose subtype matches a glob pattern
+    pub fn subtype<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.subtype = Some(pattern.into());
+        self
+    }
+
+    /// Matches devices whose path matches a glob pattern
+    pub fn path<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.path = Some(pattern.into());
+        self
+    }
+
+    /// Returns `true` if the device meets all criteria
+    pub fn matches(&self, device: &Device) -> bool {
+        fn check(pattern: &Option<String>, value: &Option<String>, glob: bool) -> bool {
+            match (pattern, value) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(pattern), Some(value)) if glob => glob_match(pattern, value),
+                (Some(pattern), Some(value)) => pattern == value,
+            }
+        }
+
+        check(&self.driver, &device.driver, false)
+            && check(&self.subtype, &device.subtype, true)
+            && check(&self.path, &device.path, true)
+    }
+}
+
+/// Rules mapping receivers to the configuration applied on activation
+///
+/// Each activation of a device is configured at most once, so the DEVICE
+/// reports GPSD echoes after a configuration do not trigger it again.
+/// Devices that already report the settings are left alone.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileManager {
+    rules: Vec<(DeviceMatch, DeviceConfig)>,
+    handled: HashMap<String, Option<Timestamp>>,
+}
+
+impl ProfileManager {
+    /// Creates a manager without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule; the first matching rule applies
+    ///
+    /// The path of the configuration is replaced by the path of the
+    /// matching device.
+    pub fn rule(mut self, criteria: DeviceMatch, config: DeviceConfig) -> Self {
+        self.add_rule(criteria, config);
+        self
+    }
+
+    /// Adds a rule to an existing manager
+    pub fn add_rule(&mut self, criteria: DeviceMatch, config: DeviceConfig) {
+        self.rules.push((criteria, config));
+    }
+
+    /// Returns the configuration of the first rule matching a device
+    pub fn profile(&self, device: &Device) -> Option<DeviceConfig> {
+        let path = device.path.as_deref()?;
+        self.rules
+            .iter()
+            .find(|(criteria, _)| criteria.matches(device))
+            .map(|(_, config)| config.clone().with_path(path))
+    }
+
+    /// Returns the configurations to send for a message
+    ///
+    /// DEVICE reports of activated devices and the devices of DEVICES
+    /// reports are checked against the rules. A DEVICE report of a
+    /// deactivated device resets its state, so it is configured again when
+    /// it comes back.
+    pub fn configs_for(&mut self, msg: &Message) -> Vec<DeviceConfig> {
+        match msg {
+            Message::Device(device) => self.on_device(device).into_iter().collect(),
+            Message::Devices(list) => list
+                .devices
+                .iter()
+                .filter_map(|device| self.on_device(device))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Sends the configurations for a message to GPSD through the stream
+    /// and returns the paths of the configured devices
+    ///
+    /// The devices echoed by GPSD arrive as regular stream items.
+    pub async fn apply<S>(
+        &mut self,
+        stream: &mut GpsdDataStream<S, v3::V3, Json>,
+        msg: &Message,
+    ) -> Result<Vec<String>>
+    where
+        S: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin,
+    {
+        let mut paths = Vec::new();
+        for config in self.configs_for(msg) {
+            stream.send_config(&config).await?;
+            paths.extend(config.device().path.clone());
+        }
+        Ok(paths)
+    }
+
+    fn on_device(&mut self, device: &Device) -> Option<DeviceConfig> {
+        let path = device.path.as_deref()?;
+        if device.activated.is_none() {
+            self.handled.remove(path);
+            return None;
+        }
+        if self.handled.get(path) == Some(&device.activated) {
+            return None;
+        }
+        self.handled.insert(path.to_string(), device.activated);
+        self.profile(device)
+            .filter(|config| !config.is_applied(device))
+    }
+}
+
+/// Matches a value against a glob pattern of `*` and `?` wildcards
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    let (mut p, mut v) = (0, 0);
+    // Position after the last `*` and the value position it matched up to
+    let mut star = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, v));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    p = after;
+                    v = matched + 1;
+                    star = Some((after, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_profile_manager() {
+        assert!(glob_match("/dev/ttyACM*", "/dev/ttyACM0"));
+        assert!(glob_match("*ROM CORE ?.01*", "SW ROM CORE 3.01 (107888)"));
+        assert!(!glob_match("/dev/ttyUSB*", "/dev/ttyACM0"));
+
+        let mut profiles = ProfileManager::new()
+            .rule(
+                DeviceMatch::any().driver("SiRF"),
+                DeviceConfig::default().bps(4800),
+            )
+            .rule(
+                DeviceMatch::any().driver("u-blox").path("/dev/ttyACM*"),
+                DeviceConfig::default().bps(115200),
+            );
+
+        let devices: Message = serde_json::from_str(fixtures::DEVICES).unwrap();
+        let configs = profiles.configs_for(&devices);
+        assert_eq!(configs, [DeviceConfig::new("/dev/ttyACM0").bps(115200)]);
+
+        // Echo of the same activation
+        let device: Message = serde_json::from_str(fixtures::DEVICE).unwrap();
+        assert!(profiles.configs_for(&device).is_empty());
+
+        // Unplugged and plugged in again
+        let Message::Device(mut device) = device else {
+            unreachable!()
+        };
+        let activated = device.activated.take();
+        assert!(
+            profiles
+                .configs_for(&Message::Device(device.clone()))
+                .is_empty()
+        );
+        device.activated = activated;
+        assert_eq!(
+            profiles.configs_for(&Message::Device(device.clone())).len(),
+            1
+        );
+
+        // Already configured
+        let mut profiles =
+            ProfileManager::new().rule(DeviceMatch::any(), DeviceConfig::default().bps(9600));
+        assert!(profiles.configs_for(&Message::Device(device)).is_empty());
+    }
+}
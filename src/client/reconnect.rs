@@ -0,0 +1,568 @@
+//! Polite reconnection to GPSD
+//!
+//! Embedded GPSD hosts are easily overwhelmed when many clients reconnect
+//! in a tight loop after a network flap. A [`ReconnectGuard`] enforces a
+//! minimum interval with random jitter between connection attempts and caps
+//! the number of attempts in progress. One guard can be shared by all
+//! clients of a process that talk to the same host.
+//!
+//! [`ReconnectingClient`] is a blocking JSON stream that transparently
+//! reconnects through a guard when the connection is lost. Its async
+//! counterpart, [`crate::client::ReconnectingClient`], re-dials with an
+//! exponential [`Backoff`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::client::{
+//!     StreamOptions,
+//!     reconnect::{ReconnectGuard, ReconnectPolicy, ReconnectingClient},
+//! };
+//!
+//! let guard = ReconnectGuard::new(ReconnectPolicy {
+//!     min_interval: Duration::from_secs(2),
+//!     jitter: Duration::from_secs(1),
+//!     max_concurrent: 1,
+//! });
+//! let client = ReconnectingClient::new("gpsd.local:2947", StreamOptions::json(), guard);
+//! for msg in client {
+//!     match msg {
+//!         Ok(msg) => println!("{msg:?}"),
+//!         Err(e) => eprintln!("connection lost: {e}"),
+//!     }
+//! }
+//! ```
+//!
+//! [`Backoff`]: crate::client::reconnect::Backoff
+//! [`ReconnectGuard`]: crate::client::reconnect::ReconnectGuard
+//! [`ReconnectingClient`]: crate::client::reconnect::ReconnectingClient
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Limits for connection attempts made through a [`ReconnectGuard`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Minimum time between the start of two attempts
+    pub min_interval: Duration,
+    /// Upper bound of the random delay added to the minimum interval
+    pub jitter: Duration,
+    /// Maximum number of attempts in progress at the same time
+    pub max_concurrent: usize,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            min_interval: Duration::from_secs(1),
+            jitter: Duration::from_millis(500),
+            max_concurrent: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct GuardState {
+    next_attempt: Option<Instant>,
+    in_progress: usize,
+}
+
+#[derive(Debug)]
+struct GuardInner {
+    policy: ReconnectPolicy,
+    state: Mutex<GuardState>,
+    released: Condvar,
+}
+
+/// Rate limiter for connection attempts
+///
+/// Cloning a guard shares its limits, so clones can be handed to clients
+/// running on different threads.
+#[derive(Debug, Clone)]
+pub struct ReconnectGuard {
+    inner: Arc<GuardInner>,
+}
+
+impl Default for ReconnectGuard {
+    fn default() -> Self {
+        Self::new(ReconnectPolicy::default())
+    }
+}
+
+impl ReconnectGuard {
+    /// Creates a guard enforcing the given policy
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        ReconnectGuard {
+            inner: Arc::new(GuardInner {
+                policy,
+                state: Mutex::new(GuardState {
+                    next_attempt: None,
+                    in_progress: 0,
+                }),
+                released: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Returns the policy of this guard
+    pub fn policy(&self) -> ReconnectPolicy {
+        self.inner.policy
+    }
+
+    /// Starts an attempt if allowed now
+    ///
+    /// Otherwise returns the time to wait before trying again, e.g. with an
+    /// async sleep. The attempt ends when the returned permit is dropped.
+    pub fn try_acquire(&self) -> std::result::Result<ReconnectPermit, Duration> {
+        let mut state = self.inner.state.lock().unwrap();
+        self.try_start(&mut state)
+    }
+
+    /// Blocks until an attempt is allowed and starts it
+    pub fn acquire(&self) -> ReconnectPermit {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            match self.try_start(&mut state) {
+                Ok(permit) => return permit,
+                Err(wait) => {
+                    state = self.inner.released.wait_timeout(state, wait).unwrap().0;
+                }
+            }
+        }
+    }
+
+    fn try_start(&self, state: &mut GuardState) -> std::result::Result<ReconnectPermit, Duration> {
+        let policy = &self.inner.policy;
+        let now = Instant::now();
+        let wait = state
+            .next_attempt
+            .and_then(|next| next.checked_duration_since(now))
+            .filter(|wait| !wait.is_zero());
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+        if state.in_progress >= policy.max_concurrent.max(1) {
+            // Woken early by the condition variable when a permit is dropped
+            return Err(policy.min_interval.max(Duration::from_millis(10)));
+        }
+
+        state.in_progress += 1;
+        state.next_attempt = Some(now + policy.min_interval + random_delay(policy.jitter));
+        Ok(ReconnectPermit {
+            guard: self.clone(),
+        })
+    }
+}
+
+/// Attempt started through a [`ReconnectGuard`], ended when dropped
+#[derive(Debug)]
+pub struct ReconnectPermit {
+    guard: ReconnectGuard,
+}
+
+impl Drop for ReconnectPermit {
+    fn drop(&mut self) {
+        let inner = &self.guard.inner;
+        let mut state = inner.state.lock().unwrap();
+        state.in_progress -= 1;
+        inner.released.notify_one();
+    }
+}
+
+/// Exponential backoff between reconnection attempts
+///
+/// The delay before the n-th consecutive attempt is `initial` multiplied by
+/// `multiplier` n - 1 times, capped at `max`, plus a random delay up to
+/// `jitter`. The first attempt after a connection is lost is made without
+/// delay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Delay before the second attempt
+    pub initial: Duration,
+    /// Upper bound of the delay, without jitter
+    pub max: Duration,
+    /// Factor the delay grows by with each failed attempt
+    pub multiplier: f64,
+    /// Upper bound of the random delay added to each delay
+    pub jitter: Duration,
+    /// Number of consecutive failed attempts before giving up, or `None`
+    /// to retry forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: Duration::from_millis(250),
+            max_attempts: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Returns the delay before an attempt, counting from zero for the
+    /// first attempt after a connection is lost
+    pub fn delay(&self, attempt: u32) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+        let factor = self.multiplier.max(1.0).powi((attempt - 1).min(64) as i32);
+        let delay = Duration::try_from_secs_f64(self.initial.as_secs_f64() * factor)
+            .unwrap_or(self.max)
+            .min(self.max);
+        delay + random_delay(self.jitter)
+    }
+}
+
+/// Returns a random delay between zero and `max`
+fn random_delay(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+#[cfg(all(feature = "proto-v3", feature = "net"))]
+pub use self::client::ReconnectingClient;
+
+#[cfg(all(feature = "proto-v3", feature = "net"))]
+mod client {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    use super::ReconnectGuard;
+    use crate::{
+        Result,
+        client::{
+            ConnectionInfo, Json, StreamOptions,
+            blocking::{GpsdClient, GpsdDataStream},
+        },
+        protocol::v3,
+    };
+
+    /// Blocking JSON stream that reconnects through a [`ReconnectGuard`]
+    ///
+    /// Errors, including failed connection attempts, are yielded and the
+    /// next call to `next()` reconnects, so the iterator never ends. The
+    /// [`generation`](crate::client::ConnectionInfo::generation) of the
+    /// connection metadata counts the reconnects.
+    pub struct ReconnectingClient<A> {
+        addr: A,
+        options: StreamOptions<Json>,
+        guard: ReconnectGuard,
+        stream: Option<GpsdDataStream<TcpStream, v3::V3, Json>>,
+        generation: u32,
+    }
+
+    impl<A> std::fmt::Debug for ReconnectingClient<A> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ReconnectingClient")
+                .field("guard", &self.guard)
+                .field("connection", &self.connection())
+                .field("generation", &self.generation)
+                .finish()
+        }
+    }
+
+    impl<A> ReconnectingClient<A> {
+        /// Creates a client that connects on the first call to `next()`
+        pub fn new(addr: A, options: StreamOptions<Json>, guard: ReconnectGuard) -> Self {
+            ReconnectingClient {
+                addr,
+                options,
+                guard,
+                stream: None,
+                generation: 0,
+            }
+        }
+
+        /// Returns metadata about the current connection, if connected
+        pub fn connection(&self) -> Option<&ConnectionInfo> {
+            self.stream.as_ref().map(GpsdDataStream::connection)
+        }
+
+        fn reconnect(&mut self) -> Result<GpsdDataStream<TcpStream, v3::V3, Json>>
+        where
+            A: ToSocketAddrs,
+        {
+            let _permit = self.guard.acquire();
+            let client = GpsdClient::connect(&self.addr)?.with_generation(self.generation);
+            self.generation += 1;
+            client.stream(self.options.clone())
+        }
+    }
+
+    impl<A: ToSocketAddrs> Iterator for ReconnectingClient<A> {
+        type Item = Result<v3::ResponseMessage>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let mut stream = match self.stream.take() {
+                Some(stream) => stream,
+                None => match self.reconnect() {
+                    Ok(stream) => stream,
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+
+            match stream.next() {
+                Some(Ok(msg)) => {
+                    self.stream = Some(stream);
+                    Some(Ok(msg))
+                }
+                Some(Err(e)) => Some(Err(e)),
+                None => Some(Err(crate::error::GpsdJsonError::ProtocolError(
+                    "Connection closed by GPSD",
+                ))),
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "proto-v3", feature = "tokio"))]
+pub(super) mod async_client {
+    use super::Backoff;
+    use crate::{
+        Result,
+        client::{
+            ConnectionInfo, GpsdDataStream, Json, StreamOptions,
+            transport::{Connector, GpsdClientBuilder},
+        },
+        error::GpsdJsonError,
+        protocol::v3,
+    };
+
+    /// Async JSON stream that re-dials GPSD when the connection drops
+    ///
+    /// Each new connection is created with the [`Connector`] and resumes
+    /// the WATCH settings of the stream, waiting between failed attempts
+    /// as configured by the [`Backoff`]. The
+    /// [`generation`](crate::client::ConnectionInfo::generation) of the
+    /// connection metadata counts the reconnects.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use gpsd_json::client::{
+    ///     ReconnectingClient, StreamOptions, reconnect::Backoff, transport::TcpConnector,
+    /// };
+    ///
+    /// # async fn example() -> gpsd_json::Result<()> {
+    /// let backoff = Backoff {
+    ///     max_attempts: Some(10),
+    ///     ..Default::default()
+    /// };
+    /// let mut client = ReconnectingClient::new(
+    ///     TcpConnector::new("gpsd.local:2947"),
+    ///     StreamOptions::json(),
+    ///     backoff,
+    /// );
+    /// loop {
+    ///     println!("{:?}", client.recv().await?);
+    /// }
+    /// # }
+    /// ```
+    pub struct ReconnectingClient<C: Connector> {
+        connector: C,
+        options: StreamOptions<Json>,
+        backoff: Backoff,
+        builder: GpsdClientBuilder<v3::V3>,
+        stream: Option<GpsdDataStream<C::Stream, v3::V3, Json>>,
+        generation: u32,
+    }
+
+    impl<C: Connector> std::fmt::Debug for ReconnectingClient<C> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ReconnectingClient")
+                .field("backoff", &self.backoff)
+                .field("connection", &self.connection())
+                .field("generation", &self.generation)
+                .finish()
+        }
+    }
+
+    impl<C: Connector> ReconnectingClient<C> {
+        /// Creates a client that connects on the first call to
+        /// [`recv`](Self::recv)
+        pub fn new(connector: C, options: StreamOptions<Json>, backoff: Backoff) -> Self {
+            ReconnectingClient {
+                connector,
+                options,
+                backoff,
+                builder: GpsdClientBuilder::new(),
+                stream: None,
+                generation: 0,
+            }
+        }
+
+        /// Sets the builder the clients are opened with, e.g. for the parse
+        /// mode
+        pub fn with_builder(mut self, builder: GpsdClientBuilder<v3::V3>) -> Self {
+            self.builder = builder;
+            self
+        }
+
+        /// Returns metadata about the current connection, if connected
+        pub fn connection(&self) -> Option<&ConnectionInfo> {
+            self.stream.as_ref().map(GpsdDataStream::connection)
+        }
+
+        /// Returns the WATCH settings resumed on each connection
+        pub fn options(&self) -> &StreamOptions<Json> {
+            &self.options
+        }
+
+        /// Replaces the WATCH settings, taking effect on the next connection
+        pub fn set_options(&mut self, options: StreamOptions<Json>) {
+            self.options = options;
+        }
+
+        /// Receives the next message, reconnecting as needed
+        ///
+        /// Connection errors and the end of the connection are handled by
+        /// reconnecting; other errors, such as malformed messages, are
+        /// returned and the connection is kept. Once the maximum number of
+        /// attempts fails, the last error is returned and the next call
+        /// starts over.
+        pub async fn recv(&mut self) -> Result<v3::ResponseMessage> {
+            loop {
+                if self.stream.is_none() {
+                    self.stream = Some(self.reconnect().await?);
+                }
+                let stream = self.stream.as_mut().expect("connected above");
+                match stream.next().await {
+                    Some(Ok(msg)) => return Ok(msg),
+                    Some(Err(GpsdJsonError::IoError(_))) | None => self.stream = None,
+                    Some(Err(e)) => return Err(e),
+                }
+            }
+        }
+
+        async fn reconnect(&mut self) -> Result<GpsdDataStream<C::Stream, v3::V3, Json>> {
+            let mut attempt = 0;
+            loop {
+                tokio::time::sleep(self.backoff.delay(attempt)).await;
+                attempt += 1;
+                let result = match self
+                    .builder
+                    .generation(self.generation)
+                    .connect_with(&self.connector)
+                    .await
+                {
+                    Ok(client) => client.stream(self.options.clone()).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(stream) => {
+                        self.generation += 1;
+                        return Ok(stream);
+                    }
+                    Err(e) if self.backoff.max_attempts.is_some_and(|max| attempt >= max) => {
+                        return Err(e);
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_guard_limits() {
+        let guard = ReconnectGuard::new(ReconnectPolicy {
+            min_interval: Duration::from_millis(50),
+            jitter: Duration::from_millis(20),
+            max_concurrent: 1,
+        });
+
+        let permit = guard.try_acquire().unwrap();
+        // Within the minimum interval
+        let wait = guard.try_acquire().unwrap_err();
+        assert!(wait > Duration::from_millis(20) && wait <= Duration::from_millis(70));
+
+        // Blocks for the interval, then for the permit in progress
+        let start = Instant::now();
+        let waiter = {
+            let guard = guard.clone();
+            std::thread::spawn(move || drop(guard.acquire()))
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!waiter.is_finished());
+        drop(permit);
+        waiter.join().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[cfg(all(feature = "proto-v3", feature = "tokio", unix))]
+    #[tokio::test]
+    async fn test_reconnecting_client_resumes_watch() {
+        use std::{
+            io::{Read, Write},
+            os::unix::net::UnixStream,
+        };
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        use crate::{
+            client::{ReconnectingClient, StreamOptions},
+            fixtures,
+            protocol::v3::ResponseMessage,
+        };
+
+        let devices = r#"{"class":"DEVICES","devices":[]}"#;
+        let watch = r#"{"class":"WATCH","enable":true,"json":true}"#;
+        let mut clients = Vec::new();
+        let mut servers = Vec::new();
+        for _ in 0..2 {
+            let (client, mut server) = UnixStream::pair().unwrap();
+            writeln!(
+                server,
+                "{}\n{devices}\n{watch}\n{}",
+                fixtures::VERSION,
+                fixtures::TPV
+            )
+            .unwrap();
+            // Closes the connection after the buffered lines
+            server.shutdown(std::net::Shutdown::Write).unwrap();
+            client.set_nonblocking(true).unwrap();
+            clients.push(client);
+            servers.push(server);
+        }
+        let clients = std::sync::Mutex::new(clients);
+        let connector = || {
+            let client = clients.lock().unwrap().pop();
+            async move {
+                let client = client.ok_or(std::io::ErrorKind::ConnectionRefused)?;
+                Ok(tokio::net::UnixStream::from_std(client)?.compat())
+            }
+        };
+        let backoff = Backoff {
+            initial: Duration::from_millis(1),
+            jitter: Duration::ZERO,
+            max_attempts: Some(2),
+            ..Default::default()
+        };
+
+        let mut client = ReconnectingClient::new(connector, StreamOptions::json(), backoff);
+        for generation in 0..2 {
+            assert!(matches!(client.recv().await, Ok(ResponseMessage::Tpv(_))));
+            assert_eq!(client.connection().unwrap().generation, generation);
+        }
+        assert!(client.recv().await.is_err());
+
+        for mut server in servers {
+            let mut requests = String::new();
+            server.read_to_string(&mut requests).unwrap();
+            assert!(requests.contains("?WATCH="));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! GPS data bus on a background task
+//!
+//! A [`GpsdService`] owns a supervised connection to GPSD on its own tokio
+//! task and publishes every received message on a broadcast channel. Any
+//! number of components subscribe with [`GpsdService::subscribe`] and get
+//! the messages from the time they subscribed, while the state of the
+//! connection is available as a [`watch`](tokio::sync::watch) channel from
+//! [`GpsdService::status`].
+//!
+//! Messages are shared as [`Arc`]s, so subscribers do not copy them. A
+//! subscriber that falls behind by more than the capacity of the channel
+//! misses messages and receives
+//! [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+//!
+//! Components interested in some messages only take a [`Subscription`]
+//! from [`GpsdService::subscription`] with their own class and device
+//! filters, like the subscriptions of a [`FanOut`], which shares a
+//! connection without reconnecting.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::{
+//!     client::{StreamOptions, service::GpsdService, transport::TcpConnector},
+//!     protocol::v3::ResponseMessage,
+//! };
+//!
+//! # async fn example() {
+//! let service = GpsdService::spawn(TcpConnector::new("gpsd.local:2947"), StreamOptions::json());
+//!
+//! let mut messages = service.subscribe();
+//! tokio::spawn(async move {
+//!     while let Ok(msg) = messages.recv().await {
+//!         if let ResponseMessage::Tpv(tpv) = msg.as_ref() {
+//!             println!("{:?}, {:?}", tpv.lat, tpv.lon);
+//!         }
+//!     }
+//! });
+//! # }
+//! ```
+//!
+//! [`Arc`]: std::sync::Arc
+//! [`FanOut`]: crate::client::fanout::FanOut
+//! [`GpsdService`]: crate::client::service::GpsdService
+//! [`GpsdService::status`]: crate::client::service::GpsdService::status
+//! [`GpsdService::subscribe`]: crate::client::service::GpsdService::subscribe
+//! [`GpsdService::subscription`]: crate::client::service::GpsdService::subscription
+//! [`Subscription`]: crate::client::fanout::Subscription
+
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    client::{
+        ConnectionInfo, Json, StreamOptions,
+        fanout::Subscription,
+        reconnect::Backoff,
+        supervisor::{Supervisor, SupervisorEvent},
+        transport::Connector,
+    },
+    protocol::v3,
+};
+
+/// Number of messages buffered for subscribers by [`GpsdService::spawn`]
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// State of the connection of a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceStatus {
+    /// Connecting to GPSD, possibly after a lost connection
+    Connecting,
+    /// Connected with the WATCH settings active
+    Connected(ConnectionInfo),
+    /// Connected, but no data arrived for the keepalive interval
+    Degraded(ConnectionInfo),
+    /// The service gave up reconnecting or was stopped
+    Stopped,
+}
+
+/// Connection to GPSD shared by subscribers, running on its own task
+///
+/// Dropping the service stops the task; subscribers then receive
+/// [`RecvError::Closed`](broadcast::error::RecvError::Closed).
+#[derive(Debug)]
+pub struct GpsdService {
+    messages: broadcast::Receiver<Arc<v3::ResponseMessage>>,
+    status: watch::Receiver<ServiceStatus>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl GpsdService {
+    /// Connects through the connector and streams with the options,
+    /// reconnecting with the default [`Backoff`] and buffering
+    /// [`DEFAULT_CAPACITY`] messages
+    pub fn spawn<C>(connector: C, options: StreamOptions<Json>) -> Self
+    where
+        C: Connector + Send + Sync + 'static,
+    {
+        Self::with_supervisor(
+            Supervisor::new(connector, options, Backoff::default()),
+            DEFAULT_CAPACITY,
+        )
+    }
+
+    /// Runs a configured supervisor, e.g. with a keepalive, buffering up to
+    /// `capacity` messages for each subscriber
+    pub fn with_supervisor<C>(supervisor: Supervisor<C>, capacity: usize) -> Self
+    where
+        C: Connector + Send + Sync + 'static,
+    {
+        let (sender, messages) = broadcast::channel(capacity.max(1));
+        let (status_sender, status) = watch::channel(ServiceStatus::Connecting);
+        let mut supervisor = supervisor;
+        let task = tokio::spawn(async move {
+            let mut info = None;
+            while let Some(event) = supervisor.next_event().await {
+                let update = match event {
+                    SupervisorEvent::Message(msg) => {
+                        // Fails only without subscribers, which is fine
+                        let _ = sender.send(Arc::new(msg));
+                        continue;
+                    }
+                    SupervisorEvent::Connected {
+                        info: connected, ..
+                    } => {
+                        info = Some(connected);
+                        ServiceStatus::Connected(connected)
+                    }
+                    SupervisorEvent::Degraded => match info {
+                        Some(info) => ServiceStatus::Degraded(info),
+                        None => continue,
+                    },
+                    SupervisorEvent::Recovered => match info {
+                        Some(info) => ServiceStatus::Connected(info),
+                        None => continue,
+                    },
+                    SupervisorEvent::Disconnected(_) => {
+                        info = None;
+                        ServiceStatus::Connecting
+                    }
+                    SupervisorEvent::GaveUp(_) => break,
+                    SupervisorEvent::Connecting { .. }
+                    | SupervisorEvent::AttemptFailed(_)
+                    | SupervisorEvent::Error(_) => continue,
+                };
+                status_sender.send_replace(update);
+            }
+            status_sender.send_replace(ServiceStatus::Stopped);
+        });
+
+        GpsdService {
+            messages,
+            status,
+            task,
+        }
+    }
+
+    /// Returns a receiver of the messages received from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<v3::ResponseMessage>> {
+        self.messages.resubscribe()
+    }
+
+    /// Returns a subscription to the messages received from now on,
+    /// which can be narrowed to classes and devices
+    pub fn subscription(&self) -> Subscription {
+        Subscription::new(self.subscribe())
+    }
+
+    /// Returns a receiver of the state of the connection
+    pub fn status(&self) -> watch::Receiver<ServiceStatus> {
+        self.status.clone()
+    }
+
+    /// Stops the service
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for GpsdService {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{io::Write, os::unix::net::UnixStream};
+
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    use super::*;
+    use crate::fixtures;
+
+    #[tokio::test]
+    async fn test_service_broadcast() {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let version = fixtures::VERSION;
+        writeln!(
+            server,
+            "{version}\n{version}\n{}\n{}",
+            fixtures::DEVICES,
+            fixtures::WATCH
+        )
+        .unwrap();
+        client.set_nonblocking(true).unwrap();
+        let client = Arc::new(std::sync::Mutex::new(Some(client)));
+        let connector = move || {
+            let client = client.lock().unwrap().take();
+            async move {
+                let client = client.ok_or(std::io::ErrorKind::ConnectionRefused)?;
+                Ok(tokio::net::UnixStream::from_std(client)?.compat())
+            }
+        };
+        let backoff = Backoff {
+            max_attempts: Some(1),
+            ..Default::default()
+        };
+
+        let service = GpsdService::with_supervisor(
+            Supervisor::new(connector, StreamOptions::json(), backoff),
+            8,
+        );
+        let mut status = service.status();
+        status
+            .wait_for(|status| matches!(status, ServiceStatus::Connected(_)))
+            .await
+            .unwrap();
+
+        let (mut first, mut second) = (service.subscribe(), service.subscribe());
+        let mut device = service.subscription().classes(["DEVICE"]);
+        let mut other_device = service.subscription().devices(["/dev/ttyUSB0"]);
+        writeln!(server, "{}\n{}", fixtures::TPV, fixtures::DEVICE).unwrap();
+        let msg = first.recv().await.unwrap();
+        assert!(matches!(msg.as_ref(), v3::ResponseMessage::Tpv(_)));
+        assert!(Arc::ptr_eq(&msg, &second.recv().await.unwrap()));
+        let msg = device.recv().await.unwrap();
+        assert!(matches!(msg.as_ref(), v3::ResponseMessage::Device(_)));
+
+        drop(server);
+        status
+            .wait_for(|status| *status == ServiceStatus::Stopped)
+            .await
+            .unwrap();
+        assert!(other_device.recv().await.is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Connection helpers for the smol runtime
+//!
+//! The async client works with any stream implementing the futures-io
+//! traits, which the `async-net` TCP stream used by smol does without a
+//! compatibility layer. This module provides the `connect` convenience of
+//! the tokio client for smol users.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::{StreamOptions, smol::connect};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = connect("127.0.0.1:2947").await?;
+//! let mut stream = client.stream(StreamOptions::json()).await?;
+//! while let Some(msg) = stream.next().await {
+//!     println!("GPS data: {:?}", msg?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use async_net::{AsyncToSocketAddrs, TcpStream};
+
+use crate::{Result, client::GpsdClient, error::GpsdJsonError};
+
+/// Connects to a GPSD server over TCP with smol
+///
+/// # Arguments
+/// * `addr` - Socket address of the GPSD server (e.g., "127.0.0.1:2947")
+pub async fn connect<A: AsyncToSocketAddrs>(addr: A) -> Result<GpsdClient<TcpStream>> {
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(GpsdJsonError::IoError)?;
+    let peer = stream.peer_addr().ok();
+    let mut client = GpsdClient::open(stream).await?;
+    client.info.peer = peer;
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_smol_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            writeln!(stream, "{}", crate::fixtures::VERSION).unwrap();
+        });
+
+        let client = futures::executor::block_on(connect(addr)).unwrap();
+        server.join().unwrap();
+        assert_eq!(client.connection().peer, Some(addr));
+    }
+}
@@ -0,0 +1,529 @@
+//! Supervised connections with connection state events
+//!
+//! A [`Supervisor`] keeps a JSON stream to GPSD alive: it dials through a
+//! [`Connector`], re-negotiates the protocol version, re-issues the WATCH
+//! settings of its [`StreamOptions`] after each reconnect and waits between
+//! failed attempts as configured by a [`Backoff`]. Unlike
+//! [`ReconnectingClient`](crate::client::ReconnectingClient), it reports
+//! every change of the connection state as an event next to the messages,
+//! so consumers can e.g. mark positions as stale while disconnected.
+//!
+//! With a [`KeepaliveConfig`], the supervisor also reports connections
+//! without data as degraded and restarts connections that went stale.
+//! [`Supervisor::spawn`] runs a supervisor on its own task and delivers the
+//! events on a channel.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use gpsd_json::client::{
+//!     StreamOptions,
+//!     reconnect::Backoff,
+//!     supervisor::{Supervisor, SupervisorEvent},
+//!     transport::TcpConnector,
+//! };
+//!
+//! # async fn example() {
+//! let supervisor = Supervisor::new(
+//!     TcpConnector::new("gpsd.local:2947"),
+//!     StreamOptions::json(),
+//!     Backoff::default(),
+//! );
+//! let mut events = std::pin::pin!(supervisor.into_stream());
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         SupervisorEvent::Connected { version, .. } => println!("GPSD {}", version.release),
+//!         SupervisorEvent::Message(msg) => println!("{msg:?}"),
+//!         SupervisorEvent::Disconnected(reason) => eprintln!("disconnected: {reason:?}"),
+//!         _ => {}
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! [`Backoff`]: crate::client::reconnect::Backoff
+//! [`Connector`]: crate::client::transport::Connector
+//! [`Supervisor`]: crate::client::supervisor::Supervisor
+//! [`Supervisor::spawn`]: crate::client::supervisor::Supervisor::spawn
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    Result,
+    client::{
+        ConnectionInfo, GpsdDataStream, Json, KeepaliveConfig, StreamOptions,
+        reconnect::Backoff,
+        transport::{Connector, GpsdClientBuilder},
+    },
+    error::GpsdJsonError,
+    protocol::v3,
+};
+
+/// Event of a supervised connection
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum SupervisorEvent {
+    /// A connection attempt starts after the delay, counting attempts from
+    /// one since the last connection
+    Connecting {
+        /// Number of the attempt
+        attempt: u32,
+        /// Delay before the attempt
+        delay: Duration,
+    },
+    /// A connection attempt failed
+    AttemptFailed(GpsdJsonError),
+    /// The connection is established and the WATCH settings are active
+    Connected {
+        /// Metadata about the connection
+        info: ConnectionInfo,
+        /// Version reported by GPSD on this connection
+        version: v3::response::Version,
+    },
+    /// A message was received
+    Message(v3::ResponseMessage),
+    /// No data arrived for the keepalive interval; the connection is kept
+    /// until it goes stale
+    Degraded,
+    /// Data arrived again after the connection was degraded
+    Recovered,
+    /// A message could not be read; the connection is kept
+    Error(GpsdJsonError),
+    /// The connection was lost or went stale, or was closed by GPSD if
+    /// there is no error
+    Disconnected(Option<GpsdJsonError>),
+    /// The maximum number of attempts failed; this is the last event
+    GaveUp(GpsdJsonError),
+}
+
+enum State<S> {
+    Idle { attempt: u32 },
+    Dialing { attempt: u32, delay: Duration },
+    Streaming(Box<GpsdDataStream<S, v3::V3, Json>>),
+    Done,
+}
+
+/// Connection supervisor re-establishing WATCH subscriptions
+///
+/// Events are produced while [`next_event`](Self::next_event) is awaited or
+/// the stream of [`into_stream`](Self::into_stream) is polled, e.g. in a
+/// spawned task.
+pub struct Supervisor<C: Connector> {
+    connector: C,
+    options: StreamOptions<Json>,
+    backoff: Backoff,
+    builder: GpsdClientBuilder<v3::V3>,
+    keepalive: Option<KeepaliveConfig>,
+    state: State<C::Stream>,
+    generation: u32,
+    last_data: Instant,
+    degraded: bool,
+    pending: Option<SupervisorEvent>,
+}
+
+impl<C: Connector> std::fmt::Debug for Supervisor<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Supervisor")
+            .field("backoff", &self.backoff)
+            .field("connection", &self.connection())
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl<C: Connector> Supervisor<C> {
+    /// Creates a supervisor that connects on the first event
+    pub fn new(connector: C, options: StreamOptions<Json>, backoff: Backoff) -> Self {
+        Supervisor {
+            connector,
+            options,
+            backoff,
+            builder: GpsdClientBuilder::new(),
+            keepalive: None,
+            state: State::Idle { attempt: 1 },
+            generation: 0,
+            last_data: Instant::now(),
+            degraded: false,
+            pending: None,
+        }
+    }
+
+    /// Enables the keepalive of the streams
+    ///
+    /// Connections without data for the interval are reported as
+    /// [`Degraded`](SupervisorEvent::Degraded), and connections without
+    /// data or a reply to a ping for the window are restarted. Replies to
+    /// pings are reported as messages but do not count as data.
+    pub fn keepalive(mut self, config: KeepaliveConfig) -> Self {
+        self.keepalive = Some(config);
+        self
+    }
+
+    /// Sets the builder the clients are opened with, e.g. for the parse
+    /// mode
+    pub fn with_builder(mut self, builder: GpsdClientBuilder<v3::V3>) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Returns metadata about the current connection, if connected
+    pub fn connection(&self) -> Option<&ConnectionInfo> {
+        match &self.state {
+            State::Streaming(stream) => Some(stream.connection()),
+            _ => None,
+        }
+    }
+
+    /// Returns the WATCH settings re-issued on each connection
+    pub fn options(&self) -> &StreamOptions<Json> {
+        &self.options
+    }
+
+    /// Replaces the WATCH settings, taking effect on the next connection
+    pub fn set_options(&mut self, options: StreamOptions<Json>) {
+        self.options = options;
+    }
+
+    /// Waits for the next event, or returns `None` after giving up
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel safe, e.g. as a branch of `tokio::select!`.
+    /// No event or message is lost if the future is dropped; a backoff
+    /// delay or connection attempt that was cut short starts over with the
+    /// next call.
+    pub async fn next_event(&mut self) -> Option<SupervisorEvent> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        // The state is only replaced after the awaits, so that dropping
+        // the future leaves it intact
+        match &mut self.state {
+            State::Idle { attempt } => {
+                let attempt = *attempt;
+                let delay = self.backoff.delay(attempt - 1);
+                self.state = State::Dialing { attempt, delay };
+                Some(SupervisorEvent::Connecting { attempt, delay })
+            }
+            State::Dialing { attempt, delay } => {
+                let attempt = *attempt;
+                tokio::time::sleep(*delay).await;
+                match self.open().await {
+                    Ok((version, stream)) => {
+                        let info = *stream.connection();
+                        self.generation += 1;
+                        self.last_data = Instant::now();
+                        self.degraded = false;
+                        self.state = State::Streaming(Box::new(stream));
+                        Some(SupervisorEvent::Connected { info, version })
+                    }
+                    Err(e) if self.backoff.max_attempts.is_some_and(|max| attempt >= max) => {
+                        self.state = State::Done;
+                        Some(SupervisorEvent::GaveUp(e))
+                    }
+                    Err(e) => {
+                        self.state = State::Idle {
+                            attempt: attempt + 1,
+                        };
+                        Some(SupervisorEvent::AttemptFailed(e))
+                    }
+                }
+            }
+            State::Streaming(stream) => {
+                let item =
+                    Self::receive(stream, self.keepalive, self.last_data, &mut self.degraded).await;
+                match item {
+                    None if self.degraded => Some(SupervisorEvent::Degraded),
+                    Some(Some(Ok(msg))) => {
+                        if matches!(msg, v3::ResponseMessage::Version(_)) {
+                            return Some(SupervisorEvent::Message(msg));
+                        }
+                        self.last_data = Instant::now();
+                        if self.degraded {
+                            self.degraded = false;
+                            self.pending = Some(SupervisorEvent::Message(msg));
+                            return Some(SupervisorEvent::Recovered);
+                        }
+                        Some(SupervisorEvent::Message(msg))
+                    }
+                    Some(Some(Err(
+                        e @ (GpsdJsonError::IoError(_) | GpsdJsonError::ConnectionStale(_)),
+                    ))) => {
+                        self.state = State::Idle { attempt: 1 };
+                        Some(SupervisorEvent::Disconnected(Some(e)))
+                    }
+                    None | Some(None) => {
+                        self.state = State::Idle { attempt: 1 };
+                        Some(SupervisorEvent::Disconnected(None))
+                    }
+                    Some(Some(Err(e))) => Some(SupervisorEvent::Error(e)),
+                }
+            }
+            State::Done => None,
+        }
+    }
+
+    /// Runs the supervisor on a new tokio task
+    ///
+    /// Events are delivered through the returned handle, which buffers up
+    /// to `capacity` events. The task ends after giving up or when the
+    /// handle is dropped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use gpsd_json::client::{
+    ///     KeepaliveConfig, StreamOptions, reconnect::Backoff, supervisor::Supervisor,
+    ///     transport::TcpConnector,
+    /// };
+    ///
+    /// # async fn example() {
+    /// let mut handle = Supervisor::new(
+    ///     TcpConnector::new("gpsd.local:2947"),
+    ///     StreamOptions::json(),
+    ///     Backoff::default(),
+    /// )
+    /// .keepalive(KeepaliveConfig::default())
+    /// .spawn(64);
+    /// while let Some(event) = handle.recv().await {
+    ///     println!("{event:?}");
+    /// }
+    /// # }
+    /// ```
+    pub fn spawn(self, capacity: usize) -> SupervisorHandle
+    where
+        C: Send + Sync + 'static,
+    {
+        let (sender, events) = tokio::sync::mpsc::channel(capacity.max(1));
+        let mut supervisor = self;
+        let task = tokio::spawn(async move {
+            while let Some(event) = supervisor.next_event().await {
+                if sender.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        SupervisorHandle { events, task }
+    }
+
+    /// Turns the supervisor into a stream of its events
+    pub fn into_stream(self) -> impl futures_util::Stream<Item = SupervisorEvent> {
+        futures_util::stream::unfold(self, |mut supervisor| async move {
+            let event = supervisor.next_event().await?;
+            Some((event, supervisor))
+        })
+    }
+
+    /// Receives the next item of the stream
+    ///
+    /// Returns `None` when the connection becomes degraded.
+    async fn receive(
+        stream: &mut GpsdDataStream<C::Stream, v3::V3, Json>,
+        keepalive: Option<KeepaliveConfig>,
+        last_data: Instant,
+        degraded: &mut bool,
+    ) -> Option<Option<Result<v3::ResponseMessage>>> {
+        let degraded_at = match keepalive {
+            Some(config) if !*degraded => last_data + config.interval,
+            _ => return Some(stream.next_alive().await),
+        };
+        let wait = degraded_at.saturating_duration_since(Instant::now());
+        match tokio::time::timeout(wait, stream.next_alive()).await {
+            Ok(item) => Some(item),
+            Err(_) => {
+                *degraded = true;
+                None
+            }
+        }
+    }
+
+    async fn open(
+        &mut self,
+    ) -> Result<(
+        v3::response::Version,
+        GpsdDataStream<C::Stream, v3::V3, Json>,
+    )> {
+        let mut client = self
+            .builder
+            .generation(self.generation)
+            .connect_with(&self.connector)
+            .await?;
+        let version = client.version().await?;
+        let mut stream = client.stream(self.options.clone()).await?;
+        if let Some(config) = self.keepalive {
+            stream = stream.keepalive(config);
+        }
+        Ok((version, stream))
+    }
+}
+
+/// Handle of a supervisor running on its own task
+///
+/// Dropping the handle stops the task.
+#[derive(Debug)]
+pub struct SupervisorHandle {
+    events: tokio::sync::mpsc::Receiver<SupervisorEvent>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl SupervisorHandle {
+    /// Waits for the next event, or returns `None` after the supervisor
+    /// gave up
+    pub async fn recv(&mut self) -> Option<SupervisorEvent> {
+        self.events.recv().await
+    }
+
+    /// Stops the supervisor
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for SupervisorHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+    };
+
+    use futures_util::StreamExt;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    use super::*;
+    use crate::fixtures;
+
+    #[tokio::test]
+    async fn test_supervisor_events() {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let devices = r#"{"class":"DEVICES","devices":[]}"#;
+        let watch = r#"{"class":"WATCH","enable":true,"json":true}"#;
+        writeln!(
+            server,
+            "{}\n{}\n{devices}\n{watch}\n{}",
+            fixtures::VERSION,
+            fixtures::VERSION,
+            fixtures::TPV
+        )
+        .unwrap();
+        // Closes the connection after the buffered lines
+        server.shutdown(std::net::Shutdown::Write).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let client = std::sync::Mutex::new(Some(client));
+        let connector = || {
+            let client = client.lock().unwrap().take();
+            async move {
+                let client = client.ok_or(std::io::ErrorKind::ConnectionRefused)?;
+                Ok(tokio::net::UnixStream::from_std(client)?.compat())
+            }
+        };
+        let backoff = Backoff {
+            max_attempts: Some(1),
+            ..Default::default()
+        };
+
+        let events: Vec<_> = Supervisor::new(connector, StreamOptions::json(), backoff)
+            .into_stream()
+            .collect()
+            .await;
+        assert!(matches!(
+            events.as_slice(),
+            [
+                SupervisorEvent::Connecting { attempt: 1, .. },
+                SupervisorEvent::Connected { .. },
+                SupervisorEvent::Message(v3::ResponseMessage::Tpv(_)),
+                SupervisorEvent::Disconnected(None),
+                SupervisorEvent::Connecting { attempt: 1, .. },
+                SupervisorEvent::GaveUp(_),
+            ]
+        ));
+
+        let mut requests = String::new();
+        server.read_to_string(&mut requests).unwrap();
+        assert!(requests.starts_with("?VERSION;"));
+        assert!(requests.contains("?WATCH="));
+    }
+
+    #[tokio::test]
+    async fn test_next_event_cancelled_backoff() {
+        let connector = || async {
+            Err::<tokio_util::compat::Compat<tokio::net::UnixStream>, _>(std::io::Error::from(
+                std::io::ErrorKind::ConnectionRefused,
+            ))
+        };
+        let backoff = Backoff {
+            initial: Duration::from_millis(50),
+            jitter: Duration::ZERO,
+            max_attempts: Some(2),
+            ..Default::default()
+        };
+        let mut supervisor = Supervisor::new(connector, StreamOptions::json(), backoff);
+        for _ in 0..2 {
+            supervisor.next_event().await;
+        }
+        assert!(matches!(
+            supervisor.next_event().await,
+            Some(SupervisorEvent::Connecting { attempt: 2, .. })
+        ));
+
+        // Dropped while waiting for the delay of the second attempt
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(10), supervisor.next_event()).await;
+        assert!(cancelled.is_err());
+        assert!(matches!(
+            supervisor.next_event().await,
+            Some(SupervisorEvent::GaveUp(_))
+        ));
+        assert!(supervisor.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_supervisor_keepalive() {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        let devices = r#"{"class":"DEVICES","devices":[]}"#;
+        let watch = r#"{"class":"WATCH","enable":true,"json":true}"#;
+        // Goes silent after the WATCH confirmation, without closing
+        let version = fixtures::VERSION;
+        writeln!(server, "{version}\n{version}\n{devices}\n{watch}").unwrap();
+        client.set_nonblocking(true).unwrap();
+        let client = std::sync::Arc::new(std::sync::Mutex::new(Some(client)));
+        let connector = move || {
+            let client = client.lock().unwrap().take();
+            async move {
+                let client = client.ok_or(std::io::ErrorKind::ConnectionRefused)?;
+                Ok(tokio::net::UnixStream::from_std(client)?.compat())
+            }
+        };
+        let backoff = Backoff {
+            max_attempts: Some(1),
+            ..Default::default()
+        };
+
+        let mut handle = Supervisor::new(connector, StreamOptions::json(), backoff)
+            .keepalive(KeepaliveConfig {
+                interval: Duration::from_millis(20),
+                window: Duration::from_millis(100),
+            })
+            .spawn(16);
+        let mut events = Vec::new();
+        while let Some(event) = handle.recv().await {
+            events.push(event);
+        }
+        assert!(matches!(
+            events.as_slice(),
+            [
+                SupervisorEvent::Connecting { .. },
+                SupervisorEvent::Connected { .. },
+                SupervisorEvent::Degraded,
+                SupervisorEvent::Disconnected(Some(GpsdJsonError::ConnectionStale(_))),
+                SupervisorEvent::Connecting { .. },
+                SupervisorEvent::GaveUp(_),
+            ]
+        ));
+    }
+}
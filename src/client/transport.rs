@@ -0,0 +1,299 @@
+//! Pluggable transports for the async client
+//!
+//! A [`Connector`] creates the stream the client talks to GPSD over, such
+//! as a TCP connection, a Unix socket, a TLS session, a serial bridge or a
+//! test double. [`GpsdClientBuilder::connect_with`] opens a client on any
+//! connector, so every transport shares one connection path. Closures
+//! returning a future of a stream are connectors too, which covers runtimes
+//! and transports without a built-in connector.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::client::transport::{GpsdClientBuilder, TcpConnector};
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let builder = GpsdClientBuilder::new();
+//! let client = builder.connect_with(&TcpConnector::new("127.0.0.1:2947")).await?;
+//!
+//! // Any stream, here a tokio connection bound to a local address
+//! let client = builder
+//!     .connect_with(&|| async {
+//!         use tokio_util::compat::TokioAsyncReadCompatExt;
+//!
+//!         let socket = tokio::net::TcpSocket::new_v4()?;
+//!         socket.bind("192.168.1.10:0".parse().unwrap())?;
+//!         Ok(socket.connect("192.168.1.20:2947".parse().unwrap()).await?.compat())
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Connector`]: crate::client::transport::Connector
+//! [`GpsdClientBuilder::connect_with`]: crate::client::transport::GpsdClientBuilder::connect_with
+
+use std::{future::Future, io, net::SocketAddr};
+
+use crate::{
+    Result,
+    client::{GpsdClientCore, GpsdJsonProtocol},
+    protocol::ParseMode,
+};
+
+/// Stream a client can talk to GPSD over
+///
+/// Implemented for every `futures_io` stream that is `Unpin` and `Send`;
+/// wrap tokio streams with `tokio_util::compat`.
+pub trait Transport: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin + Send {}
+
+impl<T> Transport for T where T: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin + Send {}
+
+/// Factory of transports to GPSD
+pub trait Connector {
+    /// Transport created by this connector
+    type Stream: Transport;
+
+    /// Creates a new transport
+    fn connect(&self) -> impl Future<Output = io::Result<Self::Stream>> + Send;
+
+    /// Returns the address of the GPSD server a transport is connected to,
+    /// if known
+    fn peer(&self, stream: &Self::Stream) -> Option<SocketAddr> {
+        let _ = stream;
+        None
+    }
+}
+
+impl<F, Fut, S> Connector for F
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = io::Result<S>> + Send,
+    S: Transport,
+{
+    type Stream = S;
+
+    fn connect(&self) -> impl Future<Output = io::Result<S>> + Send {
+        self()
+    }
+}
+
+/// Settings for opening clients on a [`Connector`]
+#[derive(Debug)]
+pub struct GpsdClientBuilder<Proto> {
+    parse_mode: ParseMode,
+    generation: u32,
+    _proto: std::marker::PhantomData<Proto>,
+}
+
+// Not derived, which would require the protocol to be `Clone`
+impl<Proto> Clone for GpsdClientBuilder<Proto> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Proto> Copy for GpsdClientBuilder<Proto> {}
+
+#[cfg(feature = "proto-v3")]
+impl GpsdClientBuilder<crate::protocol::v3::V3> {
+    /// Creates a builder for protocol version 3 clients
+    pub fn new() -> Self {
+        Self::with_protocol()
+    }
+}
+
+#[cfg(feature = "proto-v3")]
+impl Default for GpsdClientBuilder<crate::protocol::v3::V3> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Proto> GpsdClientBuilder<Proto>
+where
+    Proto: GpsdJsonProtocol,
+{
+    /// Creates a builder for clients of a protocol implementation
+    pub fn with_protocol() -> Self {
+        GpsdClientBuilder {
+            parse_mode: ParseMode::Lenient,
+            generation: 0,
+            _proto: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets how fields unknown to the message types are handled, see
+    /// [`GpsdClientCore::with_parse_mode`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// Sets the reconnect generation reported in the connection metadata
+    pub fn generation(mut self, generation: u32) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    /// Creates a transport with the connector and opens a client on it
+    pub async fn connect_with<C: Connector>(
+        &self,
+        connector: &C,
+    ) -> Result<GpsdClientCore<C::Stream, Proto>> {
+        let stream = connector
+            .connect()
+            .await
+            .map_err(crate::error::GpsdJsonError::IoError)?;
+        let peer = connector.peer(&stream);
+        let mut client = GpsdClientCore::open(stream)
+            .await?
+            .with_generation(self.generation)
+            .with_parse_mode(self.parse_mode);
+        client.info.peer = peer;
+        Ok(client)
+    }
+}
+
+#[cfg(all(feature = "tokio", feature = "net"))]
+pub use self::tokio_connectors::TcpConnector;
+#[cfg(all(feature = "tokio", feature = "rustls"))]
+pub use self::tokio_connectors::TlsConnector;
+#[cfg(all(feature = "tokio", feature = "net", unix))]
+pub use self::tokio_connectors::UnixConnector;
+
+#[cfg(all(feature = "tokio", feature = "net"))]
+mod tokio_connectors {
+    use std::{io, net::SocketAddr};
+
+    use tokio::net::{TcpStream, ToSocketAddrs};
+    use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+    use super::Connector;
+
+    /// Connector of TCP connections with tokio
+    #[derive(Debug, Clone)]
+    pub struct TcpConnector<A> {
+        addr: A,
+    }
+
+    impl<A> TcpConnector<A> {
+        /// Creates a connector for a socket address (e.g., "127.0.0.1:2947")
+        pub fn new(addr: A) -> Self {
+            TcpConnector { addr }
+        }
+    }
+
+    impl<A: ToSocketAddrs + Sync> Connector for TcpConnector<A> {
+        type Stream = Compat<TcpStream>;
+
+        async fn connect(&self) -> io::Result<Self::Stream> {
+            Ok(TcpStream::connect(&self.addr).await?.compat())
+        }
+
+        fn peer(&self, stream: &Self::Stream) -> Option<SocketAddr> {
+            stream.get_ref().peer_addr().ok()
+        }
+    }
+
+    /// Connector of Unix domain socket connections with tokio
+    #[cfg(unix)]
+    #[derive(Debug, Clone)]
+    pub struct UnixConnector {
+        path: std::path::PathBuf,
+    }
+
+    #[cfg(unix)]
+    impl UnixConnector {
+        /// Creates a connector for the socket GPSD listens on
+        pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+            UnixConnector { path: path.into() }
+        }
+    }
+
+    #[cfg(unix)]
+    impl Connector for UnixConnector {
+        type Stream = Compat<tokio::net::UnixStream>;
+
+        async fn connect(&self) -> io::Result<Self::Stream> {
+            Ok(tokio::net::UnixStream::connect(&self.path).await?.compat())
+        }
+    }
+
+    /// Connector of TLS sessions over TCP with tokio
+    ///
+    /// The server certificate is verified against the domain with the
+    /// given configuration.
+    #[cfg(feature = "rustls")]
+    #[derive(Clone)]
+    pub struct TlsConnector<A> {
+        addr: A,
+        domain: rustls::pki_types::ServerName<'static>,
+        config: std::sync::Arc<rustls::ClientConfig>,
+    }
+
+    #[cfg(feature = "rustls")]
+    impl<A> std::fmt::Debug for TlsConnector<A> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("TlsConnector")
+                .field("domain", &self.domain)
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    impl<A> TlsConnector<A> {
+        /// Creates a connector for a TLS endpoint
+        ///
+        /// Fails if `domain` is neither a DNS name nor an IP address.
+        pub fn new(
+            addr: A,
+            domain: &str,
+            config: std::sync::Arc<rustls::ClientConfig>,
+        ) -> io::Result<Self> {
+            let domain = rustls::pki_types::ServerName::try_from(domain.to_string())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            Ok(TlsConnector {
+                addr,
+                domain,
+                config,
+            })
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    impl<A: ToSocketAddrs + Sync> Connector for TlsConnector<A> {
+        type Stream = Compat<tokio_rustls::client::TlsStream<TcpStream>>;
+
+        async fn connect(&self) -> io::Result<Self::Stream> {
+            let stream = TcpStream::connect(&self.addr).await?;
+            let stream = tokio_rustls::TlsConnector::from(self.config.clone())
+                .connect(self.domain.clone(), stream)
+                .await?;
+            Ok(stream.compat())
+        }
+
+        fn peer(&self, stream: &Self::Stream) -> Option<SocketAddr> {
+            stream.get_ref().get_ref().0.peer_addr().ok()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "proto-v3"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_with_test_double() {
+        let builder = GpsdClientBuilder::new().generation(2);
+        // Reads the greeting, writes are discarded
+        let connector = || async {
+            let greeting = format!("{}\n", crate::fixtures::VERSION);
+            Ok(futures_util::io::Cursor::new(greeting.into_bytes()))
+        };
+
+        let client = futures::executor::block_on(builder.connect_with(&connector)).unwrap();
+        assert_eq!(client.connection().generation, 2);
+        assert_eq!(client.connection().peer, None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Copies of the raw traffic of a connection
+//!
+//! [`WireTap`] wraps the stream a client talks to GPSD over and copies the
+//! received and sent bytes to separate sinks, such as log files, with each
+//! line prefixed by the time it was read or written. It works with both the
+//! blocking and the async client and needs no logging framework, which helps
+//! with protocol issues reported from the field.
+//!
+//! Failing writes to a sink are ignored, so that a full disk never breaks
+//! the connection; the sink is dropped after the first failure.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::net::TcpStream;
+//!
+//! use gpsd_json::client::{blocking::GpsdClient, wiretap::WireTap};
+//!
+//! # fn example(debug: bool) -> std::io::Result<()> {
+//! let stream = TcpStream::connect("127.0.0.1:2947")?;
+//! let stream = if debug {
+//!     WireTap::files(stream, "gpsd-rx.log", "gpsd-tx.log")?
+//! } else {
+//!     WireTap::new(stream)
+//! };
+//! let client = GpsdClient::open(stream);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`WireTap`]: crate::client::wiretap::WireTap
+
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Destination of tapped traffic
+pub type Sink = Box<dyn Write + Send>;
+
+/// Sink that prefixes each line with a timestamp
+struct Tap {
+    sink: Option<Sink>,
+    line_start: bool,
+}
+
+impl Tap {
+    fn new(sink: Option<Sink>) -> Self {
+        Tap {
+            sink,
+            line_start: true,
+        }
+    }
+
+    fn record(&mut self, bytes: &[u8]) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        let mut result = Ok(());
+        for line in bytes.split_inclusive(|&b| b == b'\n') {
+            if self.line_start {
+                let time = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+                result = result.and_then(|_| write!(sink, "{time} "));
+            }
+            result = result.and_then(|_| sink.write_all(line));
+            self.line_start = line.ends_with(b"\n");
+        }
+        if result.is_err() {
+            self.sink = None;
+        }
+    }
+
+    fn flush(&mut self) {
+        if matches!(self.sink.as_mut().map(|sink| sink.flush()), Some(Err(_))) {
+            self.sink = None;
+        }
+    }
+}
+
+/// Stream that copies its traffic to sinks
+///
+/// Without sinks, it passes the traffic through unchanged, so the decision
+/// to tap can be made at runtime without changing the client type.
+pub struct WireTap<S> {
+    inner: S,
+    rx: Tap,
+    tx: Tap,
+}
+
+impl<S> std::fmt::Debug for WireTap<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WireTap")
+            .field("rx", &self.rx.sink.is_some())
+            .field("tx", &self.tx.sink.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> WireTap<S> {
+    /// Wraps a stream without tapping it
+    pub fn new(inner: S) -> Self {
+        WireTap {
+            inner,
+            rx: Tap::new(None),
+            tx: Tap::new(None),
+        }
+    }
+
+    /// Wraps a stream, appending its traffic to the files at the given paths
+    ///
+    /// The files are created if they do not exist.
+    pub fn files<P: AsRef<Path>, Q: AsRef<Path>>(inner: S, rx: P, tx: Q) -> io::Result<Self> {
+        let open = |path: &Path| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+        };
+        Ok(WireTap::new(inner)
+            .rx(open(rx.as_ref())?)
+            .tx(open(tx.as_ref())?))
+    }
+
+    /// Copies the received bytes to a sink
+    pub fn rx<W: Write + Send + 'static>(mut self, sink: W) -> Self {
+        self.rx = Tap::new(Some(Box::new(sink)));
+        self
+    }
+
+    /// Copies the sent bytes to a sink
+    pub fn tx<W: Write + Send + 'static>(mut self, sink: W) -> Self {
+        self.tx = Tap::new(Some(Box::new(sink)));
+        self
+    }
+
+    /// Returns the wrapped stream
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the wrapped stream mutably
+    ///
+    /// Traffic through the returned reference is not tapped.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped stream, dropping the sinks
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read> Read for WireTap<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.rx.record(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<S: Write> Write for WireTap<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.tx.record(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.rx.flush();
+        self.tx.flush();
+        self.inner.flush()
+    }
+}
+
+impl<S: futures_io::AsyncRead + Unpin> futures_io::AsyncRead for WireTap<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            this.rx.record(&buf[..n]);
+        }
+        poll
+    }
+}
+
+impl<S: futures_io::AsyncWrite + Unpin> futures_io::AsyncWrite for WireTap<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            this.tx.record(&buf[..n]);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.rx.flush();
+        this.tx.flush();
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.rx.flush();
+        this.tx.flush();
+        Pin::new(&mut this.inner).poll_close(cx)
+    }
+}
+
+#[cfg(all(test, feature = "proto-v3"))]
+mod tests {
+    use std::{
+        os::unix::net::UnixStream,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::client::blocking::GpsdClient;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_wire_tap() {
+        let (stream, mut server) = UnixStream::pair().unwrap();
+        let version = crate::fixtures::VERSION;
+        writeln!(server, "{version}\n{version}").unwrap();
+
+        let (rx, tx) = (Shared::default(), Shared::default());
+        let stream = WireTap::new(stream).rx(rx.clone()).tx(tx.clone());
+        let mut client = GpsdClient::open(stream).unwrap();
+        client.version().unwrap();
+
+        let rx = String::from_utf8(rx.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<_> = rx.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.ends_with(version)));
+        let (time, _) = lines[0].split_once(' ').unwrap();
+        assert!(time.parse::<crate::Timestamp>().is_ok());
+
+        let tx = String::from_utf8(tx.0.lock().unwrap().clone()).unwrap();
+        assert!(tx.ends_with(" ?VERSION;"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Pluggable decoding of compressed transports
+//!
+//! GPSD feeds relayed over bandwidth-constrained links are often passed
+//! through a compressing proxy. [`Decoded`] wraps the connection to such a
+//! proxy and decodes everything read from it, so that it can be handed to
+//! the async or blocking client like a plain connection. Requests written
+//! to the connection are passed through unchanged.
+//!
+//! Decoders implement the [`Decoder`] trait. Implementations are provided
+//! for gzip (feature `gzip`) and zstd (feature `zstd`).
+//!
+//! # Example
+//!
+//! ```ignore
+//! use gpsd_json::{
+//!     client::blocking::GpsdClient,
+//!     codec::{Decoded, ZstdDecoder},
+//! };
+//!
+//! let stream = std::net::TcpStream::connect("relay.example.com:2948")?;
+//! let client = GpsdClient::open(Decoded::new(stream, ZstdDecoder::new()?))?;
+//! ```
+//!
+//! [`Decoded`]: crate::codec::Decoded
+//! [`Decoder`]: crate::codec::Decoder
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Size of the buffer for encoded input
+const INPUT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Streaming decoder for a transport encoding
+pub trait Decoder {
+    /// Decodes a chunk of encoded input, appending decoded bytes to `output`
+    ///
+    /// Chunks may split the encoding at arbitrary positions.
+    fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<()>;
+
+    /// Called once the encoded input has ended
+    fn finish(&mut self, _output: &mut Vec<u8>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Decoder passing data through unchanged
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl Decoder for Identity {
+    fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<()> {
+        output.extend_from_slice(input);
+        Ok(())
+    }
+}
+
+/// Decoder for gzip streams, including concatenated gzip members
+#[cfg(feature = "gzip")]
+pub struct GzipDecoder {
+    inner: flate2::write::MultiGzDecoder<Vec<u8>>,
+}
+
+#[cfg(feature = "gzip")]
+impl GzipDecoder {
+    /// Creates a gzip decoder
+    pub fn new() -> Self {
+        GzipDecoder {
+            inner: flate2::write::MultiGzDecoder::new(Vec::new()),
+        }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Default for GzipDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Decoder for GzipDecoder {
+    fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<()> {
+        use std::io::Write;
+
+        self.inner.write_all(input)?;
+        self.inner.flush()?;
+        output.append(self.inner.get_mut());
+        Ok(())
+    }
+}
+
+/// Decoder for zstd streams
+#[cfg(feature = "zstd")]
+pub struct ZstdDecoder {
+    inner: zstd::stream::write::Decoder<'static, Vec<u8>>,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDecoder {
+    /// Creates a zstd decoder
+    pub fn new() -> io::Result<Self> {
+        Ok(ZstdDecoder {
+            inner: zstd::stream::write::Decoder::new(Vec::new())?,
+        })
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Decoder for ZstdDecoder {
+    fn decode(&mut self, input: &[u8], output: &mut Vec<u8>) -> io::Result<()> {
+        use std::io::Write;
+
+        self.inner.write_all(input)?;
+        self.inner.flush()?;
+        output.append(self.inner.get_mut());
+        Ok(())
+    }
+}
+
+/// Connection wrapper decoding everything read from it
+///
+/// Implements `futures_io::AsyncRead`/`AsyncWrite` if the wrapped stream
+/// does, and `std::io::Read`/`Write` likewise, so it can be used with
+/// both the async and the blocking client.
+#[derive(Debug)]
+pub struct Decoded<S, D> {
+    inner: S,
+    decoder: D,
+    input: Box<[u8]>,
+    output: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<S, D: Decoder> Decoded<S, D> {
+    /// Wraps a connection with a decoder
+    pub fn new(inner: S, decoder: D) -> Self {
+        Decoded {
+            inner,
+            decoder,
+            input: vec![0; INPUT_BUFFER_SIZE].into_boxed_slice(),
+            output: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns a reference to the wrapped connection
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the wrapped connection
+    ///
+    /// Decoded data not yet read is lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Copies pending decoded data into `buf`, returning the copied length
+    fn copy_output(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.output.len() - self.pos);
+        buf[..n].copy_from_slice(&self.output[self.pos..self.pos + n]);
+        self.pos += n;
+        if self.pos == self.output.len() {
+            self.output.clear();
+            self.pos = 0;
+        }
+        n
+    }
+
+    /// Processes `n` freshly read bytes of encoded input
+    fn process_input(&mut self, n: usize) -> io::Result<()> {
+        if n == 0 {
+            self.eof = true;
+            self.decoder.finish(&mut self.output)
+        } else {
+            self.decoder.decode(&self.input[..n], &mut self.output)
+        }
+    }
+
+    fn has_output(&self) -> bool {
+        self.pos < self.output.len()
+    }
+}
+
+impl<S, D> futures_io::AsyncRead for Decoded<S, D>
+where
+    S: futures_io::AsyncRead + Unpin,
+    D: Decoder + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            if this.has_output() || buf.is_empty() {
+                return Poll::Ready(Ok(this.copy_output(buf)));
+            }
+            if this.eof {
+                return Poll::Ready(Ok(0));
+            }
+            let n = match Pin::new(&mut this.inner).poll_read(cx, &mut this.input) {
+                Poll::Ready(Ok(n)) => n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+            this.process_input(n)?;
+        }
+    }
+}
+
+impl<S, D> futures_io::AsyncWrite for Decoded<S, D>
+where
+    S: futures_io::AsyncWrite + Unpin,
+    D: Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+impl<S: io::Read, D: Decoder> io::Read for Decoded<S, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.has_output() || buf.is_empty() {
+                return Ok(self.copy_output(buf));
+            }
+            if self.eof {
+                return Ok(0);
+            }
+            let n = self.inner.read(&mut self.input)?;
+            self.process_input(n)?;
+        }
+    }
+}
+
+impl<S: io::Write, D> io::Write for Decoded<S, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, Write};
+
+    #[test]
+    fn test_decoded_gzip_lines() {
+        // Two gzip members, as written by a proxy flushing per message
+        let mut encoded = Vec::new();
+        for line in ["{\"class\":\"TPV\",\"mode\":1}\n", "{\"class\":\"SKY\"}\n"] {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(line.as_bytes()).unwrap();
+            encoded.extend(encoder.finish().unwrap());
+        }
+
+        let decoded = Decoded::new(encoded.as_slice(), GzipDecoder::new());
+        let lines = io::BufReader::new(decoded)
+            .lines()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            lines,
+            ["{\"class\":\"TPV\",\"mode\":1}", "{\"class\":\"SKY\"}"]
+        );
+    }
+}
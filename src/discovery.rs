@@ -0,0 +1,253 @@
+//! Discovery of reachable GPSD endpoints
+//!
+//! Zero-configuration setups often do not know where GPSD runs. The
+//! [`Discovery`] helper probes the local host, a list of candidate hosts
+//! and, with the `mdns` feature, services announced as `_gpsd._tcp` on the
+//! local network. An endpoint counts as reachable when it accepts a TCP
+//! connection and greets with a GPSD VERSION message.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::{client::blocking::GpsdClient, discovery::Discovery};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let endpoints = Discovery::new()
+//!     .candidate("raspberrypi.local")
+//!     .candidate("192.168.1.20:2948")
+//!     .timeout(Duration::from_millis(300))
+//!     .run();
+//!
+//! if let Some(endpoint) = endpoints.first() {
+//!     println!("GPSD {} at {}", endpoint.version.release, endpoint.addr);
+//!     let client = GpsdClient::connect(endpoint.addr)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Discovery`]: crate::discovery::Discovery
+
+use std::{
+    io::{BufRead, BufReader},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    client::DEFAULT_PORT,
+    protocol::v3::{ResponseMessage, response::Version},
+};
+
+/// How an endpoint was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndpointSource {
+    /// GPSD on the local host
+    Localhost,
+    /// One of the configured candidate hosts
+    Candidate,
+    /// A `_gpsd._tcp` service announced over mDNS, with its instance name
+    Mdns(String),
+}
+
+/// Reachable GPSD endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// Address to connect to
+    pub addr: SocketAddr,
+    /// How the endpoint was found
+    pub source: EndpointSource,
+    /// Version greeting sent by GPSD
+    pub version: Version,
+}
+
+/// Probes hosts for reachable GPSD endpoints
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    localhost: bool,
+    candidates: Vec<String>,
+    timeout: Duration,
+    #[cfg(feature = "mdns")]
+    mdns: Option<Duration>,
+}
+
+impl Default for Discovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Discovery {
+    /// Creates a discovery probing only the local host, with a timeout of
+    /// 500 ms per endpoint
+    pub fn new() -> Self {
+        Discovery {
+            localhost: true,
+            candidates: Vec::new(),
+            timeout: Duration::from_millis(500),
+            #[cfg(feature = "mdns")]
+            mdns: None,
+        }
+    }
+
+    /// Enables or disables probing the local host
+    pub fn localhost(mut self, enable: bool) -> Self {
+        self.localhost = enable;
+        self
+    }
+
+    /// Adds a candidate host, with an optional port
+    ///
+    /// Hosts without a port are probed on [`DEFAULT_PORT`].
+    pub fn candidate(mut self, host: impl Into<String>) -> Self {
+        self.candidates.push(host.into());
+        self
+    }
+
+    /// Sets the connect and greeting timeout of every probe
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Browses for `_gpsd._tcp` services for the given time
+    #[cfg(feature = "mdns")]
+    pub fn mdns(mut self, browse: Duration) -> Self {
+        self.mdns = Some(browse);
+        self
+    }
+
+    /// Probes all configured sources and returns the reachable endpoints
+    ///
+    /// Endpoints are probed concurrently and returned in the order local
+    /// host, candidates, mDNS services. Addresses found by several sources
+    /// are returned once.
+    pub fn run(&self) -> Vec<Endpoint> {
+        let mut targets = Vec::new();
+        if self.localhost {
+            targets.push((
+                SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)),
+                EndpointSource::Localhost,
+            ));
+        }
+        for candidate in &self.candidates {
+            for addr in resolve(candidate) {
+                targets.push((addr, EndpointSource::Candidate));
+            }
+        }
+        #[cfg(feature = "mdns")]
+        if let Some(browse) = self.mdns {
+            targets.extend(browse_mdns(browse));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        targets.retain(|(addr, _)| seen.insert(*addr));
+
+        std::thread::scope(|scope| {
+            let probes: Vec<_> = targets
+                .into_iter()
+                .map(|(addr, source)| {
+                    scope.spawn(move || {
+                        probe(addr, self.timeout).map(|version| Endpoint {
+                            addr,
+                            source,
+                            version,
+                        })
+                    })
+                })
+                .collect();
+            probes
+                .into_iter()
+                .filter_map(|probe| probe.join().ok().flatten())
+                .collect()
+        })
+    }
+}
+
+/// Resolves a candidate host, adding the default port if none is given
+fn resolve(host: &str) -> Vec<SocketAddr> {
+    host.to_socket_addrs()
+        .or_else(|_| (host, DEFAULT_PORT).to_socket_addrs())
+        .map(Iterator::collect)
+        .unwrap_or_default()
+}
+
+/// Connects to an address and reads the GPSD version greeting
+fn probe(addr: SocketAddr, timeout: Duration) -> Option<Version> {
+    let stream = TcpStream::connect_timeout(&addr, timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+
+    let mut line = Vec::new();
+    BufReader::new(stream).read_until(b'\n', &mut line).ok()?;
+    match serde_json::from_slice(&line) {
+        Ok(ResponseMessage::Version(version)) => Some(version),
+        _ => None,
+    }
+}
+
+/// Collects the addresses of `_gpsd._tcp` services announced over mDNS
+#[cfg(feature = "mdns")]
+fn browse_mdns(browse: Duration) -> Vec<(SocketAddr, EndpointSource)> {
+    use mdns_sd::{ServiceDaemon, ServiceEvent};
+
+    let Ok(daemon) = ServiceDaemon::new() else {
+        return Vec::new();
+    };
+    let Ok(events) = daemon.browse("_gpsd._tcp.local.") else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    let deadline = std::time::Instant::now() + browse;
+    while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+        match events.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                for ip in info.get_addresses() {
+                    found.push((
+                        SocketAddr::new(*ip, info.get_port()),
+                        EndpointSource::Mdns(info.get_fullname().to_string()),
+                    ));
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = daemon.shutdown();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_discovery_candidates() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            writeln!(
+                stream,
+                r#"{{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}}"#
+            )
+            .unwrap();
+        });
+
+        // The second candidate does not resolve
+        let endpoints = Discovery::new()
+            .localhost(false)
+            .candidate(addr.to_string())
+            .candidate("gpsd.invalid")
+            .run();
+        server.join().unwrap();
+
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].addr, addr);
+        assert_eq!(endpoints[0].source, EndpointSource::Candidate);
+        assert_eq!(endpoints[0].version.release, "3.25");
+    }
+}
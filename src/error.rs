@@ -32,6 +32,44 @@ pub enum GpsdJsonError {
     /// Indicates an error in the GPSD protocol communication,
     /// such as unexpected message sequences or missing required responses.
     ProtocolError(&'static str),
+
+    /// Received data is not valid UTF-8
+    ///
+    /// Returned by NMEA streams configured with
+    /// [`Utf8Policy::Strict`](crate::client::Utf8Policy::Strict).
+    Utf8Error(std::str::Utf8Error),
+
+    /// Report failed physical plausibility checks
+    ///
+    /// Returned by [`Validator`](crate::validate::Validator) when rejecting
+    /// reports with out-of-range values, such as latitudes beyond ±90°.
+    ValidationError(Vec<crate::validate::Violation>),
+
+    /// Message contains fields unknown to the message types
+    ///
+    /// Returned in [`ParseMode::Strict`](crate::protocol::ParseMode::Strict)
+    /// with the JSON pointers of the unknown fields.
+    UnknownFields(Vec<String>),
+
+    /// Neither data nor a reply to a keepalive ping arrived in time
+    ///
+    /// Returned by data streams with a
+    /// [`KeepaliveConfig`](crate::client::KeepaliveConfig) with the time
+    /// since the last data.
+    ConnectionStale(std::time::Duration),
+
+    /// GPSD confirmed other WATCH settings than requested
+    ///
+    /// Returned when enabling or disabling the WATCH mode, e.g. by
+    /// [`stream`](crate::client::GpsdClient::stream), with the settings
+    /// echoed by GPSD.
+    UnexpectedWatch(Box<crate::protocol::v3::types::Watch>),
+
+    /// First line received on connect is not a VERSION message
+    ///
+    /// Contains the start of the received line, e.g. an NMEA sentence
+    /// when connected to an NMEA multiplexer instead of GPSD.
+    InvalidGreeting(Vec<u8>),
 }
 
 impl core::fmt::Display for GpsdJsonError {
@@ -43,8 +81,39 @@ impl core::fmt::Display for GpsdJsonError {
                 write!(f, "UnsupportedProtocolVersion: {major}.{minor}")
             }
             GpsdJsonError::ProtocolError(msg) => write!(f, "ProtocolError: {msg}"),
+            GpsdJsonError::Utf8Error(err) => write!(f, "Utf8Error: {err}"),
+            GpsdJsonError::ValidationError(violations) => {
+                write!(f, "ValidationError: ")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{violation}")?;
+                }
+                Ok(())
+            }
+            GpsdJsonError::UnknownFields(fields) => {
+                write!(f, "UnknownFields: {}", fields.join(", "))
+            }
+            GpsdJsonError::ConnectionStale(silence) => {
+                write!(f, "ConnectionStale: no data for {silence:?}")
+            }
+            GpsdJsonError::UnexpectedWatch(watch) => {
+                write!(f, "UnexpectedWatch: enable={:?}", watch.enable)
+            }
+            GpsdJsonError::InvalidGreeting(line) => {
+                write!(f, "InvalidGreeting: {:?}", String::from_utf8_lossy(line))
+            }
         }
     }
 }
 
 impl core::error::Error for GpsdJsonError {}
+
+/// Wraps I/O errors, e.g. of transports framed with
+/// [`GpsdCodec`](crate::protocol::codec::GpsdCodec)
+impl From<std::io::Error> for GpsdJsonError {
+    fn from(err: std::io::Error) -> Self {
+        GpsdJsonError::IoError(err)
+    }
+}
@@ -0,0 +1,80 @@
+//! Export of reports to time series and analytics formats
+//!
+//! Each submodule converts GPSD reports into the input format of an
+//! external system, so telemetry can be pushed without an intermediate
+//! schema:
+//!
+//! - [`influx`] - InfluxDB line protocol
+//! - `csv` - CSV tables, e.g. one row per satellite per epoch (requires the
+//!   `msg-sky` feature)
+//! - `arrow` - Arrow record batches and Parquet files (requires the `arrow`
+//!   feature)
+//!
+//! The file writers implement [`TrackSink`], so recording code can be
+//! written once for all of them.
+//!
+//! [`TrackSink`]: crate::export::TrackSink
+//! [`influx`]: crate::export::influx
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "msg-sky")]
+pub mod csv;
+pub mod influx;
+
+use crate::{Result, protocol::v3::response::Message};
+
+/// Destination of recorded reports
+///
+/// Implemented by the file writers of this module, so code that records a
+/// stream, e.g. with segmentation or rotation, can write to any of them or
+/// to a custom destination such as an object store uploader. Sinks pick the
+/// report classes they store and skip the others.
+///
+/// # Example
+///
+/// ```
+/// use gpsd_json::{export::TrackSink, protocol::v3::response::Message};
+///
+/// /// Counts the reports instead of storing them
+/// #[derive(Default)]
+/// struct Counter(usize);
+///
+/// impl TrackSink for Counter {
+///     fn record(&mut self, _msg: &Message) -> gpsd_json::Result<()> {
+///         self.0 += 1;
+///         Ok(())
+///     }
+///
+///     fn flush(&mut self) -> gpsd_json::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait TrackSink {
+    /// Records a report, skipping it if the sink does not store its class
+    fn record(&mut self, msg: &Message) -> Result<()>;
+
+    /// Writes buffered reports to the destination
+    fn flush(&mut self) -> Result<()>;
+}
+
+impl<T: TrackSink + ?Sized> TrackSink for &mut T {
+    fn record(&mut self, msg: &Message) -> Result<()> {
+        (**self).record(msg)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+}
+
+impl<T: TrackSink + ?Sized> TrackSink for Box<T> {
+    fn record(&mut self, msg: &Message) -> Result<()> {
+        (**self).record(msg)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+}
@@ -0,0 +1,286 @@
+//! Columnar export of fixes to Arrow record batches and Parquet files
+//!
+//! Requires the `arrow` feature. [`FixBatchBuilder`] accumulates TPV
+//! reports into Arrow record batches with the stable schema returned by
+//! [`fix_schema`], one row per report. [`ParquetFixWriter`] writes such
+//! batches to a Parquet file, so drive logs can be analyzed in DataFusion,
+//! pandas or polars without custom ETL.
+//!
+//! Columns are named as the fields in GPSD JSON. The schema version is
+//! stored in the schema metadata under `gpsd_json.schema_version`; columns
+//! are only ever added in new versions.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::{
+//!     client::blocking::GpsdClient, client::StreamOptions, export::arrow::ParquetFixWriter,
+//!     protocol::v3::ResponseMessage,
+//! };
+//!
+//! # fn example() -> gpsd_json::Result<()> {
+//! let client = GpsdClient::connect("127.0.0.1:2947")?;
+//! let mut writer = ParquetFixWriter::create("drive-01.parquet")?;
+//! for msg in client.stream(StreamOptions::json())?.take(3600) {
+//!     if let ResponseMessage::Tpv(tpv) = msg? {
+//!         writer.push(&tpv)?;
+//!     }
+//! }
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, fs::File, io::Write, path::Path, sync::Arc};
+
+use arrow_array::{
+    ArrayRef, RecordBatch,
+    builder::{
+        ArrayBuilder, Float64Builder, Int8Builder, Int32Builder, StringBuilder,
+        TimestampNanosecondBuilder,
+    },
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+
+use crate::{
+    Result,
+    error::GpsdJsonError,
+    export::TrackSink,
+    protocol::v3::response::{Message, Tpv},
+};
+
+/// Version of the fix schema, stored in the schema metadata
+pub const FIX_SCHEMA_VERSION: u32 = 1;
+
+/// Reads the value of a float column from a fix
+type FloatColumn = fn(&Tpv) -> Option<f64>;
+
+/// Float columns of the fix schema, after `time`, `device`, `mode` and
+/// `status`
+const FLOAT_COLUMNS: [(&str, FloatColumn); 17] = [
+    ("lat", |tpv| tpv.lat),
+    ("lon", |tpv| tpv.lon),
+    ("altHAE", |tpv| tpv.alt_hae),
+    ("altMSL", |tpv| tpv.alt_msl),
+    ("speed", |tpv| tpv.speed),
+    ("track", |tpv| tpv.track),
+    ("climb", |tpv| tpv.climb),
+    ("magvar", |tpv| tpv.magvar),
+    ("geoidSep", |tpv| tpv.geoid_sep),
+    ("ept", |tpv| tpv.ept),
+    ("epx", |tpv| tpv.epx),
+    ("epy", |tpv| tpv.epy),
+    ("epv", |tpv| tpv.epv),
+    ("eph", |tpv| tpv.eph),
+    ("eps", |tpv| tpv.eps),
+    ("epc", |tpv| tpv.epc),
+    ("sep", |tpv| tpv.sep),
+];
+
+/// Returns the schema of fix record batches
+pub fn fix_schema() -> SchemaRef {
+    let mut fields = vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("device", DataType::Utf8, true),
+        Field::new("mode", DataType::Int8, false),
+        Field::new("status", DataType::Int32, true),
+    ];
+    fields.extend(
+        FLOAT_COLUMNS
+            .iter()
+            .map(|(name, _)| Field::new(*name, DataType::Float64, true)),
+    );
+    let metadata = HashMap::from([(
+        "gpsd_json.schema_version".to_string(),
+        FIX_SCHEMA_VERSION.to_string(),
+    )]);
+    Arc::new(Schema::new_with_metadata(fields, metadata))
+}
+
+/// Accumulates fixes into Arrow record batches
+#[derive(Debug)]
+pub struct FixBatchBuilder {
+    time: TimestampNanosecondBuilder,
+    device: StringBuilder,
+    mode: Int8Builder,
+    status: Int32Builder,
+    floats: Vec<Float64Builder>,
+}
+
+impl Default for FixBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixBatchBuilder {
+    /// Creates an empty builder
+    pub fn new() -> Self {
+        FixBatchBuilder {
+            time: TimestampNanosecondBuilder::new().with_timezone("UTC"),
+            device: StringBuilder::new(),
+            mode: Int8Builder::new(),
+            status: Int32Builder::new(),
+            floats: FLOAT_COLUMNS
+                .iter()
+                .map(|_| Float64Builder::new())
+                .collect(),
+        }
+    }
+
+    /// Appends a fix as one row
+    pub fn push(&mut self, tpv: &Tpv) {
+        self.time
+            .append_option(tpv.time.and_then(|time| time.timestamp_nanos_opt()));
+        self.device.append_option(tpv.device.as_deref());
+        self.mode.append_value(tpv.mode as i8);
+        self.status.append_option(tpv.status.map(i32::from));
+        for (builder, (_, value)) in self.floats.iter_mut().zip(FLOAT_COLUMNS) {
+            builder.append_option(value(tpv));
+        }
+    }
+
+    /// Returns the number of rows since the last batch
+    pub fn len(&self) -> usize {
+        self.mode.len()
+    }
+
+    /// Returns `true` if no rows were appended since the last batch
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the appended rows as a record batch and starts a new one
+    pub fn finish(&mut self) -> RecordBatch {
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.time.finish()),
+            Arc::new(self.device.finish()),
+            Arc::new(self.mode.finish()),
+            Arc::new(self.status.finish()),
+        ];
+        columns.extend(self.floats.iter_mut().map(ArrayBuilder::finish));
+        RecordBatch::try_new(fix_schema(), columns).expect("columns match the fix schema")
+    }
+}
+
+/// Writes fixes to a Parquet file in batches
+pub struct ParquetFixWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    batch: FixBatchBuilder,
+    batch_size: usize,
+}
+
+impl<W: Write + Send> std::fmt::Debug for ParquetFixWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParquetFixWriter")
+            .field("pending", &self.batch.len())
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+impl ParquetFixWriter<File> {
+    /// Creates a Parquet file, replacing an existing one
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::create(path).map_err(GpsdJsonError::IoError)?;
+        Self::new(file)
+    }
+}
+
+impl<W: Write + Send> ParquetFixWriter<W> {
+    /// Creates a writer with batches of 8192 fixes
+    pub fn new(writer: W) -> Result<Self> {
+        let writer = ArrowWriter::try_new(writer, fix_schema(), None).map_err(parquet_error)?;
+        Ok(ParquetFixWriter {
+            writer,
+            batch: FixBatchBuilder::new(),
+            batch_size: 8192,
+        })
+    }
+
+    /// Sets the number of fixes buffered before a batch is written
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Appends a fix, writing a batch when it is full
+    pub fn push(&mut self, tpv: &Tpv) -> Result<()> {
+        self.batch.push(tpv);
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered fixes as a batch
+    pub fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        self.writer
+            .write(&self.batch.finish())
+            .map_err(parquet_error)
+    }
+
+    /// Writes the buffered fixes and the file footer, returning the writer
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        self.writer.into_inner().map_err(parquet_error)
+    }
+}
+
+impl<W: Write + Send> TrackSink for ParquetFixWriter<W> {
+    fn record(&mut self, msg: &Message) -> Result<()> {
+        match msg {
+            Message::Tpv(tpv) => self.push(tpv),
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        ParquetFixWriter::flush(self)
+    }
+}
+
+fn parquet_error(e: parquet::errors::ParquetError) -> GpsdJsonError {
+    GpsdJsonError::IoError(std::io::Error::other(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use arrow_array::{Array, cast::AsArray, types::Float64Type};
+
+    #[test]
+    fn test_fix_batches() {
+        let mut builder = FixBatchBuilder::new();
+        for line in [fixtures::TPV_NO_FIX, fixtures::TPV] {
+            builder.push(&serde_json::from_str(line).unwrap());
+        }
+        assert_eq!(builder.len(), 2);
+
+        let batch = builder.finish();
+        assert!(builder.is_empty());
+        assert_eq!(batch.num_rows(), 2);
+        let lat = batch
+            .column_by_name("lat")
+            .unwrap()
+            .as_primitive::<Float64Type>();
+        assert!(lat.is_null(0));
+        assert_eq!(lat.value(1), 35.681236);
+
+        let mut writer = ParquetFixWriter::new(Vec::new()).unwrap().batch_size(1);
+        writer
+            .push(&serde_json::from_str(fixtures::TPV).unwrap())
+            .unwrap();
+        let file = writer.finish().unwrap();
+        assert!(file.starts_with(b"PAR1") && file.ends_with(b"PAR1"));
+    }
+}
@@ -0,0 +1,184 @@
+//! CSV tables of satellite observations
+//!
+//! [`SatelliteCsvWriter`] flattens SKY reports into a long-format table with
+//! one row per satellite per epoch, the input expected by antenna and
+//! receiver comparison workflows:
+//!
+//! ```text
+//! time,device,gnssid,svid,el,az,ss,used
+//! ```
+//!
+//! Missing values are written as empty cells. `gnssid` and `svid` fall back
+//! to empty and the PRN respectively for receivers that do not report them.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{export::csv::SatelliteCsvWriter, protocol::v3::ResponseMessage};
+//!
+//! let sky: ResponseMessage = serde_json::from_str(
+//!     r#"{"class":"SKY","device":"/dev/ttyACM0","time":"2025-01-01T00:00:01.000Z","satellites":[{"PRN":5,"el":42.0,"az":88.0,"ss":41.0,"used":true,"gnssid":0,"svid":5}]}"#,
+//! )?;
+//!
+//! let mut writer = SatelliteCsvWriter::new(Vec::new());
+//! assert_eq!(writer.write(&sky)?, 1);
+//! assert_eq!(
+//!     String::from_utf8(writer.into_inner()).unwrap(),
+//!     "time,device,gnssid,svid,el,az,ss,used\n\
+//!      2025-01-01T00:00:01.000Z,/dev/ttyACM0,0,5,42,88,41,true\n"
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{
+    Result,
+    error::GpsdJsonError,
+    export::TrackSink,
+    protocol::v3::response::{Message, Sky},
+};
+
+/// Header row of the satellite table
+pub const SATELLITE_HEADER: &str = "time,device,gnssid,svid,el,az,ss,used";
+
+/// Writer of SKY reports as rows of satellites
+#[derive(Debug)]
+pub struct SatelliteCsvWriter<W> {
+    writer: W,
+    header: bool,
+}
+
+impl<W: Write> SatelliteCsvWriter<W> {
+    /// Creates a writer that starts the table with a header row
+    pub fn new(writer: W) -> Self {
+        SatelliteCsvWriter {
+            writer,
+            header: true,
+        }
+    }
+
+    /// Creates a writer that appends rows without a header, e.g. to an
+    /// existing table
+    pub fn without_header(writer: W) -> Self {
+        SatelliteCsvWriter {
+            writer,
+            header: false,
+        }
+    }
+
+    /// Writes the satellites of a SKY report and returns the number of rows
+    ///
+    /// Messages of other classes are skipped and write nothing.
+    pub fn write(&mut self, msg: &Message) -> io::Result<usize> {
+        match msg {
+            Message::Sky(sky) => self.write_sky(sky),
+            _ => Ok(0),
+        }
+    }
+
+    /// Writes one row per satellite of a SKY report and returns the number
+    /// of rows
+    pub fn write_sky(&mut self, sky: &Sky) -> io::Result<usize> {
+        if std::mem::take(&mut self.header) {
+            writeln!(self.writer, "{SATELLITE_HEADER}")?;
+        }
+
+        let time = sky
+            .time
+            .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            .unwrap_or_default();
+        let device = quote(sky.device.as_deref().unwrap_or_default());
+        for satellite in &sky.satellites {
+            writeln!(
+                self.writer,
+                "{time},{device},{},{},{},{},{},{}",
+                cell(satellite.gnssid.map(u8::from)),
+                satellite
+                    .svid
+                    .map_or(satellite.prn.to_string(), |svid| svid.to_string()),
+                cell(satellite.elevation),
+                cell(satellite.azimuth),
+                cell(satellite.ss),
+                satellite.used,
+            )?;
+        }
+        Ok(sky.satellites.len())
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TrackSink for SatelliteCsvWriter<W> {
+    fn record(&mut self, msg: &Message) -> Result<()> {
+        SatelliteCsvWriter::write(self, msg)
+            .map(drop)
+            .map_err(GpsdJsonError::IoError)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        SatelliteCsvWriter::flush(self).map_err(GpsdJsonError::IoError)
+    }
+}
+
+/// Formats an optional value, empty if missing
+fn cell<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quotes a text cell if it contains separators, quotes or line breaks
+fn quote(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_satellite_csv_writer() {
+        let sky: Message = serde_json::from_str(fixtures::SKY).unwrap();
+        let tpv: Message = serde_json::from_str(fixtures::TPV).unwrap();
+
+        let mut writer = SatelliteCsvWriter::new(Vec::new());
+        assert_eq!(writer.write(&tpv).unwrap(), 0);
+        let sink: &mut dyn TrackSink = &mut writer;
+        sink.record(&tpv).unwrap();
+        assert_eq!(writer.write(&sky).unwrap(), 3);
+        let Message::Sky(mut sky) = sky else {
+            unreachable!()
+        };
+        sky.device = Some("gpsd://host,1".to_string());
+        sky.satellites.truncate(1);
+        sky.satellites[0].gnssid = None;
+        sky.satellites[0].svid = None;
+        sky.satellites[0].ss = None;
+        assert_eq!(writer.write_sky(&sky).unwrap(), 1);
+
+        let table = String::from_utf8(writer.into_inner()).unwrap();
+        let rows: Vec<_> = table.lines().collect();
+        assert_eq!(
+            rows,
+            [
+                SATELLITE_HEADER,
+                "2025-01-01T00:00:01.000Z,/dev/ttyACM0,0,5,42,88,41,true",
+                "2025-01-01T00:00:01.000Z,/dev/ttyACM0,6,4,56,301,36,true",
+                "2025-01-01T00:00:01.000Z,/dev/ttyACM0,5,1,71,190,0,false",
+                "2025-01-01T00:00:01.000Z,\"gpsd://host,1\",,5,42,88,,true",
+            ]
+        );
+    }
+}
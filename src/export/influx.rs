@@ -0,0 +1,303 @@
+//! InfluxDB line protocol encoding of TPV, SKY and GST reports
+//!
+//! [`InfluxEncoder`] turns a report into one line of
+//! [InfluxDB line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/),
+//! with one measurement per class (`gpsd_tpv`, `gpsd_sky`, `gpsd_gst` by
+//! default). Numeric report fields become fields named as in GPSD JSON,
+//! the device becomes a tag, and the report time becomes the timestamp in
+//! nanoseconds. Reports without a time are written without a timestamp, so
+//! the server assigns the time of writing.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{export::influx::InfluxEncoder, protocol::v3::ResponseMessage};
+//!
+//! let encoder = InfluxEncoder::new()
+//!     .tag("host", "rover-1")
+//!     .fields("TPV", ["mode", "lat", "lon"]);
+//!
+//! let tpv: ResponseMessage = serde_json::from_str(
+//!     r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"time":"2025-01-01T00:00:01.000Z","lat":35.5,"lon":139.5,"speed":0.1}"#,
+//! )?;
+//! assert_eq!(
+//!     encoder.encode(&tpv).unwrap(),
+//!     "gpsd_tpv,device=/dev/ttyACM0,host=rover-1 mode=3i,lat=35.5,lon=139.5 1735689601000000000"
+//! );
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::protocol::v3::response::{Message, Tpv};
+
+/// Value of a line protocol field
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldValue {
+    Float(f64),
+    /// Kept as `f32` so the shortest representation is written
+    #[cfg_attr(not(feature = "small-floats"), allow(dead_code))]
+    Float32(f32),
+    Integer(i64),
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::Float(value)
+    }
+}
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self {
+        FieldValue::Float32(value)
+    }
+}
+
+/// Pushes the present, finite float fields of a report
+macro_rules! float_fields {
+    ($fields:ident, $($name:literal => $value:expr),* $(,)?) => {
+        $(
+            if let Some(value) = $value.filter(|value| value.is_finite()) {
+                $fields.push(($name, FieldValue::from(value)));
+            }
+        )*
+    };
+}
+
+/// Encoder of reports into InfluxDB line protocol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfluxEncoder {
+    prefix: String,
+    device_tag: bool,
+    tags: BTreeMap<String, String>,
+    fields: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Default for InfluxEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InfluxEncoder {
+    /// Creates an encoder with the `gpsd_` measurement prefix, tagging
+    /// reports with their device and writing all fields
+    pub fn new() -> Self {
+        InfluxEncoder {
+            prefix: "gpsd_".to_string(),
+            device_tag: true,
+            tags: BTreeMap::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the prefix of the measurement names, followed by the lowercase
+    /// class
+    pub fn measurement_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Enables or disables the `device` tag
+    pub fn device_tag(mut self, enable: bool) -> Self {
+        self.device_tag = enable;
+        self
+    }
+
+    /// Adds a tag written on every line, e.g. the host name
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key.into(), value.into());
+        self
+    }
+
+    /// Writes only the given fields of reports of a class
+    ///
+    /// Calling this again for the same class adds to its fields.
+    pub fn fields<I, F>(mut self, class: impl Into<String>, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        self.fields
+            .entry(class.into())
+            .or_default()
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Encodes a report as one line, without a line ending
+    ///
+    /// Returns `None` for classes other than TPV, SKY and GST, and for
+    /// reports without any of the selected fields.
+    pub fn encode(&self, msg: &Message) -> Option<String> {
+        let (device, time, mut fields) = match msg {
+            Message::Tpv(tpv) => (tpv.device.as_deref(), tpv.time, tpv_fields(tpv)),
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => (sky.device.as_deref(), sky.time, sky_fields(sky)),
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(gst) => (gst.device.as_deref(), gst.time, gst_fields(gst)),
+            _ => return None,
+        };
+        let class = msg.class_name();
+        if let Some(selected) = self.fields.get(class) {
+            fields.retain(|(name, _)| selected.contains(*name));
+        }
+        if fields.is_empty() {
+            return None;
+        }
+
+        let mut line = escape(&format!("{}{}", self.prefix, class.to_lowercase()), ", ");
+        let mut tags: Vec<(&str, &str)> = self
+            .tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        if let Some(device) = device.filter(|_| self.device_tag) {
+            tags.push(("device", device));
+            tags.sort();
+        }
+        for (key, value) in tags {
+            line.push(',');
+            line.push_str(&escape(key, ",= "));
+            line.push('=');
+            line.push_str(&escape(value, ",= "));
+        }
+
+        for (i, (name, value)) in fields.into_iter().enumerate() {
+            line.push(if i == 0 { ' ' } else { ',' });
+            line.push_str(name);
+            match value {
+                FieldValue::Float(value) => line.push_str(&format!("={value}")),
+                FieldValue::Float32(value) => line.push_str(&format!("={value}")),
+                FieldValue::Integer(value) => line.push_str(&format!("={value}i")),
+            }
+        }
+
+        if let Some(nanos) = time.and_then(|time| time.timestamp_nanos_opt()) {
+            line.push_str(&format!(" {nanos}"));
+        }
+        Some(line)
+    }
+}
+
+/// Escapes the given characters with a backslash
+fn escape(value: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn tpv_fields(tpv: &Tpv) -> Vec<(&'static str, FieldValue)> {
+    let mut fields = vec![("mode", FieldValue::Integer(tpv.mode as i64))];
+    if let Some(status) = tpv.status {
+        fields.push(("status", FieldValue::Integer(i32::from(status).into())));
+    }
+    float_fields!(fields,
+        "lat" => tpv.lat,
+        "lon" => tpv.lon,
+        "altHAE" => tpv.alt_hae,
+        "altMSL" => tpv.alt_msl,
+        "speed" => tpv.speed,
+        "track" => tpv.track,
+        "climb" => tpv.climb,
+        "magvar" => tpv.magvar,
+        "geoidSep" => tpv.geoid_sep,
+        "ept" => tpv.ept,
+        "epx" => tpv.epx,
+        "epy" => tpv.epy,
+        "epv" => tpv.epv,
+        "eph" => tpv.eph,
+        "eps" => tpv.eps,
+        "epc" => tpv.epc,
+        "sep" => tpv.sep,
+    );
+    fields
+}
+
+#[cfg(feature = "msg-sky")]
+fn sky_fields(sky: &crate::protocol::v3::response::Sky) -> Vec<(&'static str, FieldValue)> {
+    let mut fields = Vec::new();
+    // Older GPSD versions only send the satellite list
+    let counted = !sky.satellites.is_empty();
+    let n_sat = sky
+        .n_sat
+        .map(i64::from)
+        .or(counted.then_some(sky.satellites.len() as i64));
+    let u_sat = sky.u_sat.map(i64::from).or(counted.then(|| {
+        sky.satellites
+            .iter()
+            .filter(|satellite| satellite.used)
+            .count() as i64
+    }));
+    if let Some(n_sat) = n_sat {
+        fields.push(("nSat", FieldValue::Integer(n_sat)));
+    }
+    if let Some(u_sat) = u_sat {
+        fields.push(("uSat", FieldValue::Integer(u_sat)));
+    }
+    float_fields!(fields,
+        "xdop" => sky.dop.x,
+        "ydop" => sky.dop.y,
+        "pdop" => sky.dop.p,
+        "hdop" => sky.dop.h,
+        "vdop" => sky.dop.v,
+        "tdop" => sky.dop.t,
+        "gdop" => sky.dop.g,
+    );
+    fields
+}
+
+#[cfg(feature = "msg-gst")]
+fn gst_fields(gst: &crate::protocol::v3::response::Gst) -> Vec<(&'static str, FieldValue)> {
+    let mut fields = Vec::new();
+    float_fields!(fields,
+        "rms" => gst.rms,
+        "major" => gst.major,
+        "minor" => gst.minor,
+        "orient" => gst.orient,
+        "lat" => gst.lat,
+        "lon" => gst.lon,
+        "alt" => gst.alt,
+        "ve" => gst.ve,
+        "vn" => gst.vn,
+        "vu" => gst.vu,
+    );
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_influx_encoder() {
+        let encoder = InfluxEncoder::new()
+            .tag("host", "base station")
+            .fields("SKY", ["nSat", "uSat", "hdop"]);
+
+        #[cfg(feature = "msg-sky")]
+        {
+            let sky = serde_json::from_str(fixtures::SKY).unwrap();
+            assert_eq!(
+                encoder.encode(&sky).unwrap(),
+                r"gpsd_sky,device=/dev/ttyACM0,host=base\ station nSat=3i,uSat=2i,hdop=0.99 1735689601000000000"
+            );
+        }
+
+        #[cfg(feature = "msg-gst")]
+        {
+            let gst = serde_json::from_str(fixtures::GST).unwrap();
+            let line = encoder.clone().device_tag(false).encode(&gst).unwrap();
+            assert!(line.starts_with(r"gpsd_gst,host=base\ station rms=1.8,major=4.3,"));
+        }
+
+        let watch = serde_json::from_str(fixtures::WATCH).unwrap();
+        assert_eq!(encoder.encode(&watch), None);
+    }
+}
@@ -0,0 +1,72 @@
+//! Sample GPSD JSON lines for tests
+//!
+//! Requires the `fixtures` feature. The samples follow the output of GPSD
+//! 3.25 with a u-blox receiver on `/dev/ttyACM0`, without line endings.
+//! They are decoded by the crate's own tests without unknown fields, so
+//! downstream crates can test their handling of GPSD data with the same
+//! vetted samples.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{fixtures, protocol::v3::ResponseMessage};
+//!
+//! let msg: ResponseMessage = serde_json::from_str(fixtures::TPV).unwrap();
+//! assert!(matches!(msg, ResponseMessage::Tpv(_)));
+//! ```
+
+/// VERSION greeting sent by GPSD on connect
+pub const VERSION: &str =
+    r#"{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}"#;
+
+/// DEVICES reply listing one receiver
+pub const DEVICES: &str = r#"{"class":"DEVICES","devices":[{"class":"DEVICE","path":"/dev/ttyACM0","driver":"u-blox","subtype":"SW ROM CORE 3.01 (107888),HW 00080000","activated":"2025-01-01T00:00:00.000Z","flags":1,"native":1,"bps":9600,"parity":"N","stopbits":1,"cycle":1.00,"mincycle":0.25}]}"#;
+
+/// DEVICE notification of an activated receiver
+pub const DEVICE: &str = r#"{"class":"DEVICE","path":"/dev/ttyACM0","driver":"u-blox","activated":"2025-01-01T00:00:00.000Z","flags":1,"native":1,"bps":9600,"parity":"N","stopbits":1,"cycle":1.00,"mincycle":0.25}"#;
+
+/// WATCH reply enabling JSON output
+pub const WATCH: &str = r#"{"class":"WATCH","enable":true,"json":true,"nmea":false,"raw":0,"scaled":false,"timing":false,"split24":false,"pps":false}"#;
+
+/// TPV report with a 3D fix
+pub const TPV: &str = r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"time":"2025-01-01T00:00:01.000Z","ept":0.005,"lat":35.681236,"lon":139.767125,"altHAE":45.123,"altMSL":8.456,"alt":8.456,"epx":3.2,"epy":4.1,"epv":7.4,"track":84.3,"magtrack":77.4,"magvar":-7.6,"speed":0.012,"climb":-0.003,"eps":8.2,"epc":14.8,"geoidSep":36.667,"eph":5.1,"sep":9.0}"#;
+
+/// TPV report without a fix
+pub const TPV_NO_FIX: &str =
+    r#"{"class":"TPV","device":"/dev/ttyACM0","mode":1,"time":"2025-01-01T00:00:00.000Z"}"#;
+
+/// SKY report with dilutions of precision and three satellites
+pub const SKY: &str = r#"{"class":"SKY","device":"/dev/ttyACM0","time":"2025-01-01T00:00:01.000Z","xdop":0.61,"ydop":0.78,"vdop":1.22,"tdop":0.86,"hdop":0.99,"gdop":1.81,"pdop":1.57,"nSat":3,"uSat":2,"satellites":[{"PRN":5,"el":42.0,"az":88.0,"ss":41.0,"used":true,"gnssid":0,"svid":5,"health":1},{"PRN":68,"el":56.0,"az":301.0,"ss":36.0,"used":true,"gnssid":6,"svid":4,"freqid":3,"health":1},{"PRN":193,"el":71.0,"az":190.0,"ss":0.0,"used":false,"gnssid":5,"svid":1,"health":1}]}"#;
+
+/// GST pseudorange error statistics
+pub const GST: &str = r#"{"class":"GST","device":"/dev/ttyACM0","time":"2025-01-01T00:00:01.000Z","rms":1.800,"major":4.300,"minor":2.900,"orient":112.4,"lat":3.100,"lon":2.600,"alt":6.200}"#;
+
+/// PPS timing report
+pub const PPS: &str = r#"{"class":"PPS","device":"/dev/ttyACM0","real_sec":1735689601,"real_nsec":0,"clock_sec":1735689601,"clock_nsec":182736,"precision":-20}"#;
+
+/// TOFF time offset report
+pub const TOFF: &str = r#"{"class":"TOFF","device":"/dev/ttyACM0","real_sec":1735689601,"real_nsec":0,"clock_sec":1735689601,"clock_nsec":98374612}"#;
+
+/// ERROR reply to an unknown command
+pub const ERROR: &str = r#"{"class":"ERROR","message":"Unrecognized request 'FOO'"}"#;
+
+/// Session as received after a WATCH request: DEVICES and WATCH replies,
+/// then one epoch of reports
+pub const SESSION: &[&str] = &[DEVICES, WATCH, TPV, SKY, GST, PPS, TOFF];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{unknown_fields, v3::ResponseMessage};
+
+    #[test]
+    fn test_fixtures_decode_without_unknown_fields() {
+        for line in [VERSION, DEVICE, TPV_NO_FIX, ERROR].iter().chain(SESSION) {
+            let msg: ResponseMessage = serde_json::from_str(line).unwrap();
+            if cfg!(feature = "messages") {
+                assert!(!matches!(msg, ResponseMessage::Other(_)), "{line}");
+            }
+            assert_eq!(unknown_fields(line.as_bytes(), &msg), Vec::<String>::new());
+        }
+    }
+}
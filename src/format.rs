@@ -0,0 +1,389 @@
+//! Display formatting for speeds, headings and coordinates
+//!
+//! This module produces human-readable strings for values reported by GPSD,
+//! such as speed over ground, course and latitude/longitude. All output is
+//! locale-agnostic: a `.` is always used as decimal separator and no digit
+//! grouping is applied, so strings are stable across systems.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::format::{CoordinateFormat, Formatter, SpeedUnit};
+//!
+//! let fmt = Formatter::new()
+//!     .speed_unit(SpeedUnit::Knots)
+//!     .coordinate_format(CoordinateFormat::DegreesMinutesSeconds);
+//!
+//! assert_eq!(fmt.speed(5.0), "9.7 kn");
+//! assert_eq!(fmt.latitude(35.6812), "35°40'52.32\" N");
+//! ```
+
+/// Meters per second in one kilometer per hour
+const MPS_PER_KMH: f64 = 1000.0 / 3600.0;
+/// Meters per second in one statute mile per hour
+const MPS_PER_MPH: f64 = 1609.344 / 3600.0;
+/// Meters per second in one knot
+const MPS_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+/// Unit used to display speeds
+///
+/// GPSD reports all speeds in meters per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpeedUnit {
+    /// Meters per second (m/s)
+    #[default]
+    MetersPerSecond,
+    /// Kilometers per hour (km/h)
+    KilometersPerHour,
+    /// Statute miles per hour (mph)
+    MilesPerHour,
+    /// Nautical miles per hour (kn)
+    Knots,
+}
+
+impl SpeedUnit {
+    /// Converts a speed in meters per second to this unit
+    pub fn from_mps(self, mps: f64) -> f64 {
+        match self {
+            SpeedUnit::MetersPerSecond => mps,
+            SpeedUnit::KilometersPerHour => mps / MPS_PER_KMH,
+            SpeedUnit::MilesPerHour => mps / MPS_PER_MPH,
+            SpeedUnit::Knots => mps / MPS_PER_KNOT,
+        }
+    }
+
+    /// Converts a speed in this unit to meters per second
+    pub fn to_mps(self, value: f64) -> f64 {
+        match self {
+            SpeedUnit::MetersPerSecond => value,
+            SpeedUnit::KilometersPerHour => value * MPS_PER_KMH,
+            SpeedUnit::MilesPerHour => value * MPS_PER_MPH,
+            SpeedUnit::Knots => value * MPS_PER_KNOT,
+        }
+    }
+
+    /// Returns the unit symbol used in formatted output
+    pub fn symbol(self) -> &'static str {
+        match self {
+            SpeedUnit::MetersPerSecond => "m/s",
+            SpeedUnit::KilometersPerHour => "km/h",
+            SpeedUnit::MilesPerHour => "mph",
+            SpeedUnit::Knots => "kn",
+        }
+    }
+}
+
+/// Notation used to display latitude and longitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CoordinateFormat {
+    /// Decimal degrees, e.g. `35.68120° N`
+    #[default]
+    Decimal,
+    /// Degrees and decimal minutes, e.g. `35°40.872' N`
+    DegreesMinutes,
+    /// Degrees, minutes and decimal seconds, e.g. `35°40'52.3" N`
+    DegreesMinutesSeconds,
+}
+
+/// Number of points on the compass rose used for cardinal headings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompassPoints {
+    /// N, E, S, W
+    Four,
+    /// N, NE, E, SE, S, SW, W, NW
+    #[default]
+    Eight,
+    /// N, NNE, NE, ENE, E, ...
+    Sixteen,
+}
+
+/// Notation used to display headings and courses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HeadingFormat {
+    /// Degrees only, e.g. `123°`
+    #[default]
+    Degrees,
+    /// Cardinal direction only, e.g. `SE`
+    Cardinal(CompassPoints),
+    /// Degrees followed by cardinal direction, e.g. `123° SE`
+    Both(CompassPoints),
+}
+
+const COMPASS_16: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+/// Returns the cardinal direction closest to a heading in degrees
+///
+/// Headings outside 0-360 are normalized first. Non-finite headings
+/// return `"?"`.
+///
+/// # Example
+/// ```
+/// use gpsd_json::format::{CompassPoints, cardinal};
+///
+/// assert_eq!(cardinal(100.0, CompassPoints::Four), "E");
+/// assert_eq!(cardinal(350.0, CompassPoints::Sixteen), "N");
+/// ```
+pub fn cardinal(heading: f64, points: CompassPoints) -> &'static str {
+    if !heading.is_finite() {
+        return "?";
+    }
+
+    let step = match points {
+        CompassPoints::Four => 4,
+        CompassPoints::Eight => 2,
+        CompassPoints::Sixteen => 1,
+    };
+    let sector = 22.5 * step as f64;
+    let index = (heading.rem_euclid(360.0) / sector).round() as usize * step;
+    COMPASS_16[index % 16]
+}
+
+/// Formats a speed given in meters per second
+///
+/// # Arguments
+/// * `mps` - Speed in meters per second
+/// * `unit` - Unit to display
+/// * `precision` - Number of decimal places
+pub fn format_speed(mps: f64, unit: SpeedUnit, precision: usize) -> String {
+    format!("{:.precision$} {}", unit.from_mps(mps), unit.symbol())
+}
+
+/// Formats a heading or course given in degrees
+pub fn format_heading(heading: f64, format: HeadingFormat) -> String {
+    match format {
+        HeadingFormat::Degrees => format!("{:.0}°", whole_degrees(heading)),
+        HeadingFormat::Cardinal(points) => cardinal(heading, points).to_string(),
+        HeadingFormat::Both(points) => format!(
+            "{:.0}° {}",
+            whole_degrees(heading),
+            cardinal(heading, points)
+        ),
+    }
+}
+
+/// Rounds a heading to whole degrees in `0..360`, so that e.g. 359.6
+/// becomes 0 rather than 360
+fn whole_degrees(heading: f64) -> f64 {
+    // Adding zero turns -0 into 0
+    heading.round().rem_euclid(360.0) + 0.0
+}
+
+/// Formats a latitude in degrees with a N/S hemisphere suffix
+///
+/// # Arguments
+/// * `lat` - Latitude in degrees (positive = North)
+/// * `format` - Notation to use
+/// * `precision` - Decimal places of the last component
+pub fn format_latitude(lat: f64, format: CoordinateFormat, precision: usize) -> String {
+    format_coordinate(lat, if lat < 0.0 { 'S' } else { 'N' }, format, precision)
+}
+
+/// Formats a longitude in degrees with an E/W hemisphere suffix
+///
+/// # Arguments
+/// * `lon` - Longitude in degrees (positive = East)
+/// * `format` - Notation to use
+/// * `precision` - Decimal places of the last component
+pub fn format_longitude(lon: f64, format: CoordinateFormat, precision: usize) -> String {
+    format_coordinate(lon, if lon < 0.0 { 'W' } else { 'E' }, format, precision)
+}
+
+fn format_coordinate(
+    value: f64,
+    hemisphere: char,
+    format: CoordinateFormat,
+    precision: usize,
+) -> String {
+    if !value.is_finite() {
+        return format!("{value}");
+    }
+
+    match format {
+        CoordinateFormat::Decimal => format!("{:.precision$}° {hemisphere}", value.abs()),
+        CoordinateFormat::DegreesMinutes => {
+            let (deg, min) = split_ddm(value, precision);
+            format!("{deg}°{min:.precision$}' {hemisphere}")
+        }
+        CoordinateFormat::DegreesMinutesSeconds => {
+            let (deg, min, sec) = split_dms(value, precision);
+            format!("{deg}°{min}'{sec:.precision$}\" {hemisphere}")
+        }
+    }
+}
+
+/// Splits an angle into whole degrees and decimal minutes
+///
+/// The sign is dropped. Minutes are rounded to `precision` decimal places
+/// with carry into degrees, so `59.9999'` never prints as `60.0'`.
+pub(crate) fn split_ddm(value: f64, precision: usize) -> (u32, f64) {
+    let scale = 10f64.powi(precision as i32);
+    let total = (value.abs() * 60.0 * scale).round();
+    let per_degree = 60.0 * scale;
+
+    let deg = (total / per_degree).floor();
+    let min = (total - deg * per_degree) / scale;
+    (deg as u32, min)
+}
+
+/// Splits an angle into whole degrees, whole minutes and decimal seconds
+///
+/// The sign is dropped. Seconds are rounded to `precision` decimal places
+/// with carry into minutes and degrees.
+pub(crate) fn split_dms(value: f64, precision: usize) -> (u32, u32, f64) {
+    let scale = 10f64.powi(precision as i32);
+    let total = (value.abs() * 3600.0 * scale).round();
+    let per_minute = 60.0 * scale;
+    let per_degree = 60.0 * per_minute;
+
+    let deg = (total / per_degree).floor();
+    let rest = total - deg * per_degree;
+    let min = (rest / per_minute).floor();
+    let sec = (rest - min * per_minute) / scale;
+    (deg as u32, min as u32, sec)
+}
+
+/// Reusable display configuration
+///
+/// Bundles the unit and notation choices of an application so that all
+/// values are formatted consistently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Formatter {
+    speed_unit: SpeedUnit,
+    speed_precision: usize,
+    coordinate_format: CoordinateFormat,
+    coordinate_precision: Option<usize>,
+    heading_format: HeadingFormat,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter {
+            speed_unit: SpeedUnit::MetersPerSecond,
+            speed_precision: 1,
+            coordinate_format: CoordinateFormat::Decimal,
+            coordinate_precision: None,
+            heading_format: HeadingFormat::Degrees,
+        }
+    }
+}
+
+impl Formatter {
+    /// Creates a formatter with default settings
+    ///
+    /// Defaults to m/s with one decimal, decimal degrees and headings in degrees.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the unit used for speeds
+    pub fn speed_unit(mut self, unit: SpeedUnit) -> Self {
+        self.speed_unit = unit;
+        self
+    }
+
+    /// Sets the number of decimal places used for speeds
+    pub fn speed_precision(mut self, precision: usize) -> Self {
+        self.speed_precision = precision;
+        self
+    }
+
+    /// Sets the notation used for coordinates
+    pub fn coordinate_format(mut self, format: CoordinateFormat) -> Self {
+        self.coordinate_format = format;
+        self
+    }
+
+    /// Sets the number of decimal places of the last coordinate component
+    ///
+    /// When unset, a resolution of a few decimeters is used: 6 places for
+    /// decimal degrees, 4 for decimal minutes and 2 for decimal seconds.
+    pub fn coordinate_precision(mut self, precision: usize) -> Self {
+        self.coordinate_precision = Some(precision);
+        self
+    }
+
+    /// Sets the notation used for headings
+    pub fn heading_format(mut self, format: HeadingFormat) -> Self {
+        self.heading_format = format;
+        self
+    }
+
+    /// Formats a speed given in meters per second
+    pub fn speed(&self, mps: f64) -> String {
+        format_speed(mps, self.speed_unit, self.speed_precision)
+    }
+
+    /// Formats a heading or course given in degrees
+    pub fn heading(&self, heading: f64) -> String {
+        format_heading(heading, self.heading_format)
+    }
+
+    /// Formats a latitude given in degrees
+    pub fn latitude(&self, lat: f64) -> String {
+        format_latitude(lat, self.coordinate_format, self.precision())
+    }
+
+    /// Formats a longitude given in degrees
+    pub fn longitude(&self, lon: f64) -> String {
+        format_longitude(lon, self.coordinate_format, self.precision())
+    }
+
+    /// Formats a latitude/longitude pair separated by a comma
+    pub fn position(&self, lat: f64, lon: f64) -> String {
+        format!("{}, {}", self.latitude(lat), self.longitude(lon))
+    }
+
+    fn precision(&self) -> usize {
+        self.coordinate_precision
+            .unwrap_or(match self.coordinate_format {
+                CoordinateFormat::Decimal => 6,
+                CoordinateFormat::DegreesMinutes => 4,
+                CoordinateFormat::DegreesMinutesSeconds => 2,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_coordinates() {
+        let fmt = CoordinateFormat::DegreesMinutesSeconds;
+        assert_eq!(format_latitude(-33.8688, fmt, 1), "33°52'7.7\" S");
+        // Rounding must carry into minutes and degrees
+        assert_eq!(format_longitude(10.999_999_9, fmt, 1), "11°0'0.0\" E");
+        assert_eq!(
+            format_longitude(-0.5, CoordinateFormat::DegreesMinutes, 3),
+            "0°30.000' W"
+        );
+        assert_eq!(
+            format_latitude(35.6812, CoordinateFormat::Decimal, 2),
+            "35.68° N"
+        );
+    }
+
+    #[test]
+    fn test_format_speed_and_heading() {
+        assert_eq!(
+            format_speed(10.0, SpeedUnit::KilometersPerHour, 0),
+            "36 km/h"
+        );
+        assert_eq!(format_speed(10.0, SpeedUnit::MilesPerHour, 1), "22.4 mph");
+        assert_eq!(cardinal(-10.0, CompassPoints::Eight), "N");
+        assert_eq!(cardinal(200.0, CompassPoints::Sixteen), "SSW");
+        assert_eq!(
+            format_heading(123.4, HeadingFormat::Both(CompassPoints::Eight)),
+            "123° SE"
+        );
+        assert_eq!(format_heading(359.6, HeadingFormat::Degrees), "0°");
+        assert_eq!(format_heading(-0.4, HeadingFormat::Degrees), "0°");
+        assert_eq!(
+            format_heading(359.4, HeadingFormat::Both(CompassPoints::Eight)),
+            "359° N"
+        );
+    }
+}
@@ -0,0 +1,676 @@
+//! Geographic helpers for working with GPS positions
+//!
+//! This module provides small, dependency-free utilities for spatial
+//! processing of GPS data, such as computing the bounding box of a track
+//! and enumerating the slippy-map tiles that cover it.
+//!
+//! All coordinates are WGS84 latitude/longitude in decimal degrees,
+//! as reported by GPSD in TPV messages.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::geo::{BoundingBox, Position};
+//!
+//! let track = [
+//!     Position::new(35.6812, 139.7671),
+//!     Position::new(35.6586, 139.7454),
+//! ];
+//! let bbox = BoundingBox::from_positions(track).unwrap();
+//! for tile in bbox.tiles(14) {
+//!     println!("https://tile.openstreetmap.org/{}/{}/{}.png", tile.z, tile.x, tile.y);
+//! }
+//! ```
+
+use crate::{
+    format::{CoordinateFormat, format_latitude, format_longitude},
+    protocol::v3::response::Tpv,
+};
+
+/// H3 hexagonal cell indexing
+#[cfg(feature = "h3")]
+pub mod h3;
+
+/// Maximum latitude representable in the Web Mercator projection
+///
+/// Slippy-map tiles only cover latitudes within ±85.0511 degrees.
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Mean Earth radius in meters (IUGG)
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Maximum zoom level supported by the tile helpers
+pub const MAX_ZOOM: u8 = 30;
+
+/// A geographic position in decimal degrees
+///
+/// Latitude is positive to the North, longitude positive to the East.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Position {
+    /// Latitude in degrees (-90 to 90)
+    pub lat: f64,
+    /// Longitude in degrees (-180 to 180)
+    pub lon: f64,
+}
+
+impl Position {
+    /// Creates a new position from latitude and longitude in degrees
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Position { lat, lon }
+    }
+
+    /// Extracts the position from a TPV report
+    ///
+    /// Returns `None` if the report does not carry both latitude and longitude.
+    pub fn from_tpv(tpv: &Tpv) -> Option<Self> {
+        match (tpv.lat, tpv.lon) {
+            (Some(lat), Some(lon)) => Some(Position { lat, lon }),
+            _ => None,
+        }
+    }
+
+    /// Returns the great-circle distance to another position in meters
+    ///
+    /// Uses the haversine formula on a spherical Earth, which is accurate
+    /// to about 0.5% and well suited for the short distances between fixes.
+    pub fn distance_to(&self, other: &Position) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_M * a.sqrt().min(1.0).asin()
+    }
+}
+
+impl Position {
+    /// Formats the position as degrees, minutes and seconds
+    ///
+    /// Seconds are printed with two decimals (about 0.3 m resolution).
+    ///
+    /// # Example
+    /// ```
+    /// # use gpsd_json::geo::Position;
+    /// let pos = Position::new(35.6812, 139.7671);
+    /// assert_eq!(pos.to_dms(), "35°40'52.32\" N, 139°46'1.56\" E");
+    /// ```
+    pub fn to_dms(&self) -> String {
+        self.to_format(CoordinateFormat::DegreesMinutesSeconds, 2)
+    }
+
+    /// Formats the position as degrees and decimal minutes
+    ///
+    /// Minutes are printed with four decimals (about 0.2 m resolution).
+    ///
+    /// # Example
+    /// ```
+    /// # use gpsd_json::geo::Position;
+    /// let pos = Position::new(-33.8688, 151.2093);
+    /// assert_eq!(pos.to_ddm(), "33°52.1280' S, 151°12.5580' E");
+    /// ```
+    pub fn to_ddm(&self) -> String {
+        self.to_format(CoordinateFormat::DegreesMinutes, 4)
+    }
+
+    /// Formats the position in the given notation and precision
+    ///
+    /// See the [`format`](crate::format) module for the available notations.
+    pub fn to_format(&self, format: CoordinateFormat, precision: usize) -> String {
+        format!(
+            "{}, {}",
+            format_latitude(self.lat, format, precision),
+            format_longitude(self.lon, format, precision)
+        )
+    }
+
+    /// Parses a position from common textual notations
+    ///
+    /// Accepted inputs include:
+    /// - Signed decimal degrees: `35.6812, 139.7671` or `-33.8688 151.2093`
+    /// - Hemisphere letters as prefix or suffix: `N35.6812 E139.7671`, `33.8688S 151.2093E`
+    /// - Degrees and decimal minutes: `35°40.872'N 139°46.026'E`
+    /// - Degrees, minutes and seconds: `35°40'52.3"N, 139°46'1.6"E` or `35 40 52.3 N 139 46 1.6 E`
+    ///
+    /// Without hemisphere letters the first coordinate is the latitude.
+    /// With letters, the order may be swapped (`139.7671E 35.6812N`).
+    ///
+    /// # Example
+    /// ```
+    /// # use gpsd_json::geo::Position;
+    /// let pos = Position::parse("35°40'52.32\"N, 139°46'1.56\"E").unwrap();
+    /// assert!((pos.lat - 35.6812).abs() < 1e-6);
+    /// assert!((pos.lon - 139.7671).abs() < 1e-6);
+    /// ```
+    pub fn parse(s: &str) -> Result<Self, ParsePositionError> {
+        let tokens = tokenize(s)?;
+        let mut groups = group_tokens(&tokens)?;
+
+        // Without separators, split an even run of numbers in half
+        if groups.len() == 1 && groups[0].hemisphere.is_none() {
+            let numbers = std::mem::take(&mut groups[0].numbers);
+            if !matches!(numbers.len(), 2 | 4 | 6) {
+                return Err(ParsePositionError("expected latitude and longitude"));
+            }
+            let (lat, lon) = numbers.split_at(numbers.len() / 2);
+            groups = vec![
+                CoordinateGroup {
+                    numbers: lat.to_vec(),
+                    hemisphere: None,
+                },
+                CoordinateGroup {
+                    numbers: lon.to_vec(),
+                    hemisphere: None,
+                },
+            ];
+        }
+
+        let [first, second] = groups.as_slice() else {
+            return Err(ParsePositionError("expected latitude and longitude"));
+        };
+
+        let (lat, lon) = match (first.axis(), second.axis()) {
+            (Some(Axis::Longitude), Some(Axis::Latitude)) => (second, first),
+            (Some(a), Some(b)) if a == b => {
+                return Err(ParsePositionError("both coordinates on the same axis"));
+            }
+            (Some(Axis::Longitude), None) | (None, Some(Axis::Latitude)) => {
+                return Err(ParsePositionError("latitude must come first"));
+            }
+            _ => (first, second),
+        };
+
+        let pos = Position {
+            lat: lat.value()?,
+            lon: lon.value()?,
+        };
+        if !(-90.0..=90.0).contains(&pos.lat) {
+            return Err(ParsePositionError("latitude out of range"));
+        }
+        if !(-180.0..=180.0).contains(&pos.lon) {
+            return Err(ParsePositionError("longitude out of range"));
+        }
+        Ok(pos)
+    }
+}
+
+impl std::str::FromStr for Position {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Position::parse(s)
+    }
+}
+
+/// Error returned when a textual position cannot be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePositionError(&'static str);
+
+impl core::fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid position: {}", self.0)
+    }
+}
+
+impl core::error::Error for ParsePositionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Hemisphere(char),
+    Separator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Latitude,
+    Longitude,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CoordinateGroup {
+    numbers: Vec<f64>,
+    hemisphere: Option<char>,
+}
+
+impl CoordinateGroup {
+    fn axis(&self) -> Option<Axis> {
+        match self.hemisphere? {
+            'N' | 'S' => Some(Axis::Latitude),
+            _ => Some(Axis::Longitude),
+        }
+    }
+
+    fn value(&self) -> Result<f64, ParsePositionError> {
+        let (deg, min, sec) = match self.numbers.as_slice() {
+            [d] => (*d, 0.0, 0.0),
+            [d, m] => (*d, *m, 0.0),
+            [d, m, s] => (*d, *m, *s),
+            _ => return Err(ParsePositionError("too many coordinate components")),
+        };
+
+        if self.numbers.len() > 1 && deg.fract() != 0.0 {
+            return Err(ParsePositionError("fractional degrees with minutes"));
+        }
+        if !(0.0..60.0).contains(&min) || !(0.0..60.0).contains(&sec) {
+            return Err(ParsePositionError("minutes or seconds out of range"));
+        }
+        if self.numbers.len() > 2 && min.fract() != 0.0 {
+            return Err(ParsePositionError("fractional minutes with seconds"));
+        }
+
+        let negative = deg.is_sign_negative();
+        let mut value = deg.abs() + min / 60.0 + sec / 3600.0;
+        if negative {
+            value = -value;
+        }
+
+        match self.hemisphere {
+            Some('S') | Some('W') if negative => {
+                Err(ParsePositionError("negative value with S/W hemisphere"))
+            }
+            Some('S') | Some('W') => Ok(-value),
+            _ => Ok(value),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParsePositionError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.trim().char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '0'..='9' | '.' | '-' | '+' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(idx, next)) = chars.peek() {
+                    if next.is_ascii_digit() || next == '.' {
+                        end = idx + next.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let number = s.trim()[start..end]
+                    .parse::<f64>()
+                    .map_err(|_| ParsePositionError("malformed number"))?;
+                tokens.push(Token::Number(number));
+            }
+            'N' | 'S' | 'E' | 'W' | 'n' | 's' | 'e' | 'w' => {
+                tokens.push(Token::Hemisphere(c.to_ascii_uppercase()));
+            }
+            ',' | ';' | '/' => tokens.push(Token::Separator),
+            '°' | 'º' | '\'' | '"' | '′' | '″' | '’' | '”' => {}
+            c if c.is_whitespace() => {}
+            _ => return Err(ParsePositionError("unexpected character")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn group_tokens(tokens: &[Token]) -> Result<Vec<CoordinateGroup>, ParsePositionError> {
+    let mut groups = Vec::new();
+    let mut current = CoordinateGroup::default();
+
+    for token in tokens {
+        match *token {
+            Token::Number(n) => current.numbers.push(n),
+            Token::Hemisphere(h) => {
+                if current.numbers.is_empty() {
+                    // Prefix notation, e.g. N35.6812
+                    if current.hemisphere.replace(h).is_some() {
+                        return Err(ParsePositionError("repeated hemisphere"));
+                    }
+                } else if current.hemisphere.is_none() {
+                    // Suffix notation, e.g. 35.6812N
+                    current.hemisphere = Some(h);
+                    groups.push(std::mem::take(&mut current));
+                } else {
+                    // Prefixed coordinate followed by the next prefix
+                    groups.push(std::mem::take(&mut current));
+                    current.hemisphere = Some(h);
+                }
+            }
+            Token::Separator => {
+                if current.numbers.is_empty() {
+                    if current.hemisphere.is_some() {
+                        return Err(ParsePositionError("hemisphere without value"));
+                    }
+                } else {
+                    groups.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+
+    if !current.numbers.is_empty() {
+        groups.push(current);
+    } else if current.hemisphere.is_some() {
+        return Err(ParsePositionError("hemisphere without value"));
+    }
+
+    Ok(groups)
+}
+
+impl From<(f64, f64)> for Position {
+    fn from((lat, lon): (f64, f64)) -> Self {
+        Position { lat, lon }
+    }
+}
+
+/// Axis-aligned bounding box in latitude/longitude
+///
+/// The box does not handle tracks crossing the antimeridian; such tracks
+/// produce a box spanning the whole longitude range between the extremes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    /// Southern edge in degrees
+    pub min_lat: f64,
+    /// Western edge in degrees
+    pub min_lon: f64,
+    /// Northern edge in degrees
+    pub max_lat: f64,
+    /// Eastern edge in degrees
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    /// Creates a degenerate bounding box containing a single position
+    pub fn from_position(pos: Position) -> Self {
+        BoundingBox {
+            min_lat: pos.lat,
+            min_lon: pos.lon,
+            max_lat: pos.lat,
+            max_lon: pos.lon,
+        }
+    }
+
+    /// Computes the bounding box of a sequence of positions
+    ///
+    /// Returns `None` if the sequence is empty.
+    pub fn from_positions<I, P>(positions: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Position>,
+    {
+        let mut iter = positions.into_iter().map(Into::into);
+        let mut bbox = BoundingBox::from_position(iter.next()?);
+        for pos in iter {
+            bbox.extend(pos);
+        }
+        Some(bbox)
+    }
+
+    /// Computes the bounding box of the positions contained in TPV reports
+    ///
+    /// Reports without latitude/longitude are skipped. Returns `None`
+    /// if no report carries a position.
+    pub fn from_tpvs<'a, I>(tpvs: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a Tpv>,
+    {
+        Self::from_positions(tpvs.into_iter().filter_map(Position::from_tpv))
+    }
+
+    /// Grows the bounding box so that it contains the given position
+    pub fn extend(&mut self, pos: Position) {
+        self.min_lat = self.min_lat.min(pos.lat);
+        self.min_lon = self.min_lon.min(pos.lon);
+        self.max_lat = self.max_lat.max(pos.lat);
+        self.max_lon = self.max_lon.max(pos.lon);
+    }
+
+    /// Returns true if the position lies within the box (edges inclusive)
+    pub fn contains(&self, pos: Position) -> bool {
+        (self.min_lat..=self.max_lat).contains(&pos.lat)
+            && (self.min_lon..=self.max_lon).contains(&pos.lon)
+    }
+
+    /// Returns the center of the bounding box
+    pub fn center(&self) -> Position {
+        Position {
+            lat: (self.min_lat + self.max_lat) / 2.0,
+            lon: (self.min_lon + self.max_lon) / 2.0,
+        }
+    }
+
+    /// Returns a copy of the box expanded by `margin` degrees on every side
+    ///
+    /// Latitudes are clamped to ±90 and longitudes to ±180.
+    pub fn expand(&self, margin: f64) -> Self {
+        BoundingBox {
+            min_lat: (self.min_lat - margin).max(-90.0),
+            min_lon: (self.min_lon - margin).max(-180.0),
+            max_lat: (self.max_lat + margin).min(90.0),
+            max_lon: (self.max_lon + margin).min(180.0),
+        }
+    }
+
+    /// Returns the range of tiles covering this box at the given zoom level
+    ///
+    /// # Arguments
+    /// * `zoom` - Zoom level (clamped to `MAX_ZOOM`)
+    pub fn tile_range(&self, zoom: u8) -> TileRange {
+        let north_west = TileIndex::from_position(Position::new(self.max_lat, self.min_lon), zoom);
+        let south_east = TileIndex::from_position(Position::new(self.min_lat, self.max_lon), zoom);
+
+        TileRange {
+            z: north_west.z,
+            min_x: north_west.x,
+            max_x: south_east.x,
+            min_y: north_west.y,
+            max_y: south_east.y,
+        }
+    }
+
+    /// Iterates over all tiles covering this box at the given zoom level
+    pub fn tiles(&self, zoom: u8) -> impl Iterator<Item = TileIndex> {
+        self.tile_range(zoom).into_iter()
+    }
+}
+
+/// Slippy-map tile coordinates (z/x/y)
+///
+/// Follows the OpenStreetMap tile naming convention, where `x` grows
+/// eastwards and `y` grows southwards from the top-left corner of the map.
+///
+/// Reference: [Slippy map tilenames](https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileIndex {
+    /// Zoom level
+    pub z: u8,
+    /// Tile column
+    pub x: u32,
+    /// Tile row
+    pub y: u32,
+}
+
+impl TileIndex {
+    /// Computes the tile containing the given position at a zoom level
+    ///
+    /// Latitudes outside the Web Mercator range are clamped to the
+    /// northernmost/southernmost tile row.
+    ///
+    /// # Arguments
+    /// * `pos` - Position to locate
+    /// * `zoom` - Zoom level (clamped to `MAX_ZOOM`)
+    pub fn from_position(pos: Position, zoom: u8) -> Self {
+        let z = zoom.min(MAX_ZOOM);
+        let n = (1u64 << z) as f64;
+        let max_index = (1u64 << z) - 1;
+
+        let lat = pos
+            .lat
+            .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE)
+            .to_radians();
+        let lon = pos.lon.clamp(-180.0, 180.0);
+
+        let x = ((lon + 180.0) / 360.0 * n).floor() as u64;
+        let y = ((1.0 - lat.tan().asinh() / std::f64::consts::PI) / 2.0 * n).floor() as u64;
+
+        TileIndex {
+            z,
+            x: x.min(max_index) as u32,
+            y: y.min(max_index) as u32,
+        }
+    }
+
+    /// Returns the position of the tile's north-west corner
+    pub fn north_west(&self) -> Position {
+        let n = (1u64 << self.z) as f64;
+        let lon = self.x as f64 / n * 360.0 - 180.0;
+        let lat = (std::f64::consts::PI * (1.0 - 2.0 * self.y as f64 / n))
+            .sinh()
+            .atan()
+            .to_degrees();
+        Position { lat, lon }
+    }
+
+    /// Returns the geographic bounds covered by the tile
+    pub fn bounds(&self) -> BoundingBox {
+        let north_west = self.north_west();
+        let south_east = TileIndex {
+            z: self.z,
+            x: self.x + 1,
+            y: self.y + 1,
+        }
+        .north_west();
+
+        BoundingBox {
+            min_lat: south_east.lat,
+            min_lon: north_west.lon,
+            max_lat: north_west.lat,
+            max_lon: south_east.lon,
+        }
+    }
+}
+
+/// Rectangular range of tiles at a single zoom level
+///
+/// Both the `x` and `y` ranges are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileRange {
+    /// Zoom level
+    pub z: u8,
+    /// Westernmost tile column
+    pub min_x: u32,
+    /// Easternmost tile column
+    pub max_x: u32,
+    /// Northernmost tile row
+    pub min_y: u32,
+    /// Southernmost tile row
+    pub max_y: u32,
+}
+
+impl TileRange {
+    /// Returns the number of tiles in the range
+    pub fn len(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        (self.max_x - self.min_x + 1) as u64 * (self.max_y - self.min_y + 1) as u64
+    }
+
+    /// Returns true if the range contains no tiles
+    ///
+    /// A range built from a bounding box always contains at least one tile.
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_y > self.max_y
+    }
+
+    /// Returns true if the tile lies within this range
+    pub fn contains(&self, tile: &TileIndex) -> bool {
+        tile.z == self.z
+            && (self.min_x..=self.max_x).contains(&tile.x)
+            && (self.min_y..=self.max_y).contains(&tile.y)
+    }
+}
+
+impl IntoIterator for TileRange {
+    type Item = TileIndex;
+    type IntoIter = Box<dyn Iterator<Item = TileIndex>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let z = self.z;
+        let (min_x, max_x) = (self.min_x, self.max_x);
+        Box::new(
+            (self.min_y..=self.max_y)
+                .flat_map(move |y| (min_x..=max_x).map(move |x| TileIndex { z, x, y })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_position_parse() {
+        let expected = Position::new(-33.8688, 151.2093);
+        for input in [
+            "-33.8688, 151.2093",
+            "-33.8688 151.2093",
+            "33.8688S 151.2093E",
+            "S33.8688 E151.2093",
+            "151.2093 E, 33.8688 S",
+            "33°52.128'S 151°12.558'E",
+            "33 52 7.68 S, 151 12 33.48 E",
+            "-33 52 7.68 151 12 33.48",
+            &expected.to_dms(),
+            &expected.to_ddm(),
+        ] {
+            let pos: Position = input.parse().unwrap();
+            assert!((pos.lat - expected.lat).abs() < 1e-6, "{input}");
+            assert!((pos.lon - expected.lon).abs() < 1e-6, "{input}");
+        }
+
+        for input in [
+            "",
+            "35.0",
+            "95.0, 10.0",
+            "35 N 10 S",
+            "35°61'N 10E",
+            "-35S 10E",
+        ] {
+            assert!(Position::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_geo_tile_index() {
+        // Tokyo Station at zoom 10
+        let tile = TileIndex::from_position(Position::new(35.6812, 139.7671), 10);
+        assert_eq!(
+            tile,
+            TileIndex {
+                z: 10,
+                x: 909,
+                y: 403
+            }
+        );
+        assert!(tile.bounds().contains(Position::new(35.6812, 139.7671)));
+
+        let tile = TileIndex::from_position(Position::new(90.0, 180.0), 2);
+        assert_eq!(tile, TileIndex { z: 2, x: 3, y: 0 });
+    }
+
+    #[test]
+    fn test_geo_bounding_box_tiles() {
+        let bbox =
+            BoundingBox::from_positions([(35.0, 139.0), (36.0, 140.0), (35.5, 139.5)]).unwrap();
+        assert_eq!(bbox.min_lat, 35.0);
+        assert_eq!(bbox.max_lon, 140.0);
+
+        let range = bbox.tile_range(8);
+        let tiles: Vec<_> = bbox.tiles(8).collect();
+        assert_eq!(tiles.len() as u64, range.len());
+        assert!(tiles.iter().all(|tile| range.contains(tile)));
+        let empty = TileRange {
+            min_x: range.max_x + 1,
+            ..range
+        };
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert!(BoundingBox::from_positions(Vec::<Position>::new()).is_none());
+    }
+}
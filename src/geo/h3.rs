@@ -0,0 +1,93 @@
+//! H3 cell indexing of GPS positions
+//!
+//! This module maps positions to [H3](https://h3geo.org/) hexagonal cells,
+//! which makes it easy to aggregate large amounts of GPS data spatially
+//! (e.g. counting fixes per cell for coverage or heat maps).
+//!
+//! Requires the `h3` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::geo::{Position, h3::{H3Indexer, Resolution}};
+//!
+//! let indexer = H3Indexer::new(Resolution::Nine);
+//! let cell = indexer.cell(Position::new(35.6812, 139.7671)).unwrap();
+//! println!("cell: {cell}");
+//! ```
+
+use std::collections::HashMap;
+
+pub use h3o::{CellIndex, Resolution};
+
+use crate::{geo::Position, protocol::v3::response::Tpv};
+
+impl Position {
+    /// Returns the H3 cell containing this position at the given resolution
+    ///
+    /// Returns `None` if the coordinates are not finite.
+    pub fn h3_cell(&self, resolution: Resolution) -> Option<CellIndex> {
+        h3o::LatLng::new(self.lat, self.lon)
+            .ok()
+            .map(|latlng| latlng.to_cell(resolution))
+    }
+}
+
+/// Maps positions to H3 cells at a fixed resolution
+///
+/// The indexer is cheap to copy and can be shared between threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct H3Indexer {
+    resolution: Resolution,
+}
+
+impl H3Indexer {
+    /// Creates an indexer producing cells at the given resolution
+    pub fn new(resolution: Resolution) -> Self {
+        H3Indexer { resolution }
+    }
+
+    /// Creates an indexer from a numeric resolution (0-15)
+    ///
+    /// Returns `None` if the resolution is out of range.
+    pub fn with_level(level: u8) -> Option<Self> {
+        Resolution::try_from(level).ok().map(Self::new)
+    }
+
+    /// Returns the resolution used by this indexer
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Returns the cell containing the given position
+    ///
+    /// Returns `None` if the coordinates are not finite.
+    pub fn cell(&self, pos: Position) -> Option<CellIndex> {
+        pos.h3_cell(self.resolution)
+    }
+
+    /// Returns the cell containing the position of a TPV report
+    ///
+    /// Returns `None` if the report carries no position.
+    pub fn cell_for_tpv(&self, tpv: &Tpv) -> Option<CellIndex> {
+        Position::from_tpv(tpv).and_then(|pos| self.cell(pos))
+    }
+
+    /// Counts how many positions fall into each cell
+    ///
+    /// Positions that cannot be indexed are ignored.
+    pub fn aggregate<I, P>(&self, positions: I) -> HashMap<CellIndex, usize>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Position>,
+    {
+        let mut counts = HashMap::new();
+        for cell in positions
+            .into_iter()
+            .filter_map(|pos| self.cell(pos.into()))
+        {
+            *counts.entry(cell).or_insert(0) += 1;
+        }
+        counts
+    }
+}
@@ -0,0 +1,488 @@
+//! Jamming and spoofing heuristics
+//!
+//! This module watches a stream of GPSD reports for signs of radio
+//! interference or GNSS spoofing. None of the heuristics is conclusive on
+//! its own; they flag situations that deserve attention in deployments
+//! where the integrity of position and time matters:
+//!
+//! - The receiver's own jamming indicator (`jam` in TPV) exceeds a threshold
+//! - Consecutive fixes imply an impossible speed (a position jump)
+//! - The mean signal strength of used satellites collapses between SKY reports
+//! - GPS time advances differently from the local clock
+//!
+//! For pipelines that only need to keep implausible fixes out of their
+//! data, [`JumpFilter`] rejects or flags fixes implying impossible speed
+//! or acceleration, without the other heuristics.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::integrity::{IntegrityConfig, IntegrityMonitor};
+//! # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>) {
+//! let mut monitor = IntegrityMonitor::new(IntegrityConfig::default());
+//! for msg in &messages {
+//!     for event in monitor.update(msg) {
+//!         eprintln!("{:?} suspected: {event:?}", event.suspicion());
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! [`JumpFilter`]: crate::integrity::JumpFilter
+
+use chrono::Utc;
+
+use crate::{
+    Timestamp,
+    geo::Position,
+    protocol::v3::{
+        response::{Message, Tpv},
+        types::FixMode,
+    },
+};
+
+/// Kind of attack or interference an event hints at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suspicion {
+    /// Signals are being drowned out by interference
+    Jamming,
+    /// Counterfeit signals are steering position or time
+    Spoofing,
+}
+
+/// Integrity event emitted by the [`IntegrityMonitor`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityEvent {
+    /// The receiver reported a jamming indicator above the threshold
+    JammingIndicated {
+        /// Reported indicator value
+        jam: i32,
+    },
+    /// Two consecutive fixes imply a speed above the configured maximum
+    PositionJump {
+        /// Distance between the fixes in meters
+        distance: f64,
+        /// Time between the fixes in seconds
+        elapsed: f64,
+    },
+    /// The mean signal strength of used satellites dropped sharply
+    SignalCollapse {
+        /// Mean signal strength of the previous SKY report in dB-Hz
+        before: f64,
+        /// Mean signal strength of the current SKY report in dB-Hz
+        after: f64,
+    },
+    /// GPS time advanced differently from the local clock
+    TimeDiscontinuity {
+        /// Seconds GPS time advanced between reports
+        gps_elapsed: f64,
+        /// Seconds the local clock advanced between reports
+        local_elapsed: f64,
+    },
+}
+
+impl IntegrityEvent {
+    /// Returns the kind of attack or interference this event hints at
+    pub fn suspicion(&self) -> Suspicion {
+        match self {
+            IntegrityEvent::JammingIndicated { .. } | IntegrityEvent::SignalCollapse { .. } => {
+                Suspicion::Jamming
+            }
+            IntegrityEvent::PositionJump { .. } | IntegrityEvent::TimeDiscontinuity { .. } => {
+                Suspicion::Spoofing
+            }
+        }
+    }
+}
+
+/// Thresholds used by the [`IntegrityMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegrityConfig {
+    /// Jamming indicator value above which jamming is reported
+    ///
+    /// The scale is receiver specific; u-blox receivers report 0 to 255.
+    pub jam_threshold: i32,
+    /// Largest plausible speed in m/s between consecutive fixes
+    pub max_speed: f64,
+    /// Drop in mean signal strength in dB-Hz reported as a collapse
+    pub snr_drop: f64,
+    /// Minimum number of used satellites for the signal strength check
+    pub min_satellites: usize,
+    /// Largest accepted difference in seconds between GPS and local
+    /// elapsed time
+    pub time_tolerance: f64,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        IntegrityConfig {
+            jam_threshold: 80,
+            max_speed: 300.0,
+            snr_drop: 10.0,
+            min_satellites: 4,
+            time_tolerance: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fix {
+    position: Position,
+    time: Timestamp,
+    received: Timestamp,
+    eph: f64,
+}
+
+/// Watches GPSD reports for signs of jamming or spoofing
+///
+/// Feed every report to [`IntegrityMonitor::update`]; reports other than
+/// TPV and SKY are ignored.
+#[derive(Debug, Clone)]
+pub struct IntegrityMonitor {
+    config: IntegrityConfig,
+    last_fix: Option<Fix>,
+    last_snr: Option<f64>,
+}
+
+impl IntegrityMonitor {
+    /// Creates a monitor with the given thresholds
+    pub fn new(config: IntegrityConfig) -> Self {
+        IntegrityMonitor {
+            config,
+            last_fix: None,
+            last_snr: None,
+        }
+    }
+
+    /// Returns the thresholds used by this monitor
+    pub fn config(&self) -> &IntegrityConfig {
+        &self.config
+    }
+
+    /// Clears all history
+    pub fn reset(&mut self) {
+        self.last_fix = None;
+        self.last_snr = None;
+    }
+
+    /// Processes a report received now
+    pub fn update(&mut self, message: &Message) -> Vec<IntegrityEvent> {
+        self.update_at(message, Utc::now())
+    }
+
+    /// Processes a report with an explicit local receive time
+    ///
+    /// The receive time is compared against GPS time to detect time
+    /// discontinuities.
+    pub fn update_at(&mut self, message: &Message, received: Timestamp) -> Vec<IntegrityEvent> {
+        let mut events = Vec::new();
+        match message {
+            Message::Tpv(tpv) => self.check_tpv(tpv, received, &mut events),
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => self.check_sky(sky, &mut events),
+            _ => {}
+        }
+        events
+    }
+
+    fn check_tpv(&mut self, tpv: &Tpv, received: Timestamp, events: &mut Vec<IntegrityEvent>) {
+        if let Some(jam) = tpv.jam.filter(|&jam| jam > self.config.jam_threshold) {
+            events.push(IntegrityEvent::JammingIndicated { jam });
+        }
+
+        if tpv.mode < FixMode::Fix2D {
+            return;
+        }
+        let (Some(position), Some(time)) = (Position::from_tpv(tpv), tpv.time) else {
+            return;
+        };
+        let fix = Fix {
+            position,
+            time,
+            received,
+            eph: tpv.eph.unwrap_or(0.0),
+        };
+
+        if let Some(last) = self.last_fix.replace(fix) {
+            let gps_elapsed = (fix.time - last.time).as_seconds_f64();
+            let local_elapsed = (fix.received - last.received).as_seconds_f64();
+            if (gps_elapsed - local_elapsed).abs() > self.config.time_tolerance {
+                events.push(IntegrityEvent::TimeDiscontinuity {
+                    gps_elapsed,
+                    local_elapsed,
+                });
+            }
+
+            if gps_elapsed > 0.0 {
+                let distance = last.position.distance_to(&fix.position);
+                // Allow for the position error of both fixes
+                let reach = self.config.max_speed * gps_elapsed + last.eph + fix.eph;
+                if distance > reach {
+                    events.push(IntegrityEvent::PositionJump {
+                        distance,
+                        elapsed: gps_elapsed,
+                    });
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "msg-sky")]
+    fn check_sky(
+        &mut self,
+        sky: &crate::protocol::v3::response::Sky,
+        events: &mut Vec<IntegrityEvent>,
+    ) {
+        let used = sky
+            .satellites
+            .iter()
+            .filter(|sat| sat.used)
+            .filter_map(|sat| sat.ss.map(crate::protocol::v3::types::widen))
+            .collect::<Vec<_>>();
+        if used.len() < self.config.min_satellites.max(1) {
+            return;
+        }
+
+        let mean = used.iter().sum::<f64>() / used.len() as f64;
+        let before = self.last_snr.replace(mean);
+        if let Some(before) = before.filter(|before| before - mean >= self.config.snr_drop) {
+            events.push(IntegrityEvent::SignalCollapse {
+                before,
+                after: mean,
+            });
+        }
+    }
+}
+
+/// Action taken by the [`JumpFilter`] for implausible fixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JumpAction {
+    /// Drop the fix and keep the last accepted fix as reference
+    #[default]
+    Reject,
+    /// Pass the fix on with a flag and use it as the new reference
+    Flag,
+}
+
+/// Thresholds used by the [`JumpFilter`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JumpFilterConfig {
+    /// Largest plausible speed in m/s between fixes
+    pub max_speed: f64,
+    /// Largest plausible change of speed in m/s² between fixes
+    pub max_acceleration: f64,
+    /// Action taken for implausible fixes
+    pub action: JumpAction,
+    /// Number of consecutive rejected fixes after which the filter accepts
+    /// the next fix as a new reference
+    ///
+    /// This keeps a genuine relocation, such as after a long outage, from
+    /// locking the filter out indefinitely.
+    pub max_rejections: usize,
+}
+
+impl Default for JumpFilterConfig {
+    fn default() -> Self {
+        JumpFilterConfig {
+            max_speed: 100.0,
+            max_acceleration: 15.0,
+            action: JumpAction::Reject,
+            max_rejections: 5,
+        }
+    }
+}
+
+/// Kinematics implied by an implausible fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jump {
+    /// Speed implied by the displacement from the reference fix in m/s
+    pub speed: f64,
+    /// Change of speed implied by the fix in m/s²
+    pub acceleration: f64,
+}
+
+/// Outcome of [`JumpFilter::check`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpVerdict {
+    /// The fix is plausible, or carries no position to check
+    Accept,
+    /// The fix is implausible and passed on (`JumpAction::Flag`)
+    Flag(Jump),
+    /// The fix is implausible and dropped (`JumpAction::Reject`)
+    Reject(Jump),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reference {
+    position: Position,
+    time: Timestamp,
+    eph: f64,
+    speed: Option<f64>,
+}
+
+/// Rejects or flags fixes implying impossible speed or acceleration
+///
+/// Each fix is compared against the last accepted fix. Displacement within
+/// the combined horizontal error (`eph`) of both fixes is not counted, so
+/// position noise of a slow receiver does not trigger the filter.
+///
+/// # Example
+///
+/// ```no_run
+/// use gpsd_json::integrity::{JumpFilter, JumpFilterConfig};
+/// # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>) {
+/// let mut filter = JumpFilter::new(JumpFilterConfig::default());
+/// let plausible = messages.into_iter().filter(|msg| filter.retain(msg));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JumpFilter {
+    config: JumpFilterConfig,
+    reference: Option<Reference>,
+    rejections: usize,
+}
+
+impl JumpFilter {
+    /// Creates a filter with the given thresholds
+    pub fn new(config: JumpFilterConfig) -> Self {
+        JumpFilter {
+            config,
+            reference: None,
+            rejections: 0,
+        }
+    }
+
+    /// Returns the thresholds used by this filter
+    pub fn config(&self) -> &JumpFilterConfig {
+        &self.config
+    }
+
+    /// Clears the reference fix
+    pub fn reset(&mut self) {
+        self.reference = None;
+        self.rejections = 0;
+    }
+
+    /// Checks a TPV report against the last accepted fix
+    pub fn check(&mut self, tpv: &Tpv) -> JumpVerdict {
+        if tpv.mode < FixMode::Fix2D {
+            return JumpVerdict::Accept;
+        }
+        let (Some(position), Some(time)) = (Position::from_tpv(tpv), tpv.time) else {
+            return JumpVerdict::Accept;
+        };
+        let mut current = Reference {
+            position,
+            time,
+            eph: tpv.eph.unwrap_or(0.0),
+            speed: tpv.speed,
+        };
+
+        let Some(reference) = self.reference else {
+            self.reference = Some(current);
+            return JumpVerdict::Accept;
+        };
+        let elapsed = (current.time - reference.time).as_seconds_f64();
+        if elapsed <= 0.0 {
+            return JumpVerdict::Accept;
+        }
+
+        let distance = reference.position.distance_to(&current.position);
+        let speed = (distance - reference.eph - current.eph).max(0.0) / elapsed;
+        let acceleration = reference
+            .speed
+            .map_or(0.0, |previous| (speed - previous).abs() / elapsed);
+        current.speed.get_or_insert(speed);
+
+        if speed <= self.config.max_speed && acceleration <= self.config.max_acceleration {
+            self.reference = Some(current);
+            self.rejections = 0;
+            return JumpVerdict::Accept;
+        }
+
+        let jump = Jump {
+            speed,
+            acceleration,
+        };
+        match self.config.action {
+            JumpAction::Flag => {
+                self.reference = Some(current);
+                JumpVerdict::Flag(jump)
+            }
+            JumpAction::Reject => {
+                self.rejections += 1;
+                if self.rejections > self.config.max_rejections {
+                    self.reference = Some(current);
+                    self.rejections = 0;
+                }
+                JumpVerdict::Reject(jump)
+            }
+        }
+    }
+
+    /// Returns false for TPV reports rejected by the filter
+    ///
+    /// All other reports are retained, so this can be used directly with
+    /// `Iterator::filter` on a message stream.
+    pub fn retain(&mut self, message: &Message) -> bool {
+        match message {
+            Message::Tpv(tpv) => !matches!(self.check(tpv), JumpVerdict::Reject(_)),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tpv(sec: u32, lat: f64, jam: i32) -> Message {
+        serde_json::from_str(&format!(
+            r#"{{"class":"TPV","mode":3,"time":"2025-01-01T00:00:{sec:02}Z","lat":{lat},"lon":139.0,"eph":5.0,"jam":{jam}}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_integrity_monitor_events() {
+        let mut monitor = IntegrityMonitor::new(IntegrityConfig::default());
+        let local = |sec: i64| Timestamp::from_timestamp(1_735_689_600 + sec, 0).unwrap();
+
+        assert!(monitor.update_at(&tpv(0, 35.0, 10), local(0)).is_empty());
+        // 10 km within one second
+        assert!(matches!(
+            monitor.update_at(&tpv(1, 35.09, 10), local(1))[..],
+            [IntegrityEvent::PositionJump { .. }]
+        ));
+        // Receiver reports jamming while GPS time stalls
+        let events = monitor.update_at(&tpv(1, 35.09, 120), local(5));
+        assert_eq!(
+            events,
+            [
+                IntegrityEvent::JammingIndicated { jam: 120 },
+                IntegrityEvent::TimeDiscontinuity {
+                    gps_elapsed: 0.0,
+                    local_elapsed: 4.0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jump_filter_rejects_outlier() {
+        let mut filter = JumpFilter::new(JumpFilterConfig::default());
+        let fix = |sec, lat| match tpv(sec, lat, 0) {
+            Message::Tpv(tpv) => tpv,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(filter.check(&fix(0, 35.0)), JumpVerdict::Accept);
+        assert_eq!(filter.check(&fix(1, 35.0001)), JumpVerdict::Accept);
+        assert!(matches!(
+            filter.check(&fix(2, 36.0)),
+            JumpVerdict::Reject(_)
+        ));
+        // Compared against the last accepted fix, not the outlier
+        assert_eq!(filter.check(&fix(3, 35.0002)), JumpVerdict::Accept);
+    }
+}
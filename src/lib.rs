@@ -18,7 +18,6 @@
 //!
 //! ```ignore
 //! use gpsd_json::client::{GpsdClient, StreamOptions};
-//! use futures::StreamExt;
 //!
 //! async fn example() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Connect to GPSD server
@@ -37,14 +36,124 @@
 
 use crate::error::GpsdJsonError;
 
+/// Conversions between horizontal accuracy conventions such as CEP50 and R95
+pub mod accuracy;
+
+/// Merging of TPV, SKY and GST reports into one fix per device and epoch
+pub mod aggregate;
+
+/// Compressed capture archives indexed by receive time
+#[cfg(feature = "archive")]
+pub mod archive;
+
+/// Heuristic checks for exclusive access to a device
+pub mod claim;
+
 /// Client module for establishing connections and managing communication with GPSD
 pub mod client;
 
+/// Pluggable decoding of compressed transports
+pub mod codec;
+
+/// Discovery of reachable GPSD endpoints on the local host and network
+#[cfg(feature = "net")]
+pub mod discovery;
+
 /// Error types used throughout the library
 pub mod error;
 
+/// Export of reports to time series and columnar formats such as InfluxDB
+/// line protocol and Parquet
+pub mod export;
+
+/// Sample GPSD JSON lines for tests of downstream crates
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+
+/// Display formatting helpers for speeds, headings and coordinates
+pub mod format;
+
+/// Geographic helpers such as bounding boxes and map tile indexing
+pub mod geo;
+
+/// Jamming and spoofing heuristics over GPSD reports
+pub mod integrity;
+
+/// Motion state classification (stationary, walking, vehicle) from fixes
+pub mod motion;
+
+/// NMEA sentence routing by talker and type, and generation from reports
+pub mod nmea;
+
+/// Clock offset estimation with step detection for TOFF and PPS reports
+pub mod offset;
+
+/// Detection and history of fix outages
+pub mod outage;
+
+/// One-shot current position for scripts
+pub mod position;
+
+/// Field pruning of reports before forwarding to reduce bandwidth
+pub mod projection;
+
 /// Protocol definitions and message parsing for GPSD JSON protocol
 pub mod protocol;
 
+/// Drive test recording of combined JSON, NMEA and timing output
+pub mod recorder;
+
+/// Periodic statistics reports (fix availability, DOP, outages)
+pub mod report;
+
+/// Static survey averaging of a fixed antenna position
+pub mod survey;
+
+/// Test utilities such as concurrent client load testing
+#[cfg(feature = "testing")]
+pub mod testing;
+
+/// Tracking of the latest fix and device state, with persistable snapshots
+pub mod tracker;
+
+/// Time zone conversion of timestamps for display
+#[cfg(feature = "tz")]
+pub mod tz;
+
+/// Physical plausibility checks for GPSD reports
+pub mod validate;
+
 /// Convenience type alias for Results with GpsdJsonError
 pub type Result<T> = core::result::Result<T, GpsdJsonError>;
+
+/// The `chrono` crate used by the public API
+///
+/// Use this re-export instead of a direct dependency to stay on the same
+/// version as this crate.
+pub use chrono;
+/// The `rustls` crate used by the TLS connection helpers
+///
+/// Use this re-export instead of a direct dependency to stay on the same
+/// version as this crate.
+#[cfg(feature = "rustls")]
+pub use rustls;
+/// The `serde_json` crate used by the public API
+///
+/// Use this re-export instead of a direct dependency to stay on the same
+/// version as this crate.
+pub use serde_json;
+
+#[cfg(all(feature = "proto-v3", feature = "tokio", feature = "net"))]
+pub use position::get_position;
+
+/// UTC timestamp used throughout the public API
+///
+/// Converts to `std::time::SystemTime` with `From` for code that does not
+/// use `chrono` directly.
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// JSON value of fields that are not decoded into typed structs
+pub type JsonValue = serde_json::Value;
+
+/// JSON object of fields that are not decoded into typed structs
+pub type JsonMap = serde_json::Map<String, JsonValue>;
@@ -0,0 +1,240 @@
+//! Motion state classification from GPS fixes
+//!
+//! This module classifies a receiver as stationary, walking or travelling
+//! in a vehicle using only TPV reports, without accelerometers or other
+//! sensors. It combines the reported speed with the displacement over a
+//! short window of fixes, compared against the receiver's own horizontal
+//! error estimate (`eph`), so that position noise of a parked receiver is
+//! not mistaken for movement.
+//!
+//! State changes are subject to hysteresis: a new state must be observed
+//! for several consecutive fixes before it is reported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::motion::{MotionConfig, MotionDetector};
+//! # fn example(tpvs: Vec<gpsd_json::protocol::v3::response::Tpv>) {
+//! let mut detector = MotionDetector::new(MotionConfig::default());
+//! for tpv in &tpvs {
+//!     if let Some(state) = detector.update(tpv) {
+//!         println!("motion state changed: {state:?}");
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::{
+    Timestamp,
+    geo::Position,
+    protocol::v3::{response::Tpv, types::FixMode},
+};
+
+/// Classified motion state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MotionState {
+    /// Not enough fixes have been seen yet
+    #[default]
+    Unknown,
+    /// The receiver is not moving beyond its position noise
+    Stationary,
+    /// The receiver moves at walking pace
+    Walking,
+    /// The receiver moves at vehicle speed
+    Vehicle,
+}
+
+/// Thresholds used by the [`MotionDetector`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionConfig {
+    /// Minimum speed in m/s considered as walking
+    pub walking_speed: f64,
+    /// Minimum speed in m/s considered as vehicle travel
+    pub vehicle_speed: f64,
+    /// Number of fixes used to measure displacement
+    pub window: usize,
+    /// Displacement over the window, as a multiple of `eph`, above which
+    /// the receiver is considered to be moving
+    pub eph_factor: f64,
+    /// Horizontal error in meters assumed when a fix carries no `eph`
+    pub default_eph: f64,
+    /// Number of consecutive fixes a new state must be observed before
+    /// it is reported
+    pub hysteresis: usize,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        MotionConfig {
+            walking_speed: 0.5,
+            vehicle_speed: 3.0,
+            window: 10,
+            eph_factor: 2.0,
+            default_eph: 10.0,
+            hysteresis: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    position: Position,
+    time: Option<Timestamp>,
+    eph: f64,
+}
+
+/// Classifies motion state from a sequence of TPV reports
+///
+/// Feed every TPV report to [`MotionDetector::update`]. Reports without
+/// at least a 2D fix are ignored.
+#[derive(Debug, Clone)]
+pub struct MotionDetector {
+    config: MotionConfig,
+    samples: VecDeque<Sample>,
+    state: MotionState,
+    candidate: MotionState,
+    candidate_count: usize,
+}
+
+impl MotionDetector {
+    /// Creates a detector with the given thresholds
+    pub fn new(config: MotionConfig) -> Self {
+        MotionDetector {
+            config,
+            samples: VecDeque::with_capacity(config.window.max(2)),
+            state: MotionState::Unknown,
+            candidate: MotionState::Unknown,
+            candidate_count: 0,
+        }
+    }
+
+    /// Returns the current motion state
+    pub fn state(&self) -> MotionState {
+        self.state
+    }
+
+    /// Returns the thresholds used by this detector
+    pub fn config(&self) -> &MotionConfig {
+        &self.config
+    }
+
+    /// Clears all history and returns to the `Unknown` state
+    ///
+    /// Call this after long fix outages, since displacement across a gap
+    /// says little about the current motion.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.state = MotionState::Unknown;
+        self.candidate = MotionState::Unknown;
+        self.candidate_count = 0;
+    }
+
+    /// Processes a TPV report
+    ///
+    /// # Returns
+    /// * `Some(state)` - The motion state changed to `state`
+    /// * `None` - The state is unchanged or the report was ignored
+    pub fn update(&mut self, tpv: &Tpv) -> Option<MotionState> {
+        if tpv.mode < FixMode::Fix2D {
+            return None;
+        }
+        let position = Position::from_tpv(tpv)?;
+
+        let sample = Sample {
+            position,
+            time: tpv.time,
+            eph: tpv.eph.unwrap_or(self.config.default_eph),
+        };
+        let speed = tpv.speed.or_else(|| self.derived_speed(&sample));
+
+        if self.samples.len() == self.config.window.max(2) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+
+        let observed = self.classify(speed)?;
+        if observed == self.state {
+            self.candidate_count = 0;
+            return None;
+        }
+
+        if observed == self.candidate {
+            self.candidate_count += 1;
+        } else {
+            self.candidate = observed;
+            self.candidate_count = 1;
+        }
+
+        if self.candidate_count >= self.config.hysteresis.max(1) {
+            self.state = observed;
+            self.candidate_count = 0;
+            Some(observed)
+        } else {
+            None
+        }
+    }
+
+    /// Estimates speed from the previous sample when TPV carries none
+    fn derived_speed(&self, sample: &Sample) -> Option<f64> {
+        let last = self.samples.back()?;
+        let dt = (sample.time? - last.time?).as_seconds_f64();
+        (dt > 0.0).then(|| last.position.distance_to(&sample.position) / dt)
+    }
+
+    /// Classifies the latest sample, returning `None` when ambiguous
+    fn classify(&self, speed: Option<f64>) -> Option<MotionState> {
+        let speed = speed?;
+        if speed >= self.config.vehicle_speed {
+            return Some(MotionState::Vehicle);
+        }
+
+        // Displacement is only meaningful once the window is filled
+        if self.samples.len() < self.config.window.max(2) {
+            return None;
+        }
+        let first = self.samples.front()?;
+        let last = self.samples.back()?;
+        let displacement = first.position.distance_to(&last.position);
+        let noise = self.samples.iter().map(|s| s.eph).fold(0.0, f64::max) * self.config.eph_factor;
+
+        match (speed >= self.config.walking_speed, displacement > noise) {
+            (true, true) => Some(MotionState::Walking),
+            (false, false) => Some(MotionState::Stationary),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tpv(sec: u32, lat: f64, speed: f64) -> Tpv {
+        serde_json::from_str(&format!(
+            r#"{{"mode":3,"time":"2025-01-01T00:00:{sec:02}Z","lat":{lat},"lon":139.0,"speed":{speed},"eph":3.0}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_motion_detector_transitions() {
+        let mut detector = MotionDetector::new(MotionConfig::default());
+        let mut changes = Vec::new();
+
+        // Parked receiver jittering by about a meter
+        for sec in 0..15 {
+            let jitter = if sec % 2 == 0 { 0.00001 } else { -0.00001 };
+            changes.extend(detector.update(&tpv(sec, 35.0 + jitter, 0.1)));
+        }
+        // Driving north at 15 m/s
+        for sec in 15..20 {
+            let lat = 35.0 + (sec - 15) as f64 * 0.000135;
+            changes.extend(detector.update(&tpv(sec, lat, 15.0)));
+        }
+
+        assert_eq!(changes, [MotionState::Stationary, MotionState::Vehicle]);
+        assert_eq!(detector.state(), MotionState::Vehicle);
+    }
+}
@@ -0,0 +1,267 @@
+//! Routing and generation of NMEA sentences
+//!
+//! NMEA streams mix sentences from several constellations and vendors.
+//! This module reads only the address field of a sentence (e.g. `GPGGA`)
+//! to tell the talker and the sentence type apart, so that sentences can
+//! be filtered or fanned out to subscribers without parsing their fields.
+//! The [`generate`] submodule goes the other way and produces sentences
+//! from GPSD reports for NMEA-only consumers, which the [`bridge`]
+//! submodule writes to serial ports and TCP clients.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::nmea::{NmeaFilter, NmeaRouter, Talker};
+//! # fn example(sentences: Vec<gpsd_json::client::NmeaLine>) {
+//! let mut router = NmeaRouter::new();
+//! let fixes = router.subscribe(NmeaFilter::new().sentence("RMC").sentence("GGA"));
+//! let glonass = router.subscribe(NmeaFilter::new().talker(Talker::Glonass));
+//!
+//! for sentence in sentences {
+//!     router.dispatch(&sentence);
+//! }
+//! for sentence in fixes.try_iter() {
+//!     println!("{sentence}");
+//! }
+//! # }
+//! ```
+//!
+//! [`bridge`]: crate::nmea::bridge
+//! [`generate`]: crate::nmea::generate
+
+use std::sync::mpsc;
+
+use crate::client::NmeaLine;
+
+/// GPSD JSON to NMEA 0183 output bridge for serial ports and TCP clients
+pub mod bridge;
+/// NMEA sentence generation from TPV and SKY reports
+pub mod generate;
+
+/// Talker of an NMEA sentence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Talker {
+    /// GPS (`GP`)
+    Gps,
+    /// GLONASS (`GL`)
+    Glonass,
+    /// Galileo (`GA`)
+    Galileo,
+    /// BeiDou (`GB` or `BD`)
+    BeiDou,
+    /// QZSS (`GQ`)
+    Qzss,
+    /// NavIC (`GI`)
+    Navic,
+    /// Combined GNSS solution (`GN`)
+    Gnss,
+    /// AIS (`AI`)
+    Ais,
+    /// Proprietary sentence (`P` followed by a manufacturer code)
+    Proprietary,
+    /// Any other talker, with its two letter ID
+    Other([u8; 2]),
+}
+
+impl Talker {
+    /// Converts a two letter talker ID
+    pub fn from_id(id: [u8; 2]) -> Self {
+        match &id {
+            b"GP" => Talker::Gps,
+            b"GL" => Talker::Glonass,
+            b"GA" => Talker::Galileo,
+            b"GB" | b"BD" => Talker::BeiDou,
+            b"GQ" => Talker::Qzss,
+            b"GI" => Talker::Navic,
+            b"GN" => Talker::Gnss,
+            b"AI" => Talker::Ais,
+            _ => Talker::Other(id),
+        }
+    }
+
+    /// Returns the two letter talker ID, or `None` for proprietary sentences
+    ///
+    /// BeiDou is reported as `GB`.
+    pub fn id(self) -> Option<[u8; 2]> {
+        match self {
+            Talker::Gps => Some(*b"GP"),
+            Talker::Glonass => Some(*b"GL"),
+            Talker::Galileo => Some(*b"GA"),
+            Talker::BeiDou => Some(*b"GB"),
+            Talker::Qzss => Some(*b"GQ"),
+            Talker::Navic => Some(*b"GI"),
+            Talker::Gnss => Some(*b"GN"),
+            Talker::Ais => Some(*b"AI"),
+            Talker::Proprietary => None,
+            Talker::Other(id) => Some(id),
+        }
+    }
+}
+
+/// Address field of an NMEA sentence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address<'a> {
+    /// Talker of the sentence
+    pub talker: Talker,
+    /// Sentence type, e.g. `GGA`; for proprietary sentences the manufacturer
+    /// code followed by the vendor's sentence type, e.g. `UBX`
+    pub sentence: &'a str,
+}
+
+impl<'a> Address<'a> {
+    /// Reads the address field of a sentence starting with `$` or `!`
+    ///
+    /// Returns `None` if the line is not an NMEA sentence.
+    pub fn parse(line: &'a [u8]) -> Option<Self> {
+        let line = match line.first()? {
+            b'$' | b'!' => &line[1..],
+            _ => return None,
+        };
+        let end = line
+            .iter()
+            .position(|&b| b == b',' || b == b'*')
+            .unwrap_or(line.len());
+        let address = std::str::from_utf8(&line[..end]).ok()?;
+        if !address.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return None;
+        }
+
+        if let Some(sentence) = address.strip_prefix('P') {
+            return Some(Address {
+                talker: Talker::Proprietary,
+                sentence,
+            });
+        }
+        let id = address.as_bytes().get(..2)?;
+        Some(Address {
+            talker: Talker::from_id([id[0], id[1]]),
+            sentence: &address[2..],
+        })
+    }
+}
+
+/// Selection of NMEA sentences by talker and sentence type
+///
+/// An empty filter matches every sentence. Talkers and sentence types
+/// are each matched if any of the given values matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NmeaFilter {
+    talkers: Vec<Talker>,
+    sentences: Vec<String>,
+}
+
+impl NmeaFilter {
+    /// Creates a filter matching every sentence
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a talker to match
+    pub fn talker(mut self, talker: Talker) -> Self {
+        self.talkers.push(talker);
+        self
+    }
+
+    /// Adds a sentence type to match, e.g. `RMC`
+    pub fn sentence(mut self, sentence: impl Into<String>) -> Self {
+        self.sentences.push(sentence.into());
+        self
+    }
+
+    /// Returns true if the address matches this filter
+    pub fn matches_address(&self, address: &Address<'_>) -> bool {
+        (self.talkers.is_empty() || self.talkers.contains(&address.talker))
+            && (self.sentences.is_empty() || self.sentences.iter().any(|s| s == address.sentence))
+    }
+
+    /// Returns true if the sentence matches this filter
+    ///
+    /// Lines that are not NMEA sentences never match.
+    pub fn matches(&self, line: &[u8]) -> bool {
+        Address::parse(line).is_some_and(|address| self.matches_address(&address))
+    }
+}
+
+/// Fans out NMEA sentences to subscribers by talker and sentence type
+///
+/// Each subscriber receives the sentences matching its filter through a
+/// channel. Subscribers whose receiver was dropped are removed on the next
+/// dispatch.
+#[derive(Debug, Default)]
+pub struct NmeaRouter {
+    routes: Vec<(NmeaFilter, mpsc::Sender<NmeaLine>)>,
+}
+
+impl NmeaRouter {
+    /// Creates a router without subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a subscriber for the sentences matching `filter`
+    pub fn subscribe(&mut self, filter: NmeaFilter) -> mpsc::Receiver<NmeaLine> {
+        let (tx, rx) = mpsc::channel();
+        self.routes.push((filter, tx));
+        rx
+    }
+
+    /// Returns the number of subscribers
+    pub fn subscribers(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Sends a sentence to every matching subscriber
+    ///
+    /// Returns the number of subscribers that received the sentence.
+    pub fn dispatch(&mut self, line: &NmeaLine) -> usize {
+        let Some(address) = Address::parse(line.as_bytes()) else {
+            return 0;
+        };
+
+        let mut delivered = 0;
+        self.routes.retain(|(filter, tx)| {
+            if !filter.matches_address(&address) {
+                return true;
+            }
+            let sent = tx.send(line.clone()).is_ok();
+            delivered += usize::from(sent);
+            sent
+        });
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nmea_router_filters() {
+        assert_eq!(
+            Address::parse(b"$PUBX,00,081350.00*3B"),
+            Some(Address {
+                talker: Talker::Proprietary,
+                sentence: "UBX"
+            })
+        );
+        assert_eq!(Address::parse(b"{\"class\":\"TPV\"}"), None);
+
+        let mut router = NmeaRouter::new();
+        let fixes = router.subscribe(NmeaFilter::new().sentence("RMC").sentence("GGA"));
+        let glonass = router.subscribe(NmeaFilter::new().talker(Talker::Glonass));
+        let dropped = router.subscribe(NmeaFilter::new());
+        drop(dropped);
+
+        for line in [
+            "$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76",
+            "$GLGSV,1,1,02,65,45,120,40,66,30,200,35*6A",
+            "$GNRMC,092751.000,A,5321.6802,N,00630.3371,W,0.06,31.66,280511,,,A*45",
+        ] {
+            router.dispatch(&NmeaLine::Text(line.into()));
+        }
+
+        assert_eq!(router.subscribers(), 2);
+        assert_eq!(fixes.try_iter().count(), 2);
+        assert_eq!(glonass.try_iter().count(), 1);
+    }
+}
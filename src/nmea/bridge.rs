@@ -0,0 +1,245 @@
+//! GPSD JSON to NMEA 0183 output bridge
+//!
+//! [`NmeaBridge`] combines the [`NmeaGenerator`] with the outputs of a
+//! typical bridge: serial ports or other writers, and a TCP listener that
+//! NMEA-only clients connect to. Sentences are written when a TPV report
+//! arrives, at most once per configured interval.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::{
+//!     client::{StreamOptions, blocking::GpsdClient},
+//!     nmea::bridge::NmeaBridge,
+//! };
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let serial = std::fs::OpenOptions::new().write(true).open("/dev/ttyUSB0")?;
+//! let mut bridge = NmeaBridge::new()
+//!     .interval(Duration::from_secs(1))
+//!     .output(serial)
+//!     .listen("0.0.0.0:10110")?;
+//!
+//! let client = GpsdClient::connect("127.0.0.1:2947")?;
+//! for msg in client.stream(StreamOptions::json())? {
+//!     bridge.update(&msg?)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`NmeaBridge`]: crate::nmea::bridge::NmeaBridge
+//! [`NmeaGenerator`]: crate::nmea::generate::NmeaGenerator
+
+#[cfg(feature = "net")]
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use super::{Talker, generate::NmeaGenerator};
+use crate::protocol::v3::response::Message;
+
+/// Time after which a TCP client that does not accept data is dropped
+#[cfg(feature = "net")]
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Writes NMEA sentences generated from GPSD reports to serial ports and
+/// TCP clients
+pub struct NmeaBridge {
+    generator: NmeaGenerator,
+    interval: Option<Duration>,
+    last_output: Option<Instant>,
+    gsv: Vec<String>,
+    outputs: Vec<Box<dyn Write + Send>>,
+    #[cfg(feature = "net")]
+    listener: Option<TcpListener>,
+    #[cfg(feature = "net")]
+    clients: Vec<TcpStream>,
+}
+
+impl std::fmt::Debug for NmeaBridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut f = f.debug_struct("NmeaBridge");
+        f.field("interval", &self.interval)
+            .field("outputs", &self.outputs.len());
+        #[cfg(feature = "net")]
+        f.field("listener", &self.listener)
+            .field("clients", &self.clients.len());
+        f.finish()
+    }
+}
+
+impl Default for NmeaBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmeaBridge {
+    /// Creates a bridge without outputs that writes on every TPV report
+    pub fn new() -> Self {
+        NmeaBridge {
+            generator: NmeaGenerator::new(),
+            interval: None,
+            last_output: None,
+            gsv: Vec::new(),
+            outputs: Vec::new(),
+            #[cfg(feature = "net")]
+            listener: None,
+            #[cfg(feature = "net")]
+            clients: Vec::new(),
+        }
+    }
+
+    /// Sets the talker of the generated sentences
+    pub fn talker(mut self, talker: Talker) -> Self {
+        self.generator = self.generator.talker(talker);
+        self
+    }
+
+    /// Sets the minimum interval between two groups of sentences
+    ///
+    /// TPV reports arriving within the interval are skipped, e.g. to feed
+    /// a 1 Hz chartplotter from a 5 Hz receiver.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Adds an output such as an opened serial port
+    ///
+    /// Write errors on outputs are returned from [`update`](Self::update).
+    pub fn output(mut self, output: impl Write + Send + 'static) -> Self {
+        self.outputs.push(Box::new(output));
+        self
+    }
+
+    /// Listens for TCP clients on the given address
+    ///
+    /// Clients are accepted while the bridge is updated. Clients that
+    /// disconnect or stop reading are dropped.
+    #[cfg(feature = "net")]
+    pub fn listen(mut self, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        self.listener = Some(listener);
+        Ok(self)
+    }
+
+    /// Returns the local address of the TCP listener, if any
+    #[cfg(feature = "net")]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.as_ref()?.local_addr().ok()
+    }
+
+    /// Returns the number of connected TCP clients
+    #[cfg(feature = "net")]
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Processes a report
+    ///
+    /// SKY reports update the satellites in view. TPV reports write GGA,
+    /// RMC, GSA and the GSV sentences of the latest SKY report, unless the
+    /// previous group was written within the configured interval. Returns
+    /// the number of sentences written.
+    pub fn update(&mut self, message: &Message) -> io::Result<usize> {
+        #[cfg(feature = "net")]
+        self.accept();
+
+        match message {
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(_) => {
+                self.gsv = self.generator.update(message);
+                Ok(0)
+            }
+            Message::Tpv(_) => {
+                let now = Instant::now();
+                let due = match (self.last_output, self.interval) {
+                    (Some(last), Some(interval)) => now.duration_since(last) >= interval,
+                    _ => true,
+                };
+                if !due {
+                    return Ok(0);
+                }
+                self.last_output = Some(now);
+
+                let mut sentences = self.generator.update(message);
+                sentences.extend(self.gsv.iter().cloned());
+                self.write(&sentences)?;
+                Ok(sentences.len())
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// Writes sentences to all outputs and clients
+    pub fn write(&mut self, sentences: &[String]) -> io::Result<()> {
+        let mut buf = String::new();
+        for sentence in sentences {
+            buf.push_str(sentence);
+            buf.push_str("\r\n");
+        }
+
+        #[cfg(feature = "net")]
+        self.clients
+            .retain_mut(|client| client.write_all(buf.as_bytes()).is_ok());
+        for output in &mut self.outputs {
+            output.write_all(buf.as_bytes())?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Accepts pending TCP clients
+    #[cfg(feature = "net")]
+    fn accept(&mut self) {
+        let Some(listener) = &self.listener else {
+            return;
+        };
+        while let Ok((client, _)) = listener.accept() {
+            let ready = client
+                .set_nonblocking(false)
+                .and_then(|_| client.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)));
+            if ready.is_ok() {
+                self.clients.push(client);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "net"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_nmea_bridge_tcp_output() {
+        let mut bridge = NmeaBridge::new()
+            .interval(Duration::from_secs(60))
+            .listen("127.0.0.1:0")
+            .unwrap();
+        let client = TcpStream::connect(bridge.local_addr().unwrap()).unwrap();
+
+        let tpv: Message = serde_json::from_str(
+            r#"{"class":"TPV","mode":3,"time":"2025-01-01T00:00:00Z","lat":35.0,"lon":139.0}"#,
+        )
+        .unwrap();
+        // Wait until the pending connection is accepted
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while bridge.clients() == 0 && Instant::now() < deadline {
+            bridge.accept();
+        }
+        assert_eq!(bridge.update(&tpv).unwrap(), 3);
+        // Within the interval
+        assert_eq!(bridge.update(&tpv).unwrap(), 0);
+
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        assert!(line.starts_with("$GPGGA,000000.00,3500.00000,N,13900.00000,E,1,"));
+        assert!(line.ends_with("\r\n"));
+    }
+}
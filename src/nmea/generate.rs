@@ -0,0 +1,355 @@
+//! NMEA 0183 sentence generation from TPV and SKY reports
+//!
+//! [`NmeaGenerator`] turns GPSD reports back into GGA, RMC, GSA and GSV
+//! sentences, so that a bridge built on this crate can feed legacy
+//! consumers such as autopilots and chartplotters that only understand
+//! NMEA 0183. Sentences follow NMEA 0183 version 2.3 and are returned
+//! without the trailing `\r\n`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::nmea::{Talker, generate::NmeaGenerator};
+//! # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>, mut port: impl std::io::Write) -> std::io::Result<()> {
+//! let mut generator = NmeaGenerator::new().talker(Talker::Gnss);
+//! for msg in &messages {
+//!     for sentence in generator.update(msg) {
+//!         write!(port, "{sentence}\r\n")?;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`NmeaGenerator`]: crate::nmea::generate::NmeaGenerator
+
+use std::fmt::Write;
+
+use chrono::Timelike;
+
+use super::Talker;
+use crate::Timestamp;
+use crate::protocol::v3::{
+    response::{Message, Sky, Tpv},
+    types::{FixMode, FixStatus, widen},
+};
+
+/// Knots in one meter per second
+const KNOTS_PER_MPS: f64 = 3600.0 / 1852.0;
+/// Satellites listed in one GSV sentence
+const GSV_SATELLITES: usize = 4;
+/// Largest number of GSV sentences in one group
+const GSV_MAX_SENTENCES: usize = 9;
+/// Satellites listed in one GSA sentence
+const GSA_SATELLITES: usize = 12;
+
+/// Computes the checksum of a sentence body between `$` and `*`
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |sum, b| sum ^ b)
+}
+
+/// Produces NMEA 0183 sentences from GPSD reports
+///
+/// The generator keeps the latest SKY report, which supplies satellite
+/// counts and DOP values for the sentences generated from TPV reports.
+#[derive(Debug, Clone)]
+pub struct NmeaGenerator {
+    talker: [u8; 2],
+    sky: Option<Sky>,
+}
+
+impl Default for NmeaGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmeaGenerator {
+    /// Creates a generator using the GPS talker ID `GP`
+    pub fn new() -> Self {
+        NmeaGenerator {
+            talker: *b"GP",
+            sky: None,
+        }
+    }
+
+    /// Sets the talker of the generated sentences
+    ///
+    /// Proprietary talkers are ignored.
+    pub fn talker(mut self, talker: Talker) -> Self {
+        if let Some(id) = talker.id() {
+            self.talker = id;
+        }
+        self
+    }
+
+    /// Processes a report and returns the sentences generated from it
+    ///
+    /// TPV reports yield GGA, RMC and GSA sentences, SKY reports a group
+    /// of GSV sentences. Other reports yield nothing.
+    pub fn update(&mut self, message: &Message) -> Vec<String> {
+        match message {
+            Message::Tpv(tpv) => vec![self.gga(tpv), self.rmc(tpv), self.gsa(tpv)],
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => {
+                let sentences = self.gsv(sky);
+                self.sky = Some(sky.clone());
+                sentences
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Generates a GGA sentence (fix data)
+    pub fn gga(&self, tpv: &Tpv) -> String {
+        let fix = tpv.mode >= FixMode::Fix2D;
+        let mut body = self.address("GGA");
+        let _ = write!(
+            body,
+            ",{},{},{},{},{},{},{},",
+            utc_time(tpv.time),
+            latitude(tpv.lat.filter(|_| fix)),
+            longitude(tpv.lon.filter(|_| fix)),
+            fix_quality(tpv),
+            self.satellites_used(tpv)
+                .map(|n| format!("{n:02}"))
+                .unwrap_or_default(),
+            optional(self.sky.as_ref().and_then(|sky| sky.dop.h), 1),
+            optional(tpv.alt_msl.filter(|_| fix), 1),
+        );
+        let _ = write!(
+            body,
+            "M,{},M,{},{}",
+            optional(tpv.geoid_sep, 1),
+            optional(tpv.dgps_age, 1),
+            tpv.dgps_sta
+                .map(|station| format!("{station:04}"))
+                .unwrap_or_default(),
+        );
+        sentence(body)
+    }
+
+    /// Generates an RMC sentence (recommended minimum data)
+    pub fn rmc(&self, tpv: &Tpv) -> String {
+        let fix = tpv.mode >= FixMode::Fix2D;
+        let (magvar, direction) = match tpv.magvar {
+            Some(var) => (
+                format!("{:.1}", var.abs()),
+                if var < 0.0 { "W" } else { "E" },
+            ),
+            None => (String::new(), ""),
+        };
+        let mut body = self.address("RMC");
+        let _ = write!(
+            body,
+            ",{},{},{},{},{},{},{},{},{},{}",
+            utc_time(tpv.time),
+            if fix { 'A' } else { 'V' },
+            latitude(tpv.lat.filter(|_| fix)),
+            longitude(tpv.lon.filter(|_| fix)),
+            optional(tpv.speed.map(|speed| speed * KNOTS_PER_MPS), 1),
+            optional(tpv.track, 1),
+            tpv.time
+                .map(|time| time.format("%d%m%y").to_string())
+                .unwrap_or_default(),
+            magvar,
+            direction,
+            mode_indicator(tpv),
+        );
+        sentence(body)
+    }
+
+    /// Generates a GSA sentence (DOP and active satellites)
+    ///
+    /// Satellites and DOP values are taken from the latest SKY report.
+    pub fn gsa(&self, tpv: &Tpv) -> String {
+        let mut body = self.address("GSA");
+        let _ = write!(body, ",A,{}", (tpv.mode as u8).max(1));
+
+        let used = self
+            .sky
+            .iter()
+            .flat_map(|sky| sky.satellites.iter())
+            .filter(|sat| sat.used)
+            .map(|sat| format!("{:02}", sat.prn));
+        let mut prns = used.take(GSA_SATELLITES).collect::<Vec<_>>();
+        prns.resize(GSA_SATELLITES, String::new());
+        for prn in prns {
+            let _ = write!(body, ",{prn}");
+        }
+
+        let dop = self.sky.as_ref().map(|sky| &sky.dop);
+        let _ = write!(
+            body,
+            ",{},{},{}",
+            optional(dop.and_then(|dop| dop.p), 1),
+            optional(dop.and_then(|dop| dop.h), 1),
+            optional(dop.and_then(|dop| dop.v), 1),
+        );
+        sentence(body)
+    }
+
+    /// Generates a group of GSV sentences (satellites in view)
+    ///
+    /// A group holds at most 36 satellites; further satellites are left out.
+    pub fn gsv(&self, sky: &Sky) -> Vec<String> {
+        let satellites =
+            &sky.satellites[..sky.satellites.len().min(GSV_SATELLITES * GSV_MAX_SENTENCES)];
+        let total = satellites.len().div_ceil(GSV_SATELLITES).max(1);
+
+        (0..total)
+            .map(|index| {
+                let mut body = self.address("GSV");
+                let _ = write!(body, ",{total},{},{:02}", index + 1, satellites.len());
+                for sat in satellites
+                    .iter()
+                    .skip(index * GSV_SATELLITES)
+                    .take(GSV_SATELLITES)
+                {
+                    let _ = write!(
+                        body,
+                        ",{:02},{},{},{}",
+                        sat.prn,
+                        optional_int(sat.elevation, 2),
+                        optional_int(sat.azimuth, 3),
+                        optional_int(sat.ss, 2),
+                    );
+                }
+                sentence(body)
+            })
+            .collect()
+    }
+
+    fn address(&self, sentence: &str) -> String {
+        format!(
+            "{}{}{sentence}",
+            self.talker[0] as char, self.talker[1] as char
+        )
+    }
+
+    fn satellites_used(&self, tpv: &Tpv) -> Option<usize> {
+        if let Some(sats) = tpv.sats {
+            return Some(sats.max(0) as usize);
+        }
+        let sky = self.sky.as_ref()?;
+        Some(match sky.u_sat {
+            Some(used) => used.max(0) as usize,
+            None => sky.satellites.iter().filter(|sat| sat.used).count(),
+        })
+    }
+}
+
+/// Wraps a sentence body with `$` and the checksum
+fn sentence(body: String) -> String {
+    format!("${body}*{:02X}", checksum(&body))
+}
+
+fn utc_time(time: Option<Timestamp>) -> String {
+    time.map(|time| {
+        format!(
+            "{}.{:02}",
+            time.format("%H%M%S"),
+            time.nanosecond().min(999_999_999) / 10_000_000
+        )
+    })
+    .unwrap_or_default()
+}
+
+/// Formats a coordinate as degrees and decimal minutes with a hemisphere
+fn coordinate(value: Option<f64>, width: usize, positive: char, negative: char) -> String {
+    match value {
+        Some(value) => {
+            // Round to the printed precision first, so that minutes never
+            // round up to 60
+            let total = (value.abs() * 60.0 * 1e5).round() / 1e5;
+            let degrees = (total / 60.0).trunc();
+            let minutes = total - degrees * 60.0;
+            let hemisphere = if value < 0.0 { negative } else { positive };
+            format!("{degrees:0width$}{minutes:08.5},{hemisphere}")
+        }
+        None => ",".to_string(),
+    }
+}
+
+fn latitude(lat: Option<f64>) -> String {
+    coordinate(lat, 2, 'N', 'S')
+}
+
+fn longitude(lon: Option<f64>) -> String {
+    coordinate(lon, 3, 'E', 'W')
+}
+
+fn optional(value: Option<impl Into<f64>>, precision: usize) -> String {
+    value
+        .map(widen)
+        .map(|value| format!("{value:.precision$}"))
+        .unwrap_or_default()
+}
+
+fn optional_int(value: Option<impl Into<f64>>, width: usize) -> String {
+    value
+        .map(widen)
+        .map(|value| format!("{:0width$}", value.round() as i64))
+        .unwrap_or_default()
+}
+
+/// GGA fix quality indicator
+fn fix_quality(tpv: &Tpv) -> u8 {
+    if tpv.mode < FixMode::Fix2D {
+        return 0;
+    }
+    match tpv.status {
+        Some(FixStatus::DGps) => 2,
+        Some(FixStatus::PpsFix) => 3,
+        Some(FixStatus::RTKFixed) => 4,
+        Some(FixStatus::RTKFloat) => 5,
+        Some(FixStatus::DR | FixStatus::GnssDR) => 6,
+        Some(FixStatus::Simulated) => 8,
+        _ => 1,
+    }
+}
+
+/// RMC mode indicator (NMEA 2.3)
+fn mode_indicator(tpv: &Tpv) -> char {
+    if tpv.mode < FixMode::Fix2D {
+        return 'N';
+    }
+    match tpv.status {
+        Some(FixStatus::DGps | FixStatus::RTKFixed | FixStatus::RTKFloat) => 'D',
+        Some(FixStatus::DR | FixStatus::GnssDR) => 'E',
+        Some(FixStatus::Simulated) => 'S',
+        _ => 'A',
+    }
+}
+
+#[cfg(all(test, feature = "msg-sky"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nmea_generator_sentences() {
+        let mut generator = NmeaGenerator::new();
+        let sky = r#"{"class":"SKY","hdop":1.03,"pdop":1.56,"vdop":1.17,"uSat":2,"satellites":[{"PRN":5,"el":45.0,"az":120.0,"ss":40.0,"used":true},{"PRN":12,"el":30.0,"az":200.0,"ss":35.0,"used":true},{"PRN":25,"el":5.0,"az":10.0,"ss":0.0,"used":false}]}"#;
+        let gsv = generator.update(&serde_json::from_str(sky).unwrap());
+        assert_eq!(
+            gsv,
+            ["$GPGSV,1,1,03,05,45,120,40,12,30,200,35,25,05,010,00*4E"]
+        );
+
+        let tpv = r#"{"class":"TPV","mode":3,"status":2,"time":"2011-05-28T09:27:50.000Z","lat":53.361336666,"lon":-6.50562,"altMSL":61.7,"geoidSep":55.2,"speed":0.0308,"track":31.66}"#;
+        let sentences = generator.update(&serde_json::from_str(tpv).unwrap());
+        assert_eq!(
+            sentences[0],
+            "$GPGGA,092750.00,5321.68020,N,00630.33720,W,2,02,1.0,61.7,M,55.2,M,,*4C"
+        );
+        assert_eq!(
+            sentences[1],
+            "$GPRMC,092750.00,A,5321.68020,N,00630.33720,W,0.1,31.7,280511,,,D*72"
+        );
+        assert_eq!(sentences[2], "$GPGSA,A,3,05,12,,,,,,,,,,,1.6,1.0,1.2*31");
+        for sentence in &sentences {
+            let (body, sum) = sentence[1..].split_once('*').unwrap();
+            assert_eq!(format!("{:02X}", checksum(body)), sum);
+        }
+    }
+}
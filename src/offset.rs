@@ -0,0 +1,328 @@
+//! Clock offset estimation with step detection
+//!
+//! TOFF and PPS reports pair the GPS time of an event with the system clock
+//! time it was seen at. An [`OffsetEstimator`] follows the resulting offset
+//! series and keeps a robust estimate, the median of the recent samples.
+//!
+//! A sudden jump of the offset, e.g. when NTP steps the system clock or a
+//! receiver reports a date off by a GPS week rollover, would otherwise be
+//! taken as a real change and misguide timing consumers. Samples that
+//! deviate from the estimate by more than the step threshold are reported
+//! as suspect and kept out of the estimate. Only when several consecutive
+//! samples agree on the new offset is the step confirmed and the estimate
+//! restarted from them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::offset::{OffsetEstimator, OffsetEvent, OffsetSample};
+//! # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>) {
+//! let mut estimator = OffsetEstimator::default();
+//! for msg in &messages {
+//!     let Some(sample) = OffsetSample::from_message(msg) else {
+//!         continue;
+//!     };
+//!     match estimator.update(&sample) {
+//!         OffsetEvent::Accepted(offset) => println!("clock offset {offset:+.9} s"),
+//!         OffsetEvent::Suspect { jump, .. } => eprintln!("ignoring jump of {jump:+.3} s"),
+//!         OffsetEvent::Step { jump, kind } => eprintln!("clock stepped by {jump:+.3} s ({kind:?})"),
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! [`OffsetEstimator`]: crate::offset::OffsetEstimator
+
+use std::collections::VecDeque;
+
+use chrono::TimeDelta;
+
+use crate::{
+    Timestamp,
+    protocol::v3::response::{Message, Pps, TimeOffset},
+};
+
+/// Length of the GPS week number cycle of legacy navigation messages
+pub const WEEK_ROLLOVER: TimeDelta = TimeDelta::weeks(1024);
+
+/// Offset between the system clock and GPS time at one event
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetSample {
+    /// Device that reported the event
+    pub device: Option<String>,
+    /// GPS time of the event
+    pub real: Timestamp,
+    /// System clock time of the event
+    pub clock: Timestamp,
+}
+
+impl OffsetSample {
+    /// Extracts the sample of a TOFF report
+    pub fn from_toff(toff: &TimeOffset) -> Option<Self> {
+        Some(OffsetSample {
+            device: toff.device.clone(),
+            real: toff.real?,
+            clock: toff.clock?,
+        })
+    }
+
+    /// Extracts the sample of a PPS report
+    pub fn from_pps(pps: &Pps) -> Option<Self> {
+        Some(OffsetSample {
+            device: pps.device.clone(),
+            real: pps.real?,
+            clock: pps.clock?,
+        })
+    }
+
+    /// Extracts the sample of a TOFF or PPS report
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        match msg {
+            #[cfg(feature = "msg-toff")]
+            Message::Toff(toff) => Self::from_toff(toff),
+            #[cfg(feature = "msg-pps")]
+            Message::Pps(pps) => Self::from_pps(pps),
+            _ => None,
+        }
+    }
+
+    /// Returns the offset of the system clock from GPS time in seconds,
+    /// positive if the clock is ahead
+    pub fn offset(&self) -> f64 {
+        (self.clock - self.real).as_seconds_f64()
+    }
+}
+
+/// Likely cause of a jump in the offset series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    /// The system clock was stepped, e.g. by NTP or an operator
+    Clock,
+    /// The jump is a multiple of [`WEEK_ROLLOVER`], pointing to a receiver
+    /// that resolved the GPS week number wrongly
+    WeekRollover,
+}
+
+impl StepKind {
+    /// Classifies a jump in seconds
+    pub fn of(jump: f64) -> Self {
+        let cycle = WEEK_ROLLOVER.as_seconds_f64();
+        let cycles = (jump / cycle).round();
+        // Within a day of a whole number of cycles
+        if cycles != 0.0 && (jump - cycles * cycle).abs() < 86_400.0 {
+            StepKind::WeekRollover
+        } else {
+            StepKind::Clock
+        }
+    }
+}
+
+/// Result of a sample processed by an [`OffsetEstimator`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OffsetEvent {
+    /// The sample was taken into the estimate, which is now the given
+    /// offset in seconds
+    Accepted(f64),
+    /// The sample jumps away from the estimate and was left out; it is
+    /// either a transient or the start of a step
+    Suspect {
+        /// Difference from the estimate in seconds
+        jump: f64,
+        /// Likely cause of the jump
+        kind: StepKind,
+    },
+    /// Enough samples agree on a new offset; the estimate was restarted
+    /// from them
+    Step {
+        /// Difference of the new offset from the old estimate in seconds
+        jump: f64,
+        /// Likely cause of the jump
+        kind: StepKind,
+    },
+}
+
+/// Configuration of an [`OffsetEstimator`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffsetConfig {
+    /// Deviation from the estimate in seconds above which a sample is
+    /// suspect; the default of 128 ms is the step threshold of ntpd
+    pub step_threshold: f64,
+    /// Number of consecutive agreeing samples that confirm a step
+    pub confirm: usize,
+    /// Number of recent samples the estimate is the median of
+    pub window: usize,
+}
+
+impl Default for OffsetConfig {
+    fn default() -> Self {
+        OffsetConfig {
+            step_threshold: 0.128,
+            confirm: 3,
+            window: 16,
+        }
+    }
+}
+
+/// Robust estimate of the clock offset of one device
+///
+/// Feed the samples of one device only; use an estimator per device for
+/// several receivers.
+#[derive(Debug, Clone)]
+pub struct OffsetEstimator {
+    config: OffsetConfig,
+    samples: VecDeque<f64>,
+    candidates: Vec<f64>,
+    steps: u32,
+}
+
+impl Default for OffsetEstimator {
+    fn default() -> Self {
+        Self::new(OffsetConfig::default())
+    }
+}
+
+impl OffsetEstimator {
+    /// Creates an estimator with the given configuration
+    pub fn new(config: OffsetConfig) -> Self {
+        OffsetEstimator {
+            config,
+            samples: VecDeque::new(),
+            candidates: Vec::new(),
+            steps: 0,
+        }
+    }
+
+    /// Processes a sample
+    pub fn update(&mut self, sample: &OffsetSample) -> OffsetEvent {
+        self.update_offset(sample.offset())
+    }
+
+    /// Processes an offset in seconds
+    pub fn update_offset(&mut self, offset: f64) -> OffsetEvent {
+        let Some(estimate) = self.estimate() else {
+            return self.accept(offset);
+        };
+        let jump = offset - estimate;
+        if jump.abs() <= self.config.step_threshold {
+            self.candidates.clear();
+            return self.accept(offset);
+        }
+
+        // Candidates must agree with each other, not only jump away
+        if self
+            .candidates
+            .first()
+            .is_some_and(|first| (offset - first).abs() > self.config.step_threshold)
+        {
+            self.candidates.clear();
+        }
+        self.candidates.push(offset);
+        let kind = StepKind::of(jump);
+        if self.candidates.len() < self.config.confirm.max(1) {
+            return OffsetEvent::Suspect { jump, kind };
+        }
+
+        self.samples = std::mem::take(&mut self.candidates).into();
+        self.steps += 1;
+        let jump = self.estimate().unwrap_or(offset) - estimate;
+        OffsetEvent::Step { jump, kind }
+    }
+
+    /// Returns the current offset estimate in seconds, the median of the
+    /// recent accepted samples
+    pub fn estimate(&self) -> Option<f64> {
+        let mut samples: Vec<f64> = self.samples.iter().copied().collect();
+        samples.sort_by(f64::total_cmp);
+        let mid = samples.len() / 2;
+        match samples.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(samples[mid]),
+            _ => Some((samples[mid - 1] + samples[mid]) / 2.0),
+        }
+    }
+
+    /// Returns true while samples jump away from the estimate without a
+    /// confirmed step
+    pub fn is_suspect(&self) -> bool {
+        !self.candidates.is_empty()
+    }
+
+    /// Returns the number of confirmed steps
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Forgets all samples, e.g. after the clock was stepped on purpose
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.candidates.clear();
+    }
+
+    fn accept(&mut self, offset: f64) -> OffsetEvent {
+        if self.samples.len() >= self.config.window.max(1) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(offset);
+        OffsetEvent::Accepted(self.estimate().unwrap_or(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_steps() {
+        #[cfg(feature = "msg-toff")]
+        {
+            let toff: Message = serde_json::from_str(crate::fixtures::TOFF).unwrap();
+            let sample = OffsetSample::from_message(&toff).unwrap();
+            assert!((sample.offset() - 0.098374612).abs() < 1e-9);
+        }
+
+        let mut estimator = OffsetEstimator::default();
+        for offset in [0.001, 0.002, 0.001] {
+            assert!(matches!(
+                estimator.update_offset(offset),
+                OffsetEvent::Accepted(_)
+            ));
+        }
+        assert_eq!(estimator.estimate(), Some(0.001));
+
+        // Transient outlier
+        assert!(matches!(
+            estimator.update_offset(5.0),
+            OffsetEvent::Suspect {
+                kind: StepKind::Clock,
+                ..
+            }
+        ));
+        assert!(estimator.is_suspect());
+        assert!(matches!(
+            estimator.update_offset(0.002),
+            OffsetEvent::Accepted(_)
+        ));
+        assert!(!estimator.is_suspect());
+
+        // Clock stepped by NTP
+        assert!(matches!(
+            estimator.update_offset(-2.0),
+            OffsetEvent::Suspect { .. }
+        ));
+        assert!(matches!(
+            estimator.update_offset(-2.001),
+            OffsetEvent::Suspect { .. }
+        ));
+        let OffsetEvent::Step { jump, kind } = estimator.update_offset(-2.0) else {
+            panic!("step not confirmed");
+        };
+        assert!((jump + 2.0015).abs() < 1e-3);
+        assert_eq!(kind, StepKind::Clock);
+        assert_eq!(estimator.estimate(), Some(-2.0));
+        assert_eq!(estimator.steps(), 1);
+
+        let rollover = WEEK_ROLLOVER.as_seconds_f64();
+        assert_eq!(StepKind::of(-rollover + 18.0), StepKind::WeekRollover);
+        assert_eq!(StepKind::of(3600.0), StepKind::Clock);
+    }
+}
@@ -0,0 +1,230 @@
+//! Fix outage detection
+//!
+//! This module tracks continuous intervals without a 2D or 3D fix. An
+//! outage starts when a TPV report without a fix follows a fix, or when
+//! TPV reports stop arriving for longer than a configured gap, and ends
+//! with the next fix. Outages are reported as events and kept in a
+//! queryable history, the raw material for uptime metrics and the
+//! [`report`](crate::report) module.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::outage::{OutageEvent, OutageTracker};
+//! # fn example(tpvs: Vec<gpsd_json::protocol::v3::response::Tpv>) {
+//! let mut tracker = OutageTracker::default();
+//! for tpv in &tpvs {
+//!     match tracker.update(tpv) {
+//!         Some(OutageEvent::Started { start, .. }) => println!("fix lost at {start}"),
+//!         Some(OutageEvent::Ended(outage)) => println!("fix back after {:.0} s", outage.duration),
+//!         None => {}
+//!     }
+//! }
+//! println!("{} outages, {:.0} s in total", tracker.history().len(), tracker.total_duration());
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use chrono::TimeDelta;
+use serde::Serialize;
+
+use crate::{
+    Timestamp,
+    geo::Position,
+    protocol::v3::{response::Tpv, types::FixMode},
+};
+
+/// A continuous interval without a 2D or 3D fix
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Outage {
+    /// Time of the last fix before the outage
+    pub start: Timestamp,
+    /// Time of the first fix after the outage
+    pub end: Timestamp,
+    /// Length of the outage in seconds
+    pub duration: f64,
+    /// Last known position before the outage
+    pub last_position: Option<Position>,
+}
+
+/// Outage state change reported by the [`OutageTracker`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutageEvent {
+    /// The fix was lost
+    Started {
+        /// Time of the last fix
+        start: Timestamp,
+        /// Last known position
+        last_position: Option<Position>,
+    },
+    /// The fix was regained
+    Ended(Outage),
+}
+
+/// Configuration of an [`OutageTracker`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutageConfig {
+    /// Largest gap between fixes that is not counted as an outage
+    pub max_gap: TimeDelta,
+    /// Number of finished outages kept in the history
+    pub history: usize,
+}
+
+impl Default for OutageConfig {
+    fn default() -> Self {
+        OutageConfig {
+            max_gap: TimeDelta::seconds(10),
+            history: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastFix {
+    time: Timestamp,
+    position: Option<Position>,
+}
+
+/// Tracks intervals without a fix from a sequence of TPV reports
+///
+/// Times are taken from the reports, so replayed logs yield the same
+/// outages as live streams. Reports without time are ignored.
+#[derive(Debug, Clone)]
+pub struct OutageTracker {
+    config: OutageConfig,
+    last_fix: Option<LastFix>,
+    in_outage: bool,
+    history: VecDeque<Outage>,
+}
+
+impl Default for OutageTracker {
+    fn default() -> Self {
+        Self::new(OutageConfig::default())
+    }
+}
+
+impl OutageTracker {
+    /// Creates a tracker with the given configuration
+    pub fn new(config: OutageConfig) -> Self {
+        OutageTracker {
+            config,
+            last_fix: None,
+            in_outage: false,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Processes a TPV report
+    ///
+    /// An outage detected from a gap between two fixes is reported as a
+    /// single `Ended` event.
+    pub fn update(&mut self, tpv: &Tpv) -> Option<OutageEvent> {
+        let time = tpv.time?;
+
+        if tpv.mode < FixMode::Fix2D {
+            let last = self.last_fix?;
+            if self.in_outage {
+                return None;
+            }
+            self.in_outage = true;
+            return Some(OutageEvent::Started {
+                start: last.time,
+                last_position: last.position,
+            });
+        }
+
+        let last = self.last_fix.replace(LastFix {
+            time,
+            position: Position::from_tpv(tpv),
+        })?;
+        let gap = time - last.time > self.config.max_gap;
+        if !std::mem::take(&mut self.in_outage) && !gap {
+            return None;
+        }
+
+        let outage = Outage {
+            start: last.time,
+            end: time,
+            duration: (time - last.time).as_seconds_f64(),
+            last_position: last.position,
+        };
+        if self.history.len() == self.config.history {
+            self.history.pop_front();
+        }
+        if self.config.history > 0 {
+            self.history.push_back(outage);
+        }
+        Some(OutageEvent::Ended(outage))
+    }
+
+    /// Returns the start of the ongoing outage, if any
+    pub fn current(&self) -> Option<Timestamp> {
+        self.last_fix
+            .filter(|_| self.in_outage)
+            .map(|last| last.time)
+    }
+
+    /// Returns true if the fix is currently lost
+    pub fn in_outage(&self) -> bool {
+        self.in_outage
+    }
+
+    /// Returns the finished outages, oldest first
+    pub fn history(&self) -> impl ExactSizeIterator<Item = &Outage> + DoubleEndedIterator {
+        self.history.iter()
+    }
+
+    /// Returns the finished outages that overlap the given time range
+    pub fn between(&self, from: Timestamp, to: Timestamp) -> impl Iterator<Item = &Outage> + '_ {
+        self.history
+            .iter()
+            .filter(move |outage| outage.end > from && outage.start < to)
+    }
+
+    /// Returns the total length of the finished outages in seconds
+    pub fn total_duration(&self) -> f64 {
+        self.history.iter().map(|outage| outage.duration).sum()
+    }
+
+    /// Forgets all finished outages
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tpv(sec: u32, mode: u8) -> Tpv {
+        serde_json::from_str(&format!(
+            r#"{{"mode":{mode},"time":"2025-01-01T00:00:{sec:02}Z","lat":35.0,"lon":139.0}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_outage_tracker_events() {
+        let mut tracker = OutageTracker::default();
+
+        assert_eq!(tracker.update(&tpv(0, 3)), None);
+        assert!(matches!(
+            tracker.update(&tpv(1, 1)),
+            Some(OutageEvent::Started { .. })
+        ));
+        assert_eq!(tracker.update(&tpv(2, 1)), None);
+        assert!(tracker.in_outage());
+        assert!(matches!(
+            tracker.update(&tpv(5, 3)),
+            Some(OutageEvent::Ended(Outage { duration: 5.0, .. }))
+        ));
+        // A gap without any reports
+        assert!(matches!(
+            tracker.update(&tpv(30, 3)),
+            Some(OutageEvent::Ended(Outage { duration: 25.0, .. }))
+        ));
+        assert_eq!(tracker.history().len(), 2);
+        assert_eq!(tracker.total_duration(), 30.0);
+    }
+}
@@ -0,0 +1,153 @@
+//! One-shot current position
+//!
+//! [`get_position`] connects to GPSD, waits for a 2D or 3D fix and returns
+//! it as a [`Position`] with its accuracy and timestamp, for scripts that
+//! only need to know where they are. [`blocking::get_position`] is the
+//! blocking twin. Both give up after [`DEFAULT_FIX_TIMEOUT`]; use
+//! [`wait_for_fix`](crate::client::blocking::GpsdClient::wait_for_fix) on a
+//! client for other requirements.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let position = gpsd_json::get_position("127.0.0.1:2947").await?;
+//! println!("{}, {} ± {:?} m", position.lat, position.lon, position.horizontal_accuracy);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DEFAULT_FIX_TIMEOUT`]: crate::position::DEFAULT_FIX_TIMEOUT
+//! [`Position`]: crate::position::Position
+//! [`blocking::get_position`]: crate::position::blocking::get_position
+
+use std::time::Duration;
+
+use crate::{
+    Timestamp,
+    protocol::v3::{response::Tpv, types::FixMode},
+};
+
+/// Time to wait for a fix before giving up
+pub const DEFAULT_FIX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Position of a fix with its accuracy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    /// Latitude in degrees
+    pub lat: f64,
+    /// Longitude in degrees
+    pub lon: f64,
+    /// Altitude above mean sea level in meters, for 3D fixes
+    pub alt_msl: Option<f64>,
+    /// Altitude above the ellipsoid in meters, for 3D fixes
+    pub alt_hae: Option<f64>,
+    /// Estimated horizontal error in meters
+    pub horizontal_accuracy: Option<f64>,
+    /// Estimated vertical error in meters
+    pub vertical_accuracy: Option<f64>,
+    /// Time of the fix
+    pub time: Option<Timestamp>,
+    /// Mode of the fix
+    pub mode: FixMode,
+    /// Device that provided the fix
+    pub device: Option<String>,
+}
+
+impl Position {
+    /// Returns the position of a TPV report, or `None` if it has no
+    /// coordinates
+    ///
+    /// The horizontal accuracy is the `eph` of the report, or else the
+    /// larger of `epx` and `epy`.
+    pub fn from_tpv(tpv: &Tpv) -> Option<Self> {
+        let horizontal_accuracy = tpv.eph.or(match (tpv.epx, tpv.epy) {
+            (Some(epx), Some(epy)) => Some(epx.max(epy)),
+            (epx, epy) => epx.or(epy),
+        });
+        Some(Position {
+            lat: tpv.lat?,
+            lon: tpv.lon?,
+            alt_msl: tpv.alt_msl,
+            alt_hae: tpv.alt_hae,
+            horizontal_accuracy,
+            vertical_accuracy: tpv.epv,
+            time: tpv.time,
+            mode: tpv.mode,
+            device: tpv.device.clone(),
+        })
+    }
+}
+
+/// Returns the position of a fix, failing if it has no coordinates
+#[cfg(all(feature = "proto-v3", feature = "net"))]
+fn position_of(tpv: &Tpv) -> crate::Result<Position> {
+    Position::from_tpv(tpv).ok_or(crate::error::GpsdJsonError::ProtocolError(
+        "Fix without coordinates from GPSD",
+    ))
+}
+
+/// Connects to GPSD and returns the position of the first 2D or 3D fix
+///
+/// Fails with a [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if
+/// no fix arrives within [`DEFAULT_FIX_TIMEOUT`].
+#[cfg(all(feature = "proto-v3", feature = "tokio", feature = "net"))]
+pub async fn get_position<A: tokio::net::ToSocketAddrs + Sync>(addr: A) -> crate::Result<Position> {
+    let mut client = crate::client::GpsdClient::connect(addr).await?;
+    let tpv = client
+        .wait_for_fix(FixMode::Fix2D, DEFAULT_FIX_TIMEOUT)
+        .await?;
+    position_of(&tpv)
+}
+
+/// Blocking one-shot position
+#[cfg(all(feature = "proto-v3", feature = "net"))]
+pub mod blocking {
+    use std::net::{TcpStream, ToSocketAddrs};
+
+    use super::{DEFAULT_FIX_TIMEOUT, Position, position_of};
+    use crate::{
+        Result, client::blocking::GpsdClient, error::GpsdJsonError, protocol::v3::types::FixMode,
+    };
+
+    /// Connects to GPSD and returns the position of the first 2D or 3D fix
+    ///
+    /// Fails with a [`TimedOut`](std::io::ErrorKind::TimedOut) I/O error if
+    /// no fix arrives within [`DEFAULT_FIX_TIMEOUT`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// let position = gpsd_json::position::blocking::get_position("127.0.0.1:2947")?;
+    /// println!("{}, {}", position.lat, position.lon);
+    /// # Ok::<(), gpsd_json::error::GpsdJsonError>(())
+    /// ```
+    pub fn get_position<A: ToSocketAddrs>(addr: A) -> Result<Position> {
+        let stream = TcpStream::connect(addr).map_err(GpsdJsonError::IoError)?;
+        // Bounds the wait when GPSD is quiet
+        stream
+            .set_read_timeout(Some(std::time::Duration::from_secs(1)))
+            .map_err(GpsdJsonError::IoError)?;
+        let mut client = GpsdClient::try_from(stream)?;
+        let tpv = client.wait_for_fix(FixMode::Fix2D, DEFAULT_FIX_TIMEOUT)?;
+        position_of(&tpv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_position_from_tpv() {
+        let tpv: Tpv = serde_json::from_str(fixtures::TPV).unwrap();
+        let position = Position::from_tpv(&tpv).unwrap();
+        assert_eq!((position.lat, position.lon), (35.681236, 139.767125));
+        assert_eq!(position.horizontal_accuracy, Some(5.1));
+        assert_eq!(position.vertical_accuracy, Some(7.4));
+        assert_eq!(position.mode, FixMode::Fix3D);
+
+        let no_fix: Tpv = serde_json::from_str(fixtures::TPV_NO_FIX).unwrap();
+        assert_eq!(Position::from_tpv(&no_fix), None);
+    }
+}
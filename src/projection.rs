@@ -0,0 +1,116 @@
+//! Field pruning of reports before forwarding
+//!
+//! Forwarding every GPSD report to a cloud backend wastes bandwidth on
+//! fields the backend never reads, such as the per-satellite details of SKY
+//! reports. A [`Projection`] keeps only the selected top-level fields of
+//! each class and strips everything else before re-serialization. The
+//! `class` field is always kept, and classes without a rule are forwarded
+//! unchanged.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::projection::Projection;
+//!
+//! let projection = Projection::new()
+//!     .keep("TPV", ["time", "mode", "lat", "lon", "altHAE"])
+//!     .keep("SKY", ["time", "nSat", "uSat", "hdop"]);
+//!
+//! let sky = br#"{"class":"SKY","hdop":0.99,"nSat":1,"uSat":1,"satellites":[{"PRN":5,"used":true}]}"#;
+//! let line = projection.project_line(sky).unwrap();
+//! assert_eq!(line, r#"{"class":"SKY","hdop":0.99,"nSat":1,"uSat":1}"#);
+//! ```
+//!
+//! [`Projection`]: crate::projection::Projection
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{JsonValue, Result, error::GpsdJsonError};
+
+/// Fields to keep per report class
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Projection {
+    keep: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Projection {
+    /// Creates a projection without rules, forwarding every field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps only the given fields of reports of a class
+    ///
+    /// Calling this again for the same class adds to its fields.
+    pub fn keep<I, F>(mut self, class: impl Into<String>, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        self.keep
+            .entry(class.into())
+            .or_default()
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns the fields kept of a class, or `None` if the class is
+    /// forwarded unchanged
+    pub fn fields(&self, class: &str) -> Option<&BTreeSet<String>> {
+        self.keep.get(class)
+    }
+
+    /// Strips the fields not kept from a JSON report in place
+    ///
+    /// Values that are not objects with a `class` field are left unchanged.
+    pub fn apply(&self, value: &mut JsonValue) {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+        let Some(keep) = object
+            .get("class")
+            .and_then(JsonValue::as_str)
+            .and_then(|class| self.keep.get(class))
+        else {
+            return;
+        };
+        object.retain(|field, _| field == "class" || keep.contains(field));
+    }
+
+    /// Strips the fields not kept from a JSON line as received from GPSD
+    ///
+    /// Returns the projected line without a line ending.
+    pub fn project_line(&self, line: &[u8]) -> Result<String> {
+        let mut value: JsonValue =
+            serde_json::from_slice(line).map_err(GpsdJsonError::SerdeError)?;
+        self.apply(&mut value);
+        serde_json::to_string(&value).map_err(GpsdJsonError::SerdeError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_projection_keeps_selected_fields() {
+        let projection = Projection::new()
+            .keep("SKY", ["nSat", "uSat"])
+            .keep("SKY", ["hdop"]);
+
+        let mut sky: JsonValue = serde_json::from_str(fixtures::SKY).unwrap();
+        projection.apply(&mut sky);
+        assert_eq!(
+            sky,
+            serde_json::json!({"class": "SKY", "nSat": 3, "uSat": 2, "hdop": 0.99})
+        );
+
+        // Classes without a rule are forwarded unchanged
+        let tpv = projection.project_line(fixtures::TPV.as_bytes()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&tpv).unwrap(),
+            serde_json::from_str::<JsonValue>(fixtures::TPV).unwrap()
+        );
+    }
+}
@@ -14,17 +14,167 @@ use std::{
 
 use crate::{Result, error::GpsdJsonError};
 
+/// tokio-util codec framing GPSD messages
+#[cfg(feature = "tokio")]
+pub mod codec;
+
+/// Sans-I/O protocol state machine for custom event loops
+pub mod machine;
+
 /// Protocol version 3 implementation
 ///
 /// This is the current stable version of the GPSD JSON protocol,
 /// supporting all standard GPS data types and control messages.
 pub mod v3;
 
+/// Removes the line ending from a received line
+///
+/// Strips any trailing `\n` and `\r` bytes, so that lines terminated with
+/// LF, CRLF or a mix of both (e.g. after passing a relay that normalizes
+/// line endings) are handled the same way.
+pub(crate) fn strip_line_ending(line: &mut Vec<u8>) {
+    while matches!(line.last(), Some(b'\n' | b'\r')) {
+        line.pop();
+    }
+}
+
+/// Decodes a line received without its line ending
+pub(crate) fn decode_line<Response>(line: &[u8], mode: ParseMode) -> Result<Response>
+where
+    Response: GpsdJsonResponse,
+{
+    let msg = serde_json::from_slice(line).map_err(GpsdJsonError::SerdeError)?;
+    mode.check(line, &msg)?;
+    Ok(msg)
+}
+
 /// Trait for types that can be deserialized as GPSD response messages
 ///
 /// All GPSD response message types must implement this trait,
 /// which ensures they can be properly deserialized from JSON.
-pub trait GpsdJsonResponse: serde::de::DeserializeOwned {}
+/// Comparison is required to detect unknown fields in
+/// [`ParseMode::Strict`].
+pub trait GpsdJsonResponse: serde::de::DeserializeOwned + PartialEq {
+    /// Returns the path of the device the message is about, if any
+    ///
+    /// Used to drop messages of other devices from streams scoped to
+    /// devices.
+    fn device(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the value of the `class` field of the message, if known
+    ///
+    /// Used to drop messages of other classes from streams filtered by
+    /// class.
+    fn class_name(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Handling of JSON fields that the message types do not know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Ignore unknown fields, so that newer GPSD releases keep working
+    #[default]
+    Lenient,
+    /// Reject messages with unknown fields with
+    /// [`GpsdJsonError::UnknownFields`]
+    ///
+    /// Intended for integration tests catching schema drift. Every field
+    /// is probed by decoding the message again, see [`unknown_fields`], so
+    /// this is considerably slower than lenient parsing.
+    Strict,
+}
+
+impl ParseMode {
+    /// Checks a decoded message against the line it was decoded from
+    pub(crate) fn check<Response>(self, line: &[u8], msg: &Response) -> Result<()>
+    where
+        Response: GpsdJsonResponse,
+    {
+        if self == ParseMode::Lenient {
+            return Ok(());
+        }
+        let fields = unknown_fields(line, msg);
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(GpsdJsonError::UnknownFields(fields))
+        }
+    }
+}
+
+/// Value that no message field expects, used to probe for unknown fields
+const PROBE_VALUE: &str = "\u{0}gpsd-json-probe";
+
+/// Returns the fields of a JSON line that are ignored when decoding it
+///
+/// Fields are returned as JSON pointers, e.g. `/satellites/3/foo`. A field
+/// counts as ignored if replacing its value leaves the decoded message
+/// unchanged. Fields with null values are skipped, as are the children of
+/// ignored fields and the `class` field of nested objects. With the
+/// `extra-fields` feature, messages keep the fields they do not define as
+/// additional fields, so these are not reported either.
+pub fn unknown_fields<Response>(line: &[u8], msg: &Response) -> Vec<String>
+where
+    Response: GpsdJsonResponse,
+{
+    let Ok(root) = serde_json::from_slice::<crate::JsonValue>(line) else {
+        return Vec::new();
+    };
+    // Probing relies on decoding from a value giving the same message
+    if serde_json::from_value::<Response>(root.clone())
+        .ok()
+        .as_ref()
+        != Some(msg)
+    {
+        return Vec::new();
+    }
+
+    let mut unknown = Vec::new();
+    probe_fields(&root, &root, String::new(), msg, &mut unknown);
+    unknown
+}
+
+/// Probes the fields below `pointer`, recursing into known fields
+fn probe_fields<Response>(
+    root: &crate::JsonValue,
+    value: &crate::JsonValue,
+    pointer: String,
+    msg: &Response,
+    unknown: &mut Vec<String>,
+) where
+    Response: GpsdJsonResponse,
+{
+    match value {
+        crate::JsonValue::Object(map) => {
+            for (key, child) in map {
+                // Nested objects such as the devices of a DEVICES reply
+                // repeat their class, which is implied by the parent
+                if child.is_null() || (!pointer.is_empty() && key == "class") {
+                    continue;
+                }
+                let pointer = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                let mut probe = root.clone();
+                if let Some(field) = probe.pointer_mut(&pointer) {
+                    *field = crate::JsonValue::from(PROBE_VALUE);
+                }
+                if serde_json::from_value::<Response>(probe).is_ok_and(|probed| probed == *msg) {
+                    unknown.push(pointer);
+                } else {
+                    probe_fields(root, child, pointer, msg, unknown);
+                }
+            }
+        }
+        crate::JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                probe_fields(root, item, format!("{pointer}/{i}"), msg, unknown);
+            }
+        }
+        _ => {}
+    }
+}
 
 /// Extension trait for reading GPSD JSON responses from an async buffered reader
 ///
@@ -69,9 +219,26 @@ pub trait GpsdJsonDecodeAsync: futures_io::AsyncBufRead {
     /// # }
     /// ```
     fn poll_response<Response>(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut Vec<u8>,
+    ) -> Poll<Result<Option<Response>>>
+    where
+        Response: GpsdJsonResponse,
+    {
+        self.poll_response_with(cx, buf, ParseMode::Lenient)
+    }
+
+    /// Polls for the next GPSD response message, decoded in the given mode
+    ///
+    /// Same as [`poll_response`](Self::poll_response), but returns
+    /// [`GpsdJsonError::UnknownFields`] for messages with unknown fields in
+    /// [`ParseMode::Strict`].
+    fn poll_response_with<Response>(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut Vec<u8>,
+        mode: ParseMode,
     ) -> Poll<Result<Option<Response>>>
     where
         Response: GpsdJsonResponse,
@@ -87,11 +254,13 @@ pub trait GpsdJsonDecodeAsync: futures_io::AsyncBufRead {
                         // Found a newline, we have a complete message
                         buf.extend_from_slice(&in_buf[..=pos]);
                         self.as_mut().consume(pos + 1); // Consume up to and including the newline
+                        strip_line_ending(buf);
 
                         return match serde_json::from_slice(buf) {
                             Ok(msg) => {
+                                let ret = mode.check(buf, &msg).map(|_| Some(msg));
                                 buf.clear();
-                                Poll::Ready(Ok(Some(msg)))
+                                Poll::Ready(ret)
                             }
                             Err(e) if e.is_eof() => {
                                 // Incomplete JSON, continue reading
@@ -216,6 +385,23 @@ pub trait GpsdJsonDecode: std::io::BufRead {
     /// # }
     /// ```
     fn read_response<Response>(&mut self, buf: &mut Vec<u8>) -> Result<Option<Response>>
+    where
+        Response: GpsdJsonResponse,
+    {
+        self.read_response_with(buf, ParseMode::Lenient)
+    }
+
+    /// Reads and deserializes a single GPSD response message in the given
+    /// mode
+    ///
+    /// Same as [`read_response`](Self::read_response), but returns
+    /// [`GpsdJsonError::UnknownFields`] for messages with unknown fields in
+    /// [`ParseMode::Strict`].
+    fn read_response_with<Response>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        mode: ParseMode,
+    ) -> Result<Option<Response>>
     where
         Response: GpsdJsonResponse,
     {
@@ -225,11 +411,13 @@ pub trait GpsdJsonDecode: std::io::BufRead {
         if bytes_read == 0 {
             return Ok(None); // EOF reached
         }
+        strip_line_ending(buf);
 
         match serde_json::from_slice(buf) {
             Ok(msg) => {
+                let ret = mode.check(buf, &msg).map(|_| Some(msg));
                 buf.clear();
-                Ok(Some(msg))
+                ret
             }
             Err(e) if e.is_eof() => {
                 // Incomplete JSON, continue reading
@@ -260,12 +448,35 @@ pub trait GpsdJsonRequest {
     /// ```
     /// # struct WatchRequest;
     /// # impl gpsd_json::protocol::GpsdJsonRequest for WatchRequest {
-    /// fn to_command(&self) -> String {
-    ///     "?WATCH={\"enable\":true};".to_string()
+    /// fn to_command(&self) -> gpsd_json::Result<String> {
+    ///     Ok("?WATCH={\"enable\":true};".to_string())
     /// }
     /// # }
     /// ```
-    fn to_command(&self) -> String;
+    fn to_command(&self) -> Result<String>;
+}
+
+/// Encoder of request messages into GPSD command strings
+///
+/// [`GpsdJsonRequest::to_command`] uses the default encoder of a protocol;
+/// alternative encoders, e.g. pretty-printed for debugging or for future
+/// protocol dialects, are used with
+/// [`GpsdJsonEncode::write_request_with`] or called directly.
+///
+/// # Example
+/// ```
+/// use gpsd_json::protocol::{
+///     CommandEncoder,
+///     v3::{PrettyEncoder, RequestMessage},
+/// };
+///
+/// let command = PrettyEncoder.encode(&RequestMessage::Poll)?;
+/// assert_eq!(command, "?POLL;");
+/// # Ok::<(), gpsd_json::error::GpsdJsonError>(())
+/// ```
+pub trait CommandEncoder<Request: ?Sized> {
+    /// Encodes a request as a command string
+    fn encode(&self, request: &Request) -> Result<String>;
 }
 
 /// Extension trait for writing GPSD JSON requests to an async writer
@@ -304,7 +515,7 @@ pub trait GpsdJsonEncodeAsync: futures_io::AsyncWrite + Unpin {
         let cmd = request.to_command();
         async move {
             use futures_util::io::AsyncWriteExt;
-            self.write_all(cmd.as_bytes())
+            self.write_all(cmd?.as_bytes())
                 .await
                 .map_err(GpsdJsonError::IoError)
         }
@@ -335,10 +546,103 @@ pub trait GpsdJsonEncode: std::io::Write {
     /// # }
     /// ```
     fn write_request(&mut self, request: &impl GpsdJsonRequest) -> Result<()> {
-        let cmd = request.to_command();
+        let cmd = request.to_command()?;
+        self.write_all(cmd.as_bytes())
+            .map_err(GpsdJsonError::IoError)
+    }
+
+    /// Writes a request message encoded with the given encoder
+    fn write_request_with<Request: ?Sized>(
+        &mut self,
+        encoder: &impl CommandEncoder<Request>,
+        request: &Request,
+    ) -> Result<()> {
+        let cmd = encoder.encode(request)?;
         self.write_all(cmd.as_bytes())
             .map_err(GpsdJsonError::IoError)
     }
 }
 
 impl<W: std::io::Write + ?Sized> GpsdJsonEncode for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIXED: &[u8] = b"{\"class\":\"TPV\",\"mode\":3}\r\n{\"class\":\"TPV\",\"mode\":2}\n{\"class\":\"TPV\",\"mode\":1}\r\r\n";
+
+    #[test]
+    fn test_strip_line_ending() {
+        for (line, expected) in [
+            (&b"$GPGGA,1*5B\r\n"[..], &b"$GPGGA,1*5B"[..]),
+            (b"$GPGGA,1*5B\n", b"$GPGGA,1*5B"),
+            (b"$GPGGA,1*5B\r\r\n", b"$GPGGA,1*5B"),
+            (b"$GPGGA,1*5B ", b"$GPGGA,1*5B "),
+        ] {
+            let mut line = line.to_vec();
+            strip_line_ending(&mut line);
+            assert_eq!(line, expected);
+        }
+    }
+
+    #[test]
+    fn test_decode_mixed_line_endings() {
+        let mut modes = Vec::new();
+        let mut reader = std::io::Cursor::new(MIXED);
+        let mut buf = Vec::new();
+        while let Some(msg) = reader
+            .read_response::<v3::ResponseMessage>(&mut buf)
+            .unwrap()
+        {
+            let v3::ResponseMessage::Tpv(tpv) = msg else {
+                panic!("unexpected message: {msg:?}");
+            };
+            modes.push(tpv.mode as u8);
+        }
+        assert_eq!(modes, [3, 2, 1]);
+
+        let mut reader = futures::io::Cursor::new(MIXED);
+        let mut buf = Vec::new();
+        let mut modes = Vec::new();
+        while let Some(msg) = futures::executor::block_on(futures::future::poll_fn(|cx| {
+            Pin::new(&mut reader).poll_response::<v3::ResponseMessage>(cx, &mut buf)
+        }))
+        .unwrap()
+        {
+            let v3::ResponseMessage::Tpv(tpv) = msg else {
+                panic!("unexpected message: {msg:?}");
+            };
+            modes.push(tpv.mode as u8);
+        }
+        assert_eq!(modes, [3, 2, 1]);
+    }
+
+    #[cfg(feature = "msg-sky")]
+    #[test]
+    fn test_strict_parse_mode() {
+        let line = br#"{"class":"SKY","device":"/dev/ttyACM0","future":1,"satellites":[{"PRN":5,"used":true,"sigid2":4}],"xdop":null}"#;
+        let msg: v3::ResponseMessage = serde_json::from_slice(line).unwrap();
+        // Top-level fields are kept in the extra fields of SKY reports
+        let expected: &[&str] = if cfg!(feature = "extra-fields") {
+            &["/satellites/0/sigid2"]
+        } else {
+            &["/future", "/satellites/0/sigid2"]
+        };
+        assert_eq!(unknown_fields(line, &msg), expected);
+
+        let mut input = line.to_vec();
+        input.push(b'\n');
+        let mut buf = Vec::new();
+        assert!(
+            std::io::Cursor::new(&input)
+                .read_response_with::<v3::ResponseMessage>(&mut buf, ParseMode::Lenient)
+                .unwrap()
+                .is_some()
+        );
+        assert!(matches!(
+            std::io::Cursor::new(&input)
+                .read_response_with::<v3::ResponseMessage>(&mut buf, ParseMode::Strict),
+            Err(GpsdJsonError::UnknownFields(fields)) if fields == expected
+        ));
+    }
+}
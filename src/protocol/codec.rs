@@ -0,0 +1,173 @@
+//! tokio-util codec for the newline-delimited GPSD protocol
+//!
+//! [`GpsdCodec`] frames the bytes of a GPSD connection into response
+//! messages and encodes requests, so a socket can be wrapped in a
+//! [`Framed`](tokio_util::codec::Framed) transport and used directly in
+//! tokio or tower pipelines, without the clients of this crate. Unlike the
+//! clients, the codec performs no handshake: the version greeting arrives
+//! as the first message and WATCH mode must be enabled by sending a
+//! request.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::{SinkExt, StreamExt};
+//! use gpsd_json::protocol::{
+//!     codec::GpsdCodec,
+//!     v3::{RequestMessage, V3, types::Watch},
+//! };
+//! use tokio_util::codec::Framed;
+//!
+//! # async fn example() -> gpsd_json::Result<()> {
+//! let socket = tokio::net::TcpStream::connect("127.0.0.1:2947").await?;
+//! let mut framed = Framed::new(socket, GpsdCodec::<V3>::new());
+//!
+//! let watch = Watch { enable: Some(true), json: Some(true), ..Default::default() };
+//! framed.send(RequestMessage::Watch(Some(watch))).await?;
+//! while let Some(msg) = framed.next().await {
+//!     println!("{:?}", msg?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`GpsdCodec`]: crate::protocol::codec::GpsdCodec
+
+use tokio_util::{
+    bytes::{BufMut, BytesMut},
+    codec::{Decoder, Encoder},
+};
+
+use crate::{
+    Result,
+    client::GpsdJsonProtocol,
+    error::GpsdJsonError,
+    protocol::{GpsdJsonRequest, ParseMode, v3},
+};
+
+/// Codec framing GPSD response messages and encoding requests
+///
+/// Lines that are not JSON objects, such as NMEA sentences of a watch
+/// with `nmea` enabled, are skipped. Lines that fail to decode are
+/// returned as errors, which ends a `Framed` stream.
+#[derive(Debug, Clone)]
+pub struct GpsdCodec<Proto = v3::V3> {
+    next_index: usize,
+    parse_mode: ParseMode,
+    _proto: std::marker::PhantomData<Proto>,
+}
+
+impl<Proto: GpsdJsonProtocol> Default for GpsdCodec<Proto> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Proto: GpsdJsonProtocol> GpsdCodec<Proto> {
+    /// Creates a codec decoding messages leniently
+    pub fn new() -> Self {
+        GpsdCodec {
+            next_index: 0,
+            parse_mode: ParseMode::Lenient,
+            _proto: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets how unknown fields of received messages are handled
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// Decodes a received line, or returns `None` if it is not a JSON
+    /// object
+    fn decode_line(&self, line: &[u8]) -> Result<Option<Proto::Response>> {
+        let line = line.trim_ascii();
+        if line.first() != Some(&b'{') {
+            return Ok(None);
+        }
+        let msg = serde_json::from_slice(line).map_err(GpsdJsonError::SerdeError)?;
+        self.parse_mode.check(line, &msg)?;
+        Ok(Some(msg))
+    }
+}
+
+impl<Proto: GpsdJsonProtocol> Decoder for GpsdCodec<Proto> {
+    type Item = Proto::Response;
+    type Error = GpsdJsonError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        loop {
+            // Only scan the bytes received since the last call
+            let Some(offset) = src[self.next_index..].iter().position(|&b| b == b'\n') else {
+                self.next_index = src.len();
+                return Ok(None);
+            };
+            let line = src.split_to(self.next_index + offset + 1);
+            self.next_index = 0;
+            if let Some(msg) = self.decode_line(&line)? {
+                return Ok(Some(msg));
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        if let Some(msg) = self.decode(src)? {
+            return Ok(Some(msg));
+        }
+        // The last line may lack its line ending
+        self.next_index = 0;
+        let line = src.split();
+        self.decode_line(&line)
+    }
+}
+
+impl<Proto: GpsdJsonProtocol> Encoder<Proto::Request> for GpsdCodec<Proto> {
+    type Error = GpsdJsonError;
+
+    fn encode(&mut self, request: Proto::Request, dst: &mut BytesMut) -> Result<()> {
+        dst.put_slice(request.to_command()?.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn test_gpsd_codec() {
+        let mut codec = GpsdCodec::<v3::V3>::new();
+        let mut buf = BytesMut::new();
+
+        buf.extend_from_slice(fixtures::VERSION.as_bytes());
+        buf.extend_from_slice(b"\r\n$GPGGA,1*5B\r\n");
+        buf.extend_from_slice(&fixtures::TPV.as_bytes()[..20]);
+        assert!(matches!(
+            codec.decode(&mut buf).unwrap(),
+            Some(v3::ResponseMessage::Version(_))
+        ));
+        // The NMEA sentence is skipped and the TPV is incomplete
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(&fixtures::TPV.as_bytes()[20..]);
+        buf.extend_from_slice(b"\n{\"class\":\"TPV\",,}\n");
+        buf.extend_from_slice(fixtures::SKY.as_bytes());
+        assert!(matches!(
+            codec.decode(&mut buf).unwrap(),
+            Some(v3::ResponseMessage::Tpv(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(GpsdJsonError::SerdeError(_))
+        ));
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        let msg = codec.decode_eof(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.class_name(), "SKY");
+        assert!(codec.decode_eof(&mut buf).unwrap().is_none());
+
+        codec.encode(v3::RequestMessage::Poll, &mut buf).unwrap();
+        assert_eq!(&buf[..], b"?POLL;");
+    }
+}
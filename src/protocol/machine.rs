@@ -0,0 +1,227 @@
+//! Sans-I/O GPSD protocol state machine
+//!
+//! [`ProtocolMachine`] holds the protocol logic of the clients without
+//! performing any I/O: received bytes are fed in, decoded events come out,
+//! and requests are queued as bytes for the caller to write. This allows
+//! driving a GPSD connection from event loops that are neither `std::io`
+//! nor `futures` based, such as mio or io_uring.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::io::{Read, Write};
+//! use gpsd_json::protocol::{
+//!     machine::{Event, ProtocolMachine},
+//!     v3::{RequestMessage, V3, types::Watch},
+//! };
+//! # fn example(socket: &mut std::net::TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut machine = ProtocolMachine::<V3>::new();
+//! let mut buf = [0u8; 4096];
+//!
+//! loop {
+//!     // Called when the socket is readable
+//!     let n = socket.read(&mut buf)?;
+//!     machine.feed(&buf[..n]);
+//!
+//!     while let Some(event) = machine.poll_event() {
+//!         match event? {
+//!             Event::Connected(_) => {
+//!                 let watch = Watch { enable: Some(true), json: Some(true), ..Default::default() };
+//!                 machine.send(&RequestMessage::Watch(Some(watch)))?;
+//!             }
+//!             Event::Response(msg) => println!("{msg:?}"),
+//!             Event::Line(_) => {}
+//!         }
+//!     }
+//!
+//!     // Called when the socket is writable
+//!     let n = socket.write(machine.pending_write())?;
+//!     machine.advance_write(n);
+//! }
+//! # }
+//! ```
+//!
+//! [`ProtocolMachine`]: crate::protocol::machine::ProtocolMachine
+
+use crate::{
+    Result,
+    client::GpsdJsonProtocol,
+    error::GpsdJsonError,
+    protocol::{GpsdJsonRequest, strip_line_ending, v3},
+};
+
+/// Event decoded from the bytes received from GPSD
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<Response> {
+    /// The server sent a supported protocol version on connect
+    Connected(v3::response::Version),
+    /// A JSON response message
+    Response(Response),
+    /// A line that is not a JSON object, e.g. an NMEA sentence
+    Line(Vec<u8>),
+}
+
+/// Protocol state of a GPSD connection, without I/O
+///
+/// The machine expects the version greeting GPSD sends on connect before
+/// any other message. If the greeting is missing or the version is not
+/// supported, [`poll_event`](Self::poll_event) returns an error and the
+/// connection should be closed.
+#[derive(Debug)]
+pub struct ProtocolMachine<Proto> {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+    connected: bool,
+    _proto: std::marker::PhantomData<Proto>,
+}
+
+impl<Proto: GpsdJsonProtocol> Default for ProtocolMachine<Proto> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Proto: GpsdJsonProtocol> ProtocolMachine<Proto> {
+    /// Creates a machine for a new connection
+    pub fn new() -> Self {
+        ProtocolMachine {
+            rx: Vec::new(),
+            tx: Vec::new(),
+            connected: false,
+            _proto: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns true once a supported version greeting was received
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Appends bytes received from GPSD
+    pub fn feed(&mut self, data: &[u8]) {
+        self.rx.extend_from_slice(data);
+    }
+
+    /// Returns the next event decoded from the received bytes
+    ///
+    /// Returns `None` until a complete line was received. Lines that fail
+    /// to decode are returned as errors and skipped.
+    pub fn poll_event(&mut self) -> Option<Result<Event<Proto::Response>>> {
+        let pos = self.rx.iter().position(|&b| b == b'\n')?;
+        let mut line: Vec<u8> = self.rx.drain(..=pos).collect();
+        strip_line_ending(&mut line);
+
+        if !self.connected {
+            return Some(check_version::<Proto>(&line).map(|version| {
+                self.connected = true;
+                Event::Connected(version)
+            }));
+        }
+
+        if line.first() != Some(&b'{') {
+            return Some(Ok(Event::Line(line)));
+        }
+        Some(
+            serde_json::from_slice(&line)
+                .map(Event::Response)
+                .map_err(GpsdJsonError::SerdeError),
+        )
+    }
+
+    /// Queues a request to be written to GPSD
+    pub fn send(&mut self, request: &Proto::Request) -> Result<()> {
+        self.tx.extend_from_slice(request.to_command()?.as_bytes());
+        Ok(())
+    }
+
+    /// Returns the queued bytes that have not been written yet
+    pub fn pending_write(&self) -> &[u8] {
+        &self.tx
+    }
+
+    /// Marks `n` queued bytes as written
+    pub fn advance_write(&mut self, n: usize) {
+        self.tx.drain(..n.min(self.tx.len()));
+    }
+}
+
+/// Number of bytes of an invalid greeting kept in the error
+const GREETING_CAPTURE_LEN: usize = 80;
+
+/// Reads the version greeting GPSD sends on connect
+pub(crate) fn parse_version(line: &[u8]) -> Result<v3::response::Version> {
+    match serde_json::from_slice(line) {
+        Ok(Some(v3::ResponseMessage::Version(version))) => Ok(version),
+        _ => {
+            let line = line.trim_ascii_end();
+            Err(GpsdJsonError::InvalidGreeting(
+                line[..line.len().min(GREETING_CAPTURE_LEN)].to_vec(),
+            ))
+        }
+    }
+}
+
+/// Checks the version greeting GPSD sends on connect
+///
+/// The version must be supported by `Proto`, see
+/// [`GpsdJsonProtocol::supports`].
+pub(crate) fn check_version<Proto: GpsdJsonProtocol>(line: &[u8]) -> Result<v3::response::Version> {
+    let version = parse_version(line)?;
+    if Proto::supports(version.proto_major, version.proto_minor) {
+        Ok(version)
+    } else {
+        Err(GpsdJsonError::UnsupportedProtocolVersion((
+            version.proto_major,
+            version.proto_minor,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::v3::V3;
+
+    #[test]
+    fn test_protocol_machine_events() {
+        let mut machine = ProtocolMachine::<V3>::new();
+        machine.feed(br#"{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"#);
+        assert!(machine.poll_event().is_none());
+        machine.feed(b"\"proto_minor\":15}\r\n$GPGGA,1*5B\r\n{\"class\":\"TPV\",\"mode\":3}\n");
+
+        assert!(matches!(
+            machine.poll_event(),
+            Some(Ok(Event::Connected(_)))
+        ));
+        assert!(machine.is_connected());
+        assert!(
+            matches!(machine.poll_event(), Some(Ok(Event::Line(line))) if line == b"$GPGGA,1*5B")
+        );
+        assert!(matches!(
+            machine.poll_event(),
+            Some(Ok(Event::Response(v3::ResponseMessage::Tpv(_))))
+        ));
+        assert!(machine.poll_event().is_none());
+
+        machine.send(&v3::RequestMessage::Poll).unwrap();
+        machine.advance_write(3);
+        assert_eq!(machine.pending_write(), b"LL;");
+    }
+
+    #[test]
+    fn test_invalid_greeting() {
+        let mut machine = ProtocolMachine::<V3>::new();
+        machine.feed(b"$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76\r\n");
+        let Some(Err(GpsdJsonError::InvalidGreeting(line))) = machine.poll_event() else {
+            panic!("expected an invalid greeting");
+        };
+        assert!(line.starts_with(b"$GPGGA,092750.000"));
+        assert!(!line.ends_with(b"\n"));
+
+        let long = [b'x'; 200];
+        assert!(matches!(
+            parse_version(&long),
+            Err(GpsdJsonError::InvalidGreeting(line)) if line.len() == GREETING_CAPTURE_LEN
+        ));
+    }
+}
@@ -20,14 +20,21 @@
 //! - [Protocol Version History](https://gitlab.com/gpsd/gpsd)
 
 use crate::{
+    Result,
     client::GpsdJsonProtocol,
-    protocol::{GpsdJsonRequest, GpsdJsonResponse},
+    error::GpsdJsonError,
+    protocol::{CommandEncoder, GpsdJsonRequest, GpsdJsonResponse},
 };
 
+/// AIS vessel reports
+#[cfg(feature = "ais")]
+pub mod ais;
 /// Request message types and builders
 pub mod request;
 /// Response message types and parsers
 pub mod response;
+/// RTCM differential correction reports
+pub mod rtcm;
 /// Common data types used in protocol messages
 pub mod types;
 
@@ -61,7 +68,15 @@ impl GpsdJsonProtocol for V3 {
 /// This is a convenience alias for `response::Message` that makes it
 /// clear we're working with protocol v3 responses.
 pub type ResponseMessage = response::Message;
-impl GpsdJsonResponse for ResponseMessage {}
+impl GpsdJsonResponse for ResponseMessage {
+    fn device(&self) -> Option<&str> {
+        response::Message::device(self)
+    }
+
+    fn class_name(&self) -> Option<&str> {
+        Some(response::Message::class_name(self))
+    }
+}
 
 /// Type alias for version 3 request messages
 ///
@@ -75,19 +90,307 @@ impl GpsdJsonRequest for RequestMessage {
     /// Each request type is formatted according to the GPSD protocol:
     /// - Simple commands: `?COMMAND;`
     /// - Commands with parameters: `?COMMAND={"json":"params"};`
-    fn to_command(&self) -> String {
-        match self {
-            RequestMessage::Devices => "?DEVICES;".into(),
-            RequestMessage::Watch(Some(watch)) => {
-                format!("?WATCH={};", serde_json::to_string(watch).unwrap())
-            }
-            RequestMessage::Watch(None) => "?WATCH;".into(),
-            RequestMessage::Device(Some(device)) => {
-                format!("?DEVICE={};", serde_json::to_string(device).unwrap())
-            }
-            RequestMessage::Device(None) => "?DEVICE;".into(),
-            RequestMessage::Poll => "?POLL;".into(),
-            RequestMessage::Version => "?VERSION;".into(),
+    fn to_command(&self) -> Result<String> {
+        CompactEncoder.encode(self)
+    }
+}
+
+/// Encoder of commands with compact JSON parameters, as sent to GPSD
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactEncoder;
+
+impl CommandEncoder<RequestMessage> for CompactEncoder {
+    fn encode(&self, request: &RequestMessage) -> Result<String> {
+        encode_command(request, false)
+    }
+}
+
+/// Encoder of commands with pretty-printed JSON parameters
+///
+/// Meant for logging and debugging; GPSD expects each command on a single
+/// line, so do not send the output of this encoder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrettyEncoder;
+
+impl CommandEncoder<RequestMessage> for PrettyEncoder {
+    fn encode(&self, request: &RequestMessage) -> Result<String> {
+        encode_command(request, true)
+    }
+}
+
+fn encode_command(request: &RequestMessage, pretty: bool) -> Result<String> {
+    fn params<T: serde::Serialize>(value: &T, pretty: bool) -> Result<String> {
+        let json = if pretty {
+            serde_json::to_string_pretty(value)
+        } else {
+            serde_json::to_string(value)
+        };
+        json.map_err(GpsdJsonError::SerdeError)
+    }
+
+    Ok(match request {
+        RequestMessage::Devices => "?DEVICES;".into(),
+        RequestMessage::Watch(Some(watch)) => format!("?WATCH={};", params(watch, pretty)?),
+        RequestMessage::Watch(None) => "?WATCH;".into(),
+        RequestMessage::Device(Some(device)) => format!("?DEVICE={};", params(device, pretty)?),
+        RequestMessage::Device(None) => "?DEVICE;".into(),
+        RequestMessage::Poll => "?POLL;".into(),
+        RequestMessage::Version => "?VERSION;".into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_encoders() {
+        let watch = RequestMessage::Watch(Some(types::Watch {
+            enable: Some(true),
+            json: Some(true),
+            ..Default::default()
+        }));
+        let compact = watch.to_command().unwrap();
+        assert!(compact.starts_with(r#"?WATCH={"enable":true,"json":true,"#));
+        assert!(compact.ends_with(';') && !compact.contains('\n'));
+
+        let pretty = PrettyEncoder.encode(&watch).unwrap();
+        assert!(pretty.starts_with("?WATCH={\n  \"enable\": true,"));
+        assert_eq!(
+            CompactEncoder.encode(&RequestMessage::Version).unwrap(),
+            "?VERSION;"
+        );
+    }
+
+    /// Every request variant and option with a label, in snapshot order
+    fn snapshot_requests() -> Vec<(&'static str, RequestMessage)> {
+        use crate::client::{DeviceConfig, StreamOptions};
+
+        let watch = |label, watch| (label, RequestMessage::Watch(Some(watch)));
+        let device = |label, device| (label, RequestMessage::Device(Some(device)));
+        let path = || Some("/dev/ttyUSB0".to_string());
+
+        vec![
+            ("devices", RequestMessage::Devices),
+            ("version", RequestMessage::Version),
+            ("poll", RequestMessage::Poll),
+            ("watch query", RequestMessage::Watch(None)),
+            ("device query", RequestMessage::Device(None)),
+            watch(
+                "watch empty",
+                types::Watch {
+                    device: None,
+                    enable: None,
+                    json: None,
+                    nmea: None,
+                    pps: None,
+                    raw: None,
+                    scaled: None,
+                    split24: None,
+                    timing: None,
+                    remote: None,
+                },
+            ),
+            watch("watch default", types::Watch::default()),
+            watch(
+                "watch device",
+                types::Watch {
+                    device: path(),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch enable",
+                types::Watch {
+                    enable: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch json",
+                types::Watch {
+                    json: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch nmea",
+                types::Watch {
+                    nmea: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch pps",
+                types::Watch {
+                    pps: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch raw hex",
+                types::Watch {
+                    raw: Some(1),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch raw binary",
+                types::Watch {
+                    raw: Some(2),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch scaled",
+                types::Watch {
+                    scaled: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch split24",
+                types::Watch {
+                    split24: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch timing",
+                types::Watch {
+                    timing: Some(true),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch remote",
+                types::Watch {
+                    remote: Some("gpsd://example.com:2947".to_string()),
+                    ..Default::default()
+                },
+            ),
+            watch(
+                "watch all",
+                types::Watch {
+                    device: path(),
+                    enable: Some(true),
+                    json: Some(true),
+                    nmea: Some(true),
+                    pps: Some(true),
+                    raw: Some(2),
+                    scaled: Some(true),
+                    split24: Some(true),
+                    timing: Some(true),
+                    remote: Some("gpsd://example.com:2947".to_string()),
+                },
+            ),
+            watch("stream json", StreamOptions::json().watches().remove(0)),
+            watch("stream nmea", StreamOptions::nmea().watches().remove(0)),
+            watch("stream raw", StreamOptions::raw().watches().remove(0)),
+            watch(
+                "stream capture",
+                StreamOptions::capture().watches().remove(0),
+            ),
+            watch(
+                "stream json device",
+                StreamOptions::json()
+                    .devices(["/dev/ttyUSB0"])
+                    .watches()
+                    .remove(0),
+            ),
+            device(
+                "device path",
+                types::Device {
+                    path: path(),
+                    ..Default::default()
+                },
+            ),
+            device(
+                "device bps",
+                DeviceConfig::new("/dev/ttyUSB0").bps(9600).device().clone(),
+            ),
+            device(
+                "device parity none",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .parity(types::Parity::No)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device parity odd",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .parity(types::Parity::Odd)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device parity even",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .parity(types::Parity::Even)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device stopbits",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .stopbits(2)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device cycle",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .cycle(0.2)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device native",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .native(true)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device nmea",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .native(false)
+                    .device()
+                    .clone(),
+            ),
+            device(
+                "device all",
+                DeviceConfig::new("/dev/ttyUSB0")
+                    .bps(115200)
+                    .parity(types::Parity::No)
+                    .stopbits(1)
+                    .cycle(1.0)
+                    .native(true)
+                    .device()
+                    .clone(),
+            ),
+        ]
+    }
+
+    /// The commands sent to GPSD are a compatibility contract, so any change
+    /// to them must show up in `snapshots/requests.txt`. Run with
+    /// `UPDATE_SNAPSHOTS=1` to rewrite the file after an intended change.
+    #[test]
+    fn test_request_snapshots() {
+        let mut snapshot = String::new();
+        for (label, request) in snapshot_requests() {
+            let command = request.to_command().unwrap();
+            snapshot.push_str(&format!("{label}: {command}\n"));
+        }
+
+        // Read at runtime, a compiled-in copy would still hold the old file
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/protocol/v3/snapshots/requests.txt"
+        );
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(path, &snapshot).unwrap();
         }
+        assert_eq!(snapshot, std::fs::read_to_string(path).unwrap());
     }
 }
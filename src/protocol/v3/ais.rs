@@ -0,0 +1,855 @@
+//! AIS (Automatic Identification System) reports
+//!
+//! GPSD decodes AIVDM/AIVDO sentences received from AIS transponders and
+//! reports them as JSON objects of class `AIS`. Every report carries the
+//! AIS message type and the MMSI of the sender; the remaining fields
+//! depend on the message type and are represented by [`AisData`].
+//!
+//! Units of numeric fields depend on the `scaled` setting of the WATCH
+//! command. Unscaled reports carry the raw values of the AIS message,
+//! e.g. positions in 1/10000 minutes and speeds in 1/10 knots, while
+//! scaled reports carry degrees and knots.
+//!
+//! Reference: [ais_json.c](https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/ais_json.c)
+//!
+//! [`AisData`]: crate::protocol::v3::ais::AisData
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{JsonMap, Timestamp, geo::Position};
+
+/// Unscaled positions are in 1/10000 minutes
+const POSITION_DIVISOR: f64 = 600_000.0;
+/// Unscaled speeds and courses are in tenths
+const TENTHS_DIVISOR: f64 = 10.0;
+/// Speed over ground marking "not available", in knots
+const SPEED_NOT_AVAILABLE: f64 = 102.3;
+/// Course over ground marking "not available", in degrees
+const COURSE_NOT_AVAILABLE: f64 = 360.0;
+/// True heading marking "not available"
+const HEADING_NOT_AVAILABLE: u16 = 511;
+
+/// AIS report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ais {
+    /// Device path that provided this data
+    pub device: Option<String>,
+    /// AIS message type (1-27)
+    pub msg_type: u8,
+    /// Repeat indicator
+    pub repeat: Option<u8>,
+    /// MMSI of the sender
+    pub mmsi: u32,
+    /// True if numeric fields are scaled to conventional units
+    pub scaled: Option<bool>,
+    /// Type specific content
+    pub data: AisData,
+}
+
+impl Ais {
+    /// Returns true if numeric fields are scaled to conventional units
+    pub fn is_scaled(&self) -> bool {
+        self.scaled.unwrap_or(false)
+    }
+
+    /// Returns the class A position report of message types 1, 2 and 3
+    pub fn position_report(&self) -> Option<&PositionReport> {
+        match &self.data {
+            AisData::Position(report) => Some(report),
+            _ => None,
+        }
+    }
+
+    /// Returns the static and voyage related data of message type 5
+    pub fn static_and_voyage(&self) -> Option<&StaticAndVoyageData> {
+        match &self.data {
+            AisData::StaticAndVoyage(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns the class B position report of message type 18
+    pub fn class_b_position(&self) -> Option<&ClassBPositionReport> {
+        match &self.data {
+            AisData::ClassBPosition(report) => Some(report),
+            _ => None,
+        }
+    }
+
+    /// Returns the extended class B position report of message type 19
+    pub fn class_b_extended(&self) -> Option<&ClassBExtendedReport> {
+        match &self.data {
+            AisData::ClassBExtended(report) => Some(report),
+            _ => None,
+        }
+    }
+
+    /// Returns the class B static data of message type 24
+    ///
+    /// Depending on the `split24` WATCH option this is part A, part B or
+    /// both merged; see [`StaticDataAssembler`].
+    pub fn static_data(&self) -> Option<&StaticDataReport> {
+        match &self.data {
+            AisData::StaticData(report) => Some(report),
+            _ => None,
+        }
+    }
+}
+
+/// Type specific content of an AIS report
+#[derive(Debug, Clone, PartialEq)]
+pub enum AisData {
+    /// Class A position report (types 1, 2 and 3)
+    Position(PositionReport),
+    /// Base station report or UTC/date response (types 4 and 11)
+    BaseStation(BaseStationReport),
+    /// Class A static and voyage related data (type 5)
+    StaticAndVoyage(StaticAndVoyageData),
+    /// Standard search and rescue aircraft position report (type 9)
+    SarAircraft(SarAircraftReport),
+    /// Addressed or broadcast safety related message (types 12 and 14)
+    Safety(SafetyMessage),
+    /// Standard class B position report (type 18)
+    ClassBPosition(ClassBPositionReport),
+    /// Extended class B position report (type 19)
+    ClassBExtended(ClassBExtendedReport),
+    /// Aid-to-navigation report (type 21)
+    AidToNavigation(AidToNavigationReport),
+    /// Class B static data report (type 24)
+    StaticData(StaticDataReport),
+    /// Long range position report (type 27)
+    LongRange(LongRangeReport),
+    /// Other message types, with the fields as reported
+    Other(JsonMap),
+}
+
+/// Rate of turn of a class A vessel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnRate {
+    /// Rate of turn; degrees per minute when scaled, raw ROT indicator otherwise
+    Rate(f64),
+    /// Turning left at more than 5 degrees per 30 seconds
+    FastLeft,
+    /// Turning right at more than 5 degrees per 30 seconds
+    FastRight,
+    /// No turn information available
+    NotAvailable,
+}
+
+/// Navigation status of a class A vessel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NavigationStatus {
+    /// Under way using engine
+    UnderWayUsingEngine,
+    /// At anchor
+    AtAnchor,
+    /// Not under command
+    NotUnderCommand,
+    /// Restricted manoeuverability
+    RestrictedManoeuverability,
+    /// Constrained by her draught
+    ConstrainedByDraught,
+    /// Moored
+    Moored,
+    /// Aground
+    Aground,
+    /// Engaged in fishing
+    EngagedInFishing,
+    /// Under way sailing
+    UnderWaySailing,
+    /// AIS-SART, MOB-AIS or EPIRB-AIS active
+    SartActive,
+    /// Not defined (default)
+    NotDefined,
+    /// Code reserved for future use (9 to 13)
+    Reserved(u8),
+}
+
+impl NavigationStatus {
+    /// Converts a navigation status code
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0 => NavigationStatus::UnderWayUsingEngine,
+            1 => NavigationStatus::AtAnchor,
+            2 => NavigationStatus::NotUnderCommand,
+            3 => NavigationStatus::RestrictedManoeuverability,
+            4 => NavigationStatus::ConstrainedByDraught,
+            5 => NavigationStatus::Moored,
+            6 => NavigationStatus::Aground,
+            7 => NavigationStatus::EngagedInFishing,
+            8 => NavigationStatus::UnderWaySailing,
+            14 => NavigationStatus::SartActive,
+            15 => NavigationStatus::NotDefined,
+            code => NavigationStatus::Reserved(code),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TurnRate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawTurn {
+            Rate(f64),
+            Text(String),
+        }
+
+        match RawTurn::deserialize(deserializer)? {
+            RawTurn::Rate(rate) => Ok(TurnRate::Rate(rate)),
+            RawTurn::Text(text) => match text.as_str() {
+                "fastleft" => Ok(TurnRate::FastLeft),
+                "fastright" => Ok(TurnRate::FastRight),
+                "nan" => Ok(TurnRate::NotAvailable),
+                _ => Err(serde::de::Error::custom("Invalid value for 'turn' field")),
+            },
+        }
+    }
+}
+
+/// Ship dimensions relative to the position reference point, in meters
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub struct Dimensions {
+    /// Distance to the bow
+    pub to_bow: Option<u16>,
+    /// Distance to the stern
+    pub to_stern: Option<u16>,
+    /// Distance to the port side
+    pub to_port: Option<u16>,
+    /// Distance to the starboard side
+    pub to_starboard: Option<u16>,
+}
+
+/// Class A position report (types 1, 2 and 3)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PositionReport {
+    /// Navigation status code
+    pub status: Option<u8>,
+    /// Navigation status as text (scaled reports)
+    pub status_text: Option<String>,
+    /// Rate of turn
+    pub turn: Option<TurnRate>,
+    /// Speed over ground
+    pub speed: Option<f64>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Course over ground
+    pub course: Option<f64>,
+    /// True heading in degrees (511 if not available)
+    pub heading: Option<u16>,
+    /// UTC second of the report
+    pub second: Option<u8>,
+    /// Special maneuver indicator
+    pub maneuver: Option<u8>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// Radio status
+    pub radio: Option<u32>,
+}
+
+impl PositionReport {
+    /// Returns the navigation status
+    pub fn navigation_status(&self) -> Option<NavigationStatus> {
+        self.status.map(NavigationStatus::from_code)
+    }
+}
+
+/// Base station report or UTC/date response (types 4 and 11)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BaseStationReport {
+    /// UTC time of the report as `YYYY-MM-DDTHH:MM:SSZ`
+    pub timestamp: Option<String>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Type of the position fixing device
+    pub epfd: Option<u8>,
+    /// Type of the position fixing device as text
+    pub epfd_text: Option<String>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// Radio status
+    pub radio: Option<u32>,
+}
+
+/// Class A static and voyage related data (type 5)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StaticAndVoyageData {
+    /// IMO ship identification number
+    pub imo: Option<u32>,
+    /// AIS version indicator
+    pub ais_version: Option<u8>,
+    /// Call sign
+    pub callsign: Option<String>,
+    /// Vessel name
+    pub shipname: Option<String>,
+    /// Ship type code
+    pub shiptype: Option<u8>,
+    /// Ship type as text
+    pub shiptype_text: Option<String>,
+    /// Ship dimensions
+    #[serde(flatten)]
+    pub dimensions: Dimensions,
+    /// Type of the position fixing device
+    pub epfd: Option<u8>,
+    /// Type of the position fixing device as text
+    pub epfd_text: Option<String>,
+    /// Estimated time of arrival as `MM-DDTHH:MMZ`
+    pub eta: Option<String>,
+    /// Draught
+    pub draught: Option<f64>,
+    /// Destination
+    pub destination: Option<String>,
+    /// True if data terminal equipment is not ready
+    pub dte: Option<u8>,
+}
+
+impl StaticAndVoyageData {
+    /// Returns the estimated time of arrival, or `None` if not available
+    pub fn eta(&self) -> Option<Eta> {
+        self.eta.as_deref().and_then(Eta::parse)
+    }
+
+    /// Returns the draught in meters, or `None` if not available
+    ///
+    /// `scaled` is the `scaled` flag of the enclosing [`Ais`] report.
+    pub fn draught_meters(&self, scaled: bool) -> Option<f64> {
+        let divisor = if scaled { 1.0 } else { TENTHS_DIVISOR };
+        self.draught
+            .map(|draught| draught / divisor)
+            .filter(|&draught| draught > 0.0)
+    }
+}
+
+/// Estimated time of arrival of a vessel, in UTC
+///
+/// AIS does not transmit the year of the ETA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Eta {
+    /// Month (1-12)
+    pub month: u32,
+    /// Day of month (1-31)
+    pub day: u32,
+    /// Hour (0-23)
+    pub hour: u32,
+    /// Minute (0-59)
+    pub minute: u32,
+}
+
+impl Eta {
+    /// Parses an ETA in the `MM-DDTHH:MMZ` form reported by GPSD
+    ///
+    /// Returns `None` if the month or day is not available. An unavailable
+    /// hour or minute is taken as zero.
+    pub fn parse(eta: &str) -> Option<Self> {
+        let (date, time) = eta.trim_end_matches('Z').split_once('T')?;
+        let (month, day) = date.split_once('-')?;
+        let (hour, minute) = time.split_once(':')?;
+        let eta = Eta {
+            month: month.parse().ok()?,
+            day: day.parse().ok()?,
+            hour: hour.parse::<u32>().ok().filter(|&h| h < 24).unwrap_or(0),
+            minute: minute.parse::<u32>().ok().filter(|&m| m < 60).unwrap_or(0),
+        };
+        (eta.month > 0 && eta.day > 0).then_some(eta)
+    }
+
+    /// Returns the first matching time at or after `now`
+    ///
+    /// Looks at the year of `now` and the following year, since an ETA
+    /// is always in the future when it is sent.
+    pub fn resolve(&self, now: Timestamp) -> Option<Timestamp> {
+        (now.year()..=now.year() + 1)
+            .filter_map(|year| {
+                Utc.with_ymd_and_hms(year, self.month, self.day, self.hour, self.minute, 0)
+                    .single()
+            })
+            .find(|eta| *eta >= now)
+    }
+}
+
+impl Dimensions {
+    /// Returns the overall length in meters, if known
+    pub fn length(&self) -> Option<u16> {
+        Some(self.to_bow? + self.to_stern?).filter(|&length| length > 0)
+    }
+
+    /// Returns the overall beam in meters, if known
+    pub fn beam(&self) -> Option<u16> {
+        Some(self.to_port? + self.to_starboard?).filter(|&beam| beam > 0)
+    }
+}
+
+/// Standard search and rescue aircraft position report (type 9)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SarAircraftReport {
+    /// Altitude in meters
+    pub alt: Option<u16>,
+    /// Speed over ground
+    pub speed: Option<f64>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Course over ground
+    pub course: Option<f64>,
+    /// UTC second of the report
+    pub second: Option<u8>,
+    /// Regional reserved bits
+    pub regional: Option<u8>,
+    /// True if data terminal equipment is not ready
+    pub dte: Option<u8>,
+    /// True if assigned mode is active
+    pub assigned: Option<bool>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// Radio status
+    pub radio: Option<u32>,
+}
+
+/// Addressed or broadcast safety related message (types 12 and 14)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SafetyMessage {
+    /// Sequence number (type 12)
+    pub seqno: Option<u8>,
+    /// MMSI of the addressee (type 12)
+    pub dest_mmsi: Option<u32>,
+    /// True if the message was retransmitted (type 12)
+    pub retransmit: Option<bool>,
+    /// Message text
+    pub text: Option<String>,
+}
+
+/// Standard class B position report (type 18)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ClassBPositionReport {
+    /// Speed over ground
+    pub speed: Option<f64>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Course over ground
+    pub course: Option<f64>,
+    /// True heading in degrees (511 if not available)
+    pub heading: Option<u16>,
+    /// UTC second of the report
+    pub second: Option<u8>,
+    /// Regional reserved bits
+    pub regional: Option<u8>,
+    /// True if the unit is a carrier sense (CS) unit
+    pub cs: Option<bool>,
+    /// True if the unit has a display
+    pub display: Option<bool>,
+    /// True if the unit has DSC capability
+    pub dsc: Option<bool>,
+    /// True if the unit can use the whole marine band
+    pub band: Option<bool>,
+    /// True if the unit accepts channel assignment by message 22
+    pub msg22: Option<bool>,
+    /// True if assigned mode is active
+    pub assigned: Option<bool>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// Radio status
+    pub radio: Option<u32>,
+}
+
+/// Extended class B position report (type 19)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ClassBExtendedReport {
+    /// Speed over ground
+    pub speed: Option<f64>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Course over ground
+    pub course: Option<f64>,
+    /// True heading in degrees (511 if not available)
+    pub heading: Option<u16>,
+    /// UTC second of the report
+    pub second: Option<u8>,
+    /// Regional reserved bits
+    pub regional: Option<u8>,
+    /// Vessel name
+    pub shipname: Option<String>,
+    /// Ship type code
+    pub shiptype: Option<u8>,
+    /// Ship type as text
+    pub shiptype_text: Option<String>,
+    /// Ship dimensions
+    #[serde(flatten)]
+    pub dimensions: Dimensions,
+    /// Type of the position fixing device
+    pub epfd: Option<u8>,
+    /// Type of the position fixing device as text
+    pub epfd_text: Option<String>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// True if data terminal equipment is not ready
+    pub dte: Option<u8>,
+    /// True if assigned mode is active
+    pub assigned: Option<bool>,
+}
+
+/// Aid-to-navigation report (type 21)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AidToNavigationReport {
+    /// Type of the aid to navigation
+    pub aid_type: Option<u8>,
+    /// Type of the aid to navigation as text
+    pub aid_type_text: Option<String>,
+    /// Name of the aid to navigation
+    pub name: Option<String>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// Dimensions of the aid
+    #[serde(flatten)]
+    pub dimensions: Dimensions,
+    /// Type of the position fixing device
+    pub epfd: Option<u8>,
+    /// Type of the position fixing device as text
+    pub epfd_text: Option<String>,
+    /// UTC second of the report
+    pub second: Option<u8>,
+    /// True if the aid is off its charted position
+    pub off_position: Option<bool>,
+    /// Regional reserved bits
+    pub regional: Option<u8>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// True if the aid is virtual
+    pub virtual_aid: Option<bool>,
+    /// True if assigned mode is active
+    pub assigned: Option<bool>,
+}
+
+/// Class B static data report (type 24)
+///
+/// The report is split in part A (vessel name) and part B (the other
+/// fields) if the `split24` WATCH option is enabled.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StaticDataReport {
+    /// Part number (0 for part A, 1 for part B)
+    pub partno: Option<u8>,
+    /// Vessel name
+    pub shipname: Option<String>,
+    /// Ship type code
+    pub shiptype: Option<u8>,
+    /// Ship type as text
+    pub shiptype_text: Option<String>,
+    /// Vendor ID
+    pub vendorid: Option<String>,
+    /// Unit model code
+    pub model: Option<u8>,
+    /// Serial number
+    pub serial: Option<u32>,
+    /// Call sign
+    pub callsign: Option<String>,
+    /// MMSI of the mother ship, for auxiliary craft
+    pub mothership_mmsi: Option<u32>,
+    /// Ship dimensions
+    #[serde(flatten)]
+    pub dimensions: Dimensions,
+}
+
+impl StaticDataReport {
+    /// Returns true if the report carries the fields of both parts
+    ///
+    /// GPSD merges the parts itself unless the `split24` WATCH option is
+    /// enabled.
+    pub fn is_complete(&self) -> bool {
+        self.shipname.is_some() && self.shiptype.is_some()
+    }
+
+    /// Fills the fields missing in this report from another part
+    fn merge(&mut self, other: StaticDataReport) {
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(if self.$field.is_none() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+        merge!(
+            shipname,
+            shiptype,
+            shiptype_text,
+            vendorid,
+            model,
+            serial,
+            callsign,
+            mothership_mmsi
+        );
+        if self.dimensions == Dimensions::default() {
+            self.dimensions = other.dimensions;
+        }
+        self.partno = None;
+    }
+}
+
+/// Assembles class B static data from split type 24 reports
+///
+/// With the `split24` WATCH option enabled, GPSD reports part A (vessel
+/// name) and part B (ship type, call sign and dimensions) of a type 24
+/// message separately. The assembler keeps the first part per MMSI and
+/// returns the merged report once the other part arrives. Reports that
+/// GPSD already merged are returned unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct StaticDataAssembler {
+    pending: HashMap<u32, StaticDataReport>,
+}
+
+impl StaticDataAssembler {
+    /// Creates an assembler without pending parts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes an AIS report
+    ///
+    /// Returns the complete static data once both parts of a vessel were
+    /// seen. Reports of other message types are ignored.
+    pub fn update(&mut self, ais: &Ais) -> Option<StaticDataReport> {
+        let report = ais.static_data()?.clone();
+        if report.is_complete() {
+            self.pending.remove(&ais.mmsi);
+            return Some(report);
+        }
+
+        match self.pending.remove(&ais.mmsi) {
+            Some(mut pending) if pending.partno != report.partno => {
+                pending.merge(report);
+                Some(pending)
+            }
+            _ => {
+                self.pending.insert(ais.mmsi, report);
+                None
+            }
+        }
+    }
+
+    /// Returns the number of vessels with a pending part
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Discards all pending parts
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Long range position report (type 27)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LongRangeReport {
+    /// True if the position accuracy is better than 10 meters
+    pub accuracy: Option<bool>,
+    /// True if RAIM is in use
+    pub raim: Option<bool>,
+    /// Navigation status code
+    pub status: Option<u8>,
+    /// Navigation status as text (scaled reports)
+    pub status_text: Option<String>,
+    /// Longitude
+    pub lon: Option<f64>,
+    /// Latitude
+    pub lat: Option<f64>,
+    /// Speed over ground
+    pub speed: Option<f64>,
+    /// Course over ground
+    pub course: Option<f64>,
+    /// True if the position is not GNSS based
+    pub gnss: Option<bool>,
+}
+
+/// Implements unit-normalized accessors of reports carrying a position,
+/// speed, course and heading
+macro_rules! impl_kinematics {
+    ($($report:ty),*) => {$(
+        impl $report {
+            /// Returns the position in degrees, or `None` if not available
+            ///
+            /// `scaled` is the `scaled` flag of the enclosing [`Ais`] report.
+            pub fn position(&self, scaled: bool) -> Option<Position> {
+                let divisor = if scaled { 1.0 } else { POSITION_DIVISOR };
+                let lat = self.lat? / divisor;
+                let lon = self.lon? / divisor;
+                (lat.abs() <= 90.0 && lon.abs() <= 180.0).then(|| Position::new(lat, lon))
+            }
+
+            /// Returns the speed over ground in knots, or `None` if not available
+            ///
+            /// `scaled` is the `scaled` flag of the enclosing [`Ais`] report.
+            pub fn speed_over_ground(&self, scaled: bool) -> Option<f64> {
+                let divisor = if scaled { 1.0 } else { TENTHS_DIVISOR };
+                self.speed
+                    .map(|speed| speed / divisor)
+                    .filter(|&speed| speed < SPEED_NOT_AVAILABLE)
+            }
+
+            /// Returns the course over ground in degrees, or `None` if not available
+            ///
+            /// `scaled` is the `scaled` flag of the enclosing [`Ais`] report.
+            pub fn course_over_ground(&self, scaled: bool) -> Option<f64> {
+                let divisor = if scaled { 1.0 } else { TENTHS_DIVISOR };
+                self.course
+                    .map(|course| course / divisor)
+                    .filter(|&course| course < COURSE_NOT_AVAILABLE)
+            }
+
+            /// Returns the true heading in degrees, or `None` if not available
+            pub fn true_heading(&self) -> Option<u16> {
+                self.heading
+                    .filter(|&heading| heading != HEADING_NOT_AVAILABLE)
+            }
+        }
+    )*};
+}
+
+impl_kinematics!(PositionReport, ClassBPositionReport, ClassBExtendedReport);
+
+impl<'de> Deserialize<'de> for Ais {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawHeader {
+            device: Option<String>,
+            #[serde(rename = "type")]
+            msg_type: u8,
+            repeat: Option<u8>,
+            mmsi: u32,
+            scaled: Option<bool>,
+        }
+
+        fn content<'a, T: Deserialize<'a>, E: serde::de::Error>(value: &'a Value) -> Result<T, E> {
+            T::deserialize(value).map_err(E::custom)
+        }
+
+        let value = Value::deserialize(deserializer)?;
+        let header: RawHeader = content(&value)?;
+
+        let data = match header.msg_type {
+            1..=3 => AisData::Position(content(&value)?),
+            4 | 11 => AisData::BaseStation(content(&value)?),
+            5 => AisData::StaticAndVoyage(content(&value)?),
+            9 => AisData::SarAircraft(content(&value)?),
+            12 | 14 => AisData::Safety(content(&value)?),
+            18 => AisData::ClassBPosition(content(&value)?),
+            19 => AisData::ClassBExtended(content(&value)?),
+            21 => AisData::AidToNavigation(content(&value)?),
+            24 => AisData::StaticData(content(&value)?),
+            27 => AisData::LongRange(content(&value)?),
+            _ => {
+                let mut fields = match value {
+                    Value::Object(fields) => fields,
+                    _ => Map::new(),
+                };
+                for key in ["class", "device", "type", "repeat", "mmsi", "scaled"] {
+                    fields.remove(key);
+                }
+                AisData::Other(fields)
+            }
+        };
+
+        Ok(Ais {
+            device: header.device,
+            msg_type: header.msg_type,
+            repeat: header.repeat,
+            mmsi: header.mmsi,
+            scaled: header.scaled,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proto_v3_ais_reports() {
+        let json = r#"{"class":"AIS","device":"stdin","type":1,"repeat":0,"mmsi":244670316,"scaled":true,"status":0,"status_text":"Under way using engine","turn":"fastright","speed":10.2,"accuracy":true,"lon":4.7163,"lat":52.8013,"course":184.6,"heading":511,"second":37,"maneuver":0,"raim":false,"radio":34017}"#;
+        let ais: Ais = serde_json::from_str(json).unwrap();
+        assert_eq!(ais.mmsi, 244670316);
+        let AisData::Position(report) = ais.data else {
+            panic!("unexpected data: {:?}", ais.data);
+        };
+        assert_eq!(report.turn, Some(TurnRate::FastRight));
+        assert_eq!(report.position(true), Some(Position::new(52.8013, 4.7163)));
+        assert_eq!(report.true_heading(), None);
+
+        // Unscaled class A report of a moored vessel
+        let json = r#"{"class":"AIS","type":3,"repeat":0,"mmsi":366892000,"status":5,"turn":0,"speed":0,"accuracy":false,"lon":-73526640,"lat":22483440,"course":3600,"heading":87,"second":16,"maneuver":0,"raim":false,"radio":0}"#;
+        let ais: Ais = serde_json::from_str(json).unwrap();
+        let report = ais.position_report().unwrap();
+        assert_eq!(report.navigation_status(), Some(NavigationStatus::Moored));
+        assert_eq!(report.speed_over_ground(ais.is_scaled()), Some(0.0));
+        assert_eq!(report.course_over_ground(ais.is_scaled()), None);
+        let position = report.position(ais.is_scaled()).unwrap();
+        assert!((position.lat - 37.4724).abs() < 1e-9);
+        assert_eq!(report.true_heading(), Some(87));
+
+        let json = r#"{"class":"AIS","type":5,"repeat":0,"mmsi":351759000,"scaled":true,"imo":9134270,"ais_version":0,"callsign":"3FOF8","shipname":"EVER DIADEM","shiptype":70,"shiptype_text":"Cargo, all ships of this type","to_bow":225,"to_stern":70,"to_port":1,"to_starboard":31,"epfd":1,"epfd_text":"GPS","eta":"05-15T14:00Z","draught":12.2,"destination":"NEW YORK","dte":0}"#;
+        let ais: Ais = serde_json::from_str(json).unwrap();
+        let data = ais.static_and_voyage().unwrap();
+        assert_eq!(data.shipname.as_deref(), Some("EVER DIADEM"));
+        assert_eq!(data.dimensions.length(), Some(295));
+        assert_eq!(data.dimensions.beam(), Some(32));
+        assert_eq!(data.draught_meters(ais.is_scaled()), Some(12.2));
+        let now = "2025-12-01T00:00:00Z".parse().unwrap();
+        let eta = data.eta().unwrap().resolve(now).unwrap();
+        assert_eq!(eta.to_rfc3339(), "2026-05-15T14:00:00+00:00");
+
+        // Split type 24 parts
+        let mut assembler = StaticDataAssembler::new();
+        let part_a = r#"{"class":"AIS","type":24,"repeat":0,"mmsi":271041815,"scaled":true,"partno":0,"shipname":"PROGUY"}"#;
+        let part_b = r#"{"class":"AIS","type":24,"repeat":0,"mmsi":271041815,"scaled":true,"partno":1,"shiptype":60,"shiptype_text":"Passenger","vendorid":"1D00014","model":0,"serial":0,"callsign":"TC6163","to_bow":0,"to_stern":15,"to_port":0,"to_starboard":5}"#;
+        assert_eq!(
+            assembler.update(&serde_json::from_str(part_a).unwrap()),
+            None
+        );
+        assert_eq!(assembler.pending(), 1);
+        let merged = assembler
+            .update(&serde_json::from_str(part_b).unwrap())
+            .unwrap();
+        assert!(merged.is_complete());
+        assert_eq!(merged.shipname.as_deref(), Some("PROGUY"));
+        assert_eq!(merged.dimensions.length(), Some(15));
+        assert_eq!(assembler.pending(), 0);
+
+        let json = r#"{"class":"AIS","type":8,"repeat":0,"mmsi":2655651,"dac":265,"fid":1,"data":"184:8c0"}"#;
+        let ais: Ais = serde_json::from_str(json).unwrap();
+        let AisData::Other(fields) = ais.data else {
+            panic!("unexpected data: {:?}", ais.data);
+        };
+        assert_eq!(fields["dac"], 265);
+        assert!(!fields.contains_key("mmsi"));
+    }
+}
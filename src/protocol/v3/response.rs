@@ -12,12 +12,15 @@
 //! - DEVICE/DEVICES: GPS receiver information
 //! - VERSION: GPSD daemon version information
 //!
-//! All timestamps use the ISO 8601 format and are represented as `DateTime<Utc>`.
+//! All timestamps use the ISO 8601 format and are represented as `Timestamp`.
 
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
 use super::types::*;
+use crate::{JsonValue, Timestamp};
+
+pub use super::rtcm::{Rtcm2, Rtcm3};
 
 /// Time-Position-Velocity (TPV) report
 ///
@@ -96,7 +99,7 @@ pub struct Tpv {
     /// Temperature in degrees Celsius
     pub temp: Option<f64>,
     /// GPS time of fix
-    pub time: Option<DateTime<Utc>>,
+    pub time: Option<Timestamp>,
     /// True track (course over ground) in degrees
     pub track: Option<f64>,
     /// Spherical error probability in meters
@@ -120,13 +123,13 @@ pub struct Tpv {
     /// Reception time (when enabled by timing policy)
     #[serde(rename = "rtime")]
     #[serde(default, deserialize_with = "f64_to_datetime")]
-    pub rtime: Option<DateTime<Utc>>,
+    pub rtime: Option<Timestamp>,
     /// PPS edge time (when enabled by timing policy)
     #[serde(default, deserialize_with = "f64_to_datetime")]
-    pub pps: Option<DateTime<Utc>>,
+    pub pps: Option<Timestamp>,
     /// Start of response time (when enabled by timing policy)
     #[serde(default, deserialize_with = "f64_to_datetime")]
-    pub sor: Option<DateTime<Utc>>,
+    pub sor: Option<Timestamp>,
     /// Character count in the sentence
     pub chars: Option<u64>,
     /// Number of satellites used in solution
@@ -140,7 +143,7 @@ pub struct Tpv {
     #[cfg(feature = "extra-fields")]
     /// Additional fields not explicitly defined
     #[serde(flatten)]
-    extra: std::collections::HashMap<String, serde_json::Value>,
+    extra: std::collections::HashMap<String, crate::JsonValue>,
 }
 
 /// Satellite Sky View (SKY) report
@@ -155,7 +158,7 @@ pub struct Sky {
     #[serde(flatten)]
     pub dop: Dop,
     /// GPS time of this sky view
-    pub time: Option<DateTime<Utc>>,
+    pub time: Option<Timestamp>,
     /// Number of satellites visible
     #[serde(rename = "nSat")]
     pub n_sat: Option<i32>,
@@ -167,7 +170,7 @@ pub struct Sky {
     #[cfg(feature = "extra-fields")]
     /// Additional fields not explicitly defined
     #[serde(flatten)]
-    extra: std::collections::HashMap<String, serde_json::Value>,
+    extra: std::collections::HashMap<String, crate::JsonValue>,
 }
 
 /// GPS Pseudorange Error Statistics (GST)
@@ -181,7 +184,7 @@ pub struct Gst {
     /// Device path that provided this data
     pub device: Option<String>,
     /// GPS time of these statistics
-    pub time: Option<DateTime<Utc>>,
+    pub time: Option<Timestamp>,
     /// Altitude error in meters (1-sigma)
     pub alt: Option<f64>,
     /// Latitude error in meters (1-sigma)
@@ -205,50 +208,84 @@ pub struct Gst {
     #[cfg(feature = "extra-fields")]
     /// Additional fields not explicitly defined
     #[serde(flatten)]
-    extra: std::collections::HashMap<String, serde_json::Value>,
+    extra: std::collections::HashMap<String, crate::JsonValue>,
 }
 
 /// Attitude/orientation data
 ///
-/// Reports the orientation of the device in 3D space.
-/// Currently a placeholder for future implementation.
+/// Reports the orientation of the device in 3D space, as measured by
+/// compasses, inclinometers and IMU-equipped receivers. Angles are in
+/// degrees; each status code reports the alarm state of the reading.
+///
 /// Reference: [json_att_read](https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/libgps_json.c?ref_type=heads#L404)
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Attitude {
+    /// Device path that provided this data
     pub device: Option<String>,
+    /// Time of the measurement
+    pub time: Option<Timestamp>,
+    /// Device specific time tag of the measurement
+    #[serde(rename = "timeTag")]
+    pub time_tag: Option<u64>,
+    /// Heading in degrees from true north
+    pub heading: Option<f64>,
+    /// Heading in degrees from magnetic north
+    pub mheading: Option<f64>,
+    /// Magnetometer status
+    pub mag_st: Option<StatusCode>,
+    /// Pitch in degrees
+    pub pitch: Option<f64>,
+    /// Pitch sensor status
+    pub pitch_st: Option<StatusCode>,
+    /// Roll in degrees
+    pub roll: Option<f64>,
+    /// Roll sensor status
+    pub roll_st: Option<StatusCode>,
+    /// Yaw in degrees
+    pub yaw: Option<f64>,
+    /// Yaw sensor status
+    pub yaw_st: Option<StatusCode>,
+    /// Rate of turn in degrees per minute
+    pub rot: Option<f64>,
+    /// Magnetic dip in degrees
+    pub dip: Option<f64>,
+    /// Magnetic field strength
+    pub mag_len: Option<f64>,
+    /// Magnetic field X component
+    pub mag_x: Option<f64>,
+    /// Magnetic field Y component
+    pub mag_y: Option<f64>,
+    /// Magnetic field Z component
+    pub mag_z: Option<f64>,
+    /// Acceleration magnitude
     pub acc_len: Option<f64>,
+    /// Acceleration X component
     pub acc_x: Option<f64>,
+    /// Acceleration Y component
     pub acc_y: Option<f64>,
+    /// Acceleration Z component
     pub acc_z: Option<f64>,
-    #[serde(flatten)]
-    pub base: Baseline,
-    pub depth: Option<f64>,
-    pub dip: Option<f64>,
-    // pub gyro_temp: Option<f64>,
+    /// Gyroscope temperature in degrees Celsius
+    pub gyro_temp: Option<f64>,
+    /// Gyroscope X axis rate in degrees per second
     pub gyro_x: Option<f64>,
+    /// Gyroscope Y axis rate in degrees per second
     pub gyro_y: Option<f64>,
+    /// Gyroscope Z axis rate in degrees per second
     pub gyro_z: Option<f64>,
-    pub heading: Option<f64>,
-    pub mag_len: Option<f64>,
-    pub mag_x: Option<f64>,
-    pub mag_y: Option<f64>,
-    pub mag_z: Option<f64>,
-    pub mheading: Option<f64>,
-    pub msg: Option<String>,
-    pub pitch_st: Option<StatusCode>,
-    pub pitch: Option<f64>,
-    pub roll_st: Option<StatusCode>,
-    pub roll: Option<f64>,
+    /// Water depth in meters
+    pub depth: Option<f64>,
+    /// Temperature at the sensor in degrees Celsius
     pub temp: Option<f64>,
-    pub time: Option<DateTime<Utc>>,
-    #[serde(rename = "timeTag")]
-    pub time_tag: Option<String>,
-    pub yaw_st: Option<StatusCode>,
-    pub yaw: Option<f64>,
+    /// Vendor specific message
+    pub msg: Option<String>,
+    /// RTK baseline of dual antenna heading receivers (flattened)
+    #[serde(flatten)]
+    pub base: Baseline,
     #[cfg(feature = "extra-fields")]
     /// Additional fields not explicitly defined
     #[serde(flatten)]
-    extra: std::collections::HashMap<String, serde_json::Value>,
+    extra: std::collections::HashMap<String, crate::JsonValue>,
 }
 
 /// Inertial Measurement Unit data
@@ -281,7 +318,7 @@ pub struct Imu {
     pub roll_st: Option<StatusCode>,
     pub roll: Option<f64>,
     pub temp: Option<f64>,
-    pub time: Option<DateTime<Utc>>,
+    pub time: Option<Timestamp>,
     #[serde(rename = "timeTag")]
     pub time_tag: Option<String>,
     pub yaw_st: Option<StatusCode>,
@@ -289,9 +326,36 @@ pub struct Imu {
     #[cfg(feature = "extra-fields")]
     /// Additional fields not explicitly defined
     #[serde(flatten)]
-    extra: std::collections::HashMap<String, serde_json::Value>,
+    extra: std::collections::HashMap<String, crate::JsonValue>,
+}
+
+/// Implements access to the fields kept by the `extra-fields` feature
+#[cfg(feature = "extra-fields")]
+macro_rules! impl_extra_fields {
+    ($($name:ident),*) => {
+        $(
+            impl $name {
+                /// Returns the fields of the report not explicitly defined,
+                /// such as vendor-specific fields passed through by GPSD
+                pub fn extra(&self) -> &std::collections::HashMap<String, JsonValue> {
+                    &self.extra
+                }
+
+                /// Returns an extra field converted to `T`
+                ///
+                /// Returns `None` if the field is missing or cannot be
+                /// converted.
+                pub fn get_extra<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+                    T::deserialize(self.extra.get(key)?).ok()
+                }
+            }
+        )*
+    };
 }
 
+#[cfg(feature = "extra-fields")]
+impl_extra_fields!(Tpv, Sky, Gst, Attitude, Imu);
+
 /// Time Offset report
 ///
 /// Reports the offset between system clock and GPS time.
@@ -302,9 +366,9 @@ pub struct TimeOffset {
     /// Device path that provided this data
     pub device: Option<String>,
     /// GPS time
-    pub real: Option<DateTime<Utc>>,
+    pub real: Option<Timestamp>,
     /// System clock time
-    pub clock: Option<DateTime<Utc>>,
+    pub clock: Option<Timestamp>,
 }
 
 impl<'de> Deserialize<'de> for TimeOffset {
@@ -339,9 +403,9 @@ pub struct Pps {
     /// Device path that provided this data
     pub device: Option<String>,
     /// GPS time of PPS edge
-    pub real: Option<DateTime<Utc>>,
+    pub real: Option<Timestamp>,
     /// System clock time of PPS edge
-    pub clock: Option<DateTime<Utc>>,
+    pub clock: Option<Timestamp>,
     /// Clock precision in nanoseconds
     pub precision: Option<i32>,
     /// Quantization error of PPS signal
@@ -505,7 +569,7 @@ pub struct Poll {
     /// Number of active devices
     active: Option<i32>,
     /// Timestamp of this poll
-    time: Option<DateTime<Utc>>,
+    time: Option<Timestamp>,
     /// TPV data from active devices
     tpv: Vec<Tpv>,
     /// GST data from active devices
@@ -523,25 +587,6 @@ pub struct Error {
     pub message: String,
 }
 
-/// RTCM2 differential correction data
-///
-/// Real Time Correction Messages version 2.
-/// Currently a placeholder for future implementation.
-#[derive(Debug, Clone, PartialEq, Deserialize)]
-pub struct Rtcm2 {}
-
-/// RTCM3 differential correction data
-///
-/// Real Time Correction Messages version 3.
-/// Currently a placeholder for future implementation.
-#[derive(Debug, Clone, PartialEq, Deserialize)]
-pub struct Rtcm3 {}
-
-// https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/libgps_json.c#L959
-// #[cfg(feature = "ais")]
-// #[derive(Debug, Clone, PartialEq, Deserialize)]
-// pub struct Aivdm {}
-
 /// Raw GPS receiver data
 ///
 /// Contains raw measurement data from the GPS receiver,
@@ -553,7 +598,7 @@ pub struct Raw {
     /// Device path that provided this data
     pub device: Option<String>,
     /// GPS time of these measurements
-    pub time: Option<DateTime<Utc>>,
+    pub time: Option<Timestamp>,
     /// Raw measurement data for each satellite
     pub rawdata: Vec<Measurement>,
 }
@@ -605,12 +650,16 @@ pub enum Message {
     /// Time-Position-Velocity report
     Tpv(Tpv),
     /// GPS pseudorange error statistics
+    #[cfg(feature = "msg-gst")]
     Gst(Gst),
     /// Satellite sky view report
+    #[cfg(feature = "msg-sky")]
     Sky(Sky),
     /// Attitude/orientation data
+    #[cfg(feature = "msg-att")]
     Att(Attitude),
     /// Inertial measurement unit data
+    #[cfg(feature = "msg-imu")]
     Imu(Imu),
     /// List of available GPS devices
     Devices(DeviceList),
@@ -621,33 +670,319 @@ pub enum Message {
     /// GPSD version information
     Version(Version),
     /// RTCM2 differential correction data
+    #[cfg(feature = "msg-rtcm")]
     Rtcm2(Rtcm2),
     /// RTCM3 differential correction data
+    #[cfg(feature = "msg-rtcm")]
     Rtcm3(Rtcm3),
-    // AIS vessel data (commented out)
-    // Ais(Aivdm),
+    /// AIS vessel data
+    #[cfg(feature = "ais")]
+    Ais(super::ais::Ais),
     /// Error message from GPSD
     Error(Error),
     /// Time offset report
+    #[cfg(feature = "msg-toff")]
     Toff(TimeOffset),
     /// Pulse-per-second timing report
+    #[cfg(feature = "msg-pps")]
     Pps(Pps),
     /// Oscillator/clock discipline status
+    #[cfg(feature = "msg-osc")]
     Osc(Oscillator),
     /// Raw GPS receiver data
+    #[cfg(feature = "msg-raw")]
     Raw(Raw),
     /// Poll response with current fixes
     Poll(Poll),
-    /// Unknown/unsupported message type
+    /// Message of a class not modeled by this crate
     #[serde(untagged)]
-    Other(String),
+    Other(UnknownMessage),
+}
+
+/// Message of a class not modeled by this crate
+///
+/// Keeps the whole JSON object so that applications can forward or log
+/// messages of newer GPSD releases. Messages of known classes that fail to
+/// decode are reported as errors instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownMessage {
+    /// Value of the `class` field
+    pub class: String,
+    /// The whole JSON object, including the `class` field
+    pub value: JsonValue,
+}
+
+impl<'de> Deserialize<'de> for UnknownMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let value = JsonValue::deserialize(deserializer)?;
+        let class = value
+            .get("class")
+            .and_then(JsonValue::as_str)
+            .ok_or_else(|| D::Error::missing_field("class"))?;
+        if is_known_class(class) {
+            return Err(D::Error::custom(format!("invalid {class} message")));
+        }
+
+        Ok(UnknownMessage {
+            class: class.to_string(),
+            value,
+        })
+    }
+}
+
+/// Returns true if messages of the class are decoded into a typed variant
+fn is_known_class(class: &str) -> bool {
+    Class::from_name(class).is_some()
+}
+
+/// Message class decoded into a typed [`Message`] variant
+///
+/// Used to select the messages a stream yields, see
+/// [`StreamOptions::classes`](crate::client::StreamOptions::classes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    /// Time-Position-Velocity report
+    Tpv,
+    /// GPS pseudorange error statistics
+    #[cfg(feature = "msg-gst")]
+    Gst,
+    /// Satellite sky view report
+    #[cfg(feature = "msg-sky")]
+    Sky,
+    /// Attitude/orientation data
+    #[cfg(feature = "msg-att")]
+    Att,
+    /// Inertial measurement unit data
+    #[cfg(feature = "msg-imu")]
+    Imu,
+    /// List of available GPS devices
+    Devices,
+    /// Single GPS device information
+    Device,
+    /// Current watch settings
+    Watch,
+    /// GPSD version information
+    Version,
+    /// RTCM2 differential correction data
+    #[cfg(feature = "msg-rtcm")]
+    Rtcm2,
+    /// RTCM3 differential correction data
+    #[cfg(feature = "msg-rtcm")]
+    Rtcm3,
+    /// AIS vessel data
+    #[cfg(feature = "ais")]
+    Ais,
+    /// Error message from GPSD
+    Error,
+    /// Time offset report
+    #[cfg(feature = "msg-toff")]
+    Toff,
+    /// Pulse-per-second timing report
+    #[cfg(feature = "msg-pps")]
+    Pps,
+    /// Oscillator/clock discipline status
+    #[cfg(feature = "msg-osc")]
+    Osc,
+    /// Raw GPS receiver data
+    #[cfg(feature = "msg-raw")]
+    Raw,
+    /// Poll response with current fixes
+    Poll,
+}
+
+impl Class {
+    /// Returns the value of the `class` field, e.g. `TPV`
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Tpv => "TPV",
+            #[cfg(feature = "msg-gst")]
+            Class::Gst => "GST",
+            #[cfg(feature = "msg-sky")]
+            Class::Sky => "SKY",
+            #[cfg(feature = "msg-att")]
+            Class::Att => "ATT",
+            #[cfg(feature = "msg-imu")]
+            Class::Imu => "IMU",
+            Class::Devices => "DEVICES",
+            Class::Device => "DEVICE",
+            Class::Watch => "WATCH",
+            Class::Version => "VERSION",
+            #[cfg(feature = "msg-rtcm")]
+            Class::Rtcm2 => "RTCM2",
+            #[cfg(feature = "msg-rtcm")]
+            Class::Rtcm3 => "RTCM3",
+            #[cfg(feature = "ais")]
+            Class::Ais => "AIS",
+            Class::Error => "ERROR",
+            #[cfg(feature = "msg-toff")]
+            Class::Toff => "TOFF",
+            #[cfg(feature = "msg-pps")]
+            Class::Pps => "PPS",
+            #[cfg(feature = "msg-osc")]
+            Class::Osc => "OSC",
+            #[cfg(feature = "msg-raw")]
+            Class::Raw => "RAW",
+            Class::Poll => "POLL",
+        }
+    }
+
+    /// Returns the class of a `class` field value, if it is decoded into a
+    /// typed variant
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "TPV" => Class::Tpv,
+            #[cfg(feature = "msg-gst")]
+            "GST" => Class::Gst,
+            #[cfg(feature = "msg-sky")]
+            "SKY" => Class::Sky,
+            #[cfg(feature = "msg-att")]
+            "ATT" => Class::Att,
+            #[cfg(feature = "msg-imu")]
+            "IMU" => Class::Imu,
+            "DEVICES" => Class::Devices,
+            "DEVICE" => Class::Device,
+            "WATCH" => Class::Watch,
+            "VERSION" => Class::Version,
+            #[cfg(feature = "msg-rtcm")]
+            "RTCM2" => Class::Rtcm2,
+            #[cfg(feature = "msg-rtcm")]
+            "RTCM3" => Class::Rtcm3,
+            #[cfg(feature = "ais")]
+            "AIS" => Class::Ais,
+            "ERROR" => Class::Error,
+            #[cfg(feature = "msg-toff")]
+            "TOFF" => Class::Toff,
+            #[cfg(feature = "msg-pps")]
+            "PPS" => Class::Pps,
+            #[cfg(feature = "msg-osc")]
+            "OSC" => Class::Osc,
+            #[cfg(feature = "msg-raw")]
+            "RAW" => Class::Raw,
+            "POLL" => Class::Poll,
+            _ => return None,
+        })
+    }
+}
+
+impl core::fmt::Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Message {
+    /// Returns the value of the `class` field of this message, e.g. `TPV`
+    ///
+    /// Unrecognized messages return the class as received.
+    pub fn class_name(&self) -> &str {
+        match self {
+            Message::Tpv(_) => "TPV",
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(_) => "GST",
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(_) => "SKY",
+            #[cfg(feature = "msg-att")]
+            Message::Att(_) => "ATT",
+            #[cfg(feature = "msg-imu")]
+            Message::Imu(_) => "IMU",
+            Message::Devices(_) => "DEVICES",
+            Message::Device(_) => "DEVICE",
+            Message::Watch(_) => "WATCH",
+            Message::Version(_) => "VERSION",
+            #[cfg(feature = "msg-rtcm")]
+            Message::Rtcm2(_) => "RTCM2",
+            #[cfg(feature = "msg-rtcm")]
+            Message::Rtcm3(_) => "RTCM3",
+            #[cfg(feature = "ais")]
+            Message::Ais(_) => "AIS",
+            Message::Error(_) => "ERROR",
+            #[cfg(feature = "msg-toff")]
+            Message::Toff(_) => "TOFF",
+            #[cfg(feature = "msg-pps")]
+            Message::Pps(_) => "PPS",
+            #[cfg(feature = "msg-osc")]
+            Message::Osc(_) => "OSC",
+            #[cfg(feature = "msg-raw")]
+            Message::Raw(_) => "RAW",
+            Message::Poll(_) => "POLL",
+            Message::Other(other) => &other.class,
+        }
+    }
+
+    /// Returns the class of this message, `None` for unrecognized messages
+    pub fn class(&self) -> Option<Class> {
+        Class::from_name(self.class_name())
+    }
+
+    /// Returns the GPS time carried by the message, if any
+    ///
+    /// For TOFF and PPS reports this is the GPS time of the event
+    /// (`real_sec`/`real_nsec`), not the system clock time.
+    pub fn time(&self) -> Option<Timestamp> {
+        match self {
+            Message::Tpv(tpv) => tpv.time,
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(gst) => gst.time,
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => sky.time,
+            #[cfg(feature = "msg-att")]
+            Message::Att(att) => att.time,
+            #[cfg(feature = "msg-imu")]
+            Message::Imu(imu) => imu.time,
+            #[cfg(feature = "msg-toff")]
+            Message::Toff(toff) => toff.real,
+            #[cfg(feature = "msg-pps")]
+            Message::Pps(pps) => pps.real,
+            #[cfg(feature = "msg-raw")]
+            Message::Raw(raw) => raw.time,
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the device the message is about, if any
+    ///
+    /// Messages about GPSD itself, such as VERSION or WATCH, have no device.
+    pub fn device(&self) -> Option<&str> {
+        match self {
+            Message::Tpv(tpv) => tpv.device.as_deref(),
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(gst) => gst.device.as_deref(),
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => sky.device.as_deref(),
+            #[cfg(feature = "msg-att")]
+            Message::Att(att) => att.device.as_deref(),
+            #[cfg(feature = "msg-imu")]
+            Message::Imu(imu) => imu.device.as_deref(),
+            Message::Device(device) => device.path.as_deref(),
+            #[cfg(feature = "msg-rtcm")]
+            Message::Rtcm2(rtcm2) => rtcm2.device.as_deref(),
+            #[cfg(feature = "msg-rtcm")]
+            Message::Rtcm3(rtcm3) => rtcm3.device.as_deref(),
+            #[cfg(feature = "ais")]
+            Message::Ais(ais) => ais.device.as_deref(),
+            #[cfg(feature = "msg-toff")]
+            Message::Toff(toff) => toff.device.as_deref(),
+            #[cfg(feature = "msg-pps")]
+            Message::Pps(pps) => pps.device.as_deref(),
+            #[cfg(feature = "msg-osc")]
+            Message::Osc(osc) => Some(&osc.device),
+            #[cfg(feature = "msg-raw")]
+            Message::Raw(raw) => raw.device.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 /// Helper function to deserialize floating-point Unix timestamps to DateTime
 ///
 /// Converts a floating-point number representing seconds since Unix epoch
-/// to a DateTime<Utc> object, preserving sub-second precision.
-fn f64_to_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+/// to a Timestamp object, preserving sub-second precision.
+fn f64_to_datetime<'de, D>(deserializer: D) -> Result<Option<Timestamp>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -659,10 +994,59 @@ where
 
 /// Helper function to convert separate seconds and nanoseconds to DateTime
 ///
-/// Combines Unix timestamp seconds and nanoseconds into a DateTime<Utc> object.
-fn deserialize_to_datetime(sec: Option<i64>, nsec: Option<i64>) -> Option<DateTime<Utc>> {
+/// Combines Unix timestamp seconds and nanoseconds into a Timestamp object.
+fn deserialize_to_datetime(sec: Option<i64>, nsec: Option<i64>) -> Option<Timestamp> {
     match (sec, nsec) {
         (Some(sec), Some(nsec)) => DateTime::<Utc>::from_timestamp(sec, nsec as u32),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proto_v3_unknown_class() {
+        let json = r#"{"class":"FUTURE","device":"/dev/ttyACM0","value":42}"#;
+        let Message::Other(other) = serde_json::from_str(json).unwrap() else {
+            panic!("unexpected message");
+        };
+        assert_eq!(other.class, "FUTURE");
+        assert_eq!(other.value["value"], 42);
+
+        // Known classes that fail to decode are errors
+        assert!(serde_json::from_str::<Message>(r#"{"class":"TPV","mode":"3D"}"#).is_err());
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[test]
+    fn test_proto_v3_extra_fields() {
+        let msg: Message = serde_json::from_str(
+            r#"{"class":"TPV","mode":3,"lat":35.0,"ubx_carrsoln":2,"vendor":{"fw":"1.2"}}"#,
+        )
+        .unwrap();
+        let Message::Tpv(tpv) = msg else {
+            panic!("unexpected message: {msg:?}");
+        };
+        assert_eq!(tpv.extra().len(), 2);
+        assert_eq!(tpv.get_extra::<u8>("ubx_carrsoln"), Some(2));
+        assert_eq!(tpv.get_extra::<String>("ubx_carrsoln"), None);
+        assert_eq!(tpv.get_extra::<JsonValue>("vendor").unwrap()["fw"], "1.2");
+        assert_eq!(tpv.get_extra::<u8>("lat"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "msg-att")]
+    fn test_proto_v3_attitude_report() {
+        let json = r#"{"class":"ATT","device":"/dev/ttyUSB0","time":"2025-01-01T00:00:00.000Z","timeTag":1735689600,"heading":14223.00,"mag_st":"N","pitch":169.00,"pitch_st":"N","roll":-43.00,"roll_st":"N","yaw":-53.00,"yaw_st":"N","dip":4408.00,"mag_len":48.00,"mag_x":2.00,"mag_y":4.00,"mag_z":4.00,"acc_len":10.00,"acc_x":0.00,"acc_y":0.00,"acc_z":10.00,"gyro_x":0.00,"gyro_y":0.00,"depth":0.0,"temp":25.0}"#;
+        let Message::Att(att) = serde_json::from_str(json).unwrap() else {
+            panic!("unexpected message");
+        };
+        assert_eq!(att.time_tag, Some(1735689600));
+        assert_eq!(att.mag_st, Some(StatusCode::Normal));
+        assert_eq!(att.pitch, Some(169.0));
+        assert_eq!(att.acc_z, Some(10.0));
+        assert_eq!(att.base.status, None);
+    }
+}
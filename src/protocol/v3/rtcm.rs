@@ -0,0 +1,562 @@
+//! RTCM differential correction reports
+//!
+//! GPSD decodes RTCM correction streams received from DGPS beacons or
+//! NTRIP casters and, with JSON output enabled, dumps them as objects of
+//! class `RTCM2` or `RTCM3`. Every report carries the message type and
+//! the reference station ID; the payload depends on the message type.
+//!
+//! References:
+//! [rtcm2_json.c](https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/rtcm2_json.c),
+//! [rtcm3_json.c](https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/rtcm3_json.c)
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::types::GnssId;
+use crate::JsonMap;
+
+/// RTCM2 differential correction report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rtcm2 {
+    /// Device path that provided this data
+    pub device: Option<String>,
+    /// RTCM2 message type
+    pub msg_type: u32,
+    /// Reference station ID
+    pub station_id: Option<u32>,
+    /// Modified Z-count, the time of the message in seconds of the hour
+    pub zcount: Option<f64>,
+    /// Sequence number
+    pub seqnum: Option<u32>,
+    /// Number of data words in the message
+    pub length: Option<u32>,
+    /// Reference station health
+    pub station_health: Option<u32>,
+    /// Type specific payload
+    pub data: Rtcm2Data,
+}
+
+/// Type specific payload of an RTCM2 report
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rtcm2Data {
+    /// Differential GPS corrections (types 1 and 9)
+    Corrections(Vec<Rtcm2Correction>),
+    /// Reference station ECEF position (type 3)
+    ReferenceStation(Rtcm2Position),
+    /// Reference station datum (type 4)
+    Datum(Rtcm2Datum),
+    /// Constellation health (type 5)
+    ConstellationHealth(Vec<Rtcm2SatelliteHealth>),
+    /// Null frame (type 6)
+    Null,
+    /// Radio beacon almanac (type 7)
+    BeaconAlmanac(Vec<Rtcm2Beacon>),
+    /// GNSS time of week (type 14)
+    TimeOfWeek(Rtcm2TimeOfWeek),
+    /// Special message text (type 16)
+    Text(String),
+    /// Differential GLONASS corrections (type 31)
+    GlonassCorrections(Vec<Rtcm2GlonassCorrection>),
+    /// Other message types, with the fields as reported
+    Other(JsonMap),
+}
+
+/// Pseudorange correction for one GPS satellite
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm2Correction {
+    /// Satellite PRN
+    pub ident: u32,
+    /// User differential range error indicator
+    pub udre: u32,
+    /// Issue of data
+    pub iod: u32,
+    /// Pseudorange correction in meters
+    pub prc: f64,
+    /// Range rate correction in meters per second
+    pub rrc: f64,
+}
+
+/// Pseudorange correction for one GLONASS satellite
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm2GlonassCorrection {
+    /// Satellite slot number
+    pub ident: u32,
+    /// User differential range error indicator
+    pub udre: u32,
+    /// True if the ephemeris changed
+    pub change: bool,
+    /// Time of day of the ephemeris, in units of 30 seconds
+    pub tod: u32,
+    /// Pseudorange correction in meters
+    pub prc: f64,
+    /// Range rate correction in meters per second
+    pub rrc: f64,
+}
+
+/// ECEF position of the reference station, in meters
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Rtcm2Position {
+    /// ECEF X coordinate
+    pub x: f64,
+    /// ECEF Y coordinate
+    pub y: f64,
+    /// ECEF Z coordinate
+    pub z: f64,
+}
+
+/// Datum of the reference station
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm2Datum {
+    /// Constellation of the corrections, e.g. `GPS`
+    pub dgnss: Option<String>,
+    /// Name of the datum
+    pub datum: Option<String>,
+    /// Datum shift in X in meters
+    pub dx: Option<f64>,
+    /// Datum shift in Y in meters
+    pub dy: Option<f64>,
+    /// Datum shift in Z in meters
+    pub dz: Option<f64>,
+}
+
+/// Health of one satellite
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm2SatelliteHealth {
+    /// Satellite PRN
+    pub ident: u32,
+    /// Issue of data link
+    pub iodl: bool,
+    /// Satellite health
+    pub health: u32,
+    /// Signal to noise ratio in dB, 0 if not available
+    pub snr: f64,
+    /// True if the health is enabled
+    pub health_en: bool,
+    /// True if new navigation data is available
+    pub new_data: bool,
+    /// True if the satellite is about to go below the horizon
+    pub los_warning: bool,
+    /// Time to unhealthy, in minutes
+    pub tou: u32,
+}
+
+/// Almanac entry of a DGPS radio beacon
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm2Beacon {
+    /// Latitude of the beacon in degrees
+    pub lat: f64,
+    /// Longitude of the beacon in degrees
+    pub lon: f64,
+    /// Range of the beacon in kilometers
+    pub range: u32,
+    /// Frequency of the beacon in kHz
+    pub frequency: f64,
+    /// Health of the beacon
+    pub health: u32,
+    /// Station ID of the beacon
+    pub station_id: u32,
+    /// Bit rate of the beacon
+    pub bitrate: u32,
+}
+
+/// GNSS time of week
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Rtcm2TimeOfWeek {
+    /// GPS week number
+    pub week: u32,
+    /// Hour of the week
+    pub hour: u32,
+    /// Leap seconds (GPS-UTC offset)
+    pub leapsecs: u32,
+}
+
+/// Deserializes a field of an already parsed report
+fn content<'a, T: Deserialize<'a>, E: serde::de::Error>(value: &'a Value) -> Result<T, E> {
+    T::deserialize(value).map_err(E::custom)
+}
+
+/// Deserializes the `satellites` list of a report
+fn satellites<'a, T: Deserialize<'a>, E: serde::de::Error>(value: &'a Value) -> Result<Vec<T>, E> {
+    match value.get("satellites") {
+        Some(list) => content(list),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Returns the fields of a report without the given header fields
+fn remaining_fields(value: Value, header: &[&str]) -> Map<String, Value> {
+    let mut fields = match value {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    for key in header {
+        fields.remove(*key);
+    }
+    fields
+}
+
+impl<'de> Deserialize<'de> for Rtcm2 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawHeader {
+            device: Option<String>,
+            #[serde(rename = "type")]
+            msg_type: u32,
+            station_id: Option<u32>,
+            zcount: Option<f64>,
+            seqnum: Option<u32>,
+            length: Option<u32>,
+            station_health: Option<u32>,
+        }
+
+        let value = Value::deserialize(deserializer)?;
+        let header: RawHeader = content(&value)?;
+
+        let data = match header.msg_type {
+            1 | 9 => Rtcm2Data::Corrections(satellites(&value)?),
+            3 => Rtcm2Data::ReferenceStation(content(&value)?),
+            4 => Rtcm2Data::Datum(content(&value)?),
+            5 => Rtcm2Data::ConstellationHealth(satellites(&value)?),
+            6 => Rtcm2Data::Null,
+            7 => Rtcm2Data::BeaconAlmanac(satellites(&value)?),
+            14 => Rtcm2Data::TimeOfWeek(content(&value)?),
+            16 => Rtcm2Data::Text(
+                value
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            31 => Rtcm2Data::GlonassCorrections(satellites(&value)?),
+            _ => Rtcm2Data::Other(remaining_fields(
+                value,
+                &[
+                    "class",
+                    "device",
+                    "type",
+                    "station_id",
+                    "zcount",
+                    "seqnum",
+                    "length",
+                    "station_health",
+                ],
+            )),
+        };
+
+        Ok(Rtcm2 {
+            device: header.device,
+            msg_type: header.msg_type,
+            station_id: header.station_id,
+            zcount: header.zcount,
+            seqnum: header.seqnum,
+            length: header.length,
+            station_health: header.station_health,
+            data,
+        })
+    }
+}
+
+/// RTCM3 differential correction report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rtcm3 {
+    /// Device path that provided this data
+    pub device: Option<String>,
+    /// RTCM3 message type, e.g. 1005 or 1077
+    pub msg_type: u32,
+    /// Length of the message payload in bytes
+    pub length: Option<u32>,
+    /// Reference station ID
+    pub station_id: Option<u32>,
+    /// Type specific payload
+    pub data: Rtcm3Data,
+}
+
+/// Type specific payload of an RTCM3 report
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rtcm3Data {
+    /// Legacy GPS RTK observables (types 1001 to 1004)
+    GpsObservations(Rtcm3Observations),
+    /// Stationary reference station position (types 1005 and 1006)
+    ReferenceStation(Rtcm3ReferenceStation),
+    /// Antenna and receiver descriptors (types 1007, 1008 and 1033)
+    Antenna(Rtcm3Antenna),
+    /// Legacy GLONASS RTK observables (types 1009 to 1012)
+    GlonassObservations(Rtcm3Observations),
+    /// Unicode text string (type 1029)
+    Text(String),
+    /// Multiple signal message header (MSM1 to MSM7, e.g. types 1077
+    /// and 1087)
+    Msm(Rtcm3Msm),
+    /// Other message types, with the fields as reported
+    Other(JsonMap),
+}
+
+/// Legacy RTK observables of one epoch
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm3Observations {
+    /// Epoch time, in milliseconds of the GPS week or of the GLONASS day
+    pub tow: Option<u32>,
+    /// True if more observables of the same epoch follow
+    pub sync: Option<bool>,
+    /// True if divergence-free smoothing is used
+    pub smoothing: Option<bool>,
+    /// Smoothing interval indicator
+    pub interval: Option<u32>,
+    /// Observables per satellite
+    #[serde(default)]
+    pub satellites: Vec<Rtcm3Satellite>,
+}
+
+/// Legacy RTK observables of one satellite
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm3Satellite {
+    /// Satellite PRN or GLONASS slot number
+    pub ident: u32,
+    /// GLONASS frequency channel number
+    pub channel: Option<i32>,
+    /// L1 observables
+    #[serde(rename = "L1")]
+    pub l1: Option<Rtcm3Signal>,
+    /// L2 observables
+    #[serde(rename = "L2")]
+    pub l2: Option<Rtcm3Signal>,
+}
+
+/// Observables of one signal
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm3Signal {
+    /// Code indicator
+    pub ind: Option<u32>,
+    /// Pseudorange in meters
+    pub prange: Option<f64>,
+    /// Phase range minus pseudorange in meters
+    pub delta: Option<f64>,
+    /// Lock time indicator
+    pub lockt: Option<u32>,
+    /// Integer pseudorange modulus ambiguity
+    pub amb: Option<u32>,
+    /// Carrier to noise ratio in dB-Hz
+    #[serde(rename = "CNR")]
+    pub cnr: Option<f64>,
+}
+
+/// Antenna reference point of a stationary reference station
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm3ReferenceStation {
+    /// Constellations serviced by the station, e.g. `GPS`
+    #[serde(default)]
+    pub system: Vec<String>,
+    /// True if the station is a physical reference station
+    pub refstation: Option<bool>,
+    /// True if all observations come from a single receiver oscillator
+    pub sro: Option<bool>,
+    /// ECEF X coordinate in meters
+    pub x: f64,
+    /// ECEF Y coordinate in meters
+    pub y: f64,
+    /// ECEF Z coordinate in meters
+    pub z: f64,
+    /// Antenna height above the marker in meters (type 1006)
+    pub h: Option<f64>,
+}
+
+/// Antenna and receiver descriptors of a reference station
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Rtcm3Antenna {
+    /// Antenna descriptor
+    pub desc: Option<String>,
+    /// Antenna setup ID
+    pub setup_id: Option<u32>,
+    /// Antenna serial number (types 1008 and 1033)
+    pub serial: Option<String>,
+    /// Receiver type descriptor (type 1033)
+    pub receiver: Option<String>,
+    /// Receiver firmware version (type 1033)
+    pub firmware: Option<String>,
+}
+
+/// Header of a multiple signal message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rtcm3Msm {
+    /// Epoch time, in milliseconds of the GNSS week or of the GLONASS day
+    pub tow: Option<u32>,
+    /// True if more messages of the same epoch follow
+    pub sync: Option<bool>,
+    /// Issue of data station
+    pub iods: Option<u32>,
+    /// True if divergence-free smoothing is used
+    pub smoothing: Option<bool>,
+    /// Smoothing interval indicator
+    pub interval: Option<u32>,
+    /// Remaining fields as reported, e.g. satellite and signal data
+    pub fields: JsonMap,
+}
+
+impl Rtcm3 {
+    /// Returns the constellation and MSM level (1 to 7) of a multiple
+    /// signal message
+    ///
+    /// For example, type 1077 is GPS MSM7 and type 1087 is GLONASS MSM7.
+    pub fn msm(&self) -> Option<(GnssId, u8)> {
+        msm_type(self.msg_type)
+    }
+
+    /// Returns the observables of legacy RTK messages, if any
+    pub fn observations(&self) -> Option<&Rtcm3Observations> {
+        match &self.data {
+            Rtcm3Data::GpsObservations(obs) | Rtcm3Data::GlonassObservations(obs) => Some(obs),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the constellation and level of a multiple signal message type
+fn msm_type(msg_type: u32) -> Option<(GnssId, u8)> {
+    let gnss = match msg_type / 10 {
+        107 => GnssId::Gps,
+        108 => GnssId::Glo,
+        109 => GnssId::Gal,
+        110 => GnssId::Sbas,
+        111 => GnssId::Qzss,
+        112 => GnssId::Bd,
+        113 => GnssId::Irnss,
+        _ => return None,
+    };
+    match msg_type % 10 {
+        level @ 1..=7 => Some((gnss, level as u8)),
+        _ => None,
+    }
+}
+
+impl<'de> Deserialize<'de> for Rtcm3 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawHeader {
+            device: Option<String>,
+            #[serde(rename = "type")]
+            msg_type: u32,
+            length: Option<u32>,
+            station_id: Option<u32>,
+        }
+
+        #[derive(Deserialize)]
+        struct RawMsm {
+            tow: Option<u32>,
+            sync: Option<bool>,
+            #[serde(alias = "IODS")]
+            iods: Option<u32>,
+            smoothing: Option<bool>,
+            interval: Option<u32>,
+        }
+
+        const HEADER: &[&str] = &["class", "device", "type", "length", "station_id"];
+
+        let value = Value::deserialize(deserializer)?;
+        let header: RawHeader = content(&value)?;
+
+        let data = match header.msg_type {
+            1001..=1004 => Rtcm3Data::GpsObservations(content(&value)?),
+            1005 | 1006 => Rtcm3Data::ReferenceStation(content(&value)?),
+            1007 | 1008 | 1033 => Rtcm3Data::Antenna(content(&value)?),
+            1009..=1012 => Rtcm3Data::GlonassObservations(content(&value)?),
+            1029 => Rtcm3Data::Text(
+                value
+                    .get("msg")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            msg_type if msm_type(msg_type).is_some() => {
+                let msm: RawMsm = content(&value)?;
+                let fields = remaining_fields(
+                    value,
+                    &[
+                        HEADER,
+                        &["tow", "sync", "IODS", "iods", "smoothing", "interval"],
+                    ]
+                    .concat(),
+                );
+                Rtcm3Data::Msm(Rtcm3Msm {
+                    tow: msm.tow,
+                    sync: msm.sync,
+                    iods: msm.iods,
+                    smoothing: msm.smoothing,
+                    interval: msm.interval,
+                    fields,
+                })
+            }
+            _ => Rtcm3Data::Other(remaining_fields(value, HEADER)),
+        };
+
+        Ok(Rtcm3 {
+            device: header.device,
+            msg_type: header.msg_type,
+            length: header.length,
+            station_id: header.station_id,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proto_v3_rtcm2_reports() {
+        let json = r#"{"class":"RTCM2","type":1,"station_id":688,"zcount":843.0,"seqnum":5,"length":19,"station_health":6,"satellites":[{"ident":10,"udre":0,"iod":46,"prc":-2.400,"rrc":0.000},{"ident":13,"udre":0,"iod":94,"prc":-4.420,"rrc":0.000}]}"#;
+        let rtcm: Rtcm2 = serde_json::from_str(json).unwrap();
+        assert_eq!(rtcm.station_id, Some(688));
+        let Rtcm2Data::Corrections(corrections) = &rtcm.data else {
+            panic!("unexpected data: {:?}", rtcm.data);
+        };
+        assert_eq!(corrections.len(), 2);
+        assert_eq!(corrections[1].prc, -4.42);
+
+        let json = r#"{"class":"RTCM2","type":3,"station_id":688,"zcount":1200.0,"seqnum":7,"length":4,"station_health":6,"x":3880740.960,"y":-402441.290,"z":5036970.560}"#;
+        let rtcm: Rtcm2 = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            rtcm.data,
+            Rtcm2Data::ReferenceStation(Rtcm2Position { x: 3880740.96, .. })
+        ));
+
+        let json = r#"{"class":"RTCM2","type":20,"station_id":688,"zcount":1200.0,"seqnum":7,"length":2,"station_health":6,"data":["0x00123456","0x00abcdef"]}"#;
+        let rtcm: Rtcm2 = serde_json::from_str(json).unwrap();
+        let Rtcm2Data::Other(fields) = &rtcm.data else {
+            panic!("unexpected data: {:?}", rtcm.data);
+        };
+        assert_eq!(fields["data"][0], "0x00123456");
+    }
+
+    #[test]
+    fn test_proto_v3_rtcm3_reports() {
+        let json = r#"{"class":"RTCM3","device":"/dev/ttyACM0","type":1005,"length":19,"station_id":2003,"system":["GPS","GLONASS"],"refstation":true,"sro":false,"x":-3914218.9160,"y":3317532.7900,"z":3733813.7840}"#;
+        let rtcm: Rtcm3 = serde_json::from_str(json).unwrap();
+        assert_eq!(rtcm.station_id, Some(2003));
+        let Rtcm3Data::ReferenceStation(station) = &rtcm.data else {
+            panic!("unexpected data: {:?}", rtcm.data);
+        };
+        assert_eq!(station.system, ["GPS", "GLONASS"]);
+        assert_eq!(station.h, None);
+
+        let json = r#"{"class":"RTCM3","type":1004,"length":120,"station_id":2003,"tow":207254000,"sync":false,"smoothing":false,"interval":0,"satellites":[{"ident":5,"L1":{"ind":0,"prange":22437048.04,"delta":0.0213,"lockt":127,"amb":74,"CNR":47.25},"L2":{"ind":0,"prange":22437051.12,"delta":0.0174,"lockt":127,"CNR":41.00}}]}"#;
+        let rtcm: Rtcm3 = serde_json::from_str(json).unwrap();
+        let obs = rtcm.observations().unwrap();
+        assert_eq!(obs.satellites[0].l1.as_ref().unwrap().cnr, Some(47.25));
+
+        let json = r#"{"class":"RTCM3","type":1087,"length":212,"station_id":2003,"tow":62654000,"sync":true,"IODS":0,"smoothing":false,"interval":0,"sat_mask":"0x0000000000c0c100"}"#;
+        let rtcm: Rtcm3 = serde_json::from_str(json).unwrap();
+        assert_eq!(rtcm.msm(), Some((GnssId::Glo, 7)));
+        let Rtcm3Data::Msm(msm) = &rtcm.data else {
+            panic!("unexpected data: {:?}", rtcm.data);
+        };
+        assert_eq!(msm.sync, Some(true));
+        assert_eq!(msm.fields["sat_mask"], "0x0000000000c0c100");
+    }
+}
@@ -6,15 +6,101 @@
 //!
 //! Most types correspond directly to structures defined in the GPSD C implementation.
 
-use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_repr::Deserialize_repr;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::skip_serializing_none;
 
+use crate::Timestamp;
+
+/// Floating point type of non-critical measurements
+///
+/// Signal strengths, satellite azimuths and elevations, and DOPs are
+/// parsed into `f32` with the `small-floats` feature to halve their size
+/// on memory-tight targets. Positions and times always use `f64`.
+#[cfg(feature = "small-floats")]
+pub type SmallFloat = f32;
+
+/// Floating point type of non-critical measurements
+///
+/// Signal strengths, satellite azimuths and elevations, and DOPs are
+/// parsed into `f32` with the `small-floats` feature to halve their size
+/// on memory-tight targets. Positions and times always use `f64`.
+#[cfg(not(feature = "small-floats"))]
+pub type SmallFloat = f64;
+
+/// Widens a measurement, e.g. a [`SmallFloat`], to `f64`
+pub(crate) fn widen(value: impl Into<f64>) -> f64 {
+    value.into()
+}
+
+/// Implements conversions and serde for an enum of numeric codes with an
+/// `Other` variant for codes not listed
+///
+/// Values compare and hash by their code, so that a hand-built `Other`
+/// holding a listed code equals the named variant it decodes to.
+macro_rules! impl_code_enum {
+    ($name:ident: $repr:ty { $($variant:ident = $code:literal,)* }) => {
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                <$repr>::from(*self) == <$repr>::from(*other)
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl std::hash::Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                <$repr>::from(*self).hash(state);
+            }
+        }
+
+        impl From<$repr> for $name {
+            fn from(code: $repr) -> Self {
+                match code {
+                    $($code => $name::$variant,)*
+                    other => $name::Other(other),
+                }
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $code,)*
+                    $name::Other(code) => code,
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                <$repr>::from(*self).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                <$repr>::deserialize(deserializer).map($name::from)
+            }
+        }
+    };
+}
+
 /// GPS fix mode indicating the quality/dimension of the position fix
 ///
+/// Modes are ordered by fix quality, so `mode >= FixMode::Fix2D` tests
+/// for a usable position.
+///
 /// Reference: [gps_fix_t.mode](https://gitlab.com/gpsd/gpsd/-/blob/release-3.25/include/gps.h?ref_type=tags#L181)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize_repr)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize_repr, Deserialize_repr,
+)]
 #[repr(i32)]
 pub enum FixMode {
     /// No GPS data has been seen yet
@@ -29,32 +115,51 @@ pub enum FixMode {
 
 /// GPS fix status indicating the positioning method and augmentation used
 ///
+/// Status codes added by newer GPSD releases are kept as
+/// [`Other`](Self::Other) instead of failing the whole message.
+///
 /// Reference: [gps_fix_t.status](https://gitlab.com/gpsd/gpsd/-/blob/release-3.25/include/gps.h?ref_type=tags#L192)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize_repr)]
-#[repr(i32)]
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum FixStatus {
     /// Unknown or no status information
-    Unknown = 0,
+    Unknown,
     /// Standard GPS fix
-    Gps = 1,
+    Gps,
     /// Differential GPS (enhanced accuracy)
-    DGps = 2,
+    DGps,
     /// Real-Time Kinematic with fixed integers (centimeter accuracy)
-    RTKFixed = 3,
+    RTKFixed,
     /// Real-Time Kinematic with float solution (decimeter accuracy)
-    RTKFloat = 4,
+    RTKFloat,
     /// Dead reckoning (position estimated from sensors)
-    DR = 5,
+    DR,
     /// GNSS combined with dead reckoning
-    GnssDR = 6,
+    GnssDR,
     /// Time-only fix (surveyed position, no navigation)
-    Time = 7,
+    Time,
     /// Simulated/test data
-    Simulated = 8,
+    Simulated,
     /// Precise Positioning Service
-    PpsFix = 9,
+    PpsFix,
+    /// Status code not known to this crate; equals the named variant if
+    /// built with a known code
+    Other(i32),
 }
 
+impl_code_enum!(FixStatus: i32 {
+    Unknown = 0,
+    Gps = 1,
+    DGps = 2,
+    RTKFixed = 3,
+    RTKFloat = 4,
+    DR = 5,
+    GnssDR = 6,
+    Time = 7,
+    Simulated = 8,
+    PpsFix = 9,
+});
+
 /// GPS antenna status
 ///
 /// Indicates the electrical status of the GPS antenna connection.
@@ -118,45 +223,72 @@ impl<'de> Deserialize<'de> for SatQuality {
 /// Global Navigation Satellite System identifier
 ///
 /// Identifies which satellite constellation a satellite belongs to.
+/// Identifiers added by newer GPSD releases are kept as
+/// [`Other`](Self::Other) instead of failing the whole message.
 ///
 /// Reference: [satellite.gnssid](https://gitlab.com/gpsd/gpsd/-/blob/release-3.25/include/gps.h?ref_type=tags#L2449)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize_repr)]
-#[repr(u8)]
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum GnssId {
     /// GPS (USA)
-    Gps = 0,
+    Gps,
     /// Satellite-Based Augmentation System
-    Sbas = 1,
+    Sbas,
     /// Galileo (European Union)
-    Gal = 2,
+    Gal,
     /// BeiDou (China)
-    Bd = 3,
+    Bd,
     /// IMES (Indoor Messaging System, Japan)
-    Imes = 4,
+    Imes,
     /// QZSS (Quasi-Zenith Satellite System, Japan)
-    Qzss = 5,
+    Qzss,
     /// GLONASS (Russia)
-    Glo = 6,
+    Glo,
     /// IRNSS/NavIC (India)
-    Irnss = 7,
+    Irnss,
+    /// Identifier not known to this crate; equals the named variant if
+    /// built with a known identifier
+    Other(u8),
 }
 
+impl_code_enum!(GnssId: u8 {
+    Gps = 0,
+    Sbas = 1,
+    Gal = 2,
+    Bd = 3,
+    Imes = 4,
+    Qzss = 5,
+    Glo = 6,
+    Irnss = 7,
+});
+
 /// Satellite health status
 ///
 /// Indicates whether a satellite's signals are reliable for navigation.
+/// Values added by newer GPSD releases are kept as
+/// [`Other`](Self::Other) instead of failing the whole message.
 ///
 /// Reference: [satellite.health](https://gitlab.com/gpsd/gpsd/-/blob/release-3.25/include/gps.h?ref_type=tags#L2504)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize_repr)]
-#[repr(u8)]
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum SatHealth {
     /// Health status unknown
-    Unknown = 0,
+    Unknown,
     /// Satellite is healthy and usable
-    Ok = 1,
+    Ok,
     /// Satellite is unhealthy, do not use
-    Bad = 2,
+    Bad,
+    /// Health value not known to this crate; equals the named variant if
+    /// built with a known value
+    Other(u8),
 }
 
+impl_code_enum!(SatHealth: u8 {
+    Unknown = 0,
+    Ok = 1,
+    Bad = 2,
+});
+
 bitflags::bitflags! {
     /// Device property flags
     ///
@@ -360,25 +492,25 @@ pub struct Ned {
 pub struct Dop {
     /// Longitude dilution of precision
     #[serde(rename = "xdop")]
-    pub x: Option<f64>,
+    pub x: Option<SmallFloat>,
     /// Latitude dilution of precision
     #[serde(rename = "ydop")]
-    pub y: Option<f64>,
+    pub y: Option<SmallFloat>,
     /// Position (3D) dilution of precision
     #[serde(rename = "pdop")]
-    pub p: Option<f64>,
+    pub p: Option<SmallFloat>,
     /// Horizontal dilution of precision
     #[serde(rename = "hdop")]
-    pub h: Option<f64>,
+    pub h: Option<SmallFloat>,
     /// Vertical dilution of precision
     #[serde(rename = "vdop")]
-    pub v: Option<f64>,
+    pub v: Option<SmallFloat>,
     /// Time dilution of precision
     #[serde(rename = "tdop")]
-    pub t: Option<f64>,
+    pub t: Option<SmallFloat>,
     /// Geometric dilution of precision
     #[serde(rename = "gdop")]
-    pub g: Option<f64>,
+    pub g: Option<SmallFloat>,
 }
 
 /// RTK baseline information
@@ -461,10 +593,10 @@ pub struct Satellite {
     pub prn: i16,
     /// Azimuth angle in degrees (0-360)
     #[serde(rename = "az")]
-    pub azimuth: Option<f64>,
+    pub azimuth: Option<SmallFloat>,
     /// Elevation angle in degrees (0-90)
     #[serde(rename = "el")]
-    pub elevation: Option<f64>,
+    pub elevation: Option<SmallFloat>,
     /// Frequency ID (for GLONASS)
     pub freqid: Option<i8>,
     /// GNSS system identifier
@@ -480,7 +612,7 @@ pub struct Satellite {
     #[serde(rename = "prRes")]
     pub pr_res: Option<f64>,
     /// Signal strength in dB-Hz
-    pub ss: Option<f64>,
+    pub ss: Option<SmallFloat>,
     /// Signal ID
     pub sigid: Option<u8>,
     /// Space vehicle ID
@@ -499,12 +631,12 @@ pub struct Satellite {
 ///
 /// Reference: [json_device_read](https://gitlab.com/gpsd/gpsd/-/blob/master/libgps/shared_json.c#L28)
 #[skip_serializing_none]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Device {
     /// Device path (e.g., "/dev/ttyUSB0")
     pub path: Option<String>,
     /// Timestamp when device was activated
-    pub activated: Option<DateTime<Utc>>,
+    pub activated: Option<Timestamp>,
     /// Device capability flags
     pub flags: Option<PropertyFlags>,
     /// Driver name
@@ -583,6 +715,15 @@ impl Default for Watch {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_small_floats_sizes() {
+        assert_eq!(size_of::<Dop>(), 7 * size_of::<Option<SmallFloat>>());
+        #[cfg(all(feature = "small-floats", target_pointer_width = "64"))]
+        assert_eq!((size_of::<Dop>(), size_of::<Satellite>()), (56, 88));
+        #[cfg(all(not(feature = "small-floats"), target_pointer_width = "64"))]
+        assert_eq!((size_of::<Dop>(), size_of::<Satellite>()), (112, 112));
+    }
+
     #[test]
     fn test_proto_v3_types_flags() {
         let flags = PropertyFlags::SEEN_GPS | PropertyFlags::SEEN_AIS;
@@ -592,4 +733,27 @@ mod tests {
         let deserialized: PropertyFlags = serde_json::from_str(&serialized).unwrap();
         assert_eq!(deserialized, flags);
     }
+
+    #[test]
+    fn test_proto_v3_types_future_codes() {
+        let sat: Satellite =
+            serde_json::from_str(r#"{"PRN":5,"gnssid":9,"health":3,"used":true}"#).unwrap();
+        assert_eq!(sat.gnssid, Some(GnssId::Other(9)));
+        assert_eq!(sat.health, Some(SatHealth::Other(3)));
+
+        let status: FixStatus = serde_json::from_str("12").unwrap();
+        assert_eq!(status, FixStatus::Other(12));
+        assert_eq!(serde_json::to_string(&status).unwrap(), "12");
+        assert_eq!(FixStatus::from(2), FixStatus::DGps);
+        assert_eq!(u8::from(GnssId::Glo), 6);
+
+        // Hand-built values of known codes survive a round trip
+        let status = FixStatus::Other(2);
+        let decoded: FixStatus =
+            serde_json::from_str(&serde_json::to_string(&status).unwrap()).unwrap();
+        assert!(matches!(decoded, FixStatus::DGps));
+        assert_eq!(decoded, status);
+        let ids: std::collections::HashSet<_> = [GnssId::Glo, GnssId::Other(6)].into();
+        assert_eq!(ids.len(), 1);
+    }
 }
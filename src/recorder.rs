@@ -0,0 +1,344 @@
+//! Drive test recording of combined JSON, NMEA and timing output
+//!
+//! This module captures everything a GPSD device reports during a drive
+//! test into a structured archive directory, so that receiver behavior can
+//! be compared across firmware versions or hardware:
+//!
+//! - `json.jsonl` - JSON reports, one per line, exactly as received
+//! - `nmea.log` - NMEA sentences, one per line, exactly as received
+//! - `other.log` - Any other lines (created only when needed)
+//! - `timing.csv` - Receive timestamp, stream and class of every line
+//! - `manifest.json` - Summary of the capture, written by [`DriveTestRecorder::finish`]
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::{client::{GpsdClient, StreamOptions}, recorder::DriveTestRecorder};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = GpsdClient::connect("127.0.0.1:2947").await?;
+//! let mut recorder = DriveTestRecorder::create("captures/run-01")?
+//!     .label("firmware", "ROM 3.01");
+//!
+//! let mut stream = client.stream(StreamOptions::capture()).await?;
+//! while let Some(line) = stream.next().await {
+//!     recorder.record(&line?)?;
+//! #   break;
+//! }
+//! let manifest = recorder.finish()?;
+//! println!("captured {} lines", manifest.total_lines());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DriveTestRecorder::finish`]: crate::recorder::DriveTestRecorder::finish
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::{Result, Timestamp, error::GpsdJsonError, protocol::v3::types::Watch};
+
+/// Version of the archive layout written to the manifest
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Kind of a captured line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineKind {
+    /// GPSD JSON report
+    Json,
+    /// NMEA 0183 sentence (including AIS `!` sentences)
+    Nmea,
+    /// Anything else
+    Other,
+}
+
+impl LineKind {
+    /// Classifies a line by its first character
+    pub fn classify(line: &[u8]) -> Self {
+        match line.first() {
+            Some(b'{') => LineKind::Json,
+            Some(b'$' | b'!') => LineKind::Nmea,
+            _ => LineKind::Other,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            LineKind::Json => "json",
+            LineKind::Nmea => "nmea",
+            LineKind::Other => "other",
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            LineKind::Json => "json.jsonl",
+            LineKind::Nmea => "nmea.log",
+            LineKind::Other => "other.log",
+        }
+    }
+}
+
+/// Statistics of one captured stream file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestFile {
+    /// Stream stored in this file
+    pub stream: LineKind,
+    /// File name relative to the archive directory
+    pub path: String,
+    /// Number of lines written
+    pub lines: u64,
+    /// Number of bytes written, including line terminators
+    pub bytes: u64,
+}
+
+/// Summary of a drive test capture
+///
+/// Serialized as `manifest.json` in the archive directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Manifest {
+    /// Version of the archive layout
+    pub format_version: u32,
+    /// Time the recorder was created
+    pub started: Timestamp,
+    /// Time the recorder was finished
+    pub finished: Option<Timestamp>,
+    /// WATCH settings used for the capture, if recorded
+    pub watch: Option<Watch>,
+    /// User supplied labels (firmware version, vehicle, route, ...)
+    pub labels: BTreeMap<String, String>,
+    /// Captured stream files
+    pub files: Vec<ManifestFile>,
+    /// Number of lines per JSON class or NMEA sentence type
+    pub classes: BTreeMap<String, u64>,
+}
+
+impl Manifest {
+    /// Returns the total number of captured lines over all streams
+    pub fn total_lines(&self) -> u64 {
+        self.files.iter().map(|file| file.lines).sum()
+    }
+}
+
+struct StreamFile {
+    writer: BufWriter<File>,
+    stats: ManifestFile,
+}
+
+/// Records combined JSON, NMEA and timing output into an archive directory
+///
+/// Use [`StreamOptions::capture`](crate::client::StreamOptions::capture)
+/// to obtain a stream yielding every line GPSD sends, and pass each line
+/// to [`DriveTestRecorder::record`].
+pub struct DriveTestRecorder {
+    dir: PathBuf,
+    files: BTreeMap<LineKind, StreamFile>,
+    timing: BufWriter<File>,
+    sequence: u64,
+    manifest: Manifest,
+}
+
+impl DriveTestRecorder {
+    /// Creates a recorder writing into the given directory
+    ///
+    /// The directory is created if it does not exist. Existing capture
+    /// files in it are truncated.
+    pub fn create<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(GpsdJsonError::IoError)?;
+
+        let mut timing =
+            BufWriter::new(File::create(dir.join("timing.csv")).map_err(GpsdJsonError::IoError)?);
+        writeln!(timing, "seq,received,stream,class,bytes").map_err(GpsdJsonError::IoError)?;
+
+        let mut recorder = DriveTestRecorder {
+            dir,
+            files: BTreeMap::new(),
+            timing,
+            sequence: 0,
+            manifest: Manifest {
+                format_version: ARCHIVE_FORMAT_VERSION,
+                started: Utc::now(),
+                finished: None,
+                watch: None,
+                labels: BTreeMap::new(),
+                files: Vec::new(),
+                classes: BTreeMap::new(),
+            },
+        };
+        recorder.open_stream(LineKind::Json)?;
+        recorder.open_stream(LineKind::Nmea)?;
+        Ok(recorder)
+    }
+
+    /// Adds a label to the manifest (e.g. firmware version or route name)
+    pub fn label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.manifest.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Records the WATCH settings used for the capture in the manifest
+    pub fn watch(mut self, watch: &Watch) -> Self {
+        self.manifest.watch = Some(watch.clone());
+        self
+    }
+
+    /// Returns the archive directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Records a line received now
+    ///
+    /// The line should not contain its line terminator.
+    pub fn record(&mut self, line: &[u8]) -> Result<LineKind> {
+        self.record_at(line, Utc::now())
+    }
+
+    /// Records a line with an explicit receive timestamp
+    pub fn record_at(&mut self, line: &[u8], received: Timestamp) -> Result<LineKind> {
+        let kind = LineKind::classify(line);
+        let class = line_class(kind, line);
+
+        if !self.files.contains_key(&kind) {
+            self.open_stream(kind)?;
+        }
+        let file = self
+            .files
+            .get_mut(&kind)
+            .ok_or(GpsdJsonError::ProtocolError("Capture stream not open"))?;
+        file.writer
+            .write_all(line)
+            .and_then(|_| file.writer.write_all(b"\n"))
+            .map_err(GpsdJsonError::IoError)?;
+        file.stats.lines += 1;
+        file.stats.bytes += line.len() as u64 + 1;
+
+        writeln!(
+            self.timing,
+            "{},{},{},{},{}",
+            self.sequence,
+            received.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true),
+            kind.name(),
+            class.as_deref().unwrap_or(""),
+            line.len()
+        )
+        .map_err(GpsdJsonError::IoError)?;
+        self.sequence += 1;
+
+        if let Some(class) = class {
+            *self.manifest.classes.entry(class).or_insert(0) += 1;
+        }
+        Ok(kind)
+    }
+
+    /// Flushes all files and writes `manifest.json`
+    ///
+    /// Returns the written manifest.
+    pub fn finish(mut self) -> Result<Manifest> {
+        for file in self.files.values_mut() {
+            file.writer.flush().map_err(GpsdJsonError::IoError)?;
+        }
+        self.timing.flush().map_err(GpsdJsonError::IoError)?;
+
+        self.manifest.finished = Some(Utc::now());
+        self.manifest.files = self.files.into_values().map(|file| file.stats).collect();
+
+        let manifest_file =
+            File::create(self.dir.join("manifest.json")).map_err(GpsdJsonError::IoError)?;
+        serde_json::to_writer_pretty(manifest_file, &self.manifest)
+            .map_err(GpsdJsonError::SerdeError)?;
+        Ok(self.manifest)
+    }
+
+    fn open_stream(&mut self, kind: LineKind) -> Result<()> {
+        let path = kind.file_name();
+        let file = File::create(self.dir.join(path)).map_err(GpsdJsonError::IoError)?;
+        self.files.insert(
+            kind,
+            StreamFile {
+                writer: BufWriter::new(file),
+                stats: ManifestFile {
+                    stream: kind,
+                    path: path.into(),
+                    lines: 0,
+                    bytes: 0,
+                },
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Extracts the JSON class or NMEA sentence type of a line
+fn line_class(kind: LineKind, line: &[u8]) -> Option<String> {
+    match kind {
+        LineKind::Json => {
+            #[derive(serde::Deserialize)]
+            struct Class {
+                class: String,
+            }
+            serde_json::from_slice::<Class>(line).ok().map(|c| c.class)
+        }
+        LineKind::Nmea => {
+            let end = line.iter().position(|&b| b == b',' || b == b'*')?;
+            // Skip the leading '$' or '!' and the two character talker ID
+            let start = if line.get(1) == Some(&b'P') { 1 } else { 3 };
+            line.get(start..end)
+                .map(|ty| String::from_utf8_lossy(ty).into_owned())
+        }
+        LineKind::Other => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_archive() {
+        let dir = std::env::temp_dir().join(format!("gpsd-json-recorder-{}", std::process::id()));
+        let mut recorder = DriveTestRecorder::create(&dir)
+            .unwrap()
+            .label("firmware", "1.0");
+
+        let lines: [&[u8]; 4] = [
+            br#"{"class":"TPV","mode":3}"#,
+            b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+            b"$PUBX,00,081350.00*5A",
+            b"garbage",
+        ];
+        let kinds = lines
+            .iter()
+            .map(|line| recorder.record(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                LineKind::Json,
+                LineKind::Nmea,
+                LineKind::Nmea,
+                LineKind::Other
+            ]
+        );
+
+        let manifest = recorder.finish().unwrap();
+        assert_eq!(manifest.total_lines(), 4);
+        assert_eq!(manifest.classes.get("TPV"), Some(&1));
+        assert_eq!(manifest.classes.get("GGA"), Some(&1));
+        assert_eq!(manifest.classes.get("PUBX"), Some(&1));
+        assert!(dir.join("manifest.json").exists());
+
+        let timing = std::fs::read_to_string(dir.join("timing.csv")).unwrap();
+        assert_eq!(timing.lines().count(), 5);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+//! Periodic statistics reports
+//!
+//! This module rolls up a stream of GPSD reports into a summary per
+//! period, typically one day, for fleet health emails and SLA monitoring:
+//! fix availability, mean number of satellites used, distribution of
+//! horizontal DOP and a list of fix outages. Reports serialize to JSON.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::report::{ReportConfig, StatsCollector};
+//! # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>) {
+//! let mut collector = StatsCollector::new(ReportConfig::daily());
+//! for msg in &messages {
+//!     if let Some(report) = collector.update(msg) {
+//!         println!("{}", serde_json::to_string_pretty(&report).unwrap());
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta};
+use serde::Serialize;
+
+use crate::{
+    Timestamp,
+    outage::{OutageConfig, OutageEvent, OutageTracker},
+    protocol::v3::{
+        response::{Message, Tpv},
+        types::FixMode,
+    },
+};
+
+pub use crate::outage::Outage;
+
+/// Configuration of a [`StatsCollector`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportConfig {
+    /// Length of a report period; periods are aligned to the Unix epoch
+    pub period: TimeDelta,
+    /// Largest gap between TPV reports that is not counted as an outage
+    pub max_gap: TimeDelta,
+}
+
+impl ReportConfig {
+    /// Returns a configuration for one report per UTC day
+    pub fn daily() -> Self {
+        ReportConfig {
+            period: TimeDelta::days(1),
+            max_gap: TimeDelta::seconds(10),
+        }
+    }
+
+    /// Returns a configuration for one report per UTC hour
+    pub fn hourly() -> Self {
+        ReportConfig {
+            period: TimeDelta::hours(1),
+            ..Self::daily()
+        }
+    }
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self::daily()
+    }
+}
+
+/// Rating of a dilution of precision value
+///
+/// Bands follow the common classification of DOP values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DopRating {
+    /// Below 1
+    Ideal,
+    /// 1 to 2
+    Excellent,
+    /// 2 to 5
+    Good,
+    /// 5 to 10
+    Moderate,
+    /// 10 to 20
+    Fair,
+    /// 20 and above
+    Poor,
+}
+
+impl DopRating {
+    /// Classifies a DOP value
+    pub fn from_dop(dop: f64) -> Self {
+        match dop {
+            d if d < 1.0 => DopRating::Ideal,
+            d if d < 2.0 => DopRating::Excellent,
+            d if d < 5.0 => DopRating::Good,
+            d if d < 10.0 => DopRating::Moderate,
+            d if d < 20.0 => DopRating::Fair,
+            _ => DopRating::Poor,
+        }
+    }
+}
+
+/// Summary of one report period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// Start of the period
+    pub start: Timestamp,
+    /// End of the period (exclusive)
+    pub end: Timestamp,
+    /// Number of TPV reports
+    pub epochs: u64,
+    /// Number of TPV reports with a 2D or 3D fix
+    pub fix_epochs: u64,
+    /// Percentage of TPV reports with a 2D or 3D fix
+    pub fix_availability: f64,
+    /// Mean number of satellites used, over SKY reports
+    pub mean_satellites: Option<f64>,
+    /// Number of SKY reports per horizontal DOP rating
+    pub hdop: BTreeMap<DopRating, u64>,
+    /// Outages that ended within the period
+    pub outages: Vec<Outage>,
+    /// Total outage time in seconds
+    pub outage_duration: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Period {
+    epochs: u64,
+    fix_epochs: u64,
+    sky_reports: u64,
+    satellites: u64,
+    hdop: BTreeMap<DopRating, u64>,
+    outages: Vec<Outage>,
+}
+
+/// Rolls up GPSD reports into periodic [`Report`]s
+///
+/// Periods are determined from GPS time in the reports, so replayed logs
+/// produce the same reports as live streams.
+#[derive(Debug, Clone)]
+pub struct StatsCollector {
+    config: ReportConfig,
+    start: Option<Timestamp>,
+    period: Period,
+    outages: OutageTracker,
+}
+
+impl StatsCollector {
+    /// Creates a collector with the given configuration
+    pub fn new(config: ReportConfig) -> Self {
+        StatsCollector {
+            config,
+            start: None,
+            period: Period::default(),
+            outages: OutageTracker::new(OutageConfig {
+                max_gap: config.max_gap,
+                history: 0,
+            }),
+        }
+    }
+
+    /// Processes a report
+    ///
+    /// Returns the report of the previous period once a message of a new
+    /// period arrives. Messages without GPS time are ignored.
+    pub fn update(&mut self, message: &Message) -> Option<Report> {
+        let time = match message {
+            Message::Tpv(tpv) => tpv.time?,
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => sky.time?,
+            _ => return None,
+        };
+
+        let start = self.period_start(time);
+        let finished = match self.start {
+            Some(current) if current < start => self.take_report(),
+            _ => None,
+        };
+        self.start.get_or_insert(start);
+
+        match message {
+            Message::Tpv(tpv) => self.add_tpv(tpv),
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => self.add_sky(sky),
+            _ => {}
+        }
+        finished
+    }
+
+    /// Returns the report of the current, possibly incomplete, period
+    pub fn report(&self) -> Option<Report> {
+        let start = self.start?;
+        let period = &self.period;
+
+        let outage_duration = period.outages.iter().map(|o| o.duration).sum();
+        Some(Report {
+            start,
+            end: start + self.config.period,
+            epochs: period.epochs,
+            fix_epochs: period.fix_epochs,
+            fix_availability: if period.epochs > 0 {
+                period.fix_epochs as f64 * 100.0 / period.epochs as f64
+            } else {
+                0.0
+            },
+            mean_satellites: (period.sky_reports > 0)
+                .then(|| period.satellites as f64 / period.sky_reports as f64),
+            hdop: period.hdop.clone(),
+            outages: period.outages.clone(),
+            outage_duration,
+        })
+    }
+
+    /// Finishes the current period and returns its report
+    pub fn take_report(&mut self) -> Option<Report> {
+        let report = self.report();
+        self.start = None;
+        self.period = Period::default();
+        report
+    }
+
+    fn period_start(&self, time: Timestamp) -> Timestamp {
+        let period = self.config.period.num_seconds().max(1);
+        let secs = time.timestamp().div_euclid(period) * period;
+        DateTime::from_timestamp(secs, 0).unwrap_or(time)
+    }
+
+    fn add_tpv(&mut self, tpv: &Tpv) {
+        self.period.epochs += 1;
+        if tpv.mode >= FixMode::Fix2D {
+            self.period.fix_epochs += 1;
+        }
+        if let Some(OutageEvent::Ended(outage)) = self.outages.update(tpv) {
+            self.period.outages.push(outage);
+        }
+    }
+
+    #[cfg(feature = "msg-sky")]
+    fn add_sky(&mut self, sky: &crate::protocol::v3::response::Sky) {
+        self.period.sky_reports += 1;
+        let used = match sky.u_sat {
+            Some(used) => used.max(0) as u64,
+            None => sky.satellites.iter().filter(|sat| sat.used).count() as u64,
+        };
+        self.period.satellites += used;
+        if let Some(hdop) = sky.dop.h {
+            *self
+                .period
+                .hdop
+                .entry(DopRating::from_dop(crate::protocol::v3::types::widen(hdop)))
+                .or_insert(0) += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tpv(time: &str, mode: u8) -> Message {
+        serde_json::from_str(&format!(
+            r#"{{"class":"TPV","mode":{mode},"time":"{time}","lat":35.0,"lon":139.0}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_stats_collector_daily_report() {
+        let mut collector = StatsCollector::new(ReportConfig::daily());
+
+        assert!(collector.update(&tpv("2025-01-01T23:00:00Z", 3)).is_none());
+        assert!(collector.update(&tpv("2025-01-01T23:00:10Z", 1)).is_none());
+        assert!(collector.update(&tpv("2025-01-01T23:01:00Z", 3)).is_none());
+        let sky =
+            r#"{"class":"SKY","time":"2025-01-01T23:01:00Z","hdop":1.5,"uSat":8,"satellites":[]}"#;
+        assert!(
+            collector
+                .update(&serde_json::from_str(sky).unwrap())
+                .is_none()
+        );
+
+        let report = collector.update(&tpv("2025-01-02T00:00:00Z", 3)).unwrap();
+        assert_eq!(report.start.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(report.epochs, 3);
+        assert_eq!(report.fix_epochs, 2);
+        assert_eq!(report.outages.len(), 1);
+        assert_eq!(report.outages[0].duration, 60.0);
+
+        #[cfg(feature = "msg-sky")]
+        {
+            assert_eq!(report.mean_satellites, Some(8.0));
+            assert_eq!(report.hdop.get(&DopRating::Excellent), Some(&1));
+            let json = serde_json::to_value(&report).unwrap();
+            assert_eq!(json["hdop"]["excellent"], 1);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Static survey of a fixed antenna position
+//!
+//! Base stations for differential corrections and timing receivers need
+//! to know their antenna position precisely. This module averages the
+//! positions reported by a stationary receiver until both a minimum
+//! observation time has passed and the spread of the samples is below a
+//! target, similar to the survey-in mode of many GNSS receivers.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::survey::{SurveyConfig, SurveyIn, SurveyStatus};
+//! # fn example(tpvs: Vec<gpsd_json::protocol::v3::response::Tpv>) {
+//! let mut survey = SurveyIn::new(SurveyConfig {
+//!     min_duration: Duration::from_secs(600),
+//!     target_accuracy: 2.0,
+//!     ..Default::default()
+//! });
+//! for tpv in &tpvs {
+//!     if let SurveyStatus::Converged(result) = survey.update(tpv) {
+//!         println!("antenna at {:?} ± {:.2} m", result.position, result.accuracy);
+//!         break;
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    Timestamp,
+    geo::{EARTH_RADIUS_M, Position},
+    protocol::v3::{response::Tpv, types::FixMode},
+};
+
+/// Convergence criteria of a [`SurveyIn`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurveyConfig {
+    /// Minimum time between the first and the last sample
+    pub min_duration: Duration,
+    /// Largest accepted 3D standard deviation of the samples in meters
+    pub target_accuracy: f64,
+    /// Minimum fix mode of samples; fixes below this mode are ignored
+    pub min_mode: FixMode,
+}
+
+impl Default for SurveyConfig {
+    fn default() -> Self {
+        SurveyConfig {
+            min_duration: Duration::from_secs(300),
+            target_accuracy: 5.0,
+            min_mode: FixMode::Fix3D,
+        }
+    }
+}
+
+/// Averaged antenna position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurveyResult {
+    /// Mean position
+    pub position: Position,
+    /// Mean height above ellipsoid in meters, if reported by all samples
+    pub altitude: Option<f64>,
+    /// Horizontal standard deviation of the samples in meters
+    pub horizontal_accuracy: f64,
+    /// Vertical standard deviation of the samples in meters
+    pub vertical_accuracy: Option<f64>,
+    /// 3D standard deviation of the samples in meters
+    pub accuracy: f64,
+    /// Number of averaged samples
+    pub samples: usize,
+    /// Time between the first and the last sample
+    pub duration: Duration,
+}
+
+/// Progress of a [`SurveyIn`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurveyStatus {
+    /// No usable fix has been seen yet
+    Waiting,
+    /// Samples are being averaged; the criteria are not met yet
+    InProgress(SurveyResult),
+    /// The criteria are met
+    Converged(SurveyResult),
+}
+
+/// Running mean and variance (Welford's algorithm)
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        if self.count > 1 {
+            self.m2 / (self.count - 1) as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Averages the positions of a stationary receiver
+///
+/// Horizontal positions are averaged in a local north/east plane around
+/// the first sample, which is accurate for the small spread of a static
+/// receiver.
+#[derive(Debug, Clone)]
+pub struct SurveyIn {
+    config: SurveyConfig,
+    origin: Option<Position>,
+    first: Option<Timestamp>,
+    last: Option<Timestamp>,
+    north: Accumulator,
+    east: Accumulator,
+    up: Accumulator,
+    converged: bool,
+}
+
+impl SurveyIn {
+    /// Creates a survey with the given convergence criteria
+    pub fn new(config: SurveyConfig) -> Self {
+        SurveyIn {
+            config,
+            origin: None,
+            first: None,
+            last: None,
+            north: Accumulator::default(),
+            east: Accumulator::default(),
+            up: Accumulator::default(),
+            converged: false,
+        }
+    }
+
+    /// Returns the convergence criteria of this survey
+    pub fn config(&self) -> &SurveyConfig {
+        &self.config
+    }
+
+    /// Discards all samples and restarts the survey
+    pub fn reset(&mut self) {
+        *self = SurveyIn::new(self.config);
+    }
+
+    /// Adds a TPV report to the average
+    ///
+    /// Reports without time or position, or below the configured fix mode,
+    /// are ignored. Samples keep being averaged after convergence.
+    pub fn update(&mut self, tpv: &Tpv) -> SurveyStatus {
+        let usable = tpv.mode >= self.config.min_mode;
+        if let (true, Some(position), Some(time)) = (usable, Position::from_tpv(tpv), tpv.time) {
+            self.add(position, tpv.alt_hae, time);
+        }
+        self.status()
+    }
+
+    /// Returns the current progress
+    pub fn status(&self) -> SurveyStatus {
+        match self.estimate() {
+            None => SurveyStatus::Waiting,
+            Some(result) if self.converged => SurveyStatus::Converged(result),
+            Some(result) => SurveyStatus::InProgress(result),
+        }
+    }
+
+    /// Returns the current average, or `None` before the first sample
+    pub fn estimate(&self) -> Option<SurveyResult> {
+        let origin = self.origin?;
+        let duration = (self.last? - self.first?).to_std().unwrap_or_default();
+
+        let lat = origin.lat + (self.north.mean / EARTH_RADIUS_M).to_degrees();
+        let lon = origin.lon
+            + (self.east.mean / (EARTH_RADIUS_M * origin.lat.to_radians().cos())).to_degrees();
+        let horizontal = self.north.variance() + self.east.variance();
+        // Altitude is only averaged while every sample carries one
+        let vertical = (self.up.count == self.north.count).then(|| self.up.variance());
+
+        Some(SurveyResult {
+            position: Position::new(lat, lon),
+            altitude: vertical.map(|_| self.up.mean),
+            horizontal_accuracy: horizontal.sqrt(),
+            vertical_accuracy: vertical.map(f64::sqrt),
+            accuracy: (horizontal + vertical.unwrap_or(0.0)).sqrt(),
+            samples: self.north.count,
+            duration,
+        })
+    }
+
+    fn add(&mut self, position: Position, altitude: Option<f64>, time: Timestamp) {
+        let origin = *self.origin.get_or_insert(position);
+        self.first.get_or_insert(time);
+        self.last = Some(time);
+
+        self.north
+            .add((position.lat - origin.lat).to_radians() * EARTH_RADIUS_M);
+        self.east.add(
+            (position.lon - origin.lon).to_radians()
+                * EARTH_RADIUS_M
+                * origin.lat.to_radians().cos(),
+        );
+        if let Some(altitude) = altitude {
+            self.up.add(altitude);
+        }
+
+        if let Some(result) = self.estimate() {
+            self.converged = result.samples > 1
+                && result.duration >= self.config.min_duration
+                && result.accuracy <= self.config.target_accuracy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_survey_in_converges() {
+        let mut survey = SurveyIn::new(SurveyConfig {
+            min_duration: Duration::from_secs(30),
+            target_accuracy: 1.0,
+            ..Default::default()
+        });
+
+        let mut status = SurveyStatus::Waiting;
+        for sec in 0..60 {
+            let offset = if sec % 2 == 0 { 0.000002 } else { -0.000002 };
+            let tpv: Tpv = serde_json::from_str(&format!(
+                r#"{{"mode":3,"time":"2025-01-01T00:{:02}:{:02}Z","lat":{},"lon":139.0,"altHAE":{}}}"#,
+                sec / 60,
+                sec % 60,
+                35.0 + offset,
+                40.0 + offset * 1e5,
+            ))
+            .unwrap();
+            status = survey.update(&tpv);
+            if sec < 30 {
+                assert!(matches!(status, SurveyStatus::InProgress(_)));
+            }
+        }
+
+        let SurveyStatus::Converged(result) = status else {
+            panic!("survey did not converge: {status:?}");
+        };
+        assert!((result.position.lat - 35.0).abs() < 1e-6);
+        assert!((result.altitude.unwrap() - 40.0).abs() < 0.1);
+        assert!(result.accuracy < 1.0);
+    }
+}
@@ -0,0 +1,25 @@
+//! Test utilities for applications built on this crate
+//!
+//! Requires the `testing` feature. Not intended for production code paths.
+//!
+//! - [`expect`] - Assertions on message sequences
+//! - [`load`] - Concurrent client load testing against a GPSD host
+//! - [`mock`] - Scripted mock GPSD server
+//! - [`soak`] - Long-running leak and rate checks against the mock server
+//!
+//! [`expect`]: crate::testing::expect
+//! [`load`]: crate::testing::load
+//! [`mock`]: crate::testing::mock
+//! [`soak`]: crate::testing::soak
+
+/// Assertions on message sequences
+pub mod expect;
+
+/// Concurrent client load testing
+pub mod load;
+
+/// Scripted mock GPSD server
+pub mod mock;
+
+/// Long-running soak testing
+pub mod soak;
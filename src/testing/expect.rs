@@ -0,0 +1,169 @@
+//! Assertions on message sequences
+//!
+//! A small assertion DSL for behavioural tests of code consuming GPSD
+//! output. Collect the messages from a replay or mock stream and state
+//! which messages are expected, in order. Failed expectations panic with
+//! a description of the offending message, like `assert!`.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{
+//!     protocol::v3::{response::Message, types::FixMode},
+//!     testing::expect::Sequence,
+//! };
+//!
+//! let messages = [
+//!     r#"{"class":"DEVICES","devices":[]}"#,
+//!     r#"{"class":"WATCH","enable":true,"json":true}"#,
+//!     r#"{"class":"SKY","satellites":[]}"#,
+//!     r#"{"class":"TPV","mode":3,"lat":35.0,"lon":139.0}"#,
+//! ]
+//! .map(|json| serde_json::from_str::<Message>(json).unwrap());
+//!
+//! Sequence::new(messages)
+//!     .expect_classes(["DEVICES", "WATCH"])
+//!     .skip_until("TPV")
+//!     .expect_tpv_with(|tpv| tpv.mode == FixMode::Fix3D)
+//!     .expect_end();
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::protocol::v3::response::{Message, Tpv};
+
+/// A sequence of messages consumed by expectations
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    messages: VecDeque<Message>,
+    position: usize,
+}
+
+impl Sequence {
+    /// Creates a sequence from received messages
+    pub fn new<I: IntoIterator<Item = Message>>(messages: I) -> Self {
+        Sequence {
+            messages: messages.into_iter().collect(),
+            position: 0,
+        }
+    }
+
+    /// Returns the number of messages not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Expects the next messages to have exactly these classes, in order
+    #[track_caller]
+    pub fn expect_classes<I, S>(&mut self, classes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for class in classes {
+            let class = class.as_ref();
+            let msg = self.take(class);
+            assert!(
+                msg.class_name() == class,
+                "expected {class} at message {}, got {}",
+                self.position - 1,
+                msg.class_name()
+            );
+        }
+        self
+    }
+
+    /// Discards messages until the next one has the given class
+    ///
+    /// The matching message is not consumed.
+    #[track_caller]
+    pub fn skip_until(&mut self, class: &str) -> &mut Self {
+        while let Some(msg) = self.messages.front() {
+            if msg.class_name() == class {
+                return self;
+            }
+            self.messages.pop_front();
+            self.position += 1;
+        }
+        panic!(
+            "expected {class}, but the sequence ended at message {}",
+            self.position
+        );
+    }
+
+    /// Expects the next message to satisfy a predicate
+    #[track_caller]
+    pub fn expect_with<F: FnOnce(&Message) -> bool>(&mut self, predicate: F) -> &mut Self {
+        let msg = self.take("a message");
+        assert!(
+            predicate(&msg),
+            "message {} does not satisfy the predicate: {msg:?}",
+            self.position - 1
+        );
+        self
+    }
+
+    /// Expects the next message to be a TPV report satisfying a predicate
+    #[track_caller]
+    pub fn expect_tpv_with<F: FnOnce(&Tpv) -> bool>(&mut self, predicate: F) -> &mut Self {
+        match self.take("TPV") {
+            Message::Tpv(tpv) => assert!(
+                predicate(&tpv),
+                "TPV at message {} does not satisfy the predicate: {tpv:?}",
+                self.position - 1
+            ),
+            other => panic!(
+                "expected TPV at message {}, got {}",
+                self.position - 1,
+                other.class_name()
+            ),
+        }
+        self
+    }
+
+    /// Expects the next message to be a SKY report satisfying a predicate
+    #[cfg(feature = "msg-sky")]
+    #[track_caller]
+    pub fn expect_sky_with<F>(&mut self, predicate: F) -> &mut Self
+    where
+        F: FnOnce(&crate::protocol::v3::response::Sky) -> bool,
+    {
+        match self.take("SKY") {
+            Message::Sky(sky) => assert!(
+                predicate(&sky),
+                "SKY at message {} does not satisfy the predicate: {sky:?}",
+                self.position - 1
+            ),
+            other => panic!(
+                "expected SKY at message {}, got {}",
+                self.position - 1,
+                other.class_name()
+            ),
+        }
+        self
+    }
+
+    /// Expects all messages to have been consumed
+    #[track_caller]
+    pub fn expect_end(&mut self) {
+        if let Some(msg) = self.messages.front() {
+            panic!(
+                "expected end of sequence at message {}, got {}",
+                self.position,
+                msg.class_name()
+            );
+        }
+    }
+
+    #[track_caller]
+    fn take(&mut self, expected: &str) -> Message {
+        let Some(msg) = self.messages.pop_front() else {
+            panic!(
+                "expected {expected}, but the sequence ended at message {}",
+                self.position
+            );
+        };
+        self.position += 1;
+        msg
+    }
+}
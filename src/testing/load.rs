@@ -0,0 +1,258 @@
+//! Concurrent client load testing against a GPSD host
+//!
+//! Opens a number of client connections at once, each with the same WATCH
+//! settings, and measures how many messages every client receives and how
+//! far behind GPS time they arrive. This is useful for capacity testing of
+//! GPSD hosts shared by many consumers.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::{client::StreamOptions, testing::load::LoadTest};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let report = LoadTest::new("127.0.0.1:2947")
+//!     .clients(50)
+//!     .duration(Duration::from_secs(30))
+//!     .options(StreamOptions::json().pps(true))
+//!     .run()
+//!     .await;
+//!
+//! println!(
+//!     "{} of {} clients connected, {:.1} msg/s total",
+//!     report.connected(),
+//!     report.clients.len(),
+//!     report.message_rate()
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use crate::{
+    client::{GpsdClient, Json, StreamOptions},
+    error::GpsdJsonError,
+    protocol::v3::response::Message,
+};
+
+/// Summary statistics of message latency in seconds
+///
+/// Latency is measured as local receive time minus the GPS time carried
+/// by the message, so it includes any offset of the local clock.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LatencyStats {
+    /// Number of messages carrying a GPS time
+    pub count: usize,
+    /// Smallest observed latency
+    pub min: f64,
+    /// Largest observed latency
+    pub max: f64,
+    /// Mean latency
+    pub mean: f64,
+}
+
+impl LatencyStats {
+    fn add(&mut self, latency: f64) {
+        if self.count == 0 {
+            self.min = latency;
+            self.max = latency;
+        } else {
+            self.min = self.min.min(latency);
+            self.max = self.max.max(latency);
+        }
+        self.count += 1;
+        self.mean += (latency - self.mean) / self.count as f64;
+    }
+
+    fn merge(&mut self, other: &LatencyStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        self.mean =
+            (self.mean * self.count as f64 + other.mean * other.count as f64) / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+}
+
+/// Result of a single client in a load test
+#[derive(Debug)]
+pub struct ClientReport {
+    /// Time taken to connect and enable WATCH mode
+    pub connect_time: Option<Duration>,
+    /// Number of messages received
+    pub messages: usize,
+    /// Number of messages that failed to parse
+    pub errors: usize,
+    /// Latency of messages carrying a GPS time
+    pub latency: LatencyStats,
+    /// Error that ended the client early, if any
+    pub failure: Option<GpsdJsonError>,
+}
+
+/// Result of a load test
+#[derive(Debug)]
+pub struct LoadReport {
+    /// Wall clock duration of the measurement
+    pub elapsed: Duration,
+    /// Per-client results, in connection order
+    pub clients: Vec<ClientReport>,
+}
+
+impl LoadReport {
+    /// Returns the number of clients that connected successfully
+    pub fn connected(&self) -> usize {
+        self.clients
+            .iter()
+            .filter(|client| client.connect_time.is_some())
+            .count()
+    }
+
+    /// Returns the total number of messages received by all clients
+    pub fn total_messages(&self) -> usize {
+        self.clients.iter().map(|client| client.messages).sum()
+    }
+
+    /// Returns the total message rate over all clients in messages per second
+    pub fn message_rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.total_messages() as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the latency statistics over all clients
+    pub fn latency(&self) -> LatencyStats {
+        let mut stats = LatencyStats::default();
+        for client in &self.clients {
+            stats.merge(&client.latency);
+        }
+        stats
+    }
+}
+
+/// Load test configuration
+#[derive(Debug, Clone)]
+pub struct LoadTest {
+    addr: String,
+    clients: usize,
+    duration: Duration,
+    options: StreamOptions<Json>,
+}
+
+impl LoadTest {
+    /// Creates a load test against the given GPSD address
+    ///
+    /// Defaults to 10 clients streaming JSON for 10 seconds.
+    pub fn new<A: Into<String>>(addr: A) -> Self {
+        LoadTest {
+            addr: addr.into(),
+            clients: 10,
+            duration: Duration::from_secs(10),
+            options: StreamOptions::json(),
+        }
+    }
+
+    /// Sets the number of concurrent clients
+    pub fn clients(mut self, clients: usize) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    /// Sets how long every client streams data
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the WATCH options used by every client
+    pub fn options(mut self, options: StreamOptions<Json>) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Runs the load test
+    ///
+    /// All clients are driven concurrently on the current task. Failures
+    /// of individual clients are recorded in their [`ClientReport`]
+    /// rather than aborting the test.
+    pub async fn run(&self) -> LoadReport {
+        let start = Instant::now();
+        let deadline = tokio::time::Instant::from_std(start + self.duration);
+        let clients =
+            futures_util::future::join_all((0..self.clients).map(|_| self.run_client(deadline)))
+                .await;
+
+        LoadReport {
+            elapsed: start.elapsed(),
+            clients,
+        }
+    }
+
+    async fn run_client(&self, deadline: tokio::time::Instant) -> ClientReport {
+        let mut report = ClientReport {
+            connect_time: None,
+            messages: 0,
+            errors: 0,
+            latency: LatencyStats::default(),
+            failure: None,
+        };
+
+        let start = Instant::now();
+        let connect = async {
+            GpsdClient::connect(self.addr.as_str())
+                .await?
+                .stream(self.options.clone())
+                .await
+        };
+        let mut stream = match tokio::time::timeout_at(deadline, connect).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(err)) => {
+                report.failure = Some(err);
+                return report;
+            }
+            Err(_) => {
+                report.failure = Some(GpsdJsonError::ProtocolError(
+                    "Load test ended before the client connected",
+                ));
+                return report;
+            }
+        };
+        report.connect_time = Some(start.elapsed());
+
+        while let Ok(next) = tokio::time::timeout_at(deadline, stream.next()).await {
+            match next {
+                Some(Ok(msg)) => {
+                    report.messages += 1;
+                    if let Some(time) = Message::time(&msg) {
+                        report.latency.add((Utc::now() - time).as_seconds_f64());
+                    }
+                }
+                Some(Err(GpsdJsonError::SerdeError(_))) => report.errors += 1,
+                Some(Err(err)) => {
+                    report.failure = Some(err);
+                    break;
+                }
+                None => {
+                    report.failure = Some(GpsdJsonError::ProtocolError(
+                        "Connection closed by GPSD during load test",
+                    ));
+                    break;
+                }
+            }
+        }
+        report
+    }
+}
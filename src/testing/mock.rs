@@ -0,0 +1,461 @@
+//! Scripted mock GPSD server
+//!
+//! A [`Scenario`] describes a session declaratively: the greeting sent on
+//! connect, the replies to expected commands and the lines streamed in
+//! between. A [`MockServer`] plays the scenario to every client that
+//! connects, so multi-step interactions such as errors during the
+//! handshake or device hotplug while streaming can be tested without a
+//! running GPSD.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::{
+//!     client::{StreamOptions, blocking::GpsdClient},
+//!     testing::mock::{MockServer, Scenario, reply_with},
+//! };
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::start(
+//!     Scenario::new()
+//!         .on_watch(reply_with([
+//!             r#"{"class":"DEVICES","devices":[]}"#,
+//!             r#"{"class":"WATCH","enable":true,"json":true}"#,
+//!         ]))
+//!         .then_stream("tests/data/drive.jsonl")
+//!         .then_send([r#"{"class":"DEVICE","path":"/dev/ttyACM0","activated":"2025-01-01T00:00:00.000Z"}"#])
+//!         .then_close(),
+//! )?;
+//!
+//! let client = GpsdClient::connect(server.addr())?;
+//! for msg in client.stream(StreamOptions::json())? {
+//!     println!("{:?}", msg?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MockServer`]: crate::testing::mock::MockServer
+//! [`Scenario`]: crate::testing::mock::Scenario
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Version greeting of GPSD 3.25, sent on connect by default
+pub const DEFAULT_GREETING: &str = crate::fixtures::VERSION;
+
+/// Interval at which the accept loop checks for shutdown
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Lines sent in reply to a command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reply {
+    lines: Vec<String>,
+    close: bool,
+}
+
+impl Reply {
+    /// Closes the connection after the reply
+    pub fn then_close(mut self) -> Self {
+        self.close = true;
+        self
+    }
+}
+
+/// Creates a reply of the given lines, sent in order
+pub fn reply_with<I, S>(lines: I) -> Reply
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    Reply {
+        lines: lines.into_iter().map(Into::into).collect(),
+        close: false,
+    }
+}
+
+/// Step of a scenario
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Waits for a command and replies to it
+    Request { command: String, reply: Reply },
+    /// Sends lines
+    Send(Vec<String>),
+    /// Sends lines over and over
+    Repeat(Vec<String>, usize),
+    /// Sends the lines of a file
+    Stream(PathBuf),
+    /// Sends the lines of an archive
+    #[cfg(feature = "archive")]
+    Archive(PathBuf),
+    /// Pauses the session
+    Wait(Duration),
+    /// Closes the connection
+    Close,
+}
+
+/// Declarative script of a GPSD session
+///
+/// Steps are played in order. A command step waits for the client to send
+/// the command; other commands received meanwhile are recorded but not
+/// answered. When all steps are played, the connection stays open until
+/// the client closes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    greeting: Option<String>,
+    interval: Duration,
+    steps: Vec<Step>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scenario {
+    /// Creates a scenario that only sends the [`DEFAULT_GREETING`]
+    pub fn new() -> Self {
+        Scenario {
+            greeting: Some(DEFAULT_GREETING.to_string()),
+            interval: Duration::ZERO,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Replaces the greeting sent on connect, e.g. with an unsupported
+    /// protocol version
+    pub fn greeting(mut self, line: impl Into<String>) -> Self {
+        self.greeting = Some(line.into());
+        self
+    }
+
+    /// Sends no greeting on connect
+    pub fn without_greeting(mut self) -> Self {
+        self.greeting = None;
+        self
+    }
+
+    /// Sets the delay between lines sent by send and stream steps
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Waits for a command such as `WATCH` and replies to it
+    pub fn on_request(mut self, command: impl Into<String>, reply: Reply) -> Self {
+        self.steps.push(Step::Request {
+            command: command.into().to_ascii_uppercase(),
+            reply,
+        });
+        self
+    }
+
+    /// Waits for a `?WATCH` command and replies to it
+    pub fn on_watch(self, reply: Reply) -> Self {
+        self.on_request("WATCH", reply)
+    }
+
+    /// Waits for a `?DEVICES` command and replies to it
+    pub fn on_devices(self, reply: Reply) -> Self {
+        self.on_request("DEVICES", reply)
+    }
+
+    /// Waits for a `?DEVICE` command and replies to it
+    pub fn on_device(self, reply: Reply) -> Self {
+        self.on_request("DEVICE", reply)
+    }
+
+    /// Waits for a `?VERSION` command and replies to it
+    pub fn on_version(self, reply: Reply) -> Self {
+        self.on_request("VERSION", reply)
+    }
+
+    /// Waits for a `?POLL` command and replies to it
+    pub fn on_poll(self, reply: Reply) -> Self {
+        self.on_request("POLL", reply)
+    }
+
+    /// Sends lines, e.g. reports or a DEVICE notification
+    pub fn then_send<I, S>(mut self, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.steps
+            .push(Step::Send(lines.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Sends lines the given number of times, e.g. `usize::MAX` to keep
+    /// streaming until the client disconnects
+    pub fn then_repeat<I, S>(mut self, lines: I, times: usize) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.steps.push(Step::Repeat(
+            lines.into_iter().map(Into::into).collect(),
+            times,
+        ));
+        self
+    }
+
+    /// Sends the non-empty lines of a file, such as a recorded session
+    ///
+    /// The file is read when the step is played. The connection is closed
+    /// if it cannot be read.
+    pub fn then_stream(mut self, path: impl Into<PathBuf>) -> Self {
+        self.steps.push(Step::Stream(path.into()));
+        self
+    }
+
+    /// Sends the lines of a [capture archive](crate::archive), such as a
+    /// recorded session
+    ///
+    /// The archive is read when the step is played. The connection is
+    /// closed if it cannot be read.
+    #[cfg(feature = "archive")]
+    pub fn then_archive(mut self, path: impl Into<PathBuf>) -> Self {
+        self.steps.push(Step::Archive(path.into()));
+        self
+    }
+
+    /// Pauses the session
+    pub fn then_wait(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Wait(duration));
+        self
+    }
+
+    /// Closes the connection
+    pub fn then_close(mut self) -> Self {
+        self.steps.push(Step::Close);
+        self
+    }
+
+    /// Plays the scenario on a connection
+    fn play(&self, stream: TcpStream, requests: &Mutex<Vec<String>>) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+
+        if let Some(greeting) = &self.greeting {
+            writeln!(writer, "{greeting}")?;
+        }
+
+        for step in &self.steps {
+            match step {
+                Step::Request { command, reply } => {
+                    loop {
+                        let Some(request) = read_command(&mut reader)? else {
+                            return Ok(());
+                        };
+                        let matched = command_name(&request) == *command;
+                        requests.lock().unwrap().push(request);
+                        if matched {
+                            break;
+                        }
+                    }
+                    for line in &reply.lines {
+                        writeln!(writer, "{line}")?;
+                    }
+                    if reply.close {
+                        return Ok(());
+                    }
+                }
+                Step::Send(lines) => {
+                    for line in lines {
+                        self.send_line(&mut writer, line)?;
+                    }
+                }
+                Step::Repeat(lines, times) => {
+                    for _ in 0..*times {
+                        for line in lines {
+                            self.send_line(&mut writer, line)?;
+                        }
+                    }
+                }
+                Step::Stream(path) => {
+                    let file = BufReader::new(std::fs::File::open(path)?);
+                    for line in file.lines() {
+                        let line = line?;
+                        if !line.trim().is_empty() {
+                            self.send_line(&mut writer, &line)?;
+                        }
+                    }
+                }
+                #[cfg(feature = "archive")]
+                Step::Archive(path) => {
+                    let archive =
+                        crate::archive::ArchiveReader::open(path).map_err(io::Error::other)?;
+                    for frame in archive {
+                        let frame = frame.map_err(io::Error::other)?;
+                        self.send_line(&mut writer, &String::from_utf8_lossy(&frame.line))?;
+                    }
+                }
+                Step::Wait(duration) => std::thread::sleep(*duration),
+                Step::Close => return Ok(()),
+            }
+        }
+
+        // Keep the connection open and record commands until the client
+        // closes it
+        while let Some(request) = read_command(&mut reader)? {
+            requests.lock().unwrap().push(request);
+        }
+        Ok(())
+    }
+
+    fn send_line(&self, writer: &mut TcpStream, line: &str) -> io::Result<()> {
+        if !self.interval.is_zero() {
+            std::thread::sleep(self.interval);
+        }
+        writeln!(writer, "{line}")
+    }
+}
+
+/// Reads a command terminated by `;`, or `None` at end of stream
+fn read_command(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut buf = Vec::new();
+    if reader.read_until(b';', &mut buf)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&buf).trim().to_string()))
+}
+
+/// Returns the name of a command, e.g. `WATCH` for `?WATCH={...};`
+fn command_name(request: &str) -> String {
+    request
+        .trim_start_matches('?')
+        .split(['=', ';'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+/// Mock GPSD server playing a scenario to every client
+///
+/// The server listens on a random local port and stops accepting clients
+/// when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<String>>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server playing the scenario
+    pub fn start(scenario: Scenario) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let scenario = Arc::new(scenario);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let requests = requests.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let Ok((stream, _)) = listener.accept() else {
+                        std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                        continue;
+                    };
+                    if stream.set_nonblocking(false).is_err() {
+                        continue;
+                    }
+                    let scenario = scenario.clone();
+                    let requests = requests.clone();
+                    std::thread::spawn(move || {
+                        let _ = scenario.play(stream, &requests);
+                    });
+                }
+            })
+        };
+
+        Ok(MockServer {
+            addr,
+            requests,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the address clients connect to
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the commands received from all clients, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{StreamOptions, blocking::GpsdClient},
+        error::GpsdJsonError,
+        testing::expect::Sequence,
+    };
+
+    #[test]
+    fn test_mock_server_scenario() {
+        let server = MockServer::start(
+            Scenario::new()
+                .on_watch(reply_with([
+                    r#"{"class":"DEVICES","devices":[]}"#,
+                    r#"{"class":"WATCH","enable":true,"json":true}"#,
+                ]))
+                .then_send([
+                    r#"{"class":"TPV","mode":3,"lat":35.0,"lon":139.0}"#,
+                    r#"{"class":"DEVICE","path":"/dev/ttyACM0","activated":"2025-01-01T00:00:00.000Z"}"#,
+                ])
+                .then_close(),
+        )
+        .unwrap();
+
+        let client = GpsdClient::connect(server.addr()).unwrap();
+        let messages = client
+            .stream(StreamOptions::json())
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        Sequence::new(messages)
+            .expect_classes(["TPV", "DEVICE"])
+            .expect_end();
+        assert!(server.requests()[0].starts_with("?WATCH="));
+
+        // Unsupported protocol version during the handshake
+        let server = MockServer::start(Scenario::new().greeting(
+            r#"{"class":"VERSION","release":"2.9","rev":"2.9","proto_major":2,"proto_minor":0}"#,
+        ))
+        .unwrap();
+        assert!(matches!(
+            GpsdClient::connect(server.addr()),
+            Err(GpsdJsonError::UnsupportedProtocolVersion((2, 0)))
+        ));
+    }
+}
@@ -0,0 +1,303 @@
+//! Long-running soak testing against the mock server
+//!
+//! Streams reports from a [`MockServer`] to a blocking client at a fixed
+//! rate for a long time and periodically samples the memory held by the
+//! client. Slow leaks in buffers or queues show up as growth between the
+//! first and the last samples, long before they would in production.
+//!
+//! By default the buffers of the stream itself are sampled, see
+//! [`GpsdDataStream::buffer_capacity`]. A [`memory probe`](crate::testing::soak::SoakTest::probe)
+//! adds memory held elsewhere, e.g. by a counting global allocator or by
+//! consumers of the messages.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use gpsd_json::testing::soak::SoakTest;
+//!
+//! let report = SoakTest::new()
+//!     .duration(Duration::from_secs(4 * 3600))
+//!     .rate(1000.0)
+//!     .run();
+//!
+//! report.assert_no_parse_errors();
+//! report.assert_stable_memory(4096);
+//! report.assert_min_rate(900.0);
+//! ```
+//!
+//! [`GpsdDataStream::buffer_capacity`]: crate::client::blocking::GpsdDataStream::buffer_capacity
+//! [`MockServer`]: crate::testing::mock::MockServer
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    client::{ParseErrorPolicy, StreamOptions, blocking::GpsdClient},
+    error::GpsdJsonError,
+    fixtures,
+    testing::mock::{MockServer, Scenario, reply_with},
+};
+
+/// Memory held by the client at one point of a soak test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Time since the start of streaming
+    pub elapsed: Duration,
+    /// Number of messages received so far
+    pub messages: u64,
+    /// Bytes held by the client, as sampled
+    pub memory: usize,
+}
+
+/// Result of a soak test
+#[derive(Debug)]
+pub struct SoakReport {
+    /// Wall clock duration of the measurement
+    pub elapsed: Duration,
+    /// Number of messages received
+    pub messages: u64,
+    /// Number of lines that failed to parse
+    pub parse_errors: u64,
+    /// Memory samples, in order
+    pub samples: Vec<Sample>,
+    /// Error that ended the test early, if any
+    pub failure: Option<GpsdJsonError>,
+}
+
+impl SoakReport {
+    /// Returns the message rate in messages per second
+    pub fn message_rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.messages as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the growth of memory from the first sample to the largest
+    /// one in bytes
+    pub fn memory_growth(&self) -> usize {
+        let Some(first) = self.samples.first() else {
+            return 0;
+        };
+        self.samples
+            .iter()
+            .map(|sample| sample.memory.saturating_sub(first.memory))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Panics if the test ended early or a line failed to parse
+    pub fn assert_no_parse_errors(&self) {
+        if let Some(failure) = &self.failure {
+            panic!("soak test failed after {:?}: {failure}", self.elapsed);
+        }
+        assert_eq!(
+            self.parse_errors, 0,
+            "{} of {} lines failed to parse",
+            self.parse_errors, self.messages
+        );
+    }
+
+    /// Panics if memory grew by more than `tolerance` bytes
+    pub fn assert_stable_memory(&self, tolerance: usize) {
+        assert!(
+            self.samples.len() >= 2,
+            "soak test took {} memory samples, at least 2 are needed",
+            self.samples.len()
+        );
+        let growth = self.memory_growth();
+        assert!(
+            growth <= tolerance,
+            "memory grew by {growth} bytes over {:?}, tolerance is {tolerance}: {:?}",
+            self.elapsed,
+            self.samples
+        );
+    }
+
+    /// Panics if fewer than `rate` messages per second were received
+    pub fn assert_min_rate(&self, rate: f64) {
+        let actual = self.message_rate();
+        assert!(
+            actual >= rate,
+            "received {actual:.1} msg/s, expected at least {rate:.1}"
+        );
+    }
+}
+
+/// Soak test configuration
+#[derive(Debug, Clone)]
+pub struct SoakTest {
+    lines: Vec<String>,
+    duration: Duration,
+    rate: f64,
+    sample_interval: Duration,
+    probe: Option<fn() -> usize>,
+}
+
+impl Default for SoakTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoakTest {
+    /// Creates a soak test streaming one TPV, SKY and GST per epoch
+    ///
+    /// Defaults to 100 messages per second for one minute, sampled every
+    /// second.
+    pub fn new() -> Self {
+        SoakTest {
+            lines: [fixtures::TPV, fixtures::SKY, fixtures::GST]
+                .map(String::from)
+                .to_vec(),
+            duration: Duration::from_secs(60),
+            rate: 100.0,
+            sample_interval: Duration::from_secs(1),
+            probe: None,
+        }
+    }
+
+    /// Replaces the lines streamed over and over by the server
+    pub fn lines<I, S>(mut self, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.lines = lines.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how long data is streamed
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the rate the server sends lines at in lines per second
+    ///
+    /// A rate of zero or less sends as fast as the client reads.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Sets the interval between memory samples
+    pub fn sample_interval(mut self, interval: Duration) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+
+    /// Adds the bytes returned by `probe` to every memory sample
+    pub fn probe(mut self, probe: fn() -> usize) -> Self {
+        self.probe = Some(probe);
+        self
+    }
+
+    /// Runs the soak test
+    ///
+    /// Blocks for the configured duration. Errors other than parse errors
+    /// end the test early and are recorded in the report.
+    pub fn run(&self) -> SoakReport {
+        let mut report = SoakReport {
+            elapsed: Duration::ZERO,
+            messages: 0,
+            parse_errors: 0,
+            samples: Vec::new(),
+            failure: None,
+        };
+
+        let interval = if self.rate > 0.0 {
+            Duration::from_secs_f64(1.0 / self.rate)
+        } else {
+            Duration::ZERO
+        };
+        let scenario = Scenario::new()
+            .interval(interval)
+            .on_watch(reply_with([fixtures::DEVICES, fixtures::WATCH]))
+            .then_repeat(self.lines.clone(), usize::MAX);
+        let server = match MockServer::start(scenario) {
+            Ok(server) => server,
+            Err(err) => {
+                report.failure = Some(GpsdJsonError::IoError(err));
+                return report;
+            }
+        };
+        let options = StreamOptions::json().on_parse_error(ParseErrorPolicy::Skip);
+        let mut stream = match GpsdClient::connect(server.addr()).and_then(|c| c.stream(options)) {
+            Ok(stream) => stream,
+            Err(err) => {
+                report.failure = Some(err);
+                return report;
+            }
+        };
+
+        let start = Instant::now();
+        let mut next_sample = start + self.sample_interval;
+        while start.elapsed() < self.duration {
+            match stream.next() {
+                Some(Ok(_)) => report.messages += 1,
+                Some(Err(err)) => {
+                    report.failure = Some(err);
+                    break;
+                }
+                None => {
+                    report.failure = Some(GpsdJsonError::ProtocolError(
+                        "Connection closed by the mock server during soak test",
+                    ));
+                    break;
+                }
+            }
+
+            if Instant::now() >= next_sample {
+                next_sample += self.sample_interval;
+                report.samples.push(Sample {
+                    elapsed: start.elapsed(),
+                    messages: report.messages,
+                    memory: stream.buffer_capacity() + self.probe.map_or(0, |probe| probe()),
+                });
+            }
+        }
+
+        report.elapsed = start.elapsed();
+        report.parse_errors = stream.skipped_lines();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soak_short() {
+        let report = SoakTest::new()
+            .lines([fixtures::TPV, r#"{"class":"TPV",,}"#])
+            .duration(Duration::from_millis(300))
+            .rate(0.0)
+            .sample_interval(Duration::from_millis(50))
+            .run();
+        assert!(report.failure.is_none());
+        assert!(report.messages > 0);
+        assert!(report.parse_errors > 0);
+        report.assert_stable_memory(4096);
+    }
+
+    #[test]
+    #[ignore = "runs for an hour, set GPSD_SOAK_SECS to change"]
+    fn test_soak_long() {
+        let secs = std::env::var("GPSD_SOAK_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(3600);
+        let report = SoakTest::new()
+            .duration(Duration::from_secs(secs))
+            .rate(1000.0)
+            .sample_interval(Duration::from_secs(10))
+            .run();
+        report.assert_no_parse_errors();
+        report.assert_stable_memory(4096);
+        report.assert_min_rate(500.0);
+    }
+}
@@ -0,0 +1,494 @@
+//! Tracking of the latest fix and device state
+//!
+//! [`FixTracker`] follows a stream of GPSD reports and keeps the state an
+//! application usually displays: the last known fix, the devices GPSD
+//! reported and the active WATCH settings. It also keeps the latest report
+//! of each class per device with the time it was received, so health
+//! endpoints can tell how old the data is. The state can be saved as a
+//! serializable [`Snapshot`] and restored on the next start, so that the
+//! last known position is available before the first new fix arrives.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use gpsd_json::tracker::{FixTracker, Snapshot};
+//! # fn example(messages: Vec<gpsd_json::protocol::v3::response::Message>) -> Result<(), Box<dyn std::error::Error>> {
+//! // Warm start from the previous run
+//! let mut tracker = match std::fs::read("state.json") {
+//!     Ok(json) => FixTracker::restore(serde_json::from_slice::<Snapshot>(&json)?),
+//!     Err(_) => FixTracker::new(),
+//! };
+//! if let Some(fix) = tracker.last_fix() {
+//!     println!("last known position: {:?}", fix.position);
+//! }
+//!
+//! for msg in &messages {
+//!     tracker.update(msg);
+//! }
+//! std::fs::write("state.json", serde_json::to_vec(&tracker.snapshot())?)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FixTracker`]: crate::tracker::FixTracker
+//! [`Snapshot`]: crate::tracker::Snapshot
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Timestamp,
+    geo::Position,
+    protocol::v3::{
+        response::{Message, Tpv},
+        types::{Device, FixMode, FixStatus, Watch},
+    },
+};
+
+/// Version of the snapshot format
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Report class whose latest report is kept by a [`FixTracker`]
+pub trait Report: Sized + 'static {
+    /// Value of the `class` field
+    const CLASS: &'static str;
+
+    /// Returns the report if the message is of this class
+    fn from_message(message: &Message) -> Option<&Self>;
+
+    /// Returns the device that sent the report
+    fn device(&self) -> Option<&str>;
+}
+
+macro_rules! impl_report {
+    ($($(#[$cfg:meta])* $ty:path => $variant:ident, $class:literal;)*) => {
+        $(
+            $(#[$cfg])*
+            impl Report for $ty {
+                const CLASS: &'static str = $class;
+
+                fn from_message(message: &Message) -> Option<&Self> {
+                    match message {
+                        Message::$variant(report) => Some(report),
+                        _ => None,
+                    }
+                }
+
+                fn device(&self) -> Option<&str> {
+                    self.device.as_deref()
+                }
+            }
+        )*
+    };
+}
+
+impl_report! {
+    Tpv => Tpv, "TPV";
+    #[cfg(feature = "msg-sky")]
+    crate::protocol::v3::response::Sky => Sky, "SKY";
+    #[cfg(feature = "msg-gst")]
+    crate::protocol::v3::response::Gst => Gst, "GST";
+    #[cfg(feature = "msg-att")]
+    crate::protocol::v3::response::Attitude => Att, "ATT";
+    #[cfg(feature = "msg-imu")]
+    crate::protocol::v3::response::Imu => Imu, "IMU";
+    #[cfg(feature = "msg-toff")]
+    crate::protocol::v3::response::TimeOffset => Toff, "TOFF";
+    #[cfg(feature = "msg-pps")]
+    crate::protocol::v3::response::Pps => Pps, "PPS";
+}
+
+#[cfg(feature = "msg-osc")]
+impl Report for crate::protocol::v3::response::Oscillator {
+    const CLASS: &'static str = "OSC";
+
+    fn from_message(message: &Message) -> Option<&Self> {
+        match message {
+            Message::Osc(osc) => Some(osc),
+            _ => None,
+        }
+    }
+
+    fn device(&self) -> Option<&str> {
+        Some(&self.device)
+    }
+}
+
+/// Latest report of a class from one device
+#[derive(Debug, Clone)]
+struct Latest {
+    message: Message,
+    received: Instant,
+}
+
+/// The last reported fix with at least a 2D position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastFix {
+    /// GPS time of the fix
+    pub time: Option<Timestamp>,
+    /// Position of the fix
+    pub position: Position,
+    /// Height above ellipsoid in meters
+    pub alt_hae: Option<f64>,
+    /// Altitude above mean sea level in meters
+    pub alt_msl: Option<f64>,
+    /// Fix mode
+    pub mode: FixMode,
+    /// Fix status
+    pub status: Option<FixStatus>,
+    /// Speed over ground in meters per second
+    pub speed: Option<f64>,
+    /// True track in degrees
+    pub track: Option<f64>,
+    /// Estimated horizontal position error in meters
+    pub eph: Option<f64>,
+    /// Estimated vertical position error in meters
+    pub epv: Option<f64>,
+    /// Device that reported the fix
+    pub device: Option<String>,
+}
+
+impl LastFix {
+    /// Extracts the fix from a TPV report with at least a 2D fix
+    pub fn from_tpv(tpv: &Tpv) -> Option<Self> {
+        if tpv.mode < FixMode::Fix2D {
+            return None;
+        }
+        Some(LastFix {
+            time: tpv.time,
+            position: Position::from_tpv(tpv)?,
+            alt_hae: tpv.alt_hae,
+            alt_msl: tpv.alt_msl,
+            mode: tpv.mode,
+            status: tpv.status,
+            speed: tpv.speed,
+            track: tpv.track,
+            eph: tpv.eph,
+            epv: tpv.epv,
+            device: tpv.device.clone(),
+        })
+    }
+}
+
+/// Serializable state of a [`FixTracker`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Version of the snapshot format
+    pub version: u32,
+    /// Time the snapshot was taken
+    pub saved: Timestamp,
+    /// Last known fix
+    pub last_fix: Option<LastFix>,
+    /// Known devices
+    pub devices: Vec<Device>,
+    /// Active WATCH settings
+    pub watch: Option<Watch>,
+}
+
+/// Follows GPSD reports and keeps the latest fix and device state
+#[derive(Debug, Clone, Default)]
+pub struct FixTracker {
+    last_fix: Option<LastFix>,
+    mode: Option<FixMode>,
+    devices: BTreeMap<String, Device>,
+    watch: Option<Watch>,
+    restored: Option<Timestamp>,
+    latest: BTreeMap<(String, String), Latest>,
+}
+
+impl FixTracker {
+    /// Creates an empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tracker from a previously saved snapshot
+    pub fn restore(snapshot: Snapshot) -> Self {
+        FixTracker {
+            last_fix: snapshot.last_fix,
+            mode: None,
+            devices: snapshot
+                .devices
+                .into_iter()
+                .map(|device| (device.path.clone().unwrap_or_default(), device))
+                .collect(),
+            watch: snapshot.watch,
+            restored: Some(snapshot.saved),
+            latest: BTreeMap::new(),
+        }
+    }
+
+    /// Returns a serializable snapshot of the current state
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            saved: Utc::now(),
+            last_fix: self.last_fix.clone(),
+            devices: self.devices.values().cloned().collect(),
+            watch: self.watch.clone(),
+        }
+    }
+
+    /// Processes a report
+    ///
+    /// TPV reports update the fix, DEVICES and DEVICE reports the known
+    /// devices and WATCH reports the active WATCH settings. Reports of the
+    /// classes implementing [`Report`] are kept as the latest of their
+    /// class and device.
+    pub fn update(&mut self, message: &Message) {
+        self.update_at(message, Instant::now());
+    }
+
+    /// Processes a report received at the given time
+    pub fn update_at(&mut self, message: &Message, received: Instant) {
+        if let Some(device) = report_device(message) {
+            self.latest.insert(
+                (
+                    message.class_name().to_string(),
+                    device.unwrap_or_default().to_string(),
+                ),
+                Latest {
+                    message: message.clone(),
+                    received,
+                },
+            );
+        }
+
+        match message {
+            Message::Tpv(tpv) => {
+                self.mode = Some(tpv.mode);
+                if let Some(fix) = LastFix::from_tpv(tpv) {
+                    self.last_fix = Some(fix);
+                    self.restored = None;
+                }
+            }
+            Message::Devices(list) => {
+                self.devices = list
+                    .devices
+                    .iter()
+                    .map(|device| (device.path.clone().unwrap_or_default(), device.clone()))
+                    .collect();
+            }
+            Message::Device(device) => {
+                let path = device.path.clone().unwrap_or_default();
+                match self.devices.get_mut(&path) {
+                    Some(known) => merge_device(known, device),
+                    None => {
+                        self.devices.insert(path, device.clone());
+                    }
+                }
+            }
+            Message::Watch(watch) => self.watch = Some(watch.clone()),
+            _ => {}
+        }
+    }
+
+    /// Returns the last known fix
+    ///
+    /// After [`restore`](Self::restore), this is the fix from the snapshot
+    /// until a new fix arrives; see [`is_restored`](Self::is_restored).
+    pub fn last_fix(&self) -> Option<&LastFix> {
+        self.last_fix.as_ref()
+    }
+
+    /// Returns the fix mode of the latest TPV report
+    ///
+    /// Unlike [`last_fix`](Self::last_fix), this reflects reports without
+    /// a fix and is `None` until the first TPV report.
+    pub fn mode(&self) -> Option<FixMode> {
+        self.mode
+    }
+
+    /// Returns true if the last fix comes from a restored snapshot
+    pub fn is_restored(&self) -> bool {
+        self.restored.is_some()
+    }
+
+    /// Returns the time the restored snapshot was saved, if any
+    pub fn restored_from(&self) -> Option<Timestamp> {
+        self.restored
+    }
+
+    /// Returns the known devices, ordered by path
+    pub fn devices(&self) -> impl Iterator<Item = &Device> {
+        self.devices.values()
+    }
+
+    /// Returns a known device by path
+    pub fn device(&self, path: &str) -> Option<&Device> {
+        self.devices.get(path)
+    }
+
+    /// Returns the active WATCH settings
+    pub fn watch(&self) -> Option<&Watch> {
+        self.watch.as_ref()
+    }
+
+    /// Returns the latest report of a class from any device
+    ///
+    /// ```no_run
+    /// # use gpsd_json::{tracker::FixTracker, protocol::v3::response::Tpv};
+    /// # fn example(tracker: &FixTracker) {
+    /// if let Some(tpv) = tracker.latest::<Tpv>() {
+    ///     println!("{:?}, {:?}", tpv.lat, tpv.lon);
+    /// }
+    /// # }
+    /// ```
+    pub fn latest<T: Report>(&self) -> Option<&T> {
+        self.newest::<T>()
+            .and_then(|latest| T::from_message(&latest.message))
+    }
+
+    /// Returns the latest report of a class from a device
+    pub fn latest_from<T: Report>(&self, device: &str) -> Option<&T> {
+        self.entry::<T>(device)
+            .and_then(|latest| T::from_message(&latest.message))
+    }
+
+    /// Returns the time since the latest report of a class from any device
+    /// was received
+    pub fn age<T: Report>(&self) -> Option<Duration> {
+        self.newest::<T>().map(|latest| latest.received.elapsed())
+    }
+
+    /// Returns the time since the latest report of a class from a device
+    /// was received
+    ///
+    /// ```no_run
+    /// # use gpsd_json::{tracker::FixTracker, protocol::v3::response::Tpv};
+    /// # fn example(tracker: &FixTracker) {
+    /// let age = tracker.age_from::<Tpv>("/dev/ttyACM0");
+    /// # }
+    /// ```
+    pub fn age_from<T: Report>(&self, device: &str) -> Option<Duration> {
+        self.entry::<T>(device)
+            .map(|latest| latest.received.elapsed())
+    }
+
+    fn entry<T: Report>(&self, device: &str) -> Option<&Latest> {
+        self.latest.get(&(T::CLASS.to_string(), device.to_string()))
+    }
+
+    fn newest<T: Report>(&self) -> Option<&Latest> {
+        self.latest
+            .iter()
+            .filter(|((class, _), _)| class == T::CLASS)
+            .map(|(_, latest)| latest)
+            .max_by_key(|latest| latest.received)
+    }
+}
+
+/// Returns the device of a report kept as the latest of its class, or
+/// `None` if the message is not such a report
+fn report_device(message: &Message) -> Option<Option<&str>> {
+    match message {
+        Message::Tpv(_) => Some(message.device()),
+        #[cfg(feature = "msg-sky")]
+        Message::Sky(_) => Some(message.device()),
+        #[cfg(feature = "msg-gst")]
+        Message::Gst(_) => Some(message.device()),
+        #[cfg(feature = "msg-att")]
+        Message::Att(_) => Some(message.device()),
+        #[cfg(feature = "msg-imu")]
+        Message::Imu(_) => Some(message.device()),
+        #[cfg(feature = "msg-toff")]
+        Message::Toff(_) => Some(message.device()),
+        #[cfg(feature = "msg-pps")]
+        Message::Pps(_) => Some(message.device()),
+        #[cfg(feature = "msg-osc")]
+        Message::Osc(_) => Some(message.device()),
+        _ => None,
+    }
+}
+
+/// Updates the fields of a known device reported in a DEVICE message
+fn merge_device(known: &mut Device, update: &Device) {
+    macro_rules! merge {
+        ($($field:ident),*) => {
+            $(if update.$field.is_some() {
+                known.$field = update.$field.clone();
+            })*
+        };
+    }
+    merge!(
+        activated, flags, driver, hexdata, sernum, subtype, subtype1, native, bps, parity,
+        stopbits, cycle, mincycle
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_tracker_snapshot_roundtrip() {
+        let mut tracker = FixTracker::new();
+        for json in [
+            r#"{"class":"DEVICES","devices":[{"path":"/dev/ttyACM0","driver":"u-blox"}]}"#,
+            r#"{"class":"DEVICE","path":"/dev/ttyACM0","bps":115200}"#,
+            r#"{"class":"TPV","mode":3,"lat":35.0,"lon":139.0,"altHAE":40.0}"#,
+            r#"{"class":"TPV","mode":1}"#,
+        ] {
+            tracker.update(&serde_json::from_str(json).unwrap());
+        }
+        assert_eq!(tracker.mode(), Some(FixMode::NoFix));
+
+        let json = serde_json::to_string(&tracker.snapshot()).unwrap();
+        let restored = FixTracker::restore(serde_json::from_str(&json).unwrap());
+
+        assert!(restored.is_restored());
+        let fix = restored.last_fix().unwrap();
+        assert_eq!(fix.position, Position::new(35.0, 139.0));
+        assert_eq!(fix.mode, FixMode::Fix3D);
+        let device = restored.device("/dev/ttyACM0").unwrap();
+        assert_eq!(device.driver.as_deref(), Some("u-blox"));
+        assert_eq!(device.bps, Some(115200));
+    }
+
+    #[test]
+    fn test_fix_tracker_latest_per_class() {
+        let mut tracker = FixTracker::new();
+        let now = Instant::now();
+        for (secs, json) in [
+            (
+                3,
+                r#"{"class":"SKY","device":"/dev/ttyACM0","satellites":[]}"#,
+            ),
+            (
+                2,
+                r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"lat":35.0,"lon":139.0}"#,
+            ),
+            (
+                1,
+                r#"{"class":"TPV","device":"/dev/ttyUSB0","mode":2,"lat":35.1,"lon":139.1}"#,
+            ),
+        ] {
+            tracker.update_at(
+                &serde_json::from_str(json).unwrap(),
+                now - Duration::from_secs(secs),
+            );
+        }
+
+        assert_eq!(tracker.latest::<Tpv>().unwrap().lat, Some(35.1));
+        assert_eq!(
+            tracker.latest_from::<Tpv>("/dev/ttyACM0").unwrap().lat,
+            Some(35.0)
+        );
+        assert!(tracker.latest::<Tpv>().is_some());
+        #[cfg(feature = "msg-sky")]
+        {
+            use crate::protocol::v3::response::Sky;
+
+            assert!(tracker.latest_from::<Sky>("/dev/ttyUSB0").is_none());
+            let sky = tracker.age_from::<Sky>("/dev/ttyACM0").unwrap();
+            assert!(sky >= Duration::from_secs(3));
+        }
+        let tpv = tracker.age::<Tpv>().unwrap();
+        assert!(tpv >= Duration::from_secs(1) && tpv < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,93 @@
+//! Time zone conversion of timestamps for display
+//!
+//! GPSD reports all times in UTC, and this library keeps them in UTC for
+//! storage and computation. This module converts them into a configured
+//! time zone from the IANA database when they are shown to people, such
+//! as in log review tools.
+//!
+//! Requires the `tz` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::tz::{LocalTimeFormatter, Tz};
+//!
+//! let fmt = LocalTimeFormatter::new(Tz::Asia__Tokyo);
+//! let utc = "2025-01-01T15:00:00Z".parse().unwrap();
+//! assert_eq!(fmt.format(&utc), "2025-01-02T00:00:00.000+09:00");
+//!
+//! let fmt = LocalTimeFormatter::from_name("Europe/Berlin")
+//!     .unwrap()
+//!     .pattern("%Y-%m-%d %H:%M:%S %Z");
+//! assert_eq!(fmt.format(&utc), "2025-01-01 16:00:00 CET");
+//! ```
+
+use chrono::{DateTime, SecondsFormat};
+
+use crate::{Timestamp, protocol::v3::response::Message};
+
+pub use chrono_tz::{ParseError, Tz};
+
+/// Converts a UTC timestamp into the given time zone
+pub fn to_timezone(time: &Timestamp, tz: Tz) -> DateTime<Tz> {
+    time.with_timezone(&tz)
+}
+
+/// Formats UTC timestamps in a configured time zone
+///
+/// Without a pattern, timestamps are formatted as RFC 3339 with
+/// millisecond precision and the local UTC offset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalTimeFormatter {
+    tz: Tz,
+    pattern: Option<String>,
+}
+
+impl LocalTimeFormatter {
+    /// Creates a formatter for the given time zone
+    pub fn new(tz: Tz) -> Self {
+        LocalTimeFormatter { tz, pattern: None }
+    }
+
+    /// Creates a formatter from an IANA time zone name such as `Asia/Tokyo`
+    pub fn from_name(name: &str) -> Result<Self, ParseError> {
+        name.parse().map(Self::new)
+    }
+
+    /// Sets a [`chrono::format::strftime`] pattern used for formatting
+    pub fn pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.pattern = Some(pattern.into());
+        self
+    }
+
+    /// Returns the configured time zone
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// Converts a UTC timestamp into the configured time zone
+    pub fn convert(&self, time: &Timestamp) -> DateTime<Tz> {
+        to_timezone(time, self.tz)
+    }
+
+    /// Formats a UTC timestamp in the configured time zone
+    pub fn format(&self, time: &Timestamp) -> String {
+        let local = self.convert(time);
+        match &self.pattern {
+            Some(pattern) => local.format(pattern).to_string(),
+            None => local.to_rfc3339_opts(SecondsFormat::Millis, false),
+        }
+    }
+
+    /// Returns the time of a message in the configured time zone
+    ///
+    /// See [`Message::time`] for which time is used per message class.
+    pub fn message_time(&self, message: &Message) -> Option<DateTime<Tz>> {
+        message.time().map(|time| self.convert(&time))
+    }
+
+    /// Formats the time of a message in the configured time zone
+    pub fn format_message(&self, message: &Message) -> Option<String> {
+        message.time().map(|time| self.format(&time))
+    }
+}
@@ -0,0 +1,265 @@
+//! Physical plausibility checks for GPSD reports
+//!
+//! GPS drivers occasionally emit values that are syntactically valid JSON
+//! but physically impossible, such as latitudes beyond the poles, negative
+//! speeds or a 3D fix without coordinates. This module checks reports
+//! against the ranges documented in the gpsd_json specification so that
+//! such glitches can be flagged or dropped before they reach downstream
+//! storage.
+//!
+//! # Example
+//!
+//! ```
+//! use gpsd_json::{
+//!     protocol::v3::response::Message,
+//!     validate::{ValidationPolicy, Validator},
+//! };
+//!
+//! let msg: Message =
+//!     serde_json::from_str(r#"{"class":"TPV","mode":3,"lat":91.5,"lon":139.0}"#).unwrap();
+//!
+//! let validator = Validator::new();
+//! let validated = validator.validate(msg.clone()).unwrap();
+//! assert_eq!(validated.violations[0].field, "lat");
+//!
+//! let strict = Validator::new().policy(ValidationPolicy::Reject);
+//! assert!(strict.validate(msg).is_err());
+//! ```
+
+use crate::{
+    Result,
+    error::GpsdJsonError,
+    protocol::v3::{
+        response::{Message, Tpv},
+        types::{FixMode, FixStatus, widen},
+    },
+};
+
+/// A single failed plausibility check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Message class the violation was found in (e.g. `TPV`)
+    pub class: &'static str,
+    /// Name of the offending field as it appears in the JSON report
+    pub field: &'static str,
+    /// Description of the failed check
+    pub reason: &'static str,
+}
+
+impl core::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{} {}", self.class, self.field, self.reason)
+    }
+}
+
+/// Action taken when a report fails validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ValidationPolicy {
+    /// Pass the report through together with its violations
+    #[default]
+    Annotate,
+    /// Return [`GpsdJsonError::ValidationError`] for the report
+    Reject,
+}
+
+/// A report that passed through the [`Validator`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validated {
+    /// The validated report, unchanged
+    pub message: Message,
+    /// Failed checks; empty if the report is plausible
+    pub violations: Vec<Violation>,
+}
+
+impl Validated {
+    /// Returns true if no check failed
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks GPSD reports for physically implausible values
+///
+/// The validator checks TPV, SKY and GST reports; all other classes are
+/// passed through without checks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Validator {
+    policy: ValidationPolicy,
+    max_error: f64,
+    max_speed: f64,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Validator {
+    /// Creates a validator with the `Annotate` policy and default limits
+    ///
+    /// By default, error estimates above 100 km and speeds above
+    /// 2000 m/s (beyond COCOM limits for civil receivers) are flagged.
+    pub fn new() -> Self {
+        Validator {
+            policy: ValidationPolicy::Annotate,
+            max_error: 100_000.0,
+            max_speed: 2_000.0,
+        }
+    }
+
+    /// Sets the action taken for implausible reports
+    pub fn policy(mut self, policy: ValidationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the largest accepted error estimate in meters
+    ///
+    /// Applies to `eph`, `epv`, `epx`, `epy` and `sep` of TPV reports and
+    /// to the deviations of GST reports.
+    pub fn max_error(mut self, meters: f64) -> Self {
+        self.max_error = meters;
+        self
+    }
+
+    /// Sets the largest accepted speed over ground in meters per second
+    pub fn max_speed(mut self, mps: f64) -> Self {
+        self.max_speed = mps;
+        self
+    }
+
+    /// Validates a report according to the configured policy
+    ///
+    /// # Errors
+    /// Returns [`GpsdJsonError::ValidationError`] if the policy is
+    /// [`ValidationPolicy::Reject`] and any check failed.
+    pub fn validate(&self, message: Message) -> Result<Validated> {
+        let violations = self.check(&message);
+        if self.policy == ValidationPolicy::Reject && !violations.is_empty() {
+            return Err(GpsdJsonError::ValidationError(violations));
+        }
+        Ok(Validated {
+            message,
+            violations,
+        })
+    }
+
+    /// Returns all failed checks for a report
+    pub fn check(&self, message: &Message) -> Vec<Violation> {
+        let mut checks = Checks::default();
+        match message {
+            Message::Tpv(tpv) => self.check_tpv(tpv, &mut checks),
+            #[cfg(feature = "msg-sky")]
+            Message::Sky(sky) => self.check_sky(sky, &mut checks),
+            #[cfg(feature = "msg-gst")]
+            Message::Gst(gst) => self.check_gst(gst, &mut checks),
+            _ => {}
+        }
+        checks.violations
+    }
+
+    fn check_tpv(&self, tpv: &Tpv, checks: &mut Checks) {
+        checks.class = "TPV";
+        checks.range("lat", tpv.lat, -90.0, 90.0);
+        checks.range("lon", tpv.lon, -180.0, 180.0);
+        checks.range("speed", tpv.speed, 0.0, self.max_speed);
+        checks.range("track", tpv.track, 0.0, 360.0);
+        checks.range("magtrack", tpv.magtrack, 0.0, 360.0);
+        checks.range("eph", tpv.eph, 0.0, self.max_error);
+        checks.range("epv", tpv.epv, 0.0, self.max_error);
+        checks.range("epx", tpv.epx, 0.0, self.max_error);
+        checks.range("epy", tpv.epy, 0.0, self.max_error);
+        checks.range("sep", tpv.sep, 0.0, self.max_error);
+        checks.range("eps", tpv.eps, 0.0, f64::MAX);
+        checks.range("epc", tpv.epc, 0.0, f64::MAX);
+        checks.range("epd", tpv.epd, 0.0, 360.0);
+        checks.range("ept", tpv.ept, 0.0, f64::MAX);
+
+        if tpv.mode >= FixMode::Fix2D && (tpv.lat.is_none() || tpv.lon.is_none()) {
+            checks.fail("mode", "reports a fix without lat/lon");
+        }
+        let augmented = matches!(
+            tpv.status,
+            Some(FixStatus::DGps | FixStatus::RTKFixed | FixStatus::RTKFloat)
+        );
+        if tpv.mode < FixMode::Fix2D && augmented {
+            checks.fail("status", "reports an augmented fix without a fix mode");
+        }
+    }
+
+    #[cfg(feature = "msg-sky")]
+    fn check_sky(&self, sky: &crate::protocol::v3::response::Sky, checks: &mut Checks) {
+        checks.class = "SKY";
+        for sat in &sky.satellites {
+            checks.range("az", sat.azimuth, 0.0, 360.0);
+            checks.range("el", sat.elevation, -90.0, 90.0);
+            checks.range("ss", sat.ss, 0.0, 254.0);
+        }
+        if sky
+            .u_sat
+            .zip(sky.n_sat)
+            .is_some_and(|(used, seen)| used > seen)
+        {
+            checks.fail("uSat", "exceeds nSat");
+        }
+    }
+
+    #[cfg(feature = "msg-gst")]
+    fn check_gst(&self, gst: &crate::protocol::v3::response::Gst, checks: &mut Checks) {
+        checks.class = "GST";
+        checks.range("lat", gst.lat, 0.0, self.max_error);
+        checks.range("lon", gst.lon, 0.0, self.max_error);
+        checks.range("alt", gst.alt, 0.0, self.max_error);
+    }
+}
+
+#[derive(Default)]
+struct Checks {
+    class: &'static str,
+    violations: Vec<Violation>,
+}
+
+impl Checks {
+    fn range(&mut self, field: &'static str, value: Option<impl Into<f64>>, min: f64, max: f64) {
+        match value.map(widen) {
+            Some(v) if v.is_nan() => self.fail(field, "is not a number"),
+            Some(v) if v < min || v > max => self.fail(field, "is out of range"),
+            _ => {}
+        }
+    }
+
+    fn fail(&mut self, field: &'static str, reason: &'static str) {
+        self.violations.push(Violation {
+            class: self.class,
+            field,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validator_tpv() {
+        let validator = Validator::new();
+        let check = |json: &str| {
+            let msg: Message = serde_json::from_str(json).unwrap();
+            validator
+                .check(&msg)
+                .into_iter()
+                .map(|v| v.field)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(check(r#"{"class":"TPV","mode":3,"lat":35.0,"lon":139.0,"speed":1.2}"#).is_empty());
+        assert_eq!(
+            check(r#"{"class":"TPV","mode":3,"lat":35.0,"lon":181.0,"speed":-1.0,"eph":-2.0}"#),
+            ["lon", "speed", "eph"]
+        );
+        assert_eq!(check(r#"{"class":"TPV","mode":3}"#), ["mode"]);
+        assert_eq!(check(r#"{"class":"TPV","mode":1,"status":3}"#), ["status"]);
+    }
+}